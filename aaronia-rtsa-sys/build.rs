@@ -7,7 +7,11 @@ fn search() -> Option<String> {
     const LIB_NAME: &str = "libAaroniaRTSAAPI.so";
     const HEADER_NAME: &str = "aaroniartsaapi.h";
 
-    println!("cargo:rustc-link-lib={LIB}");
+    // dlopen builds only need the header, the library is loaded at runtime
+    let dlopen = env::var_os("CARGO_FEATURE_DLOPEN").is_some();
+    if !dlopen {
+        println!("cargo:rustc-link-lib={LIB}");
+    }
 
     let paths = env::var_os("RTSA_DIR")
         .unwrap_or(concat!(env!("HOME"), "/Aaronia/RTSA/Aaronia-RTSA-Suite-PRO").into());
@@ -15,7 +19,7 @@ fn search() -> Option<String> {
     for dir in env::split_paths(&paths) {
         let lib_path = dir.join(LIB_NAME);
         let inc_path = dir.join(HEADER_NAME);
-        if lib_path.is_file() && inc_path.is_file() {
+        if (dlopen || lib_path.is_file()) && inc_path.is_file() {
             let dir = dir.to_string_lossy();
             println!("cargo:rustc-link-search={dir}");
             return Some(dir.into());
@@ -30,7 +34,11 @@ fn search() -> Option<String> {
     const LIB_NAME: &str = "AaroniaRTSAAPI.lib";
     const HEADER_NAME: &str = "aaroniartsaapi.h";
 
-    println!("cargo:rustc-link-lib={LIB}");
+    // dlopen builds only need the header, the library is loaded at runtime
+    let dlopen = env::var_os("CARGO_FEATURE_DLOPEN").is_some();
+    if !dlopen {
+        println!("cargo:rustc-link-lib={LIB}");
+    }
 
     let paths = env::var("RTSA_DIR")
         .unwrap_or(r"C:\Program Files\Aaronia AG\Aaronia RTSA-Suite PRO".into());
@@ -38,7 +46,7 @@ fn search() -> Option<String> {
     for dir in env::split_paths(&paths) {
         let lib_path = dir.join("sdk").join(LIB_NAME);
         let inc_path = dir.join("sdk").join(HEADER_NAME);
-        if lib_path.is_file() && inc_path.is_file() {
+        if (dlopen || lib_path.is_file()) && inc_path.is_file() {
             let lib_dir = dir.to_string_lossy();
             println!("cargo:rustc-link-search={lib_dir}");
             let dir = dir.join("sdk").to_string_lossy().into();
@@ -49,7 +57,13 @@ fn search() -> Option<String> {
 }
 
 fn main() {
-    let dir = search().expect("sdk not found, set RTSA_DIR environment variable");
+    let dir = search().unwrap_or_else(|| {
+        if env::var_os("CARGO_FEATURE_DLOPEN").is_some() {
+            panic!("sdk header not found, set RTSA_DIR environment variable")
+        } else {
+            panic!("sdk not found, set RTSA_DIR environment variable")
+        }
+    });
 
     println!("cargo:rerun-if-env-changed=RTSA_DIR");
 
@@ -0,0 +1,68 @@
+//! Benchmarks of the receive path against the in-crate stub of the RTSA library.
+//!
+//! Run with `cargo bench --no-default-features --features sys-stub`. The stub delivers packets
+//! without waiting, i.e., the benchmarks measure the overhead of the wrapper, not the device.
+use aaronia_rtsa::pipeline::OwnedPacket;
+use aaronia_rtsa::ApiHandle;
+use aaronia_rtsa::Device;
+use aaronia_rtsa::OutputFormat;
+use aaronia_rtsa::SampleFormat;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+
+fn device() -> Device {
+    let mut api = ApiHandle::new().unwrap();
+    api.rescan_devices().unwrap();
+    let mut dev = api.get_device().unwrap();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    dev
+}
+
+fn receive(c: &mut Criterion) {
+    let mut dev = device();
+    let n = dev.packet(0).unwrap().num() as u64;
+
+    let mut g = c.benchmark_group("receive");
+    g.throughput(Throughput::Elements(n));
+    g.bench_function("packet_consume", |b| {
+        b.iter(|| {
+            let p = dev.packet(0).unwrap();
+            std::hint::black_box(p.samples());
+            dev.consume(0).unwrap();
+        })
+    });
+    g.bench_function("owned_packet", |b| {
+        b.iter(|| {
+            let p = OwnedPacket::from(&dev.packet(0).unwrap());
+            dev.consume(0).unwrap();
+            p
+        })
+    });
+    g.finish();
+}
+
+fn encode(c: &mut Criterion) {
+    let mut dev = device();
+    let samples = dev.packet(0).unwrap().samples().to_vec();
+    let mut out = Vec::new();
+
+    let mut g = c.benchmark_group("encode");
+    g.throughput(Throughput::Elements(samples.len() as u64));
+    for format in [SampleFormat::F32, SampleFormat::I16, SampleFormat::I8] {
+        g.bench_function(format.to_string(), |b| {
+            b.iter(|| {
+                out.clear();
+                format.encode(&samples, &mut out)
+            })
+        });
+    }
+    g.finish();
+}
+
+criterion_group!(benches, receive, encode);
+criterion_main!(benches);
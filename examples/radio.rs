@@ -0,0 +1,49 @@
+//! Minimal FM broadcast receiver, writing audio to stdout.
+//!
+//! `cargo run --release --example radio -- 99.9e6 | aplay -f FLOAT_LE -r 48000`
+use aaronia_rtsa::demod::Demodulator;
+use aaronia_rtsa::demod::Mode;
+use aaronia_rtsa::ApiHandle;
+use std::io::Write;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let frequency: f64 = std::env::args()
+        .nth(1)
+        .map(|f| f.parse())
+        .transpose()?
+        .unwrap_or(99.9e6);
+
+    let mut api = ApiHandle::new()?;
+    api.rescan_devices()?;
+    let mut dev = api.get_device()?;
+    dev.open()?;
+    dev.set("device/receiverchannel", "Rx1")?;
+    dev.set("device/outputformat", "iq")?;
+    dev.set("device/receiverclock", "92MHz")?;
+    dev.set("main/decimation", "1 / 64")?;
+    dev.set_float("main/centerfreq", frequency)?;
+    dev.set_float("main/reflevel", -30.0)?;
+    dev.connect()?;
+    dev.start()?;
+
+    let p = dev.packet(0)?;
+    let rate = p.sample_rate().ok_or("unknown sample rate")?;
+    let mut demod = Demodulator::new(Mode::Wfm, rate, 48e3);
+    eprintln!(
+        "sample rate {rate} Hz, audio rate {} Hz",
+        demod.audio_rate()
+    );
+
+    let mut stdout = std::io::stdout().lock();
+    let mut audio = Vec::new();
+    loop {
+        let p = dev.packet(0)?;
+        audio.clear();
+        demod.process_packet(&p, &mut audio);
+        dev.consume(0)?;
+
+        for a in &audio {
+            stdout.write_all(&a.to_le_bytes())?;
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! AM/FM/SSB receiver, playing audio on the default output device.
+//!
+//! `cargo run --release --example receiver --features audio -- 99.9e6 wfm`
+//!
+//! Once per second, the latency of the streaming path is printed, i.e., the age of the packets
+//! in the device queue and the audio latency from the demodulator to the speaker.
+use aaronia_rtsa::audio::AudioSink;
+use aaronia_rtsa::demod::Demodulator;
+use aaronia_rtsa::demod::Mode;
+use aaronia_rtsa::ApiHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let frequency: f64 = args
+        .next()
+        .map(|f| f.parse())
+        .transpose()?
+        .unwrap_or(99.9e6);
+    let mode = match args.next().as_deref() {
+        None | Some("wfm") => Mode::Wfm,
+        Some("nfm") => Mode::Nfm,
+        Some("am") => Mode::Am,
+        Some("usb") => Mode::Usb,
+        Some("lsb") => Mode::Lsb,
+        Some(m) => return Err(format!("unknown mode {m}, use wfm, nfm, am, usb, or lsb").into()),
+    };
+    let decimation = match mode {
+        Mode::Wfm => "1 / 64",
+        _ => "1 / 512",
+    };
+
+    let mut api = ApiHandle::new()?;
+    api.rescan_devices()?;
+    let mut dev = api.get_device()?;
+    dev.open()?;
+    dev.set("device/receiverchannel", "Rx1")?;
+    dev.set("device/outputformat", "iq")?;
+    dev.set("device/receiverclock", "92MHz")?;
+    dev.set("main/decimation", decimation)?;
+    dev.set_float("main/centerfreq", frequency)?;
+    dev.set_float("main/reflevel", -30.0)?;
+    dev.connect()?;
+
+    let mut sink = AudioSink::new(48e3)?;
+    let info = dev.start_and_wait(Duration::from_secs(2), |_| {})?;
+    let rate = info.sample_rate.ok_or("unknown sample rate")?;
+    let mut demod = Demodulator::new(mode, rate, sink.sample_rate());
+    eprintln!(
+        "{mode:?} at {frequency} Hz, sample rate {rate} Hz, audio rate {} Hz, startup {:?}",
+        sink.sample_rate(),
+        info.startup
+    );
+
+    let mut audio = Vec::new();
+    let mut report = Instant::now();
+    loop {
+        let p = dev.packet(0)?;
+        audio.clear();
+        demod.process_packet(&p, &mut audio);
+        let queue = dev.clock()? - p.end_time();
+        dev.consume(0)?;
+        sink.write(&audio);
+
+        if report.elapsed() >= Duration::from_secs(1) {
+            report = Instant::now();
+            eprintln!(
+                "latency: queue {:.1} ms, audio {:.1} ms, underruns {}, dropped {}",
+                queue * 1e3,
+                sink.latency().as_secs_f64() * 1e3,
+                sink.underruns(),
+                sink.dropped()
+            );
+        }
+    }
+}
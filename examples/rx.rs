@@ -1,7 +1,9 @@
+use aaronia_rtsa::render::Spectrogram;
+use aaronia_rtsa::render::Waterfall;
 use aaronia_rtsa::version;
 use aaronia_rtsa::ApiHandle;
 use aaronia_rtsa::Device;
-use num_complex::Complex32;
+use aaronia_rtsa::SpectrumView;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("RTSA library version: {}", version());
@@ -22,47 +24,57 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     dev.connect()?;
     dev.start()?;
 
-    let mut s = rx(&mut dev)?;
+    let waterfall = rx(&mut dev)?;
 
     dev.stop()?;
     dev.disconnect()?;
     dev.close()?;
 
-    plot(&mut s);
+    Spectrogram::new().render(&waterfall).save_png("rx.png")?;
+    println!("spectrogram written to rx.png");
 
     Ok(())
 }
 
-const N: usize = 8192 * 2;
-fn rx(dev: &mut Device) -> Result<[Complex32; N], aaronia_rtsa::Error> {
-    let mut samples = [Complex32::new(0.0, 0.0); N];
-    let mut i = 0;
+const FFT: usize = 1024;
+const ROWS: usize = 256;
+fn rx(dev: &mut Device) -> Result<Waterfall, aaronia_rtsa::Error> {
+    let fft = rustfft::FftPlanner::new().plan_fft_forward(FFT);
+    let mut waterfall = Waterfall::new(ROWS);
+    let mut buf = Vec::with_capacity(FFT);
+    let mut time = 0.0;
 
-    while i < N {
+    while waterfall.len() < ROWS {
         let p = dev.packet(0)?;
-        let cur = p.samples();
-        let n = std::cmp::min(N - i, cur.len());
-        samples[i..i + n].copy_from_slice(&cur[0..n]);
-        i += n;
+        let rate = p.sample_rate().unwrap_or(1.0);
+        for (i, s) in p.samples().iter().enumerate() {
+            if buf.is_empty() {
+                time = p.start_time() + i as f64 / rate;
+            }
+            buf.push(*s);
+            if buf.len() == FFT {
+                fft.process(&mut buf);
+                // shift the zero frequency to the center
+                buf.rotate_left(FFT / 2);
+                let db: Vec<f32> = buf
+                    .iter()
+                    .map(|s| 10.0 * (s.norm_sqr() / FFT as f32).log10())
+                    .collect();
+                let step = rate / FFT as f64;
+                waterfall.push(
+                    time,
+                    &SpectrumView {
+                        start_frequency: p.start_frequency() - rate / 2.0,
+                        step_frequency: step,
+                        rbw_frequency: step,
+                        data: &db,
+                    },
+                );
+                buf.clear();
+            }
+        }
         dev.consume(0)?;
     }
 
-    Ok(samples)
-}
-
-fn plot(s: &mut [num_complex::Complex32]) {
-    use gnuplot::*;
-
-    let mut planner = rustfft::FftPlanner::new();
-    planner.plan_fft_forward(s.len()).process(s);
-
-    let abs = s.iter().map(|s| s.norm_sqr().log10());
-
-    let mut fg = Figure::new();
-    fg.axes2d().set_title("Spectrum", &[]).lines(
-        0..s.len(),
-        abs,
-        &[LineWidth(3.0), Color("blue"), LineStyle(DotDash)],
-    );
-    fg.show().unwrap();
+    Ok(waterfall)
 }
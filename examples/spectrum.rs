@@ -1,3 +1,5 @@
+use aaronia_rtsa::render::Spectrogram;
+use aaronia_rtsa::render::Waterfall;
 use aaronia_rtsa::version;
 use aaronia_rtsa::ApiHandle;
 use aaronia_rtsa::Device;
@@ -22,32 +24,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     dev.connect()?;
     dev.start()?;
 
-    let s = rx(&mut dev)?;
+    let waterfall = rx(&mut dev)?;
 
     dev.stop()?;
     dev.disconnect()?;
     dev.close()?;
 
-    plot(&s);
+    Spectrogram::new()
+        .render(&waterfall)
+        .save_png("spectrum.png")?;
+    println!("spectrogram written to spectrum.png");
 
     Ok(())
 }
 
-fn rx(dev: &mut Device) -> Result<Vec<f32>, aaronia_rtsa::Error> {
-    let p = dev.packet(2)?;
-    let cur = Vec::from(p.spectrum());
-    dev.consume(2)?;
-    Ok(cur)
-}
-
-fn plot(s: &Vec<f32>) {
-    use gnuplot::*;
-
-    let mut fg = Figure::new();
-    fg.axes2d().set_title("Spectrum", &[]).lines(
-        0..s.len(),
-        s.iter(),
-        &[LineWidth(3.0), Color("blue"), LineStyle(DotDash)],
-    );
-    fg.show().unwrap();
+const ROWS: usize = 200;
+fn rx(dev: &mut Device) -> Result<Waterfall, aaronia_rtsa::Error> {
+    let mut waterfall = Waterfall::new(ROWS);
+    while waterfall.len() < ROWS {
+        let p = dev.packet(2)?;
+        waterfall.push_packet(&p);
+        dev.consume(2)?;
+    }
+    Ok(waterfall)
 }
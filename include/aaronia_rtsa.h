@@ -0,0 +1,215 @@
+#ifndef AARONIA_RTSA_H
+#define AARONIA_RTSA_H
+
+/* Generated with cbindgen from src/capi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of the functions of the C interface.
+typedef enum RtsaStatus {
+  // Success.
+  RTSA_STATUS_OK = 0,
+  // No packet became available, before the timeout.
+  RTSA_STATUS_EMPTY = 1,
+  // Error of the RTSA library or the device, see `rtsa_last_error()`.
+  RTSA_STATUS_ERROR = -1,
+  // Null pointer or invalid string argument.
+  RTSA_STATUS_INVALID_ARGUMENT = -2,
+  // Panic of the Rust implementation.
+  RTSA_STATUS_PANIC = -3,
+} RtsaStatus;
+
+// Memory size of the RTSA library, see [`Memory`].
+typedef enum RtsaMemory {
+  RTSA_MEMORY_SMALL,
+  RTSA_MEMORY_MEDIUM,
+  RTSA_MEMORY_LARGE,
+  RTSA_MEMORY_LUDICROUS,
+} RtsaMemory;
+
+// Library handle, see [`ApiHandle`].
+typedef struct RtsaApi RtsaApi;
+
+// Device handle, see [`Device`].
+typedef struct RtsaDevice RtsaDevice;
+
+// Metadata and payload of a packet, filled by `rtsa_device_packet()`.
+//
+// The payload points into the packet buffer of the RTSA library, i.e., it is valid, until the
+// packet is consumed.
+typedef struct RtsaPacket {
+  // Device clock time of the first sample in seconds.
+  double start_time;
+  // Device clock time after the last sample in seconds.
+  double end_time;
+  // Start frequency in Hz, i.e., the center frequency of IQ packets.
+  double start_frequency;
+  // Frequency step in Hz, i.e., the sample rate of IQ packets or the bin width of spectra.
+  double step_frequency;
+  // Span in Hz.
+  double span_frequency;
+  // Number of IQ samples or spectrum bins.
+  size_t num;
+  // Interleaved IQ samples (`2 * num` floats) or the bins of the first spectrum in dBm (`num`
+  // floats).
+  const float *samples;
+} RtsaPacket;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Copy the message of the last error of the calling thread as null-terminated string.
+//
+// Returns the length of the message without the terminator. The message is truncated to
+// `len - 1` bytes. Pass a null buffer to query the length.
+//
+// # Safety
+//
+// `buf` has to be null or valid for writes of `len` bytes.
+size_t rtsa_last_error(char *buf, size_t len);
+
+// Create a library handle, see [`ApiHandle::with_mem()`].
+//
+// # Safety
+//
+// `api` has to be valid for writes.
+enum RtsaStatus rtsa_api_new(enum RtsaMemory memory, struct RtsaApi **api);
+
+// Release a library handle. Devices keep the library initialized, until they are released.
+//
+// # Safety
+//
+// `api` has to be null or created by `rtsa_api_new()` and not released before.
+void rtsa_api_free(struct RtsaApi *api);
+
+// Rescan for devices, see [`ApiHandle::rescan_devices()`].
+//
+// # Safety
+//
+// `api` has to be null or a valid library handle.
+enum RtsaStatus rtsa_api_rescan(struct RtsaApi *api);
+
+// Query the number of detected devices.
+//
+// # Safety
+//
+// `api` has to be null or a valid library handle and `count` valid for writes.
+enum RtsaStatus rtsa_api_device_count(struct RtsaApi *api, size_t *count);
+
+// Create a handle for the detected device with the given index.
+//
+// # Safety
+//
+// `api` has to be null or a valid library handle and `dev` valid for writes.
+enum RtsaStatus rtsa_device_new(struct RtsaApi *api, size_t index, struct RtsaDevice **dev);
+
+// Release a device handle, stopping, disconnecting, and closing the device as needed.
+//
+// # Safety
+//
+// `dev` has to be null or created by `rtsa_device_new()` and not released before.
+void rtsa_device_free(struct RtsaDevice *dev);
+
+// Open the device, see [`Device::open()`].
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle.
+enum RtsaStatus rtsa_device_open(struct RtsaDevice *dev);
+
+// Connect the device, see [`Device::connect()`].
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle.
+enum RtsaStatus rtsa_device_connect(struct RtsaDevice *dev);
+
+// Start the device, see [`Device::start()`].
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle.
+enum RtsaStatus rtsa_device_start(struct RtsaDevice *dev);
+
+// Stop the device, see [`Device::stop()`].
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle.
+enum RtsaStatus rtsa_device_stop(struct RtsaDevice *dev);
+
+// Disconnect the device, see [`Device::disconnect()`].
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle.
+enum RtsaStatus rtsa_device_disconnect(struct RtsaDevice *dev);
+
+// Close the device, see [`Device::close()`].
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle.
+enum RtsaStatus rtsa_device_close(struct RtsaDevice *dev);
+
+// Reconnect a lost device and restore its configuration, see [`Device::recover()`].
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle.
+enum RtsaStatus rtsa_device_recover(struct RtsaDevice *dev);
+
+// Set a configuration parameter as string, e.g., the option of an enum parameter.
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle, `path` and `value` null or null-terminated.
+enum RtsaStatus rtsa_device_set(struct RtsaDevice *dev, const char *path, const char *value);
+
+// Set a number parameter.
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle, `path` null or null-terminated.
+enum RtsaStatus rtsa_device_set_float(struct RtsaDevice *dev, const char *path, double value);
+
+// Get a number parameter.
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle, `path` null or null-terminated, and `value`
+// valid for writes.
+enum RtsaStatus rtsa_device_get_float(struct RtsaDevice *dev, const char *path, double *value);
+
+// Get the packet at the head of the queue of a data channel, waiting at most `timeout_ms`.
+//
+// Polls according to the [`PollStrategy`](crate::PollStrategy) of the device. Returns
+// [`RtsaStatus::Empty`], if no packet became available in time; a timeout of zero does not
+// wait. The packet stays at the head of the queue, until it is consumed with
+// `rtsa_device_consume()`.
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle and `packet` valid for writes.
+enum RtsaStatus rtsa_device_packet(struct RtsaDevice *dev,
+                                   int32_t chan,
+                                   uint32_t timeout_ms,
+                                   struct RtsaPacket *packet);
+
+// Consume the packet at the head of the queue of a data channel, see [`Device::consume()`].
+//
+// # Safety
+//
+// `dev` has to be null or a valid device handle.
+enum RtsaStatus rtsa_device_consume(struct RtsaDevice *dev, int32_t chan);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* AARONIA_RTSA_H */
@@ -0,0 +1,139 @@
+//! Automatic adjustment of the reference level.
+use num_complex::Complex32;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::ConfigItem;
+use crate::Device;
+use crate::Error;
+use crate::RxChannel;
+
+/// Adjust the reference level of a receiver automatically, based on the sample magnitudes and
+/// overload indications of the health tree.
+///
+/// Samples are passed to [`update()`](Agc::update), which tracks the peak power. Once per
+/// `interval`, the reference level is adjusted to keep the peak `headroom` dB below full scale.
+/// Adjustments within `hysteresis` dB are skipped to avoid constant retuning. If samples clip or
+/// the health tree reports an overload, the reference level is raised by `step` dB.
+#[derive(Debug, Clone)]
+pub struct Agc {
+    /// Minimum reference level in dBm.
+    pub min_reflevel: f64,
+    /// Maximum reference level in dBm.
+    pub max_reflevel: f64,
+    /// Target distance of the peak power to full scale in dB (default: 10).
+    pub headroom: f32,
+    /// Tolerated deviation from the target in dB (default: 3).
+    pub hysteresis: f32,
+    /// Increase of the reference level on overload in dB (default: 10).
+    pub step: f64,
+    /// Sample magnitude, considered as clipping (default: 0.98).
+    pub clip_level: f32,
+    /// Minimum time between adjustments (default: 100ms).
+    pub interval: Duration,
+    /// Check the health tree for overload indications (default: true).
+    pub check_health: bool,
+    /// Receiver, whose reference level is adjusted (default: Rx1).
+    pub channel: RxChannel,
+    peak: f32,
+    clipped: bool,
+    last: Option<Instant>,
+}
+
+impl Agc {
+    /// Create an AGC that keeps the reference level within the given bounds.
+    pub fn new(min_reflevel: f64, max_reflevel: f64) -> Self {
+        assert!(min_reflevel <= max_reflevel);
+        Self {
+            min_reflevel,
+            max_reflevel,
+            headroom: 10.0,
+            hysteresis: 3.0,
+            step: 10.0,
+            clip_level: 0.98,
+            interval: Duration::from_millis(100),
+            check_health: true,
+            channel: RxChannel::Rx1,
+            peak: 0.0,
+            clipped: false,
+            last: None,
+        }
+    }
+
+    /// Track the samples and adjust the reference level, if the interval has elapsed.
+    ///
+    /// Returns the new reference level, read back from the device, if it was changed.
+    pub fn update(
+        &mut self,
+        dev: &mut Device,
+        samples: &[Complex32],
+    ) -> std::result::Result<Option<f64>, Error> {
+        let Some(adjustment) = self.adjustment(dev, samples)? else {
+            return Ok(None);
+        };
+        let current = dev.rx_gain(self.channel)?.reflevel;
+        let target = (current + adjustment)
+            .clamp(self.min_reflevel, self.max_reflevel)
+            .round();
+
+        if target == current {
+            return Ok(None);
+        }
+        dev.set_rx_reflevel(self.channel, target).map(Some)
+    }
+
+    /// Track the samples and compute the adjustment, if the interval has elapsed, without
+    /// changing the device.
+    ///
+    /// Returns the increase of the reference level in dB, i.e., the gain reduction, that keeps
+    /// the peak at the target. Applications with their own gain control loop can apply it to
+    /// other stages, e.g., with [`Device::set_rx_attenuation()`].
+    pub fn adjustment(
+        &mut self,
+        dev: &mut Device,
+        samples: &[Complex32],
+    ) -> std::result::Result<Option<f64>, Error> {
+        let clip = self.clip_level * self.clip_level;
+        for s in samples {
+            let p = s.norm_sqr();
+            self.peak = self.peak.max(p);
+            self.clipped |= p >= clip;
+        }
+
+        let now = Instant::now();
+        match self.last {
+            Some(t) if now.duration_since(t) < self.interval => return Ok(None),
+            None => {
+                self.last = Some(now);
+                return Ok(None);
+            }
+            _ => {}
+        }
+        self.last = Some(now);
+
+        let overload = self.clipped || (self.check_health && Self::overload(dev)?);
+        let peak_dbfs = 10.0 * self.peak.log10();
+        self.peak = 0.0;
+        self.clipped = false;
+
+        if overload {
+            return Ok(Some(self.step));
+        }
+        let deviation = peak_dbfs + self.headroom;
+        if !deviation.is_finite() || deviation.abs() <= self.hysteresis {
+            return Ok(None);
+        }
+        Ok(Some(deviation as f64))
+    }
+
+    /// Check the health tree for active overload indications.
+    fn overload(dev: &mut Device) -> std::result::Result<bool, Error> {
+        Ok(dev.health_leaves()?.iter().any(|(path, item)| {
+            let path = path.to_lowercase();
+            let relevant = ["overload", "overflow", "clip"]
+                .iter()
+                .any(|k| path.contains(k));
+            relevant && matches!(item, ConfigItem::Bool(true))
+        }))
+    }
+}
@@ -0,0 +1,99 @@
+//! Host-side aggregation of spectra across arbitrary time windows.
+use crate::Packet;
+use crate::Spectrum;
+
+/// Aggregate spectra into max-hold, min-hold, and exponential-average traces.
+///
+/// All spectra have to have the same frequency axis. If the number of bins changes, the
+/// aggregator is reset. The average is computed on linear power and converted back to dB.
+#[derive(Debug, Clone)]
+pub struct Aggregator {
+    alpha: f32,
+    max: Option<Spectrum>,
+    min: Option<Spectrum>,
+    avg: Option<Spectrum>,
+    count: usize,
+}
+
+impl Aggregator {
+    /// Create an aggregator, where `alpha` is the weight of a new spectrum in the average.
+    pub fn new(alpha: f32) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0);
+        Self {
+            alpha,
+            max: None,
+            min: None,
+            avg: None,
+            count: 0,
+        }
+    }
+
+    /// Add the spectrum of a packet.
+    pub fn push_packet(&mut self, packet: &Packet) {
+        self.push(&packet.to_spectrum());
+    }
+
+    /// Add a spectrum.
+    pub fn push(&mut self, spectrum: &Spectrum) {
+        if self
+            .max
+            .as_ref()
+            .is_some_and(|m| m.data.len() != spectrum.data.len())
+        {
+            self.reset();
+        }
+
+        match (&mut self.max, &mut self.min, &mut self.avg) {
+            (Some(max), Some(min), Some(avg)) => {
+                for (i, v) in spectrum.data.iter().enumerate() {
+                    max.data[i] = max.data[i].max(*v);
+                    min.data[i] = min.data[i].min(*v);
+                    avg.data[i] += self.alpha * (10f32.powf(*v / 10.0) - avg.data[i]);
+                }
+                for t in [max, min, avg] {
+                    t.end_time = spectrum.end_time;
+                }
+            }
+            _ => {
+                self.max = Some(spectrum.clone());
+                self.min = Some(spectrum.clone());
+                let mut avg = spectrum.clone();
+                avg.data.iter_mut().for_each(|v| *v = 10f32.powf(*v / 10.0));
+                self.avg = Some(avg);
+            }
+        }
+        self.count += 1;
+    }
+
+    /// Max-hold trace.
+    pub fn max_hold(&self) -> Option<&Spectrum> {
+        self.max.as_ref()
+    }
+
+    /// Min-hold trace.
+    pub fn min_hold(&self) -> Option<&Spectrum> {
+        self.min.as_ref()
+    }
+
+    /// Exponential average trace.
+    pub fn average(&self) -> Option<Spectrum> {
+        self.avg.as_ref().map(|a| {
+            let mut s = a.clone();
+            s.data.iter_mut().for_each(|v| *v = 10.0 * v.log10());
+            s
+        })
+    }
+
+    /// Number of spectra since the last reset.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Clear all traces.
+    pub fn reset(&mut self) {
+        self.max = None;
+        self.min = None;
+        self.avg = None;
+        self.count = 0;
+    }
+}
@@ -0,0 +1,169 @@
+//! Audio output through [cpal](https://docs.rs/cpal), enabled with the `audio` feature.
+//!
+//! [`AudioSink`] plays the output of a [`Demodulator`](crate::demod::Demodulator) on the
+//! default output device:
+//!
+//! - create the sink and the demodulator with the rate of the sink, see
+//!   [`AudioSink::sample_rate()`]
+//! - demodulate packets and [`write()`](AudioSink::write) the audio to the sink
+//!
+//! The sink buffers samples between the receive loop and the audio callback. Since the device
+//! clock and the audio clock drift, the buffer is bounded by [`AudioSink::max_latency`]; excess
+//! samples are dropped and the callback plays silence, if the buffer runs empty.
+use cpal::traits::DeviceTrait;
+use cpal::traits::HostTrait;
+use cpal::traits::StreamTrait;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::Error;
+
+/// State, shared with the audio callback.
+#[derive(Debug, Default)]
+struct Shared {
+    buffer: Mutex<VecDeque<f32>>,
+    underruns: AtomicU64,
+    dropped: AtomicU64,
+    /// Delay from the callback to the playback in µs, reported by the backend.
+    output_delay: AtomicU64,
+}
+
+/// Mono audio output on the default output device.
+///
+/// The stream of the backend is not `Send` on all platforms, i.e., the sink has to stay on the
+/// thread that created it.
+pub struct AudioSink {
+    /// Maximum buffered audio, before samples are dropped (default: 200ms).
+    pub max_latency: Duration,
+    sample_rate: f64,
+    shared: Arc<Shared>,
+    _stream: cpal::Stream,
+}
+
+fn audio_error<E: std::fmt::Display>(e: E) -> Error {
+    Error::Audio(e.to_string())
+}
+
+impl AudioSink {
+    /// Open the default output device with the supported sample rate closest to `sample_rate`.
+    ///
+    /// Returns [`Error::Audio`], if there is no output device or it does not support 32-bit
+    /// float samples.
+    pub fn new(sample_rate: f64) -> std::result::Result<Self, Error> {
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or_else(|| Error::Audio("no output device".to_string()))?;
+        let target = sample_rate.round() as u32;
+        let config = device
+            .supported_output_configs()
+            .map_err(audio_error)?
+            .filter(|c| c.sample_format() == cpal::SampleFormat::F32)
+            .map(|c| {
+                let rate = target.clamp(c.min_sample_rate().0, c.max_sample_rate().0);
+                c.with_sample_rate(cpal::SampleRate(rate))
+            })
+            .min_by_key(|c| (c.sample_rate().0.abs_diff(target), c.channels()))
+            .ok_or_else(|| Error::Audio("no output configuration with f32 samples".to_string()))?;
+        let config: cpal::StreamConfig = config.into();
+        let channels = config.channels as usize;
+
+        let shared = Arc::new(Shared::default());
+        let cb = shared.clone();
+        let stream = device
+            .build_output_stream(
+                &config,
+                move |data: &mut [f32], info: &cpal::OutputCallbackInfo| {
+                    let t = info.timestamp();
+                    if let Some(d) = t.playback.duration_since(&t.callback) {
+                        cb.output_delay
+                            .store(d.as_micros() as u64, Ordering::Relaxed);
+                    }
+                    let mut buffer = cb.buffer.lock().unwrap();
+                    let mut underrun = false;
+                    for frame in data.chunks_mut(channels) {
+                        let s = buffer.pop_front().unwrap_or_else(|| {
+                            underrun = true;
+                            0.0
+                        });
+                        frame.fill(s);
+                    }
+                    if underrun {
+                        cb.underruns.fetch_add(1, Ordering::Relaxed);
+                    }
+                },
+                |_e| {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %_e, "audio stream error");
+                },
+                None,
+            )
+            .map_err(audio_error)?;
+        stream.play().map_err(audio_error)?;
+
+        Ok(Self {
+            max_latency: Duration::from_millis(200),
+            sample_rate: config.sample_rate.0 as f64,
+            shared,
+            _stream: stream,
+        })
+    }
+
+    /// Sample rate of the output device.
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Queue mono samples for playback.
+    ///
+    /// If the buffered audio exceeds [`max_latency`](Self::max_latency), the oldest samples
+    /// are dropped.
+    pub fn write(&mut self, samples: &[f32]) {
+        let max = (self.max_latency.as_secs_f64() * self.sample_rate) as usize;
+        let mut buffer = self.shared.buffer.lock().unwrap();
+        buffer.extend(samples);
+        let excess = buffer.len().saturating_sub(max);
+        if excess > 0 {
+            buffer.drain(..excess);
+            self.shared
+                .dropped
+                .fetch_add(excess as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Duration of the buffered audio.
+    pub fn buffered(&self) -> Duration {
+        let n = self.shared.buffer.lock().unwrap().len();
+        Duration::from_secs_f64(n as f64 / self.sample_rate)
+    }
+
+    /// Latency from [`write()`](Self::write) to playback, i.e., the buffered audio and the delay
+    /// of the backend, as reported by the last callback.
+    pub fn latency(&self) -> Duration {
+        self.buffered() + Duration::from_micros(self.shared.output_delay.load(Ordering::Relaxed))
+    }
+
+    /// Number of callbacks that ran out of samples and played silence.
+    pub fn underruns(&self) -> u64 {
+        self.shared.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Number of samples, dropped since the buffer exceeded the maximum latency.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl std::fmt::Debug for AudioSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AudioSink")
+            .field("max_latency", &self.max_latency)
+            .field("sample_rate", &self.sample_rate)
+            .field("shared", &self.shared)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,276 @@
+use aaronia_rtsa::io::Cf32Writer;
+use aaronia_rtsa::io::IqWriter;
+use aaronia_rtsa::profiles;
+use aaronia_rtsa::profiles::ProfileStore;
+use aaronia_rtsa::version;
+use aaronia_rtsa::ApiHandle;
+use aaronia_rtsa::ConfigValue;
+use aaronia_rtsa::Device;
+use clap::Parser;
+use clap::Subcommand;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Command-line utility for Aaronia Spectran devices")]
+struct Args {
+    /// Serial number of the device (default: first detected device)
+    #[arg(short, long, global = true)]
+    device: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List detected devices
+    List,
+    /// Show device configuration and health
+    Info,
+    /// Get or set configuration parameters
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Manage named configuration profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    /// Receive IQ samples into a file with interleaved little-endian f32 (cf32)
+    Rx {
+        /// Output file
+        #[arg(short, long)]
+        out: PathBuf,
+        /// Center frequency in Hz
+        #[arg(short, long, default_value_t = 2.45e9)]
+        frequency: f64,
+        /// Reference level in dBm
+        #[arg(short, long, default_value_t = -20.0)]
+        reflevel: f64,
+        /// Decimation (e.g., "1 / 64")
+        #[arg(long, default_value = "1 / 64")]
+        decimation: String,
+        /// Number of samples
+        #[arg(short, long, default_value_t = 1_000_000)]
+        samples: usize,
+    },
+    /// Receive a spectrum and print it or render it as PNG
+    Spectrum {
+        /// Output image
+        #[arg(long)]
+        png: Option<PathBuf>,
+        /// Center frequency in Hz
+        #[arg(short, long, default_value_t = 2.45e9)]
+        frequency: f64,
+        /// Reference level in dBm
+        #[arg(short, long, default_value_t = -20.0)]
+        reflevel: f64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print the value of a configuration parameter
+    Get { path: String },
+    /// Set a configuration parameter and print the new value
+    Set { path: String, value: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfileCommand {
+    /// List stored profiles
+    List,
+    /// Print the entries of a profile
+    Show { name: String },
+    /// Save the device configuration as profile
+    Save { name: String },
+    /// Apply a profile to the device and print the parameters that did not take effect
+    Apply { name: String },
+    /// Remove a profile
+    Remove { name: String },
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    let mut api = ApiHandle::new()?;
+    api.rescan_devices()?;
+
+    if let Command::List = args.command {
+        println!("RTSA library version: {}", version());
+        for d in api.devices()? {
+            println!("{d:?}");
+        }
+        return Ok(());
+    }
+
+    if let Command::Profile { command } = &args.command {
+        let store = ProfileStore::user()?;
+        match command {
+            ProfileCommand::List => {
+                for name in store.list()? {
+                    println!("{name}");
+                }
+                return Ok(());
+            }
+            ProfileCommand::Show { name } => {
+                print!("{}", profiles::format(&store.load(name)?));
+                return Ok(());
+            }
+            ProfileCommand::Remove { name } => {
+                store.remove(name)?;
+                return Ok(());
+            }
+            ProfileCommand::Save { .. } | ProfileCommand::Apply { .. } => {}
+        }
+    }
+
+    let mut dev = match &args.device {
+        Some(serial) => {
+            let info = api
+                .devices()?
+                .into_iter()
+                .find(|d| d.serial() == *serial)
+                .ok_or(aaronia_rtsa::Error::ErrorNotFound)?;
+            api.get_this_device(&info)?
+        }
+        None => api.get_device()?,
+    };
+    dev.open()?;
+
+    match args.command {
+        Command::List => unreachable!(),
+        Command::Info => {
+            dev.print_config()?;
+            dev.print_health()?;
+        }
+        Command::Config { command } => match command {
+            ConfigCommand::Get { path } => println!("{}", value(&mut dev, &path)?),
+            ConfigCommand::Set { path, value: v } => {
+                dev.set(&path, v)?;
+                println!("{}", value(&mut dev, &path)?);
+            }
+        },
+        Command::Profile { command } => match command {
+            ProfileCommand::Save { name } => {
+                dev.save_profile(&name)?;
+                println!("{}", ProfileStore::user()?.path(&name)?.display());
+            }
+            ProfileCommand::Apply { name } => {
+                dev.apply_profile(&name)?;
+                let profile = ProfileStore::user()?.load(&name)?;
+                for change in dev.diff_against(&profile)? {
+                    println!("{change}");
+                }
+            }
+            _ => unreachable!(),
+        },
+        Command::Rx {
+            out,
+            frequency,
+            reflevel,
+            decimation,
+            samples,
+        } => {
+            dev.set("device/receiverchannel", "Rx1")?;
+            dev.set("device/outputformat", "iq")?;
+            dev.set("main/decimation", decimation)?;
+            dev.set_float("main/centerfreq", frequency)?;
+            dev.set_float("main/reflevel", reflevel)?;
+            dev.connect()?;
+            dev.start()?;
+
+            let mut w = Cf32Writer::create(out)?;
+            let mut n = 0;
+            while n < samples {
+                let p = dev.packet(0)?;
+                let s = p.samples();
+                let s = &s[..std::cmp::min(s.len(), samples - n)];
+                w.write(s)?;
+                n += s.len();
+                dev.consume(0)?;
+            }
+            w.finish()?;
+
+            dev.stop()?;
+            dev.disconnect()?;
+        }
+        Command::Spectrum {
+            png,
+            frequency,
+            reflevel,
+        } => {
+            dev.set("device/receiverchannel", "Rx1")?;
+            dev.set("device/outputformat", "spectra")?;
+            dev.set_float("main/centerfreq", frequency)?;
+            dev.set_float("main/reflevel", reflevel)?;
+            dev.connect()?;
+            dev.start()?;
+
+            let p = dev.packet(2)?;
+            let s = p.to_spectrum();
+            dev.consume(2)?;
+
+            dev.stop()?;
+            dev.disconnect()?;
+
+            match png {
+                Some(path) => render(&s.data, &path)?,
+                None => {
+                    for (i, v) in s.data.iter().enumerate() {
+                        println!("{}\t{}", s.start_frequency + i as f64 * s.step_frequency, v);
+                    }
+                }
+            }
+        }
+    }
+
+    dev.close()?;
+    Ok(())
+}
+
+fn value(dev: &mut Device, path: &str) -> Result<String, aaronia_rtsa::Error> {
+    let item = dev.get(path)?;
+    Ok(match ConfigValue::from_item(&item) {
+        Some(v) => v.to_string(),
+        None => format!("{item:?}"),
+    })
+}
+
+const WIDTH: usize = 1024;
+const HEIGHT: usize = 512;
+
+/// Render the spectrum as white trace on black background.
+fn render(data: &[f32], path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    let mut img = vec![0u8; WIDTH * HEIGHT];
+
+    let min = data.iter().cloned().fold(f32::INFINITY, f32::min);
+    let max = data.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let range = (max - min).max(1e-6);
+
+    let mut last: Option<usize> = None;
+    for x in 0..WIDTH {
+        let i = x * data.len() / WIDTH;
+        let Some(v) = data.get(i) else {
+            break;
+        };
+        let y = ((max - v) / range * (HEIGHT - 1) as f32) as usize;
+        let (lo, hi) = match last {
+            Some(l) => (l.min(y), l.max(y)),
+            None => (y, y),
+        };
+        for row in lo..=hi {
+            img[row * WIDTH + x] = 255;
+        }
+        last = Some(y);
+    }
+
+    let w = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(w, WIDTH as u32, HEIGHT as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&img)?;
+
+    Ok(())
+}
@@ -0,0 +1,370 @@
+//! Live spectrum and waterfall viewer.
+//!
+//! `cargo run --release --bin aaronia-viewer --features viewer -- [serial]`
+//!
+//! A receive thread owns the device, streams spectra, and applies configuration changes from the
+//! controls while the device is running.
+use aaronia_rtsa::config_path;
+use aaronia_rtsa::render::Colormap;
+use aaronia_rtsa::render::Spectrogram;
+use aaronia_rtsa::render::Waterfall;
+use aaronia_rtsa::ApiHandle;
+use aaronia_rtsa::ConfigPath;
+use aaronia_rtsa::ConfigValue;
+use aaronia_rtsa::Device;
+use aaronia_rtsa::Error;
+use aaronia_rtsa::OutputFormat;
+use aaronia_rtsa::Spectrum;
+use eframe::egui;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+const CENTER_FREQUENCY: ConfigPath = config_path!("main/centerfreq");
+const REFERENCE_LEVEL: ConfigPath = config_path!("main/reflevel");
+const FFT_SIZE: ConfigPath = config_path!("device/fft0/fftsize");
+
+/// Spectra data channel.
+const CHANNEL: i32 = 2;
+/// Number of spectra in the waterfall.
+const ROWS: usize = 300;
+/// FFT sizes, offered by the controls.
+const FFT_SIZES: [u32; 7] = [256, 512, 1024, 2048, 4096, 8192, 16384];
+
+/// State, shared between the receive thread and the UI.
+struct Shared {
+    spectrum: Option<Spectrum>,
+    waterfall: Waterfall,
+    packets: u64,
+    error: Option<String>,
+}
+
+/// Receive thread, owning the [`Device`].
+struct Receiver {
+    commands: mpsc::Sender<(ConfigPath, ConfigValue)>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Result<(), Error>>>,
+}
+
+impl Receiver {
+    fn spawn(mut dev: Device, shared: Arc<Mutex<Shared>>, ctx: egui::Context) -> Self {
+        let (commands, rx) = mpsc::channel::<(ConfigPath, ConfigValue)>();
+        let running = Arc::new(AtomicBool::new(true));
+        let r = running.clone();
+
+        let thread = std::thread::spawn(move || {
+            while r.load(Ordering::Relaxed) {
+                // configuration changes are applied while the device is running
+                for (path, value) in rx.try_iter() {
+                    if let Err(e) = dev.set_typed(path, &value) {
+                        shared.lock().unwrap().error = Some(format!("{path}: {e}"));
+                    }
+                }
+
+                match dev.packet(CHANNEL) {
+                    Ok(p) => {
+                        let mut s = shared.lock().unwrap();
+                        s.waterfall.push_packet(&p);
+                        s.spectrum = Some(p.to_spectrum());
+                        s.packets += 1;
+                        drop(s);
+                        dev.consume(CHANNEL)?;
+                        ctx.request_repaint();
+                    }
+                    Err(e) => {
+                        shared.lock().unwrap().error = Some(e.to_string());
+                        std::thread::sleep(Duration::from_millis(100));
+                    }
+                }
+            }
+
+            dev.stop()?;
+            dev.disconnect()?;
+            dev.close()
+        });
+
+        Self {
+            commands,
+            running,
+            thread: Some(thread),
+        }
+    }
+
+    fn set(&self, path: ConfigPath, value: ConfigValue) {
+        let _ = self.commands.send((path, value));
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(t) = self.thread.take() {
+            match t.join() {
+                Ok(Err(e)) => eprintln!("failed to shut down device: {e}"),
+                Err(_) => eprintln!("receive thread panicked"),
+                Ok(Ok(())) => {}
+            }
+        }
+    }
+}
+
+struct Viewer {
+    receiver: Receiver,
+    shared: Arc<Mutex<Shared>>,
+    center_frequency: f64,
+    reference_level: f64,
+    fft_size: u32,
+    range: f32,
+    colormap: Colormap,
+    texture: Option<egui::TextureHandle>,
+    rate: (Instant, u64, f64),
+}
+
+impl Viewer {
+    /// Create the viewer for a started device.
+    fn new(cc: &eframe::CreationContext<'_>, mut dev: Device) -> Self {
+        let float = |v: Result<ConfigValue, Error>| match v {
+            Ok(ConfigValue::Float(f)) => Some(f),
+            Ok(ConfigValue::Int(i)) => Some(i as f64),
+            _ => None,
+        };
+        let center_frequency = float(dev.get_typed(CENTER_FREQUENCY)).unwrap_or(0.0);
+        let reference_level = float(dev.get_typed(REFERENCE_LEVEL)).unwrap_or(0.0);
+        let fft_size = float(dev.get_typed(FFT_SIZE)).map_or(1024, |f| f as u32);
+
+        let shared = Arc::new(Mutex::new(Shared {
+            spectrum: None,
+            waterfall: Waterfall::new(ROWS),
+            packets: 0,
+            error: None,
+        }));
+        let receiver = Receiver::spawn(dev, shared.clone(), cc.egui_ctx.clone());
+
+        Self {
+            receiver,
+            shared,
+            center_frequency,
+            reference_level,
+            fft_size,
+            range: 80.0,
+            colormap: Colormap::Viridis,
+            texture: None,
+            rate: (Instant::now(), 0, 0.0),
+        }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Center (MHz)");
+            let mut mhz = self.center_frequency / 1e6;
+            let r = ui.add(
+                egui::DragValue::new(&mut mhz)
+                    .speed(0.1)
+                    .clamp_range(0.0..=6000.0),
+            );
+            if r.changed() {
+                self.center_frequency = mhz * 1e6;
+                self.receiver
+                    .set(CENTER_FREQUENCY, ConfigValue::Float(self.center_frequency));
+            }
+
+            ui.label("Ref level (dBm)");
+            let r = ui.add(
+                egui::DragValue::new(&mut self.reference_level)
+                    .speed(1.0)
+                    .clamp_range(-100.0..=10.0),
+            );
+            if r.changed() {
+                self.receiver
+                    .set(REFERENCE_LEVEL, ConfigValue::Float(self.reference_level));
+            }
+
+            ui.label("FFT size");
+            let before = self.fft_size;
+            egui::ComboBox::from_id_source("fft_size")
+                .selected_text(self.fft_size.to_string())
+                .show_ui(ui, |ui| {
+                    for n in FFT_SIZES {
+                        ui.selectable_value(&mut self.fft_size, n, n.to_string());
+                    }
+                });
+            if self.fft_size != before {
+                self.receiver
+                    .set(FFT_SIZE, ConfigValue::Float(self.fft_size as f64));
+            }
+
+            ui.label("Range (dB)");
+            ui.add(egui::Slider::new(&mut self.range, 20.0..=150.0));
+
+            egui::ComboBox::from_id_source("colormap")
+                .selected_text(format!("{:?}", self.colormap))
+                .show_ui(ui, |ui| {
+                    for c in [Colormap::Viridis, Colormap::Inferno, Colormap::Grayscale] {
+                        ui.selectable_value(&mut self.colormap, c, format!("{c:?}"));
+                    }
+                });
+        });
+    }
+
+    /// Draw the spectrum trace, scaled from the reference level down by the range.
+    fn spectrum(&self, ui: &mut egui::Ui, spectrum: &Spectrum, height: f32) {
+        let (rect, _) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), height),
+            egui::Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+
+        let top = self.reference_level as f32;
+        let bottom = top - self.range;
+        let y = |v: f32| rect.top() + (top - v).clamp(0.0, self.range) / self.range * rect.height();
+        let grid = egui::Stroke::new(1.0, egui::Color32::from_gray(60));
+        let mut level = (top / 10.0).floor() * 10.0;
+        while level > bottom {
+            painter.hline(rect.x_range(), y(level), grid);
+            painter.text(
+                egui::pos2(rect.left() + 2.0, y(level)),
+                egui::Align2::LEFT_BOTTOM,
+                format!("{level} dBm"),
+                egui::FontId::monospace(10.0),
+                egui::Color32::GRAY,
+            );
+            level -= 10.0;
+        }
+
+        let n = spectrum.data.len().max(2);
+        let points: Vec<egui::Pos2> = spectrum
+            .data
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let x = rect.left() + i as f32 / (n - 1) as f32 * rect.width();
+                egui::pos2(x, y(*v))
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.0, egui::Color32::YELLOW),
+        ));
+
+        let stop = spectrum.start_frequency + (n - 1) as f64 * spectrum.step_frequency;
+        for (f, align) in [
+            (spectrum.start_frequency, egui::Align2::LEFT_BOTTOM),
+            (
+                (spectrum.start_frequency + stop) / 2.0,
+                egui::Align2::CENTER_BOTTOM,
+            ),
+            (stop, egui::Align2::RIGHT_BOTTOM),
+        ] {
+            let x = rect.left()
+                + ((f - spectrum.start_frequency) / (stop - spectrum.start_frequency))
+                    .clamp(0.0, 1.0) as f32
+                    * rect.width();
+            painter.text(
+                egui::pos2(x, rect.bottom() - 2.0),
+                align,
+                format!("{:.3} MHz", f / 1e6),
+                egui::FontId::monospace(10.0),
+                egui::Color32::GRAY,
+            );
+        }
+    }
+}
+
+impl eframe::App for Viewer {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let spectrogram = Spectrogram {
+            colormap: self.colormap,
+            min: Some(self.reference_level as f32 - self.range),
+            max: Some(self.reference_level as f32),
+            width: None,
+            axes: false,
+        };
+        let (spectrum, image, packets, error) = {
+            let s = self.shared.lock().unwrap();
+            (
+                s.spectrum.clone(),
+                spectrogram.render(&s.waterfall),
+                s.packets,
+                s.error.clone(),
+            )
+        };
+
+        let (since, count, rate) = &mut self.rate;
+        if since.elapsed() >= Duration::from_secs(1) {
+            *rate = (packets - *count) as f64 / since.elapsed().as_secs_f64();
+            *since = Instant::now();
+            *count = packets;
+        }
+
+        egui::TopBottomPanel::top("controls").show(ctx, |ui| self.controls(ui));
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("{:.1} packets/s", self.rate.2));
+                if let Some(e) = error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, e);
+                }
+            });
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let height = ui.available_height() / 2.0;
+            match &spectrum {
+                Some(s) => self.spectrum(ui, s, height),
+                None => {
+                    ui.allocate_exact_size(
+                        egui::vec2(ui.available_width(), height),
+                        egui::Sense::hover(),
+                    );
+                }
+            }
+
+            let image = egui::ColorImage::from_rgb([image.width, image.height], &image.data);
+            let texture = match &mut self.texture {
+                Some(t) => {
+                    t.set(image, egui::TextureOptions::NEAREST);
+                    t
+                }
+                None => self.texture.insert(ctx.load_texture(
+                    "waterfall",
+                    image,
+                    egui::TextureOptions::NEAREST,
+                )),
+            };
+            let size = ui.available_size();
+            ui.add(egui::Image::new((texture.id(), size)));
+        });
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut api = ApiHandle::new()?;
+    api.rescan_devices()?;
+    let mut dev = match std::env::args().nth(1) {
+        Some(serial) => {
+            let info = api
+                .devices()?
+                .into_iter()
+                .find(|d| d.serial() == serial)
+                .ok_or(Error::ErrorNotFound)?;
+            api.get_this_device(&info)?
+        }
+        None => api.get_device()?,
+    };
+    dev.open()?;
+    dev.set("device/receiverchannel", "Rx1")?;
+    dev.set_output_format(OutputFormat::Spectra)?;
+    dev.connect()?;
+    dev.start()?;
+
+    eframe::run_native(
+        "aaronia-viewer",
+        eframe::NativeOptions::default(),
+        Box::new(|cc| Box::new(Viewer::new(cc, dev))),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Bridge of a data channel to a [crossbeam](https://docs.rs/crossbeam-channel) channel.
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use crossbeam_channel::TrySendError;
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::pipeline::OwnedPacket;
+use crate::Device;
+use crate::Error;
+
+#[derive(Default)]
+struct Counters {
+    forwarded: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Receive thread, started by [`Device::spawn_rx()`].
+///
+/// The thread stops, when [`stop()`](Self::stop) is called, the bridge is dropped, all receivers
+/// are dropped, or the device fails.
+pub struct RxBridge {
+    stop: Arc<AtomicBool>,
+    counters: Arc<Counters>,
+    thread: Option<JoinHandle<std::result::Result<Device, Error>>>,
+}
+
+impl RxBridge {
+    /// Number of packets, sent to the channel.
+    pub fn forwarded(&self) -> u64 {
+        self.counters.forwarded.load(Ordering::Acquire)
+    }
+
+    /// Number of packets, dropped because the channel was full.
+    pub fn dropped(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Acquire)
+    }
+
+    /// Check if the receive thread is running.
+    pub fn is_running(&self) -> bool {
+        self.thread.as_ref().is_some_and(|t| !t.is_finished())
+    }
+
+    /// Stop the receive thread and get the [`Device`] back, or the error that stopped it.
+    pub fn stop(mut self) -> std::result::Result<Device, Error> {
+        self.stop.store(true, Ordering::Release);
+        self.thread
+            .take()
+            .unwrap()
+            .join()
+            .map_err(|_| Error::Error)?
+    }
+
+    fn run(
+        dev: &mut Device,
+        chan: i32,
+        tx: &Sender<OwnedPacket>,
+        stop: &AtomicBool,
+        counters: &Counters,
+    ) -> crate::Result {
+        dev.drive(chan, stop, |p| {
+            match tx.try_send(OwnedPacket::from(p)) {
+                Ok(()) => counters.forwarded.fetch_add(1, Ordering::Release),
+                Err(TrySendError::Full(_)) => counters.dropped.fetch_add(1, Ordering::Release),
+                Err(TrySendError::Disconnected(_)) => return ControlFlow::Break(()),
+            };
+            ControlFlow::Continue(())
+        })
+    }
+}
+
+impl std::fmt::Debug for RxBridge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RxBridge")
+            .field("forwarded", &self.forwarded())
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
+
+impl Drop for RxBridge {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+impl Device {
+    /// Receive data channel `chan` on a background thread and forward copies of the packets
+    /// through a bounded channel with `capacity` packets.
+    ///
+    /// The thread consumes packets at the pace of the device. If the consumers fall behind and
+    /// the channel is full, new packets are dropped and counted, see [`RxBridge::dropped()`].
+    /// The [`Device`] has to be started. It is returned by [`RxBridge::stop()`].
+    pub fn spawn_rx(self, chan: i32, capacity: usize) -> (RxBridge, Receiver<OwnedPacket>) {
+        let (tx, rx) = crossbeam_channel::bounded(capacity.max(1));
+        let stop = Arc::new(AtomicBool::new(false));
+        let counters = Arc::new(Counters::default());
+
+        let s = stop.clone();
+        let c = counters.clone();
+        let mut dev = self;
+        let thread =
+            std::thread::spawn(move || RxBridge::run(&mut dev, chan, &tx, &s, &c).map(|_| dev));
+
+        (
+            RxBridge {
+                stop,
+                counters,
+                thread: Some(thread),
+            },
+            rx,
+        )
+    }
+}
@@ -0,0 +1,224 @@
+//! Energy detection and extraction of bursts from IQ streams.
+use num_complex::Complex32;
+use std::collections::VecDeque;
+use std::sync::mpsc;
+
+use crate::Device;
+use crate::Packet;
+use crate::Result;
+use crate::StreamTime;
+
+/// Burst, extracted by a [`BurstDetector`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Burst {
+    /// IQ samples of the burst, including the pre-trigger samples.
+    pub samples: Vec<Complex32>,
+    /// Time of the first sample.
+    pub start: StreamTime,
+    /// Time after the last sample, i.e., `start` plus the duration of the samples.
+    pub stop: StreamTime,
+    /// Center frequency of the stream in Hz.
+    pub center_frequency: f64,
+    /// Sample rate of the stream in Hz.
+    pub sample_rate: f64,
+    /// Peak power of the samples in dBFS.
+    pub peak_power: f32,
+    /// The burst was split, since it exceeded the maximum length.
+    pub truncated: bool,
+}
+
+/// Stream, the detector is synchronized to.
+#[derive(Debug, Clone, Copy)]
+struct Stream {
+    center_frequency: f64,
+    sample_rate: f64,
+    end: StreamTime,
+}
+
+/// Energy detector that extracts bursts from an IQ stream.
+///
+/// The detector averages the power over `window` samples. A burst starts, when the average
+/// exceeds `threshold`, and ends, when it falls `hysteresis` dB below. Bursts include up to
+/// `pre_trigger` samples before the averaging window that triggered. The detector keeps state
+/// between packets; after gaps or frequency changes, a running burst is finished and the
+/// detector restarts.
+#[derive(Debug, Clone)]
+pub struct BurstDetector {
+    /// Start threshold of the average power in dBFS.
+    pub threshold: f32,
+    /// Difference between start and stop threshold in dB (default: 3).
+    pub hysteresis: f32,
+    /// Number of samples of the power average (default: 16).
+    pub window: usize,
+    /// Number of samples before the averaging window that are included (default: 64).
+    pub pre_trigger: usize,
+    /// Minimum number of samples of a burst; shorter bursts are discarded (default: 0).
+    pub min_length: usize,
+    /// Maximum number of samples of a burst; longer bursts are split (default: 1M).
+    pub max_length: usize,
+    history: VecDeque<Complex32>,
+    powers: VecDeque<f32>,
+    sum: f64,
+    current: Option<Burst>,
+    stream: Option<Stream>,
+}
+
+/// Convert linear power to dB.
+fn db(p: f32) -> f32 {
+    10.0 * p.log10()
+}
+
+impl BurstDetector {
+    /// Create a detector with a start threshold in dBFS.
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            hysteresis: 3.0,
+            window: 16,
+            pre_trigger: 64,
+            min_length: 0,
+            max_length: 1 << 20,
+            history: VecDeque::new(),
+            powers: VecDeque::new(),
+            sum: 0.0,
+            current: None,
+            stream: None,
+        }
+    }
+
+    /// Check if a burst is in progress.
+    pub fn in_burst(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Process the IQ samples of a [`Packet`] and return the finished bursts.
+    pub fn process(&mut self, packet: &Packet) -> Vec<Burst> {
+        match packet.sample_rate() {
+            Some(rate) => self.process_samples(
+                packet.samples(),
+                packet.start_stream_time(),
+                rate,
+                packet.start_frequency(),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    /// Process IQ samples, starting at `start`, and return the finished bursts.
+    pub fn process_samples(
+        &mut self,
+        samples: &[Complex32],
+        start: StreamTime,
+        sample_rate: f64,
+        center_frequency: f64,
+    ) -> Vec<Burst> {
+        let mut bursts = Vec::new();
+
+        // restart after gaps and frequency or rate changes
+        let tolerance = 0.5 / sample_rate;
+        if let Some(s) = self.stream {
+            if s.center_frequency != center_frequency
+                || s.sample_rate != sample_rate
+                || !s.end.approx_eq(start, tolerance)
+            {
+                bursts.extend(self.flush());
+            }
+        }
+        self.stream = Some(Stream {
+            center_frequency,
+            sample_rate,
+            end: start.sample_time(samples.len() as i64, sample_rate),
+        });
+
+        let window = self.window.max(1);
+        let on = 10f32.powf(self.threshold / 10.0) as f64;
+        let off = 10f32.powf((self.threshold - self.hysteresis) / 10.0) as f64;
+
+        for (i, s) in samples.iter().enumerate() {
+            let p = s.norm_sqr();
+            self.powers.push_back(p);
+            self.sum += p as f64;
+            if self.powers.len() > window {
+                self.sum -= self.powers.pop_front().unwrap() as f64;
+            }
+            let avg = self.sum.max(0.0) / self.powers.len() as f64;
+            let time = |n: usize| start.sample_time(n as i64, sample_rate);
+
+            match self.current.as_mut() {
+                None => {
+                    self.history.push_back(*s);
+                    if self.history.len() > self.pre_trigger + window {
+                        self.history.pop_front();
+                    }
+                    if self.powers.len() == window && avg > on {
+                        let samples: Vec<Complex32> = self.history.drain(..).collect();
+                        let peak = samples.iter().map(|s| s.norm_sqr()).fold(0.0, f32::max);
+                        self.current = Some(Burst {
+                            start: start
+                                .sample_time(i as i64 + 1 - samples.len() as i64, sample_rate),
+                            stop: time(i + 1),
+                            samples,
+                            center_frequency,
+                            sample_rate,
+                            peak_power: db(peak),
+                            truncated: false,
+                        });
+                    }
+                }
+                Some(b) => {
+                    b.samples.push(*s);
+                    b.stop = time(i + 1);
+                    b.peak_power = b.peak_power.max(db(p));
+                    if avg < off {
+                        bursts.extend(self.finish());
+                    } else if b.samples.len() >= self.max_length {
+                        b.truncated = true;
+                        let next = Burst {
+                            samples: Vec::new(),
+                            start: b.stop,
+                            stop: b.stop,
+                            center_frequency,
+                            sample_rate,
+                            peak_power: f32::NEG_INFINITY,
+                            truncated: false,
+                        };
+                        bursts.extend(self.current.replace(next));
+                    }
+                }
+            }
+        }
+        bursts
+    }
+
+    /// Finish the running burst, if it is long enough.
+    fn finish(&mut self) -> Option<Burst> {
+        self.current
+            .take()
+            .filter(|b| b.samples.len() >= self.min_length.max(1))
+    }
+
+    /// Finish a running burst and reset the detector, e.g., at the end of a stream.
+    pub fn flush(&mut self) -> Option<Burst> {
+        self.history.clear();
+        self.powers.clear();
+        self.sum = 0.0;
+        self.stream = None;
+        self.finish()
+    }
+
+    /// Receive packets of data channel `chan` and send the bursts to `tx`.
+    ///
+    /// This call is blocking and returns, once the receiver is dropped. Received packets are
+    /// consumed. The [`Device`] has to be started and configured to output IQ samples.
+    pub fn run(&mut self, dev: &mut Device, chan: i32, tx: &mpsc::Sender<Burst>) -> Result {
+        loop {
+            let bursts = self.process(&dev.packet(chan)?);
+            dev.consume(chan)?;
+            for b in bursts {
+                if tx.send(b).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::ConfigValue;
+use crate::Device;
+use crate::Error;
+use crate::HardwareModel;
+use crate::Hz;
+use crate::Result;
+use crate::RxChannel;
+
+const FREQUENCY_PATH: &str = "main/centerfreq";
+pub(crate) const DECIMATION_PATH: &str = "main/decimation";
+const CLOCK_PATH: &str = "device/receiverclock";
+const CHANNEL_PATH: &str = "device/receiverchannel";
+const TX_PATH: &str = "device/transmittermode";
+
+/// Sample rates and frequencies closer than this are considered equal.
+const TOLERANCE: f64 = 1e-3;
+
+/// Features of the connected hardware variant, returned by [`Device::capabilities()`].
+///
+/// Ranges and options are read from the configuration tree. Receiver clocks are further limited
+/// to the real-time bandwidth of the model or of an installed `RTBW` option (e.g., `RTBW245`), TX
+/// requires a model with TX or the `TX` option.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities {
+    /// Hardware model, if recognized.
+    pub model: Option<HardwareModel>,
+    /// Lowest center frequency.
+    pub min_frequency: Hz,
+    /// Highest center frequency.
+    pub max_frequency: Hz,
+    /// Usable receiver clocks, in the order of the `device/receiverclock` options.
+    pub receiver_clocks: Vec<Hz>,
+    /// Decimation factors, in the order of the `main/decimation` options.
+    pub decimations: Vec<u32>,
+    /// Selectable receiver channels.
+    pub rx_channels: Vec<RxChannel>,
+    /// Transmitting is supported.
+    pub tx: bool,
+}
+
+/// Parse a clock option, e.g., `245MHz`.
+fn parse_clock(option: &str) -> Option<Hz> {
+    let o = option.trim().to_lowercase();
+    let (number, scale) = if let Some(n) = o.strip_suffix("mhz") {
+        (n, 1e6)
+    } else if let Some(n) = o.strip_suffix("khz") {
+        (n, 1e3)
+    } else {
+        (o.strip_suffix("hz").unwrap_or(&o), 1.0)
+    };
+    number.trim().parse::<f64>().ok().map(|v| Hz(v * scale))
+}
+
+/// Parse a decimation option, e.g., `Full` or `1 / 4`.
+fn parse_decimation(option: &str) -> Option<u32> {
+    if option.trim().eq_ignore_ascii_case("full") {
+        return Some(1);
+    }
+    option.rsplit('/').next()?.trim().parse().ok()
+}
+
+/// Bandwidth option, e.g., `RTBW245` for a maximum receiver clock of 245 MHz.
+fn bandwidth_option(option: &str) -> Option<Hz> {
+    let o = option.trim().to_uppercase();
+    o.strip_prefix("RTBW")?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|v| Hz(v * 1e6))
+}
+
+impl Capabilities {
+    /// All supported sample rates, i.e., receiver clocks divided by decimation factors, in
+    /// ascending order.
+    pub fn sample_rates(&self) -> Vec<Hz> {
+        let mut rates: Vec<Hz> = self
+            .receiver_clocks
+            .iter()
+            .flat_map(|c| self.decimations.iter().map(move |d| *c / *d as f64))
+            .collect();
+        rates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        rates.dedup_by(|a, b| (a.0 - b.0).abs() < TOLERANCE);
+        rates
+    }
+
+    /// Highest supported sample rate.
+    pub fn max_sample_rate(&self) -> Option<Hz> {
+        self.sample_rates().last().copied()
+    }
+
+    /// Check if the center frequency is in the supported range.
+    pub fn supports_frequency(&self, frequency: impl Into<Hz>) -> bool {
+        let f = frequency.into();
+        f >= self.min_frequency && f <= self.max_frequency
+    }
+
+    /// Check if the sample rate is supported.
+    pub fn supports_sample_rate(&self, rate: impl Into<Hz>) -> bool {
+        self.clock_and_decimation(rate.into()).is_some()
+    }
+
+    /// Indices of the receiver clock and decimation options for a sample rate.
+    ///
+    /// Prefers the lowest receiver clock, i.e., the least decimation.
+    fn clock_and_decimation(&self, rate: Hz) -> Option<(usize, usize)> {
+        self.receiver_clocks
+            .iter()
+            .enumerate()
+            .flat_map(|(c, clock)| {
+                self.decimations
+                    .iter()
+                    .enumerate()
+                    .map(move |(d, dec)| (c, d, *clock / *dec as f64))
+            })
+            .find(|(_, _, r)| (r.0 - rate.0).abs() < TOLERANCE)
+            .map(|(c, d, _)| (c, d))
+    }
+
+    /// Check if a configuration parameter can be set to a value.
+    ///
+    /// Parameters, that are not covered by the capabilities, are accepted.
+    pub fn allows(&self, path: &str, value: &ConfigValue) -> bool {
+        match (path, value) {
+            (FREQUENCY_PATH, ConfigValue::Float(f)) => self.supports_frequency(*f),
+            (FREQUENCY_PATH, ConfigValue::Int(f)) => self.supports_frequency(*f as f64),
+            (CLOCK_PATH, ConfigValue::String(s)) => {
+                parse_clock(s).is_some_and(|c| self.receiver_clocks.contains(&c))
+            }
+            (CLOCK_PATH, ConfigValue::Int(i)) => {
+                *i >= 0 && (*i as usize) < self.receiver_clocks.len()
+            }
+            (CHANNEL_PATH, ConfigValue::String(s)) => self
+                .rx_channels
+                .iter()
+                .any(|c| c.as_str().eq_ignore_ascii_case(s)),
+            (TX_PATH, _) => self.tx,
+            _ => true,
+        }
+    }
+}
+
+impl Device {
+    /// Query the [`Capabilities`] of the connected hardware variant.
+    ///
+    /// The device has to be opened. The capabilities are cached until the device is opened
+    /// again.
+    pub fn capabilities(&mut self) -> std::result::Result<Capabilities, Error> {
+        if let Some(c) = &self.capabilities {
+            return Ok(c.clone());
+        }
+
+        let details = self.info()?;
+        let frequency = self.config_entry(FREQUENCY_PATH)?;
+        let clocks = self
+            .config_entry(CLOCK_PATH)
+            .map(|e| e.options)
+            .unwrap_or_default();
+        let decimations = self
+            .config_entry(DECIMATION_PATH)
+            .map(|e| {
+                e.options
+                    .iter()
+                    .map_while(|o| parse_decimation(o))
+                    .collect()
+            })
+            .unwrap_or_else(|_| vec![1]);
+        let rf = self.rf_capabilities()?;
+
+        // the highest clock of the model or of a bandwidth option; options are ascending
+        let max_clock = details
+            .model
+            .map(|m| m.max_receiver_clock())
+            .into_iter()
+            .chain(details.options.iter().filter_map(|o| bandwidth_option(o)))
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        let receiver_clocks = clocks
+            .iter()
+            .map_while(|o| {
+                parse_clock(o).filter(|c| max_clock.is_none_or(|m| c.0 <= m.0 + TOLERANCE))
+            })
+            .collect();
+        let tx = details.model.is_some_and(|m| m.has_tx())
+            || details.options.iter().any(|o| o.eq_ignore_ascii_case("tx"));
+
+        let caps = Capabilities {
+            model: details.model,
+            min_frequency: Hz(frequency.min),
+            max_frequency: Hz(frequency.max),
+            receiver_clocks,
+            decimations,
+            rx_channels: rf.rx_channels,
+            tx,
+        };
+        self.capabilities = Some(caps.clone());
+        Ok(caps)
+    }
+
+    /// Set the center frequency.
+    ///
+    /// Returns [`Error::ErrorValueInvalid`] without accessing the device, if the frequency is out
+    /// of the range of the [`Capabilities`].
+    pub fn set_center_frequency(&mut self, frequency: impl Into<Hz>) -> Result {
+        let f = frequency.into();
+        if !self.capabilities()?.supports_frequency(f) {
+            return Err(Error::ErrorValueInvalid);
+        }
+        self.set_float(FREQUENCY_PATH, f)
+    }
+
+    /// Set the IQ sample rate, mapped to `device/receiverclock` and `main/decimation`.
+    ///
+    /// Returns [`Error::ErrorValueInvalid`] without accessing the device, if the rate is not one
+    /// of the [supported rates](Capabilities::sample_rates).
+    pub fn set_sample_rate(&mut self, rate: impl Into<Hz>) -> Result {
+        let (c, d) = self
+            .capabilities()?
+            .clock_and_decimation(rate.into())
+            .ok_or(Error::ErrorValueInvalid)?;
+        self.set_int(CLOCK_PATH, c as i64)?;
+        self.set_int(DECIMATION_PATH, d as i64)
+    }
+
+    /// Get the IQ sample rate, derived from `device/receiverclock` and `main/decimation`.
+    pub fn sample_rate(&mut self) -> std::result::Result<Hz, Error> {
+        let clock = self.enum_option(CLOCK_PATH)?;
+        let decimation = self.enum_option(DECIMATION_PATH)?;
+        match (parse_clock(&clock), parse_decimation(&decimation)) {
+            (Some(c), Some(d)) => Ok(c / d as f64),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+
+    /// Check a value against the [`Capabilities`].
+    ///
+    /// Values are accepted, if the capabilities cannot be queried, e.g., for device types
+    /// without the parameters.
+    pub(crate) fn check_capabilities(&mut self, path: &str, value: &ConfigValue) -> Result {
+        match self.capabilities() {
+            Ok(c) if !c.allows(path, value) => Err(Error::ErrorValueInvalid),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,499 @@
+//! C interface to the safe API, e.g., to embed the device lifecycle, recovery, and streaming in
+//! C and C++ applications.
+//!
+//! The header `include/aaronia_rtsa.h` is generated with
+//! [cbindgen](https://github.com/mozilla/cbindgen), i.e.,
+//! `cbindgen --config cbindgen.toml --output include/aaronia_rtsa.h`. Link a shared library of
+//! this crate, built with the `capi` feature, i.e.,
+//! `cargo rustc --release --lib --features capi --crate-type cdylib`.
+//!
+//! - Handles are opaque pointers, created by `rtsa_api_new()` and `rtsa_device_new()` and
+//!   released by the corresponding `_free()` function.
+//! - Functions return an [`RtsaStatus`]. The message of the last error of the calling thread is
+//!   copied with `rtsa_last_error()`.
+//! - Panics are caught at the boundary and reported as [`RtsaStatus::Panic`].
+//!
+//! ```c
+//! RtsaApi *api;
+//! RtsaDevice *dev;
+//! RtsaPacket packet;
+//! rtsa_api_new(RTSA_MEMORY_MEDIUM, &api);
+//! rtsa_api_rescan(api);
+//! rtsa_device_new(api, 0, &dev);
+//! rtsa_device_open(dev);
+//! rtsa_device_connect(dev);
+//! rtsa_device_set_float(dev, "main/centerfreq", 2.4e9);
+//! rtsa_device_start(dev);
+//! if (rtsa_device_packet(dev, 0, 1000, &packet) == RTSA_STATUS_OK) {
+//!     // packet.samples holds 2 * packet.num floats
+//!     rtsa_device_consume(dev, 0);
+//! }
+//! rtsa_device_free(dev);
+//! rtsa_api_free(api);
+//! ```
+use num_complex::Complex32;
+use std::cell::RefCell;
+use std::ffi::c_char;
+use std::ffi::CStr;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::poll::Poller;
+use crate::ApiHandle;
+use crate::ConfigItem;
+use crate::Device;
+use crate::Error;
+use crate::Memory;
+
+thread_local! {
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Result of the functions of the C interface.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtsaStatus {
+    /// Success.
+    Ok = 0,
+    /// No packet became available, before the timeout.
+    Empty = 1,
+    /// Error of the RTSA library or the device, see `rtsa_last_error()`.
+    Error = -1,
+    /// Null pointer or invalid string argument.
+    InvalidArgument = -2,
+    /// Panic of the Rust implementation.
+    Panic = -3,
+}
+
+/// Memory size of the RTSA library, see [`Memory`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtsaMemory {
+    Small,
+    Medium,
+    Large,
+    Ludicrous,
+}
+
+impl From<RtsaMemory> for Memory {
+    fn from(m: RtsaMemory) -> Self {
+        match m {
+            RtsaMemory::Small => Memory::Small,
+            RtsaMemory::Medium => Memory::Medium,
+            RtsaMemory::Large => Memory::Large,
+            RtsaMemory::Ludicrous => Memory::Ludicrous,
+        }
+    }
+}
+
+/// Library handle, see [`ApiHandle`].
+pub struct RtsaApi {
+    inner: ApiHandle,
+}
+
+/// Device handle, see [`Device`].
+pub struct RtsaDevice {
+    inner: Device,
+}
+
+/// Metadata and payload of a packet, filled by `rtsa_device_packet()`.
+///
+/// The payload points into the packet buffer of the RTSA library, i.e., it is valid, until the
+/// packet is consumed.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RtsaPacket {
+    /// Device clock time of the first sample in seconds.
+    pub start_time: f64,
+    /// Device clock time after the last sample in seconds.
+    pub end_time: f64,
+    /// Start frequency in Hz, i.e., the center frequency of IQ packets.
+    pub start_frequency: f64,
+    /// Frequency step in Hz, i.e., the sample rate of IQ packets or the bin width of spectra.
+    pub step_frequency: f64,
+    /// Span in Hz.
+    pub span_frequency: f64,
+    /// Number of IQ samples or spectrum bins.
+    pub num: usize,
+    /// Interleaved IQ samples (`2 * num` floats) or the bins of the first spectrum in dBm (`num`
+    /// floats).
+    pub samples: *const f32,
+}
+
+impl Default for RtsaPacket {
+    fn default() -> Self {
+        Self {
+            start_time: 0.0,
+            end_time: 0.0,
+            start_frequency: 0.0,
+            step_frequency: 0.0,
+            span_frequency: 0.0,
+            num: 0,
+            samples: std::ptr::null(),
+        }
+    }
+}
+
+fn set_last_error(msg: String) {
+    LAST_ERROR.with(|e| *e.borrow_mut() = msg);
+}
+
+/// Run `f`, mapping errors and panics to a status and recording their message.
+fn guard<F: FnOnce() -> std::result::Result<RtsaStatus, Error>>(f: F) -> RtsaStatus {
+    match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(status)) => status,
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            RtsaStatus::Error
+        }
+        Err(panic) => {
+            let msg = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic".to_string());
+            set_last_error(msg);
+            RtsaStatus::Panic
+        }
+    }
+}
+
+fn invalid(msg: &str) -> RtsaStatus {
+    set_last_error(msg.to_string());
+    RtsaStatus::InvalidArgument
+}
+
+/// Convert a C string to a `&str`.
+unsafe fn c_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Call `f` with the device, if the handle is not null.
+unsafe fn with_device<F: FnOnce(&mut Device) -> crate::Result>(
+    dev: *mut RtsaDevice,
+    f: F,
+) -> RtsaStatus {
+    match dev.as_mut() {
+        Some(d) => guard(|| f(&mut d.inner).map(|_| RtsaStatus::Ok)),
+        None => invalid("device handle is null"),
+    }
+}
+
+/// Copy the message of the last error of the calling thread as null-terminated string.
+///
+/// Returns the length of the message without the terminator. The message is truncated to
+/// `len - 1` bytes. Pass a null buffer to query the length.
+///
+/// # Safety
+///
+/// `buf` has to be null or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_last_error(buf: *mut c_char, len: usize) -> usize {
+    LAST_ERROR.with(|e| {
+        let e = e.borrow();
+        if !buf.is_null() && len > 0 {
+            let n = e.len().min(len - 1);
+            std::ptr::copy_nonoverlapping(e.as_ptr(), buf as *mut u8, n);
+            *buf.add(n) = 0;
+        }
+        e.len()
+    })
+}
+
+/// Create a library handle, see [`ApiHandle::with_mem()`].
+///
+/// # Safety
+///
+/// `api` has to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_api_new(memory: RtsaMemory, api: *mut *mut RtsaApi) -> RtsaStatus {
+    if api.is_null() {
+        return invalid("api is null");
+    }
+    guard(|| {
+        let inner = ApiHandle::with_mem(memory.into())?;
+        *api = Box::into_raw(Box::new(RtsaApi { inner }));
+        Ok(RtsaStatus::Ok)
+    })
+}
+
+/// Release a library handle. Devices keep the library initialized, until they are released.
+///
+/// # Safety
+///
+/// `api` has to be null or created by `rtsa_api_new()` and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_api_free(api: *mut RtsaApi) {
+    if !api.is_null() {
+        guard(|| {
+            drop(Box::from_raw(api));
+            Ok(RtsaStatus::Ok)
+        });
+    }
+}
+
+/// Rescan for devices, see [`ApiHandle::rescan_devices()`].
+///
+/// # Safety
+///
+/// `api` has to be null or a valid library handle.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_api_rescan(api: *mut RtsaApi) -> RtsaStatus {
+    match api.as_mut() {
+        Some(a) => guard(|| a.inner.rescan_devices().map(|_| RtsaStatus::Ok)),
+        None => invalid("api handle is null"),
+    }
+}
+
+/// Query the number of detected devices.
+///
+/// # Safety
+///
+/// `api` has to be null or a valid library handle and `count` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_api_device_count(api: *mut RtsaApi, count: *mut usize) -> RtsaStatus {
+    let (Some(a), false) = (api.as_mut(), count.is_null()) else {
+        return invalid("api or count is null");
+    };
+    guard(|| {
+        *count = a.inner.devices()?.len();
+        Ok(RtsaStatus::Ok)
+    })
+}
+
+/// Create a handle for the detected device with the given index.
+///
+/// # Safety
+///
+/// `api` has to be null or a valid library handle and `dev` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_new(
+    api: *mut RtsaApi,
+    index: usize,
+    dev: *mut *mut RtsaDevice,
+) -> RtsaStatus {
+    let (Some(a), false) = (api.as_mut(), dev.is_null()) else {
+        return invalid("api or dev is null");
+    };
+    guard(|| {
+        let devices = a.inner.devices()?;
+        let info = devices.get(index).ok_or(Error::ErrorNotFound)?;
+        let inner = a.inner.get_this_device(info)?;
+        *dev = Box::into_raw(Box::new(RtsaDevice { inner }));
+        Ok(RtsaStatus::Ok)
+    })
+}
+
+/// Release a device handle, stopping, disconnecting, and closing the device as needed.
+///
+/// # Safety
+///
+/// `dev` has to be null or created by `rtsa_device_new()` and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_free(dev: *mut RtsaDevice) {
+    if !dev.is_null() {
+        guard(|| {
+            drop(Box::from_raw(dev));
+            Ok(RtsaStatus::Ok)
+        });
+    }
+}
+
+/// Open the device, see [`Device::open()`].
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_open(dev: *mut RtsaDevice) -> RtsaStatus {
+    with_device(dev, |d| d.open())
+}
+
+/// Connect the device, see [`Device::connect()`].
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_connect(dev: *mut RtsaDevice) -> RtsaStatus {
+    with_device(dev, |d| d.connect())
+}
+
+/// Start the device, see [`Device::start()`].
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_start(dev: *mut RtsaDevice) -> RtsaStatus {
+    with_device(dev, |d| d.start())
+}
+
+/// Stop the device, see [`Device::stop()`].
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_stop(dev: *mut RtsaDevice) -> RtsaStatus {
+    with_device(dev, |d| d.stop())
+}
+
+/// Disconnect the device, see [`Device::disconnect()`].
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_disconnect(dev: *mut RtsaDevice) -> RtsaStatus {
+    with_device(dev, |d| d.disconnect())
+}
+
+/// Close the device, see [`Device::close()`].
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_close(dev: *mut RtsaDevice) -> RtsaStatus {
+    with_device(dev, |d| d.close())
+}
+
+/// Reconnect a lost device and restore its configuration, see [`Device::recover()`].
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_recover(dev: *mut RtsaDevice) -> RtsaStatus {
+    with_device(dev, |d| d.recover())
+}
+
+/// Set a configuration parameter as string, e.g., the option of an enum parameter.
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle, `path` and `value` null or null-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_set(
+    dev: *mut RtsaDevice,
+    path: *const c_char,
+    value: *const c_char,
+) -> RtsaStatus {
+    let (Some(p), Some(v)) = (c_str(path), c_str(value)) else {
+        return invalid("path or value is not a valid string");
+    };
+    with_device(dev, |d| d.set(p, v))
+}
+
+/// Set a number parameter.
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle, `path` null or null-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_set_float(
+    dev: *mut RtsaDevice,
+    path: *const c_char,
+    value: f64,
+) -> RtsaStatus {
+    let Some(p) = c_str(path) else {
+        return invalid("path is not a valid string");
+    };
+    with_device(dev, |d| d.set_float(p, value))
+}
+
+/// Get a number parameter.
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle, `path` null or null-terminated, and `value`
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_get_float(
+    dev: *mut RtsaDevice,
+    path: *const c_char,
+    value: *mut f64,
+) -> RtsaStatus {
+    let (Some(p), false) = (c_str(path), value.is_null()) else {
+        return invalid("path is not a valid string or value is null");
+    };
+    with_device(dev, |d| match d.get(p)? {
+        ConfigItem::Number(v) => {
+            *value = v;
+            Ok(())
+        }
+        _ => Err(Error::ErrorValueInvalid),
+    })
+}
+
+/// Get the packet at the head of the queue of a data channel, waiting at most `timeout_ms`.
+///
+/// Polls according to the [`PollStrategy`](crate::PollStrategy) of the device. Returns
+/// [`RtsaStatus::Empty`], if no packet became available in time; a timeout of zero does not
+/// wait. The packet stays at the head of the queue, until it is consumed with
+/// `rtsa_device_consume()`.
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle and `packet` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_packet(
+    dev: *mut RtsaDevice,
+    chan: i32,
+    timeout_ms: u32,
+    packet: *mut RtsaPacket,
+) -> RtsaStatus {
+    let (Some(d), false) = (dev.as_mut(), packet.is_null()) else {
+        return invalid("dev or packet is null");
+    };
+    guard(|| {
+        let dev = &mut d.inner;
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms as u64);
+        let mut poller = Poller::new(dev.poll_strategy());
+        let p = loop {
+            match dev.try_packet(chan) {
+                Err(Error::Empty) if Instant::now() < deadline => poller.wait(),
+                Err(Error::Empty) => return Ok(RtsaStatus::Empty),
+                r => break r?,
+            }
+        };
+        let (samples, num) = match p.try_samples() {
+            Ok(s) => (interleaved(s), s.len()),
+            Err(_) => {
+                let bins = p.try_spectrum_row(0)?;
+                (bins, bins.len())
+            }
+        };
+        *packet = RtsaPacket {
+            start_time: p.start_time(),
+            end_time: p.end_time(),
+            start_frequency: p.start_frequency(),
+            step_frequency: p.step_frequency(),
+            span_frequency: p.span_frequency(),
+            num,
+            samples: samples.as_ptr(),
+        };
+        Ok(RtsaStatus::Ok)
+    })
+}
+
+/// View complex samples as interleaved floats.
+fn interleaved(s: &[Complex32]) -> &[f32] {
+    // Complex32 is repr(C) with two f32
+    unsafe { std::slice::from_raw_parts(s.as_ptr() as *const f32, 2 * s.len()) }
+}
+
+/// Consume the packet at the head of the queue of a data channel, see [`Device::consume()`].
+///
+/// # Safety
+///
+/// `dev` has to be null or a valid device handle.
+#[no_mangle]
+pub unsafe extern "C" fn rtsa_device_consume(dev: *mut RtsaDevice, chan: i32) -> RtsaStatus {
+    with_device(dev, |d| d.consume(chan))
+}
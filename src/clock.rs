@@ -0,0 +1,176 @@
+use std::collections::VecDeque;
+use std::time::UNIX_EPOCH;
+
+use crate::ClockAnchor;
+use crate::ConfigItem;
+use crate::Device;
+use crate::Error;
+
+/// Configuration path of the reference clock source.
+const REFERENCE_PATH: &str = "device/referenceclock";
+
+/// Source of the reference clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReferenceClock {
+    /// Internal oscillator.
+    Internal,
+    /// External reference input, e.g., 10 MHz with PPS.
+    External,
+    /// GPS-disciplined oscillator.
+    Gps,
+}
+
+impl ReferenceClock {
+    /// Check if an option of the reference clock parameter selects this source.
+    fn matches(&self, option: &str) -> bool {
+        let option = option.to_lowercase();
+        match self {
+            ReferenceClock::Internal => option.contains("int"),
+            ReferenceClock::External => option.contains("ext"),
+            ReferenceClock::Gps => option.contains("gps"),
+        }
+    }
+}
+
+/// Synchronization status, returned by [`ClockSync::update()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClockStatus {
+    /// Offset of the host time to the stream time in seconds, averaged over the window.
+    pub offset: f64,
+    /// Drift of the host clock relative to the stream clock in ppm, if the window has at least
+    /// two readings.
+    pub drift_ppm: Option<f64>,
+    /// Lock state of the reference, if reported by the health tree.
+    pub locked: Option<bool>,
+}
+
+/// Track the offset between host time and [`StreamTime`](crate::StreamTime).
+///
+/// With a GPS-disciplined or external reference, the stream clock is locked to UTC, i.e., the
+/// offset is the error of the host clock.
+#[derive(Debug, Clone)]
+pub struct ClockSync {
+    anchors: VecDeque<ClockAnchor>,
+    window: usize,
+}
+
+impl ClockSync {
+    /// Create a tracker that averages over the last `window` readings.
+    pub fn new(window: usize) -> Self {
+        Self {
+            anchors: VecDeque::with_capacity(window.max(1)),
+            window: window.max(1),
+        }
+    }
+
+    /// Read the clocks and the lock state of the [`Device`].
+    pub fn update(&mut self, dev: &mut Device) -> std::result::Result<ClockStatus, Error> {
+        let anchor = dev.clock_anchor()?;
+        if self.anchors.len() == self.window {
+            self.anchors.pop_front();
+        }
+        self.anchors.push_back(anchor);
+
+        Ok(ClockStatus {
+            offset: self.offset().unwrap_or_default(),
+            drift_ppm: self.drift_ppm(),
+            locked: dev.reference_locked()?,
+        })
+    }
+
+    /// Offset of the host time to the stream time in seconds, averaged over the window.
+    pub fn offset(&self) -> Option<f64> {
+        if self.anchors.is_empty() {
+            return None;
+        }
+        let sum: f64 = self.anchors.iter().map(Self::anchor_offset).sum();
+        Some(sum / self.anchors.len() as f64)
+    }
+
+    /// Drift of the host clock relative to the stream clock in ppm, i.e., the slope of a linear
+    /// fit of the offsets over stream time.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        let n = self.anchors.len() as f64;
+        if n < 2.0 {
+            return None;
+        }
+        let t0 = self.anchors[0].stream.as_secs();
+        let xs: Vec<f64> = self
+            .anchors
+            .iter()
+            .map(|a| a.stream.as_secs() - t0)
+            .collect();
+        let ys: Vec<f64> = self.anchors.iter().map(Self::anchor_offset).collect();
+        let mx = xs.iter().sum::<f64>() / n;
+        let my = ys.iter().sum::<f64>() / n;
+        let sxx: f64 = xs.iter().map(|x| (x - mx).powi(2)).sum();
+        let sxy: f64 = xs.iter().zip(&ys).map(|(x, y)| (x - mx) * (y - my)).sum();
+        if sxx == 0.0 {
+            return None;
+        }
+        Some(sxy / sxx * 1e6)
+    }
+
+    /// Remove all readings.
+    pub fn reset(&mut self) {
+        self.anchors.clear();
+    }
+
+    fn anchor_offset(a: &ClockAnchor) -> f64 {
+        let system = a
+            .system
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        system - a.stream.as_secs()
+    }
+}
+
+impl Device {
+    /// Select the [`ReferenceClock`] source.
+    ///
+    /// The source is matched against the options of `device/referenceclock`, since their names
+    /// depend on the device and firmware.
+    pub fn set_reference_clock(&mut self, clock: ReferenceClock) -> crate::Result {
+        let entry = self.config_entry(REFERENCE_PATH)?;
+        let option = entry
+            .options
+            .iter()
+            .find(|o| clock.matches(o))
+            .ok_or(Error::ErrorValueInvalid)?
+            .clone();
+        self.set(REFERENCE_PATH, option)
+    }
+
+    /// Get the selected [`ReferenceClock`] source, `None` if the option is not recognized.
+    pub fn reference_clock(&mut self) -> std::result::Result<Option<ReferenceClock>, Error> {
+        let option = match self.get(REFERENCE_PATH)? {
+            ConfigItem::Enum { value, options, .. } => {
+                options.get(value as usize).cloned().unwrap_or_default()
+            }
+            ConfigItem::String(s) => s,
+            _ => return Ok(None),
+        };
+        Ok([
+            ReferenceClock::Gps,
+            ReferenceClock::External,
+            ReferenceClock::Internal,
+        ]
+        .into_iter()
+        .find(|c| c.matches(&option)))
+    }
+
+    /// Lock state of the reference clock, i.e., the first boolean health item with `lock` in
+    /// its path, or `None` if the device does not report it.
+    pub fn reference_locked(&mut self) -> std::result::Result<Option<bool>, Error> {
+        Ok(self
+            .health_leaves()?
+            .into_iter()
+            .find_map(|(path, item)| match item {
+                ConfigItem::Bool(b) if path.to_lowercase().contains("lock") => Some(b),
+                _ => None,
+            }))
+    }
+}
@@ -0,0 +1,228 @@
+//! Phase-coherent reception with both receivers, e.g., for direction finding.
+//!
+//! The receivers share the local oscillator, i.e., their samples are phase coherent, but the
+//! packets of the two data channels are queued independently:
+//!
+//! - enable both receivers with IQ output through [`Device::configure_coherent_rx()`]
+//! - read aligned sample pairs `(Rx1, Rx2)` with [`CoherentRx::read_pairs()`], or align packets
+//!   that are read otherwise with a [`PairAligner`]
+//! - estimate the phase difference of the receivers with [`phase_difference()`]
+use num_complex::Complex32;
+use std::collections::VecDeque;
+
+use crate::Device;
+use crate::Error;
+use crate::OutputFormat;
+use crate::Packet;
+use crate::Result;
+use crate::RxChannel;
+use crate::StreamTime;
+
+/// Samples of one receiver that are not paired yet.
+#[derive(Debug, Clone, Default)]
+struct Queue {
+    samples: VecDeque<Complex32>,
+    start: StreamTime,
+    sample_rate: f64,
+    frequency: f64,
+}
+
+impl Queue {
+    fn end(&self) -> StreamTime {
+        self.start
+            .sample_time(self.samples.len() as i64, self.sample_rate)
+    }
+
+    /// Drop `n` samples from the front.
+    fn skip(&mut self, n: usize) -> usize {
+        let n = n.min(self.samples.len());
+        self.samples.drain(..n);
+        self.start = self.start.sample_time(n as i64, self.sample_rate);
+        n
+    }
+}
+
+/// Aligns the IQ streams of the two receivers by timestamp and pairs their samples.
+///
+/// Samples, that have no counterpart in the other stream, e.g., after a gap or since one
+/// receiver started earlier, are dropped. Streams are only paired, if they have the same sample
+/// rate and center frequency.
+#[derive(Debug, Clone, Default)]
+pub struct PairAligner {
+    queues: [Queue; 2],
+    dropped: u64,
+}
+
+impl PairAligner {
+    /// Create an aligner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the IQ samples of a [`Packet`] of receiver `rx` (0 for Rx1, 1 for Rx2).
+    ///
+    /// Packets without sample rate, i.e., without samples, are ignored.
+    pub fn push(&mut self, rx: usize, packet: &Packet) {
+        if let Some(rate) = packet.sample_rate() {
+            self.push_samples(
+                rx,
+                packet.samples(),
+                packet.start_stream_time(),
+                rate,
+                packet.start_frequency(),
+            );
+        }
+    }
+
+    /// Add IQ samples of receiver `rx`, starting at `start`.
+    ///
+    /// Panics, if `rx` is not 0 or 1.
+    pub fn push_samples(
+        &mut self,
+        rx: usize,
+        samples: &[Complex32],
+        start: StreamTime,
+        sample_rate: f64,
+        frequency: f64,
+    ) {
+        assert!(rx < 2, "receiver {rx} out of range (2 receivers)");
+        let q = &mut self.queues[rx];
+        // restart after gaps and frequency or rate changes
+        if !q.samples.is_empty()
+            && (q.sample_rate != sample_rate
+                || q.frequency != frequency
+                || !q.end().approx_eq(start, 0.5 / sample_rate))
+        {
+            self.dropped += q.samples.len() as u64;
+            q.samples.clear();
+        }
+        if q.samples.is_empty() {
+            q.start = start;
+            q.sample_rate = sample_rate;
+            q.frequency = frequency;
+        }
+        q.samples.extend(samples);
+    }
+
+    /// Time after the last queued sample of receiver `rx`, `None` if there are no samples.
+    pub fn end(&self, rx: usize) -> Option<StreamTime> {
+        let q = &self.queues[rx];
+        (!q.samples.is_empty()).then(|| q.end())
+    }
+
+    /// Append the aligned sample pairs `(Rx1, Rx2)` to `out`.
+    ///
+    /// Returns the time of the first appended pair or `None`, if no samples overlap yet.
+    pub fn pairs(&mut self, out: &mut Vec<(Complex32, Complex32)>) -> Option<StreamTime> {
+        let [a, b] = &mut self.queues;
+        if a.samples.is_empty() || b.samples.is_empty() {
+            return None;
+        }
+        if a.sample_rate != b.sample_rate || a.frequency != b.frequency {
+            // the older stream is stale, e.g., after retuning
+            let old = if a.start < b.start { a } else { b };
+            self.dropped += old.samples.len() as u64;
+            old.samples.clear();
+            return None;
+        }
+
+        // drop the samples of the earlier stream, until both start at the same time
+        let offset = a.start.sample_index(b.start, a.sample_rate);
+        let skipped = if offset > 0 {
+            a.skip(offset as usize)
+        } else {
+            b.skip(offset.unsigned_abs() as usize)
+        };
+        self.dropped += skipped as u64;
+
+        let n = a.samples.len().min(b.samples.len());
+        if n == 0 {
+            return None;
+        }
+        let time = a.start;
+        out.extend(a.samples.drain(..n).zip(b.samples.drain(..n)));
+        a.start = a.start.sample_time(n as i64, a.sample_rate);
+        b.start = a.start;
+        Some(time)
+    }
+
+    /// Number of samples that were dropped, since they could not be paired.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Drop all queued samples.
+    pub fn reset(&mut self) {
+        for q in &mut self.queues {
+            q.samples.clear();
+        }
+    }
+}
+
+/// Reader of aligned sample pairs of both receivers, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct CoherentRx {
+    aligner: PairAligner,
+}
+
+impl CoherentRx {
+    /// Create a reader.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read packets of data channels 0 and 1 until aligned pairs are available and append them
+    /// to `out`.
+    ///
+    /// Returns the time of the first appended pair. Packets are consumed. The channel that lags
+    /// behind is read first, so that the queues of the device are drained evenly. Blocks like
+    /// [`Device::packet()`].
+    pub fn read_pairs(
+        &mut self,
+        dev: &mut Device,
+        out: &mut Vec<(Complex32, Complex32)>,
+    ) -> std::result::Result<StreamTime, Error> {
+        loop {
+            if let Some(time) = self.aligner.pairs(out) {
+                return Ok(time);
+            }
+            let rx = match (self.aligner.end(0), self.aligner.end(1)) {
+                (None, _) => 0,
+                (_, None) => 1,
+                (Some(a), Some(b)) if a <= b => 0,
+                _ => 1,
+            };
+            let p = dev.packet(rx as i32)?;
+            self.aligner.push(rx, &p);
+            dev.consume(rx as i32)?;
+        }
+    }
+
+    /// Get the [`PairAligner`], e.g., for the number of dropped samples.
+    pub fn aligner(&self) -> &PairAligner {
+        &self.aligner
+    }
+}
+
+/// Mean phase of Rx2 relative to Rx1 in radians, in `(-pi, pi]`.
+///
+/// The phase is the argument of the sum of `rx2 * conj(rx1)`, i.e., pairs are weighted with
+/// their power.
+pub fn phase_difference(pairs: &[(Complex32, Complex32)]) -> f32 {
+    pairs
+        .iter()
+        .map(|(a, b)| b * a.conj())
+        .sum::<Complex32>()
+        .arg()
+}
+
+impl Device {
+    /// Configure the phase-coherent dual-receiver mode, i.e., enable both receivers
+    /// ([`RxChannel::Both`]) with IQ output on data channels 0 (Rx1) and 1 (Rx2).
+    ///
+    /// Read the aligned samples with [`CoherentRx`].
+    pub fn configure_coherent_rx(&mut self) -> Result {
+        self.set_rx_channel(RxChannel::Both)?;
+        self.set_output_format(OutputFormat::Iq)
+    }
+}
@@ -0,0 +1,416 @@
+use crate::Config;
+use crate::ConfigEntry;
+use crate::ConfigItem;
+use crate::ConfigType;
+use crate::Device;
+use crate::Error;
+use crate::Result;
+
+/// Value of a configuration parameter in a [`ConfigProfile`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
+pub enum ConfigValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// String parameter or the selected option of an enum parameter.
+    String(String),
+}
+
+impl ConfigValue {
+    /// Convert a [`ConfigItem`] to a value that can be written back to the device.
+    ///
+    /// Returns `None` for items that do not hold a value, i.e., groups, buttons, and blobs.
+    pub fn from_item(item: &ConfigItem) -> Option<Self> {
+        match item {
+            ConfigItem::Bool(b) => Some(ConfigValue::Bool(*b)),
+            ConfigItem::Enum { value, options, .. } => match options.get(*value as usize) {
+                Some(o) => Some(ConfigValue::String(o.clone())),
+                None => Some(ConfigValue::Int(*value)),
+            },
+            ConfigItem::Number(n) => Some(ConfigValue::Float(*n)),
+            ConfigItem::String(s) => Some(ConfigValue::String(s.clone())),
+            ConfigItem::Blob | ConfigItem::Button | ConfigItem::Group(_) | ConfigItem::Other => {
+                None
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigValue::Bool(b) => write!(f, "{b}"),
+            ConfigValue::Int(i) => write!(f, "{i}"),
+            ConfigValue::Float(v) => write!(f, "{v}"),
+            ConfigValue::String(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl ConfigValue {
+    /// Check if two values set the parameter to the same value.
+    ///
+    /// Numbers compare across integer, float, and boolean values with a relative tolerance, since
+    /// the device reports all numbers as floats.
+    pub fn same_as(&self, other: &ConfigValue) -> bool {
+        fn number(v: &ConfigValue) -> Option<f64> {
+            match v {
+                ConfigValue::Bool(b) => Some(*b as i64 as f64),
+                ConfigValue::Int(i) => Some(*i as f64),
+                ConfigValue::Float(f) => Some(*f),
+                ConfigValue::String(_) => None,
+            }
+        }
+        match (self, other) {
+            (ConfigValue::String(a), ConfigValue::String(b)) => a == b,
+            _ => match (number(self), number(other)) {
+                (Some(a), Some(b)) => a == b || (a - b).abs() <= 1e-9 * a.abs().max(b.abs()),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Difference of a configuration parameter between two [`ConfigProfile`]s.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConfigChange {
+    /// The parameter is only set in the new profile.
+    Added { path: String, value: ConfigValue },
+    /// The parameter is only set in the old profile.
+    Removed { path: String, value: ConfigValue },
+    /// The parameter has a different value.
+    Changed {
+        path: String,
+        old: ConfigValue,
+        new: ConfigValue,
+    },
+}
+
+impl ConfigChange {
+    /// Configuration path of the parameter.
+    pub fn path(&self) -> &str {
+        match self {
+            ConfigChange::Added { path, .. }
+            | ConfigChange::Removed { path, .. }
+            | ConfigChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigChange::Added { path, value } => write!(f, "+ {path} = {value}"),
+            ConfigChange::Removed { path, value } => write!(f, "- {path} = {value}"),
+            ConfigChange::Changed { path, old, new } => write!(f, "~ {path}: {old} -> {new}"),
+        }
+    }
+}
+
+/// Snapshot of the [`Device`] configuration.
+///
+/// A profile maps configuration paths (e.g., `main/centerfreq`) to values. Entries keep their
+/// insertion order, which is also the order in which they are applied. With the `serde` feature,
+/// profiles (de)serialize as maps, i.e., they can be stored as JSON or TOML.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigProfile {
+    entries: Vec<(String, ConfigValue)>,
+}
+
+impl ConfigProfile {
+    /// Create an empty profile.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the value of a configuration path.
+    pub fn get(&self, path: &str) -> Option<&ConfigValue> {
+        self.entries.iter().find(|(p, _)| p == path).map(|(_, v)| v)
+    }
+
+    /// Set the value of a configuration path.
+    ///
+    /// Existing entries are updated in place; new entries are appended.
+    pub fn set<S: Into<String>>(&mut self, path: S, value: ConfigValue) {
+        let path = path.into();
+        match self.entries.iter_mut().find(|(p, _)| *p == path) {
+            Some((_, v)) => *v = value,
+            None => self.entries.push((path, value)),
+        }
+    }
+
+    /// Remove a configuration path from the profile.
+    pub fn remove(&mut self, path: &str) -> Option<ConfigValue> {
+        let i = self.entries.iter().position(|(p, _)| p == path)?;
+        Some(self.entries.remove(i).1)
+    }
+
+    /// Iterate over paths and values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ConfigValue)> {
+        self.entries.iter().map(|(p, v)| (p.as_str(), v))
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the profile is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Changes from this profile to `other`.
+    ///
+    /// Removed and changed parameters are listed in the order of this profile, followed by the
+    /// added parameters in the order of `other`. Values are compared with
+    /// [`ConfigValue::same_as()`].
+    pub fn diff(&self, other: &ConfigProfile) -> Vec<ConfigChange> {
+        let mut changes = Vec::new();
+        for (path, old) in self.iter() {
+            match other.get(path) {
+                None => changes.push(ConfigChange::Removed {
+                    path: path.to_string(),
+                    value: old.clone(),
+                }),
+                Some(new) if !old.same_as(new) => changes.push(ConfigChange::Changed {
+                    path: path.to_string(),
+                    old: old.clone(),
+                    new: new.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (path, value) in other.iter() {
+            if self.get(path).is_none() {
+                changes.push(ConfigChange::Added {
+                    path: path.to_string(),
+                    value: value.clone(),
+                });
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ConfigProfile {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (p, v) in &self.entries {
+            map.serialize_entry(p, v)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ConfigProfile {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = ConfigProfile;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a map of configuration paths to values")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut access: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut profile = ConfigProfile::new();
+                while let Some((p, v)) = access.next_entry::<String, ConfigValue>()? {
+                    profile.set(p, v);
+                }
+                Ok(profile)
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+impl Device {
+    /// Export all configuration parameters that hold a value into a [`ConfigProfile`].
+    pub fn export_config(&mut self) -> std::result::Result<ConfigProfile, Error> {
+        let mut root = Config::new();
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+
+        let mut leaves = Vec::new();
+        self.config_leaves(&mut root, "", &mut leaves)?;
+
+        let mut profile = ConfigProfile::new();
+        for (path, item) in leaves {
+            if let Some(v) = ConfigValue::from_item(&item) {
+                profile.set(path, v);
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// Compare a [`ConfigProfile`] against the current configuration of the device.
+    ///
+    /// Lists the parameters of the profile that the device does not have (anymore) as
+    /// [`ConfigChange::Removed`] and parameters with a different value as
+    /// [`ConfigChange::Changed`], from the profile to the device value. Parameters that the
+    /// profile does not set are ignored. An empty result means that the profile is in effect.
+    pub fn diff_against(
+        &mut self,
+        profile: &ConfigProfile,
+    ) -> std::result::Result<Vec<ConfigChange>, Error> {
+        let current = self.export_config()?;
+        let mut changes = profile.diff(&current);
+        changes.retain(|c| !matches!(c, ConfigChange::Added { .. }));
+        Ok(changes)
+    }
+
+    /// Apply all values of a [`ConfigProfile`] in order.
+    pub fn apply_config(&mut self, profile: &ConfigProfile) -> Result {
+        for (path, value) in profile.iter() {
+            self.set_value(path, value)?;
+        }
+        Ok(())
+    }
+
+    /// Set an enum parameter to the option that matches `variant`.
+    ///
+    /// The variant is matched against the options of the parameter, ignoring case, whitespace,
+    /// and separators, e.g., `IQ` selects `iq` and `1/64` selects `1 / 64`. Unique prefixes and
+    /// options with a single typo are accepted, too. Returns the selected option,
+    /// [`Error::InvalidOption`] with the valid options, or [`Error::OptionDisabled`] with the
+    /// enabled options, if the device currently rejects the option.
+    pub fn set_enum<S: AsRef<str>>(
+        &mut self,
+        path: S,
+        variant: &str,
+    ) -> std::result::Result<String, Error> {
+        let path = path.as_ref();
+        let entry = self.config_entry(path)?;
+        if entry.kind != ConfigType::Enum {
+            return Err(Error::ErrorInvalidParameter);
+        }
+
+        match match_option(&entry.options, variant) {
+            Some(option) => {
+                let option = option.to_string();
+                if let Some(i) = entry.options.iter().position(|o| *o == option) {
+                    check_option(path, &entry, i)?;
+                }
+                self.set(path, &option)?;
+                Ok(option)
+            }
+            None => Err(Error::InvalidOption {
+                path: path.to_string(),
+                value: variant.to_string(),
+                options: entry.options,
+            }),
+        }
+    }
+
+    /// Check that a value of an enum parameter does not select a disabled option.
+    ///
+    /// Values of other parameters and unknown options are left to the device.
+    pub(crate) fn check_enabled(&mut self, path: &str, value: &ConfigValue) -> Result {
+        let entry = match self.config_entry(path) {
+            Ok(e) if e.kind == ConfigType::Enum => e,
+            _ => return Ok(()),
+        };
+        let i = match value {
+            ConfigValue::String(s) => entry.options.iter().position(|o| o == s),
+            ConfigValue::Int(i) => usize::try_from(*i).ok(),
+            _ => None,
+        };
+        match i {
+            Some(i) => check_option(path, &entry, i),
+            None => Ok(()),
+        }
+    }
+
+    /// Set [`Device`] configuration parameter from a [`ConfigValue`].
+    pub fn set_value<S: AsRef<str>>(&mut self, path: S, value: &ConfigValue) -> Result {
+        match value {
+            ConfigValue::Bool(b) => self.set_int(path, *b as i64),
+            ConfigValue::Int(i) => self.set_int(path, *i),
+            ConfigValue::Float(f) => self.set_float(path, *f),
+            ConfigValue::String(s) => self.set(path, s),
+        }
+    }
+}
+
+/// Return [`Error::OptionDisabled`], if option `i` of the enum parameter is disabled.
+fn check_option(path: &str, entry: &ConfigEntry, i: usize) -> Result {
+    if !entry.is_disabled(i) {
+        return Ok(());
+    }
+    Err(Error::OptionDisabled {
+        path: path.to_string(),
+        value: entry
+            .options
+            .get(i)
+            .cloned()
+            .unwrap_or_else(|| i.to_string()),
+        options: entry.enabled_options(),
+    })
+}
+
+/// Lowercase alphanumeric characters of an option.
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance of two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = (prev + (ca != *cb) as usize).min(row[j] + 1).min(cur + 1);
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// The only item of an iterator.
+fn unique<'a>(mut it: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+    match (it.next(), it.next()) {
+        (Some(o), None) => Some(o),
+        _ => None,
+    }
+}
+
+/// Find the option that matches `variant` exactly, normalized, by unique prefix, or with a
+/// single edit.
+fn match_option<'a>(options: &'a [String], variant: &str) -> Option<&'a str> {
+    if let Some(o) = options.iter().find(|o| *o == variant) {
+        return Some(o);
+    }
+    let v = normalize(variant);
+    if v.is_empty() {
+        return None;
+    }
+    unique(options.iter().filter(|o| normalize(o) == v))
+        .or_else(|| unique(options.iter().filter(|o| normalize(o).starts_with(&v))))
+        .or_else(|| unique(options.iter().filter(|o| distance(&normalize(o), &v) <= 1)))
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::dsp::IqCorrector;
+use crate::Device;
+use crate::Packet;
+use crate::PayloadKind;
+
+/// Host-side IQ correction of a [`Device`], see [`Device::set_iq_correction()`].
+#[derive(Debug, Clone)]
+pub(crate) struct Correction {
+    /// Settings of the correctors of new channels.
+    template: IqCorrector,
+    /// Corrector and start time of the last corrected packet per data channel.
+    channels: HashMap<i32, (IqCorrector, f64)>,
+}
+
+impl Device {
+    /// Enable the host-side DC offset and IQ imbalance correction of received packets with the
+    /// settings of `corrector`, or disable it with `None`.
+    ///
+    /// IQ packets are corrected in place, when they are fetched, i.e., the correction applies
+    /// to [`packet()`](Self::packet), [`try_packet()`](Self::try_packet),
+    /// [`select()`](Self::select), and all streaming helpers that use them. Every data channel
+    /// has its own [`IqCorrector`]. Each packet is corrected once, i.e., fetching a packet again
+    /// before it is consumed returns the same samples. Packets with interleaved samples of
+    /// multiple receivers are not corrected.
+    pub fn set_iq_correction(&mut self, corrector: Option<IqCorrector>) {
+        self.correction = corrector.map(|template| Correction {
+            template,
+            channels: HashMap::new(),
+        });
+    }
+
+    /// Get the [`IqCorrector`] of data channel `chan` with its current estimates.
+    ///
+    /// Returns `None`, if the correction is disabled or no packet of the channel was corrected.
+    pub fn iq_correction(&self, chan: i32) -> Option<&IqCorrector> {
+        self.correction
+            .as_ref()
+            .and_then(|c| c.channels.get(&chan))
+            .map(|(c, _)| c)
+    }
+
+    /// Correct a fetched packet, if the correction is enabled and the packet is new.
+    pub(crate) fn correct_packet(&mut self, chan: i32, packet: &Packet) {
+        let Some(correction) = self.correction.as_mut() else {
+            return;
+        };
+        if packet.payload_kind() != PayloadKind::Iq
+            || packet.size() != 2
+            || packet.stride() != 2
+            || packet.inner.fp32.is_null()
+        {
+            return;
+        }
+        let template = &correction.template;
+        let (corrector, last) = correction
+            .channels
+            .entry(chan)
+            .or_insert_with(|| (template.clone(), f64::NAN));
+        if *last == packet.start_time() {
+            return;
+        }
+        *last = packet.start_time();
+
+        // the payload is owned by the library until the packet is consumed
+        let samples = unsafe {
+            std::slice::from_raw_parts_mut(
+                packet.inner.fp32 as *mut num_complex::Complex32,
+                packet.num().max(0) as usize,
+            )
+        };
+        corrector.process(samples);
+    }
+}
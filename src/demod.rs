@@ -0,0 +1,227 @@
+//! Demodulation of IQ streams to audio.
+use num_complex::Complex32;
+use std::f64::consts::PI;
+
+use crate::dsp::Resampler;
+use crate::Packet;
+
+/// Channel rate of wideband FM, before decimation to the audio rate.
+const WFM_RATE: f64 = 240e3;
+/// Frequency deviation of narrowband FM.
+const NFM_DEVIATION: f64 = 5e3;
+/// Frequency deviation of wideband FM.
+const WFM_DEVIATION: f64 = 75e3;
+/// Audio bandwidth of single-sideband signals.
+const SSB_BANDWIDTH: f64 = 2.8e3;
+/// Lower audio frequency of single-sideband signals.
+const SSB_LOW: f64 = 200.0;
+
+/// Modulation of the received signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Amplitude modulation.
+    Am,
+    /// Narrowband FM, e.g., voice radio with 5 kHz deviation.
+    Nfm,
+    /// Wideband FM, e.g., broadcast radio with 75 kHz deviation.
+    Wfm,
+    /// Upper sideband.
+    Usb,
+    /// Lower sideband.
+    Lsb,
+}
+
+/// Low-pass FIR filter for complex samples.
+#[derive(Debug, Clone)]
+struct Fir {
+    taps: Vec<f32>,
+    history: Vec<Complex32>,
+}
+
+impl Fir {
+    /// Windowed sinc with `cutoff` relative to the sample rate.
+    fn lowpass(cutoff: f64, n: usize) -> Self {
+        let center = (n - 1) as f64 / 2.0;
+        let taps = (0..n)
+            .map(|i| {
+                let t = i as f64 - center;
+                let sinc = if t == 0.0 {
+                    2.0 * cutoff
+                } else {
+                    (2.0 * PI * cutoff * t).sin() / (PI * t)
+                };
+                let w = 0.54 - 0.46 * (2.0 * PI * i as f64 / (n - 1) as f64).cos();
+                (sinc * w) as f32
+            })
+            .collect();
+        Self {
+            taps,
+            history: vec![Complex32::new(0.0, 0.0); n - 1],
+        }
+    }
+
+    fn filter(&mut self, x: Complex32) -> Complex32 {
+        self.history.push(x);
+        let n = self.taps.len();
+        let y = self.history[self.history.len() - n..]
+            .iter()
+            .rev()
+            .zip(&self.taps)
+            .map(|(x, t)| x * *t)
+            .sum();
+        self.history.remove(0);
+        y
+    }
+}
+
+/// AM, FM, and SSB demodulator that converts IQ samples to audio.
+///
+/// The IQ stream is resampled to the channel rate of the mode, demodulated, and, for wideband FM,
+/// de-emphasized and decimated to the audio rate. The demodulator keeps state between calls,
+/// i.e., it can be fed with consecutive packets of a stream tuned to the signal.
+#[derive(Debug, Clone)]
+pub struct Demodulator {
+    mode: Mode,
+    channel: Resampler,
+    audio: Option<Resampler>,
+    iq: Vec<Complex32>,
+    mono: Vec<Complex32>,
+    last: Complex32,
+    dc: f32,
+    deemphasis: f32,
+    deemphasis_state: f32,
+    ssb: Option<(Fir, f64)>,
+}
+
+impl Demodulator {
+    /// Create a demodulator for IQ samples at `input_rate` with audio output at `audio_rate`.
+    pub fn new(mode: Mode, input_rate: f64, audio_rate: f64) -> Self {
+        let (channel, audio) = match mode {
+            Mode::Wfm => (
+                Resampler::new(input_rate, WFM_RATE),
+                Some(Resampler::new(WFM_RATE, audio_rate)),
+            ),
+            _ => (Resampler::new(input_rate, audio_rate), None),
+        };
+
+        let ssb = match mode {
+            Mode::Usb | Mode::Lsb => {
+                let rate = channel.output_rate();
+                let cutoff = SSB_BANDWIDTH / 2.0 / rate;
+                Some((Fir::lowpass(cutoff, 129), 0.0))
+            }
+            _ => None,
+        };
+
+        let mut d = Self {
+            mode,
+            channel,
+            audio,
+            iq: Vec::new(),
+            mono: Vec::new(),
+            last: Complex32::new(0.0, 0.0),
+            dc: 0.0,
+            deemphasis: 0.0,
+            deemphasis_state: 0.0,
+            ssb,
+        };
+        d.set_deemphasis(50e-6);
+        d
+    }
+
+    /// Modulation of the demodulator.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Exact audio sample rate.
+    pub fn audio_rate(&self) -> f64 {
+        match &self.audio {
+            Some(a) => a.output_rate(),
+            None => self.channel.output_rate(),
+        }
+    }
+
+    /// Set the de-emphasis time constant of wideband FM (default: 50 µs, 75 µs in the Americas).
+    pub fn set_deemphasis(&mut self, tau: f64) {
+        let rate = self.channel.output_rate();
+        self.deemphasis = (1.0 - (-1.0 / (rate * tau)).exp()) as f32;
+    }
+
+    /// Reset the demodulator state.
+    pub fn reset(&mut self) {
+        self.channel.reset();
+        if let Some(a) = self.audio.as_mut() {
+            a.reset();
+        }
+        self.last = Complex32::new(0.0, 0.0);
+        self.dc = 0.0;
+        self.deemphasis_state = 0.0;
+        if let Some((fir, phase)) = self.ssb.as_mut() {
+            fir.history.fill(Complex32::new(0.0, 0.0));
+            *phase = 0.0;
+        }
+    }
+
+    /// Demodulate `input` and append the audio samples to `output`.
+    pub fn process(&mut self, input: &[Complex32], output: &mut Vec<f32>) {
+        self.iq.clear();
+        self.channel.process(input, &mut self.iq);
+        let rate = self.channel.output_rate();
+
+        match self.mode {
+            Mode::Am => {
+                for s in &self.iq {
+                    // remove the carrier with a slow DC blocker
+                    let env = s.norm();
+                    self.dc += 0.001 * (env - self.dc);
+                    output.push(env - self.dc);
+                }
+            }
+            Mode::Nfm => {
+                let gain = (rate / (2.0 * PI * NFM_DEVIATION)) as f32;
+                for s in &self.iq {
+                    output.push((s * self.last.conj()).arg() * gain);
+                    self.last = *s;
+                }
+            }
+            Mode::Wfm => {
+                let gain = (rate / (2.0 * PI * WFM_DEVIATION)) as f32;
+                self.mono.clear();
+                for s in &self.iq {
+                    let x = (s * self.last.conj()).arg() * gain;
+                    self.last = *s;
+                    self.deemphasis_state += self.deemphasis * (x - self.deemphasis_state);
+                    self.mono.push(Complex32::new(self.deemphasis_state, 0.0));
+                }
+                self.iq.clear();
+                if let Some(a) = self.audio.as_mut() {
+                    a.process(&self.mono, &mut self.iq);
+                }
+                output.extend(self.iq.iter().map(|s| s.re));
+            }
+            Mode::Usb | Mode::Lsb => {
+                // shift the sideband to baseband, low-pass, shift back, and take the real part
+                let (fir, phase) = self.ssb.as_mut().unwrap();
+                let center = SSB_LOW + SSB_BANDWIDTH / 2.0;
+                let shift = if self.mode == Mode::Usb {
+                    center
+                } else {
+                    -center
+                };
+                let step = 2.0 * PI * shift / rate;
+                for s in &self.iq {
+                    let lo = Complex32::from_polar(1.0, *phase as f32);
+                    let y = fir.filter(s * lo.conj()) * lo;
+                    output.push(y.re);
+                    *phase = (*phase + step) % (2.0 * PI);
+                }
+            }
+        }
+    }
+
+    /// Demodulate the IQ samples of a [`Packet`] and append the audio samples to `output`.
+    pub fn process_packet(&mut self, packet: &Packet, output: &mut Vec<f32>) {
+        self.process(packet.samples(), output);
+    }
+}
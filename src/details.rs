@@ -0,0 +1,120 @@
+use crate::ConfigItem;
+use crate::Device;
+use crate::Error;
+use crate::Hz;
+
+/// Hardware model of a Spectran device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HardwareModel {
+    /// Spectran V6.
+    V6,
+    /// Spectran V6 Eco.
+    V6Eco,
+    /// Spectran V6 Plus.
+    V6Plus,
+    /// Spectran V6 X.
+    X,
+}
+
+impl HardwareModel {
+    /// Parse a model name as reported by the device, e.g., `SPECTRAN V6 ECO`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        let mut words = name.split(|c: char| !c.is_ascii_alphanumeric());
+        if name.contains("eco") {
+            Some(HardwareModel::V6Eco)
+        } else if name.contains("plus") {
+            Some(HardwareModel::V6Plus)
+        } else if words.any(|w| w == "x" || w == "v6x") {
+            Some(HardwareModel::X)
+        } else if name.contains("v6") {
+            Some(HardwareModel::V6)
+        } else {
+            None
+        }
+    }
+
+    /// Highest receiver clock of the model without bandwidth options.
+    pub fn max_receiver_clock(&self) -> Hz {
+        match self {
+            HardwareModel::V6Eco => Hz(92e6),
+            HardwareModel::V6 => Hz(184e6),
+            HardwareModel::V6Plus | HardwareModel::X => Hz(245e6),
+        }
+    }
+
+    /// Check if the model can transmit without the TX option.
+    pub fn has_tx(&self) -> bool {
+        matches!(self, HardwareModel::V6Plus | HardwareModel::X)
+    }
+}
+
+/// Firmware and hardware information, returned by [`Device::info()`].
+///
+/// Items are looked up by name in the configuration and health trees, since their paths depend
+/// on the device and firmware. Items that are not reported are `None` or empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceDetails {
+    /// Serial number.
+    pub serial: String,
+    /// Hardware model, if recognized.
+    pub model: Option<HardwareModel>,
+    /// Model name as reported by the device.
+    pub model_name: Option<String>,
+    /// Firmware version.
+    pub firmware_version: Option<String>,
+    /// Date of the factory calibration.
+    pub calibration_date: Option<String>,
+    /// Installed hardware or license options.
+    pub options: Vec<String>,
+}
+
+/// Value of a leaf as string, `None` for items without a value.
+fn text(item: &ConfigItem) -> Option<String> {
+    match item {
+        ConfigItem::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        ConfigItem::Number(n) => Some(n.to_string()),
+        ConfigItem::Enum { value, options, .. } => options.get(*value as usize).cloned(),
+        _ => None,
+    }
+}
+
+impl Device {
+    /// Gather firmware version, hardware model, calibration date, and options of the device.
+    pub fn info(&mut self) -> std::result::Result<DeviceDetails, Error> {
+        let mut details = DeviceDetails {
+            serial: self.serial.to_string_lossy(),
+            ..Default::default()
+        };
+
+        let mut leaves = self.health_leaves()?;
+        leaves.extend(self.root_leaves()?);
+
+        for (path, item) in leaves {
+            let name = path.rsplit('/').next().unwrap_or_default().to_lowercase();
+            if name.contains("option") || name.contains("license") {
+                match &item {
+                    ConfigItem::Bool(true) => details.options.push(name),
+                    ConfigItem::String(s) => details.options.extend(
+                        s.split(',')
+                            .map(str::trim)
+                            .filter(|o| !o.is_empty())
+                            .map(String::from),
+                    ),
+                    _ => {}
+                }
+            } else if name.contains("firmware") || name == "fwversion" || name == "version" {
+                details.firmware_version = details.firmware_version.or_else(|| text(&item));
+            } else if name.contains("model") || name.contains("product") {
+                details.model_name = details.model_name.or_else(|| text(&item));
+            } else if name.contains("calib") && (name.contains("date") || name.contains("time")) {
+                details.calibration_date = details.calibration_date.or_else(|| text(&item));
+            }
+        }
+
+        details.model = details.model_name.as_deref().and_then(HardwareModel::parse);
+        Ok(details)
+    }
+}
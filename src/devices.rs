@@ -0,0 +1,91 @@
+use widestring::WideCString;
+
+use crate::ApiHandle;
+use crate::DeviceInfo;
+use crate::Error;
+
+/// Lazy enumeration of detected devices, returned by [`ApiHandle::iter_devices()`].
+///
+/// Every call to `next()` queries the next device from the library, i.e., iteration can stop
+/// early, e.g., with `find()`. The iterator ends after the first error.
+pub struct DeviceIter<'a> {
+    api: &'a mut ApiHandle,
+    device_type: WideCString,
+    index: i32,
+    done: bool,
+}
+
+impl Iterator for DeviceIter<'_> {
+    type Item = std::result::Result<DeviceInfo, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut di = DeviceInfo::new();
+        let r = unsafe {
+            ffi!(AARTSAAPI_EnumDevice(
+                &mut self.api.inner,
+                self.device_type.as_ptr(),
+                self.index,
+                &mut di.inner,
+            ); device_type = self.device_type.to_string_lossy(), index = self.index)
+        };
+        self.index += 1;
+        match r {
+            Ok(()) => Some(Ok(di)),
+            Err(Error::Empty) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl ApiHandle {
+    /// Iterate over the detected devices of a type, e.g., `spectranv6`.
+    pub fn iter_devices(&mut self, device_type: &str) -> DeviceIter<'_> {
+        DeviceIter {
+            api: self,
+            device_type: WideCString::from_str_truncate(device_type),
+            index: 0,
+            done: false,
+        }
+    }
+
+    /// Rescan and merge the detected devices into `devices`.
+    ///
+    /// Entries of known devices keep their position and are updated. Devices that disappeared
+    /// are kept, but marked as not ready and not active. New devices are appended. Returns the
+    /// number of new devices.
+    pub fn refresh_devices(
+        &mut self,
+        devices: &mut Vec<DeviceInfo>,
+    ) -> std::result::Result<usize, Error> {
+        self.rescan_devices()?;
+        let detected = self.devices()?;
+
+        for d in devices.iter_mut() {
+            match detected.iter().find(|n| n.serial() == d.serial()) {
+                Some(n) => *d = n.clone(),
+                None => {
+                    d.inner.ready = false;
+                    d.inner.active = false;
+                }
+            }
+        }
+
+        let mut added = 0;
+        for n in detected {
+            if !devices.iter().any(|d| d.serial() == n.serial()) {
+                devices.push(n);
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+}
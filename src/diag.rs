@@ -0,0 +1,106 @@
+use std::cell::RefCell;
+
+use crate::Error;
+
+thread_local! {
+    static LAST: RefCell<Option<Diagnostic>> = const { RefCell::new(None) };
+}
+
+/// Failed call of an RTSA library function, see [`last_diagnostic()`].
+///
+/// The RTSA API reports bare result codes without further details. Diagnostics record, which
+/// function failed and the arguments that are relevant to find the cause, e.g., the serial
+/// number, data channel, or configuration path.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Name of the function, e.g., `AARTSAAPI_ConfigFind`.
+    pub function: &'static str,
+    /// Names and values of the relevant arguments.
+    pub args: Vec<(&'static str, String)>,
+    /// Result code of the function.
+    pub code: u32,
+    /// Error of the result code.
+    pub error: Error,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}(", self.function)?;
+        for (i, (name, value)) in self.args.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{name}: {value}")?;
+        }
+        write!(f, ") returned {:#010x} ({})", self.code, self.error)
+    }
+}
+
+/// Get the last failed RTSA library call on this thread.
+///
+/// Calls that return [`Error::Empty`], [`Error::Retry`], or a device state are not failures.
+pub fn last_diagnostic() -> Option<Diagnostic> {
+    LAST.with(|l| l.borrow().clone())
+}
+
+/// Record a failed call, formatting the arguments only for warnings and errors.
+pub(crate) fn record<F: FnOnce() -> Vec<(&'static str, String)>>(
+    function: &'static str,
+    code: u32,
+    error: &Error,
+    args: F,
+) {
+    if code < 0x4000_0000 {
+        return;
+    }
+    let d = Diagnostic {
+        function,
+        args: args(),
+        code,
+        error: error.clone(),
+    };
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(diagnostic = %d, "RTSA call failed");
+
+    LAST.with(|l| *l.borrow_mut() = Some(d));
+}
+
+/// [`Error`] with the [`Diagnostic`] of the failed call, returned by [`Error::with_detail()`].
+#[derive(Debug, Clone)]
+pub struct DetailedError {
+    /// The error.
+    pub error: Error,
+    /// Diagnostic of the call that caused the error, if known.
+    pub detail: Option<Diagnostic>,
+}
+
+impl std::fmt::Display for DetailedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.detail {
+            Some(d) => write!(f, "{}: {d}", self.error),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+impl std::error::Error for DetailedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl Error {
+    /// Attach the [`Diagnostic`] of the last failed call on this thread, if it returned this
+    /// error.
+    ///
+    /// Has to be called on the thread, where the error occurred, before other calls fail.
+    pub fn with_detail(self) -> DetailedError {
+        let detail = last_diagnostic()
+            .filter(|d| std::mem::discriminant(&d.error) == std::mem::discriminant(&self));
+        DetailedError {
+            error: self,
+            detail,
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use widestring::WideCString;
+
+use crate::Config;
+use crate::ConfigInfo;
+use crate::ConfigItem;
+use crate::ConfigType;
+use crate::Device;
+use crate::Error;
+
+/// Description of a configuration parameter, returned by [`Device::find_config()`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigEntry {
+    /// Full path, e.g., `device/fft0/fftmergemode`.
+    pub path: String,
+    /// Human-readable title.
+    pub title: String,
+    /// Type of the parameter.
+    pub kind: ConfigType,
+    /// Minimum value of number parameters.
+    pub min: f64,
+    /// Maximum value of number parameters.
+    pub max: f64,
+    /// Step size of number parameters, zero if continuous.
+    pub step: f64,
+    /// Unit of number parameters.
+    pub unit: String,
+    /// Options of enum parameters.
+    pub options: Vec<String>,
+    /// Mask of the options that the device currently rejects, i.e., bit `i` disables option `i`.
+    pub disabled_options: u64,
+    /// Current value.
+    pub value: ConfigItem,
+}
+
+impl Device {
+    /// Find configuration parameters, whose path contains `query` (case-insensitive).
+    ///
+    /// Groups are not returned, only their leaves, e.g., querying `fft0` returns all parameters
+    /// of the first FFT.
+    pub fn find_config(&mut self, query: &str) -> std::result::Result<Vec<ConfigEntry>, Error> {
+        let query = query.to_lowercase();
+        self.find_config_by(|path| path.to_lowercase().contains(&query))
+    }
+
+    /// Find configuration parameters, whose path matches the predicate, e.g., a regex.
+    pub fn find_config_by<F: FnMut(&str) -> bool>(
+        &mut self,
+        mut predicate: F,
+    ) -> std::result::Result<Vec<ConfigEntry>, Error> {
+        let mut root = Config::new();
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+
+        let mut out = Vec::new();
+        self.config_entries(&mut root, "", &mut predicate, &mut out)?;
+        Ok(out)
+    }
+
+    /// Get the description of the configuration parameter at `path`.
+    pub fn config_entry<S: AsRef<str>>(
+        &mut self,
+        path: S,
+    ) -> std::result::Result<ConfigEntry, Error> {
+        let mut root = Config::new();
+        let mut node = Config::new();
+        let p = WideCString::from_str_truncate(path.as_ref());
+
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+        unsafe {
+            ffi!(AARTSAAPI_ConfigFind(
+                &mut self.inner,
+                &mut root.inner,
+                &mut node.inner,
+                p.as_ptr(),
+            ); path = path.as_ref())?
+        };
+
+        self.entry(&mut node, path.as_ref().to_string())
+    }
+
+    fn config_entries<F: FnMut(&str) -> bool>(
+        &mut self,
+        group: &mut Config,
+        prefix: &str,
+        predicate: &mut F,
+        out: &mut Vec<ConfigEntry>,
+    ) -> crate::Result {
+        let mut node = Config::new();
+        let mut r = unsafe {
+            ffi!(AARTSAAPI_ConfigFirst(
+                &mut self.inner,
+                &mut group.inner,
+                &mut node.inner,
+            ))
+        };
+
+        loop {
+            match r {
+                Ok(()) => {}
+                Err(Error::Empty) => break,
+                Err(e) => return Err(e),
+            }
+
+            let info = self.node_info(&mut node)?;
+            let name = WideCString::from_vec_truncate(info.inner.name).to_string_lossy();
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}/{name}")
+            };
+
+            match ConfigType::from(info.inner.type_) {
+                ConfigType::Group => self.config_entries(&mut node, &path, predicate, out)?,
+                _ => {
+                    if predicate(&path) {
+                        out.push(self.entry(&mut node, path)?);
+                    }
+                }
+            }
+
+            r = unsafe {
+                ffi!(AARTSAAPI_ConfigNext(
+                    &mut self.inner,
+                    &mut group.inner,
+                    &mut node.inner,
+                ))
+            };
+        }
+
+        Ok(())
+    }
+
+    fn node_info(&mut self, node: &mut Config) -> std::result::Result<ConfigInfo, Error> {
+        let mut info = ConfigInfo::new();
+        unsafe {
+            ffi!(AARTSAAPI_ConfigGetInfo(
+                &mut self.inner,
+                &mut node.inner,
+                &mut info.inner,
+            ))?
+        };
+        Ok(info)
+    }
+
+    fn entry(
+        &mut self,
+        node: &mut Config,
+        path: String,
+    ) -> std::result::Result<ConfigEntry, Error> {
+        let info = self.node_info(node)?;
+        let kind = ConfigType::from(info.inner.type_);
+        let options = match kind {
+            ConfigType::Enum => WideCString::from_vec_truncate(info.inner.options)
+                .to_string_lossy()
+                .split(';')
+                .map(|s| s.into())
+                .collect(),
+            _ => Vec::new(),
+        };
+        let (_, value) = self.parse_item(node)?;
+
+        Ok(ConfigEntry {
+            path,
+            title: WideCString::from_vec_truncate(info.inner.title).to_string_lossy(),
+            kind,
+            min: info.inner.minValue,
+            max: info.inner.maxValue,
+            step: info.inner.stepValue,
+            unit: WideCString::from_vec_truncate(info.inner.unit).to_string_lossy(),
+            options,
+            disabled_options: info.inner.disabledOptions,
+            value,
+        })
+    }
+}
+
+impl ConfigEntry {
+    /// Check if option `i` of an enum parameter is disabled.
+    pub fn is_disabled(&self, i: usize) -> bool {
+        option_disabled(self.disabled_options, i)
+    }
+
+    /// Options of an enum parameter that the device accepts.
+    pub fn enabled_options(&self) -> Vec<String> {
+        self.options
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.is_disabled(*i))
+            .map(|(_, o)| o.clone())
+            .collect()
+    }
+}
+
+/// Check if bit `i` of a mask of disabled options is set.
+pub(crate) fn option_disabled(mask: u64, i: usize) -> bool {
+    i < 64 && mask & (1 << i) != 0
+}
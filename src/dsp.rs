@@ -0,0 +1,11 @@
+//! Signal processing on received streams.
+mod channelizer;
+pub use channelizer::ChannelOutput;
+pub use channelizer::Channelizer;
+mod iq_correction;
+pub use iq_correction::IqCorrector;
+mod resampler;
+pub use resampler::Resampler;
+mod spectrum;
+pub use spectrum::SpectrumEngine;
+pub use spectrum::Window;
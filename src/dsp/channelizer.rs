@@ -0,0 +1,188 @@
+use num_complex::Complex32;
+use rustfft::Fft;
+use rustfft::FftPlanner;
+use std::sync::Arc;
+
+use crate::Packet;
+use crate::StreamTime;
+
+/// Default length of the prototype filter relative to the number of channels.
+const TAPS_PER_CHANNEL: usize = 12;
+
+/// IQ samples of one channel of a [`Channelizer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelOutput {
+    /// Index of the channel.
+    pub index: usize,
+    /// Center frequency of the channel in Hz.
+    pub frequency: f64,
+    /// Sample rate of the channel in Hz.
+    pub sample_rate: f64,
+    /// Time of the first sample, compensated for the filter delay.
+    pub start: StreamTime,
+    /// IQ samples, shifted to baseband.
+    pub samples: Vec<Complex32>,
+}
+
+/// Polyphase filter bank that splits a wideband IQ stream into equally spaced channels.
+///
+/// The channelizer is critically sampled, i.e., `N` channels are spaced by `input_rate / N` and
+/// have a sample rate of `input_rate / N`. Channel `k` is centered at `k * input_rate / N`
+/// relative to the input center frequency, with channels in the upper half of the indices at
+/// negative offsets, like the bins of an FFT. Adjacent channels overlap at their -6 dB points.
+/// The channelizer keeps state between calls, i.e., it can be fed with consecutive packets of a
+/// stream.
+pub struct Channelizer {
+    channels: usize,
+    taps_per_phase: usize,
+    taps: Vec<f32>,
+    input_rate: f64,
+    history: Vec<Complex32>,
+    pos: usize,
+    fft: Arc<dyn Fft<f32>>,
+    buffer: Vec<Complex32>,
+}
+
+impl Channelizer {
+    /// Create a channelizer with `channels` channels for a stream with `input_rate`.
+    pub fn new(channels: usize, input_rate: f64) -> Self {
+        Self::with_taps(channels, input_rate, TAPS_PER_CHANNEL)
+    }
+
+    /// Create a channelizer with a prototype filter of `taps_per_channel * channels` taps
+    /// (default: 12). Longer filters have steeper channel edges but more delay.
+    pub fn with_taps(channels: usize, input_rate: f64, taps_per_channel: usize) -> Self {
+        assert!(channels > 0 && taps_per_channel > 0 && input_rate > 0.0);
+        let n = channels * taps_per_channel;
+
+        // windowed sinc with cutoff at half the channel spacing, normalized to unity gain
+        let cutoff = 0.5 / channels as f64;
+        let center = (n - 1) as f64 / 2.0;
+        let mut taps: Vec<f64> = (0..n)
+            .map(|i| {
+                let t = i as f64 - center;
+                let x = 2.0 * std::f64::consts::PI * cutoff * t;
+                let sinc = if t == 0.0 { 1.0 } else { x.sin() / x };
+                let a = 2.0 * std::f64::consts::PI * i as f64 / (n - 1).max(1) as f64;
+                let w = 0.42 - 0.5 * a.cos() + 0.08 * (2.0 * a).cos();
+                sinc * w
+            })
+            .collect();
+        let sum: f64 = taps.iter().sum();
+        taps.iter_mut().for_each(|t| *t /= sum);
+
+        Self {
+            channels,
+            taps_per_phase: taps_per_channel,
+            taps: taps.into_iter().map(|t| t as f32).collect(),
+            input_rate,
+            history: vec![Complex32::new(0.0, 0.0); n - 1],
+            pos: n - 1 + channels - 1,
+            fft: FftPlanner::new().plan_fft_inverse(channels),
+            buffer: vec![Complex32::new(0.0, 0.0); channels],
+        }
+    }
+
+    /// Number of channels.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Input sample rate.
+    pub fn input_rate(&self) -> f64 {
+        self.input_rate
+    }
+
+    /// Sample rate and spacing of the channels.
+    pub fn channel_rate(&self) -> f64 {
+        self.input_rate / self.channels as f64
+    }
+
+    /// Center frequency of channel `index` relative to the center of the input.
+    pub fn frequency_offset(&self, index: usize) -> f64 {
+        let k = if index <= self.channels / 2 {
+            index as f64
+        } else {
+            index as f64 - self.channels as f64
+        };
+        k * self.channel_rate()
+    }
+
+    /// Filter delay in seconds.
+    pub fn delay(&self) -> f64 {
+        (self.taps.len() - 1) as f64 / 2.0 / self.input_rate
+    }
+
+    /// Reset the filter state.
+    pub fn reset(&mut self) {
+        let n = self.taps.len();
+        self.history.clear();
+        self.history.resize(n - 1, Complex32::new(0.0, 0.0));
+        self.pos = n - 1 + self.channels - 1;
+    }
+
+    /// Channelize `input` and append the samples of channel `k` to `output[k]`.
+    ///
+    /// `output` has to hold one vector per channel. Returns the time of the first appended
+    /// sample, relative to the first input sample and compensated for the filter delay.
+    pub fn process(&mut self, input: &[Complex32], output: &mut [Vec<Complex32>]) -> f64 {
+        assert_eq!(output.len(), self.channels, "one output per channel");
+        let n = self.channels;
+        let len = self.taps.len();
+        let first = (self.pos as f64 - self.history.len() as f64) / self.input_rate - self.delay();
+
+        self.history.extend_from_slice(input);
+        while self.pos < self.history.len() {
+            // polyphase partial sums, combined by the inverse DFT
+            for r in 0..n {
+                let mut acc = Complex32::new(0.0, 0.0);
+                for p in 0..self.taps_per_phase {
+                    acc += self.history[self.pos - p * n - r] * self.taps[p * n + r];
+                }
+                self.buffer[r] = acc;
+            }
+            self.fft.process(&mut self.buffer);
+            for (o, y) in output.iter_mut().zip(&self.buffer) {
+                o.push(*y);
+            }
+            self.pos += n;
+        }
+
+        // keep the samples of the next output
+        let drop = self.pos - (len - 1);
+        self.history.drain(0..drop);
+        self.pos -= drop;
+
+        first
+    }
+
+    /// Channelize the IQ samples of a [`Packet`].
+    ///
+    /// The channel frequencies are derived from the center frequency of the packet.
+    pub fn process_packet(&mut self, packet: &Packet) -> Vec<ChannelOutput> {
+        let mut output = vec![Vec::new(); self.channels];
+        let offset = self.process(packet.samples(), &mut output);
+        let start = StreamTime::from_secs(packet.start_time() + offset);
+        output
+            .into_iter()
+            .enumerate()
+            .map(|(index, samples)| ChannelOutput {
+                index,
+                frequency: packet.start_frequency() + self.frequency_offset(index),
+                sample_rate: self.channel_rate(),
+                start,
+                samples,
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for Channelizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Channelizer")
+            .field("channels", &self.channels)
+            .field("taps", &self.taps.len())
+            .field("input_rate", &self.input_rate)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,147 @@
+use num_complex::Complex32;
+
+/// Estimates and removes the DC offset and the gain and phase imbalance of an IQ stream.
+///
+/// The corrector tracks the mean of the samples and the power and correlation of the I and Q
+/// components with exponential averaging. The DC offset is subtracted; Q is orthogonalized
+/// against I and scaled to the power of I, i.e., the image of a tone caused by the imbalance is
+/// suppressed. The corrector keeps state between calls, i.e., it can be fed with consecutive
+/// packets of a stream. The estimates assume that the signal itself has no DC component and
+/// that I and Q are uncorrelated with equal power, which holds for most signals over a long
+/// enough average.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IqCorrector {
+    /// Remove the DC offset (default: true).
+    pub dc: bool,
+    /// Correct the gain and phase imbalance (default: true).
+    pub imbalance: bool,
+    /// Time constant of the averaging in samples (default: 65536).
+    pub time_constant: f64,
+    /// Update the estimates with new samples (default: true). Disable to keep converged
+    /// estimates fixed.
+    pub adapt: bool,
+    mean: Complex32,
+    power_i: f32,
+    power_q: f32,
+    correlation: f32,
+    initialized: bool,
+}
+
+impl Default for IqCorrector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IqCorrector {
+    /// Create a corrector with default settings.
+    pub fn new() -> Self {
+        Self {
+            dc: true,
+            imbalance: true,
+            time_constant: 65536.0,
+            adapt: true,
+            mean: Complex32::new(0.0, 0.0),
+            power_i: 0.0,
+            power_q: 0.0,
+            correlation: 0.0,
+            initialized: false,
+        }
+    }
+
+    /// Update the estimates with a block of samples.
+    fn estimate(&mut self, samples: &[Complex32]) {
+        if samples.is_empty() {
+            return;
+        }
+        let n = samples.len() as f32;
+        let mean = samples.iter().sum::<Complex32>() / n;
+        let (mut pi, mut pq, mut c) = (0.0, 0.0, 0.0);
+        for s in samples {
+            let d = s - mean;
+            pi += d.re * d.re;
+            pq += d.im * d.im;
+            c += d.re * d.im;
+        }
+
+        let a = if self.initialized {
+            1.0 - (-(samples.len() as f64) / self.time_constant.max(1.0)).exp() as f32
+        } else {
+            1.0
+        };
+        self.mean += (mean - self.mean) * a;
+        self.power_i += (pi / n - self.power_i) * a;
+        self.power_q += (pq / n - self.power_q) * a;
+        self.correlation += (c / n - self.correlation) * a;
+        self.initialized = true;
+    }
+
+    /// Estimate the errors from `samples` and correct them in place.
+    pub fn process(&mut self, samples: &mut [Complex32]) {
+        if self.adapt {
+            self.estimate(samples);
+        }
+        self.apply(samples);
+    }
+
+    /// Correct `samples` in place with the current estimates, without updating them.
+    pub fn apply(&self, samples: &mut [Complex32]) {
+        if !self.initialized {
+            return;
+        }
+        let dc = if self.dc {
+            self.mean
+        } else {
+            Complex32::new(0.0, 0.0)
+        };
+        // Q' = (Q - c / p_i * I) * sqrt(p_i / (p_q - c^2 / p_i))
+        let residual = self.power_q - self.correlation * self.correlation / self.power_i;
+        let (leak, scale) = if self.imbalance && self.power_i > 0.0 && residual > 0.0 {
+            (
+                self.correlation / self.power_i,
+                (self.power_i / residual).sqrt(),
+            )
+        } else {
+            (0.0, 1.0)
+        };
+        for s in samples {
+            let d = *s - dc;
+            *s = Complex32::new(d.re, (d.im - leak * d.re) * scale);
+        }
+    }
+
+    /// Estimated DC offset.
+    pub fn dc_offset(&self) -> Complex32 {
+        self.mean
+    }
+
+    /// Estimated amplitude ratio of Q to I.
+    pub fn gain_imbalance(&self) -> f32 {
+        if self.power_i > 0.0 {
+            (self.power_q / self.power_i).sqrt()
+        } else {
+            1.0
+        }
+    }
+
+    /// Estimated phase error between I and Q in radians, i.e., the deviation from quadrature.
+    pub fn phase_imbalance(&self) -> f32 {
+        let p = (self.power_i * self.power_q).sqrt();
+        if p > 0.0 {
+            (self.correlation / p).clamp(-1.0, 1.0).asin()
+        } else {
+            0.0
+        }
+    }
+
+    /// Discard the estimates.
+    pub fn reset(&mut self) {
+        *self = Self {
+            dc: self.dc,
+            imbalance: self.imbalance,
+            time_constant: self.time_constant,
+            adapt: self.adapt,
+            ..Self::new()
+        };
+    }
+}
@@ -0,0 +1,153 @@
+use num_complex::Complex32;
+
+use crate::Packet;
+
+/// Maximum interpolation and decimation factors, considered for the rational approximation.
+const MAX_FACTOR: u64 = 1024;
+/// Length of the prototype filter relative to the larger of both factors.
+const TAPS_PER_FACTOR: usize = 12;
+
+/// Find a rational approximation `l / m` of `r` with continued fractions.
+fn rational(r: f64) -> (usize, usize) {
+    let (mut h0, mut h1) = (0u64, 1u64);
+    let (mut k0, mut k1) = (1u64, 0u64);
+    let mut x = r;
+
+    loop {
+        let a = x.floor() as u64;
+        let h2 = a * h1 + h0;
+        let k2 = a * k1 + k0;
+        if h2 > MAX_FACTOR || k2 > MAX_FACTOR {
+            break;
+        }
+        (h0, h1) = (h1, h2);
+        (k0, k1) = (k1, k2);
+        let frac = x - a as f64;
+        if frac < 1e-9 {
+            break;
+        }
+        x = 1.0 / frac;
+    }
+
+    (h1.max(1) as usize, k1.max(1) as usize)
+}
+
+/// Polyphase rational resampler for IQ streams.
+///
+/// The resampler converts the sample rate by a rational factor `L / M`, which is the closest
+/// approximation of the requested ratio with factors up to 1024. It keeps state between calls,
+/// i.e., it can be fed with consecutive packets of a stream.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    interp: usize,
+    decim: usize,
+    taps_per_phase: usize,
+    taps: Vec<f32>,
+    input_rate: f64,
+    history: Vec<Complex32>,
+    pos: usize,
+}
+
+impl Resampler {
+    /// Create a resampler from `input_rate` to `output_rate`.
+    pub fn new(input_rate: f64, output_rate: f64) -> Self {
+        assert!(input_rate > 0.0 && output_rate > 0.0);
+        let (interp, decim) = rational(output_rate / input_rate);
+
+        let n = TAPS_PER_FACTOR * interp.max(decim);
+        let taps_per_phase = n.div_ceil(interp);
+        let n = taps_per_phase * interp;
+
+        // windowed sinc with cutoff at the lower Nyquist frequency, gain compensates interpolation
+        let cutoff = 0.5 / interp.max(decim) as f64;
+        let center = (n - 1) as f64 / 2.0;
+        let taps = (0..n)
+            .map(|i| {
+                let t = i as f64 - center;
+                let sinc = if t == 0.0 {
+                    1.0
+                } else {
+                    (2.0 * std::f64::consts::PI * cutoff * t).sin()
+                        / (2.0 * std::f64::consts::PI * cutoff * t)
+                };
+                let w = 0.42 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / (n - 1) as f64).cos()
+                    + 0.08 * (4.0 * std::f64::consts::PI * i as f64 / (n - 1) as f64).cos();
+                (interp as f64 * 2.0 * cutoff * sinc * w) as f32
+            })
+            .collect();
+
+        Self {
+            interp,
+            decim,
+            taps_per_phase,
+            taps,
+            input_rate,
+            history: vec![Complex32::new(0.0, 0.0); taps_per_phase - 1],
+            pos: (taps_per_phase - 1) * interp,
+        }
+    }
+
+    /// Interpolation and decimation factor.
+    pub fn ratio(&self) -> (usize, usize) {
+        (self.interp, self.decim)
+    }
+
+    /// Input sample rate.
+    pub fn input_rate(&self) -> f64 {
+        self.input_rate
+    }
+
+    /// Exact output sample rate, i.e., the input rate times `L / M`.
+    pub fn output_rate(&self) -> f64 {
+        self.input_rate * self.interp as f64 / self.decim as f64
+    }
+
+    /// Filter delay in seconds.
+    pub fn delay(&self) -> f64 {
+        (self.taps.len() - 1) as f64 / 2.0 / self.interp as f64 / self.input_rate
+    }
+
+    /// Reset the filter state.
+    pub fn reset(&mut self) {
+        self.history.fill(Complex32::new(0.0, 0.0));
+        self.pos = (self.taps_per_phase - 1) * self.interp;
+    }
+
+    /// Resample `input` and append the result to `output`.
+    ///
+    /// Returns the time of the first appended sample, relative to the first input sample and
+    /// compensated for the filter delay.
+    pub fn process(&mut self, input: &[Complex32], output: &mut Vec<Complex32>) -> f64 {
+        let h = self.taps_per_phase;
+        let l = self.interp;
+
+        let first = (self.pos as f64 / l as f64 - (h - 1) as f64) / self.input_rate - self.delay();
+
+        self.history.extend_from_slice(input);
+        let buf = &self.history;
+
+        while self.pos / l < buf.len() {
+            let i = self.pos / l;
+            let phase = self.pos % l;
+            let mut acc = Complex32::new(0.0, 0.0);
+            for k in 0..h {
+                acc += buf[i - k] * self.taps[phase + k * l];
+            }
+            output.push(acc);
+            self.pos += self.decim;
+        }
+
+        let drop = buf.len() - (h - 1);
+        self.history.drain(0..drop);
+        self.pos -= drop * l;
+
+        first
+    }
+
+    /// Resample the IQ samples of a [`Packet`] and append the result to `output`.
+    ///
+    /// Returns the device clock time of the first appended sample.
+    pub fn process_packet(&mut self, packet: &Packet, output: &mut Vec<Complex32>) -> f64 {
+        packet.start_time() + self.process(packet.samples(), output)
+    }
+}
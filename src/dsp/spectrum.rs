@@ -0,0 +1,275 @@
+use num_complex::Complex32;
+use rustfft::Fft;
+use rustfft::FftPlanner;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::Packet;
+use crate::Spectrum;
+
+/// Window function of a [`SpectrumEngine`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Window {
+    /// No window, i.e., the highest resolution but strong leakage.
+    Rectangular,
+    #[default]
+    Hann,
+    Hamming,
+    /// 4-term Blackman-Harris with sidelobes below -92 dB.
+    BlackmanHarris,
+    /// Flat top window for accurate amplitudes of tones between bins.
+    FlatTop,
+}
+
+impl Window {
+    /// Coefficients of a window of length `n`.
+    pub fn coefficients(self, n: usize) -> Vec<f32> {
+        let terms: &[f64] = match self {
+            Window::Rectangular => &[1.0],
+            Window::Hann => &[0.5, 0.5],
+            Window::Hamming => &[0.54, 0.46],
+            Window::BlackmanHarris => &[0.35875, 0.48829, 0.14128, 0.01168],
+            Window::FlatTop => &[
+                0.21557895,
+                0.41663158,
+                0.277263158,
+                0.083578947,
+                0.006947368,
+            ],
+        };
+        // periodic windows, i.e., the DFT-even form for spectral analysis
+        (0..n)
+            .map(|i| {
+                let x = 2.0 * PI * i as f64 / n as f64;
+                let w: f64 = terms
+                    .iter()
+                    .enumerate()
+                    .map(|(k, a)| {
+                        let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                        sign * a * (k as f64 * x).cos()
+                    })
+                    .sum();
+                w as f32
+            })
+            .collect()
+    }
+}
+
+/// Host-side FFT of IQ streams, e.g., for sizes or windows that the device FFT does not offer.
+///
+/// The engine splits the stream into frames of `size` samples, which overlap by the given
+/// fraction, applies the [`Window`], and averages the power of `averages` frames into one
+/// [`Spectrum`], i.e., the same type as [`Packet::to_spectrum()`] for device spectra. Bins are
+/// ordered by frequency and in dB relative to full scale, i.e., a tone with amplitude one is at
+/// 0 dB. The engine keeps state between calls and restarts after gaps and frequency or rate
+/// changes.
+pub struct SpectrumEngine {
+    size: usize,
+    window: Window,
+    overlap: f64,
+    averages: usize,
+    coefficients: Vec<f32>,
+    scale: f32,
+    fft: Arc<dyn Fft<f32>>,
+    frame: Vec<Complex32>,
+    pending: Vec<Complex32>,
+    // time of the first pending sample, center frequency, and sample rate
+    stream: Option<(f64, f64, f64)>,
+    power: Vec<f32>,
+    frames: usize,
+    start_time: f64,
+}
+
+impl SpectrumEngine {
+    /// Create an engine with an FFT of `size` bins, no overlap, and no averaging.
+    pub fn new(size: usize, window: Window) -> Self {
+        assert!(size > 0, "FFT size has to be positive");
+        let coefficients = window.coefficients(size);
+        let sum: f32 = coefficients.iter().sum();
+        Self {
+            size,
+            window,
+            overlap: 0.0,
+            averages: 1,
+            coefficients,
+            scale: 1.0 / (sum * sum),
+            fft: FftPlanner::new().plan_fft_forward(size),
+            frame: vec![Complex32::new(0.0, 0.0); size],
+            pending: Vec::new(),
+            stream: None,
+            power: vec![0.0; size],
+            frames: 0,
+            start_time: 0.0,
+        }
+    }
+
+    /// Set the overlap of consecutive frames as fraction of the size, clamped to `[0, 1)`.
+    ///
+    /// At least one new sample is used per frame.
+    pub fn set_overlap(&mut self, overlap: f64) {
+        self.overlap = overlap.clamp(0.0, 1.0);
+        if self.hop() == 0 {
+            self.overlap = (self.size - 1) as f64 / self.size as f64;
+        }
+    }
+
+    /// Set the number of frames, averaged into one spectrum (at least one).
+    pub fn set_averages(&mut self, averages: usize) {
+        self.averages = averages.max(1);
+        self.reset_average();
+    }
+
+    /// Number of bins.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Window function.
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    /// Overlap of consecutive frames as fraction of the size.
+    pub fn overlap(&self) -> f64 {
+        self.overlap
+    }
+
+    /// Number of averaged frames per spectrum.
+    pub fn averages(&self) -> usize {
+        self.averages
+    }
+
+    /// Samples between the start of two frames.
+    pub fn hop(&self) -> usize {
+        self.size - (self.overlap * self.size as f64).round() as usize
+    }
+
+    /// Resolution bandwidth at `sample_rate`, i.e., the equivalent noise bandwidth of a bin.
+    pub fn rbw(&self, sample_rate: f64) -> f64 {
+        let sum: f64 = self.coefficients.iter().map(|w| *w as f64).sum();
+        let squares: f64 = self.coefficients.iter().map(|w| (*w as f64).powi(2)).sum();
+        squares / (sum * sum) * sample_rate
+    }
+
+    /// Drop buffered samples and the running average.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.stream = None;
+        self.reset_average();
+    }
+
+    fn reset_average(&mut self) {
+        self.power.iter_mut().for_each(|p| *p = 0.0);
+        self.frames = 0;
+    }
+
+    /// Compute the spectra of IQ samples, starting at `start_time`, with the given center
+    /// frequency and sample rate.
+    ///
+    /// Returns the spectra, completed by the samples. Samples that do not continue the previous
+    /// call, i.e., after a gap or with a different frequency or rate, restart the engine.
+    pub fn process(
+        &mut self,
+        samples: &[Complex32],
+        start_time: f64,
+        center_frequency: f64,
+        sample_rate: f64,
+    ) -> Vec<Spectrum> {
+        let continues = self.stream.is_some_and(|(t, f, r)| {
+            let next = t + self.pending.len() as f64 / r;
+            f == center_frequency && r == sample_rate && (start_time - next).abs() < 0.5 / r
+        });
+        if !continues {
+            self.reset();
+            self.stream = Some((start_time, center_frequency, sample_rate));
+        }
+        self.pending.extend_from_slice(samples);
+
+        let mut out = Vec::new();
+        let hop = self.hop();
+        while self.pending.len() >= self.size {
+            let (time, _, _) = self.stream.unwrap();
+            if self.frames == 0 {
+                self.start_time = time;
+            }
+            self.accumulate();
+            if self.frames == self.averages {
+                let end_time = time + self.size as f64 / sample_rate;
+                out.push(self.spectrum(end_time, center_frequency, sample_rate));
+                self.reset_average();
+            }
+            self.pending.drain(..hop);
+            self.stream = Some((
+                time + hop as f64 / sample_rate,
+                center_frequency,
+                sample_rate,
+            ));
+        }
+        out
+    }
+
+    /// Compute the spectra of the IQ samples of a [`Packet`].
+    ///
+    /// Returns no spectra for packets without a sample rate, e.g., spectra packets.
+    pub fn process_packet(&mut self, packet: &Packet) -> Vec<Spectrum> {
+        match packet.sample_rate() {
+            Some(rate) => self.process(
+                packet.samples(),
+                packet.start_time(),
+                packet.start_frequency(),
+                rate,
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    /// Add the power of the first pending frame to the running average.
+    fn accumulate(&mut self) {
+        for ((f, s), w) in self
+            .frame
+            .iter_mut()
+            .zip(&self.pending)
+            .zip(&self.coefficients)
+        {
+            *f = s * *w;
+        }
+        self.fft.process(&mut self.frame);
+        for (p, f) in self.power.iter_mut().zip(&self.frame) {
+            *p += f.norm_sqr();
+        }
+        self.frames += 1;
+    }
+
+    /// Spectrum of the running average with bins in ascending frequency.
+    fn spectrum(&self, end_time: f64, center_frequency: f64, sample_rate: f64) -> Spectrum {
+        let n = self.size;
+        let scale = self.scale / self.frames as f32;
+        let data = (0..n)
+            .map(|i| {
+                let p = self.power[(i + n.div_ceil(2)) % n] * scale;
+                10.0 * p.max(1e-20).log10()
+            })
+            .collect();
+        let step = sample_rate / n as f64;
+        Spectrum {
+            start_time: self.start_time,
+            end_time,
+            start_frequency: center_frequency - (n / 2) as f64 * step,
+            step_frequency: step,
+            rbw_frequency: self.rbw(sample_rate),
+            data,
+        }
+    }
+}
+
+impl std::fmt::Debug for SpectrumEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpectrumEngine")
+            .field("size", &self.size)
+            .field("window", &self.window)
+            .field("overlap", &self.overlap)
+            .field("averages", &self.averages)
+            .finish_non_exhaustive()
+    }
+}
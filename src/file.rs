@@ -0,0 +1,41 @@
+use std::path::Path;
+use widestring::WideCString;
+
+use crate::ApiHandle;
+use crate::Device;
+use crate::DeviceInfo;
+use crate::Error;
+
+/// Device type of virtual devices that replay RTSA-Suite `.rtsa` recordings.
+pub const FILE_DEVICE_TYPE: &str = "spectranfile/raw";
+
+impl ApiHandle {
+    /// Open a `.rtsa` recording as virtual [`Device`].
+    ///
+    /// The device is opened and can be configured, connected, and started like hardware, i.e.,
+    /// processing can be tested against recorded captures. The path takes the place of the
+    /// serial number.
+    pub fn open_file_device<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> std::result::Result<Device, Error> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(Error::ErrorNotFound);
+        }
+        let path = std::fs::canonicalize(path).map_err(|_| Error::ErrorNotFound)?;
+
+        let mut dev = Device::new(&DeviceInfo::new(), &self.rt)?;
+        dev.serial = WideCString::from_str_truncate(path.to_string_lossy());
+        dev.device_type = FILE_DEVICE_TYPE;
+        dev.open()?;
+        Ok(dev)
+    }
+}
+
+impl Device {
+    /// Check if the device replays a recording, see [`ApiHandle::open_file_device()`].
+    pub fn is_file_device(&self) -> bool {
+        self.device_type == FILE_DEVICE_TYPE
+    }
+}
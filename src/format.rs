@@ -0,0 +1,127 @@
+use crate::ConfigItem;
+use crate::ConfigValue;
+use crate::Device;
+use crate::Error;
+use crate::Result;
+
+/// Data format, selected with `device/outputformat`.
+///
+/// The format determines which data channels carry packets and what payload they hold: IQ
+/// samples of Rx1 and Rx2 are on channels 0 and 1, spectra on channel 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputFormat {
+    /// IQ samples.
+    Iq,
+    /// Spectra.
+    Spectra,
+    /// IQ samples and spectra.
+    Both,
+    /// Raw ADC samples.
+    Raw,
+}
+
+/// Payload of the packets of a data channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PayloadKind {
+    /// IQ samples, see [`Packet::samples()`](crate::Packet::samples).
+    Iq,
+    /// Spectrum, see [`Packet::spectrum()`](crate::Packet::spectrum).
+    Spectrum,
+    /// Raw ADC samples.
+    Raw,
+}
+
+impl OutputFormat {
+    /// Value of the `device/outputformat` configuration parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Iq => "iq",
+            OutputFormat::Spectra => "spectra",
+            OutputFormat::Both => "both",
+            OutputFormat::Raw => "raw",
+        }
+    }
+
+    /// Data channels that carry packets.
+    pub fn channels(&self) -> &'static [i32] {
+        match self {
+            OutputFormat::Iq | OutputFormat::Raw => &[0, 1],
+            OutputFormat::Spectra => &[2],
+            OutputFormat::Both => &[0, 1, 2],
+        }
+    }
+
+    /// Payload of the packets on data channel `chan` or `None`, if the channel is not used.
+    pub fn payload(&self, chan: i32) -> Option<PayloadKind> {
+        if !self.channels().contains(&chan) {
+            return None;
+        }
+        match (self, chan) {
+            (OutputFormat::Raw, _) => Some(PayloadKind::Raw),
+            (_, 2) => Some(PayloadKind::Spectrum),
+            _ => Some(PayloadKind::Iq),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Error> {
+        match s.to_lowercase().as_str() {
+            "iq" => Ok(OutputFormat::Iq),
+            "spectra" => Ok(OutputFormat::Spectra),
+            "both" => Ok(OutputFormat::Both),
+            "raw" => Ok(OutputFormat::Raw),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+}
+
+impl Device {
+    /// Set the [`OutputFormat`].
+    ///
+    /// Returns [`Error::OptionDisabled`], if the format is not available on the device.
+    pub fn set_output_format(&mut self, format: OutputFormat) -> Result {
+        let value = ConfigValue::String(format.as_str().to_string());
+        self.check_enabled("device/outputformat", &value)?;
+        self.set("device/outputformat", format.as_str())
+    }
+
+    /// Get the [`OutputFormat`] from the device configuration.
+    pub fn output_format(&mut self) -> std::result::Result<OutputFormat, Error> {
+        match self.get("device/outputformat")? {
+            ConfigItem::Enum { value, options, .. } => options
+                .get(value as usize)
+                .ok_or(Error::ErrorValueInvalid)?
+                .parse(),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+
+    /// Check that data channel `chan` carries packets with the output format, set through this
+    /// handle.
+    ///
+    /// Channels are not checked, if the output format was not set through this handle.
+    pub(crate) fn check_channel(&self, chan: i32) -> Result {
+        match self.known_output_format() {
+            Some(f) if f.payload(chan).is_none() => Err(Error::ErrorInvalidChannel),
+            _ => Ok(()),
+        }
+    }
+
+    /// Output format, set through this handle.
+    pub(crate) fn known_output_format(&self) -> Option<OutputFormat> {
+        match self.profile.get("device/outputformat") {
+            Some(ConfigValue::String(s)) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! [FutureSDR](https://www.futuresdr.org) blocks, wrapping a [`Device`].
+//!
+//! The blocks take an opened and configured [`Device`]. They connect and start the device, when
+//! the flowgraph is initialized, and stop and disconnect it, when the flowgraph terminates.
+use futuresdr::anyhow::Result;
+use futuresdr::macros::async_trait;
+use futuresdr::macros::message_handler;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+use num_complex::Complex32;
+
+use crate::Device;
+use crate::Packet;
+use crate::PacketFlags;
+
+/// Offset between the gain in dB, used in messages, and the reference level.
+///
+/// This matches the mapping of the Seify Aaronia driver.
+const REFLEVEL_OFFSET: f64 = -8.0;
+
+fn pmt_to_f64(p: &Pmt) -> Option<f64> {
+    match p {
+        Pmt::F32(v) => Some(*v as f64),
+        Pmt::F64(v) => Some(*v),
+        Pmt::U32(v) => Some(*v as f64),
+        Pmt::U64(v) => Some(*v as f64),
+        _ => None,
+    }
+}
+
+/// Aaronia Source block
+///
+/// # Outputs
+///
+/// `out`: IQ samples of the configured data channel
+///
+/// # Message Inputs
+///
+/// `freq`: Center frequency in Hz, set as `main/centerfreq`
+///
+/// `gain`: Gain in dB, set as `main/reflevel`
+///
+/// `config`: [`Pmt::MapStrPmt`] of configuration paths and values
+pub struct AaroniaSource {
+    dev: Device,
+    chan: i32,
+    packet: Option<(Packet, usize)>,
+}
+
+#[allow(clippy::new_ret_no_self)]
+impl AaroniaSource {
+    /// Create Aaronia Source block, streaming IQ samples from data channel `chan`.
+    ///
+    /// The [`Device`] has to be opened and configured to output IQ samples.
+    pub fn new(dev: Device, chan: i32) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("AaroniaSource").blocking().build(),
+            StreamIoBuilder::new()
+                .add_output::<Complex32>("out")
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("freq", Self::freq_handler)
+                .add_input("gain", Self::gain_handler)
+                .add_input("config", Self::config_handler)
+                .build(),
+            AaroniaSource {
+                dev,
+                chan,
+                packet: None,
+            },
+        )
+    }
+
+    #[message_handler]
+    fn freq_handler(
+        &mut self,
+        _io: &mut WorkIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+        p: Pmt,
+    ) -> Result<Pmt> {
+        match pmt_to_f64(&p) {
+            Some(f) => {
+                self.dev.set_float("main/centerfreq", f)?;
+                Ok(Pmt::Ok)
+            }
+            None => Ok(Pmt::InvalidValue),
+        }
+    }
+
+    #[message_handler]
+    fn gain_handler(
+        &mut self,
+        _io: &mut WorkIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+        p: Pmt,
+    ) -> Result<Pmt> {
+        match pmt_to_f64(&p) {
+            Some(g) => {
+                self.dev.set_float("main/reflevel", REFLEVEL_OFFSET - g)?;
+                Ok(Pmt::Ok)
+            }
+            None => Ok(Pmt::InvalidValue),
+        }
+    }
+
+    #[message_handler]
+    fn config_handler(
+        &mut self,
+        _io: &mut WorkIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+        p: Pmt,
+    ) -> Result<Pmt> {
+        apply_config(&mut self.dev, &p)
+    }
+}
+
+#[doc(hidden)]
+#[async_trait]
+impl Kernel for AaroniaSource {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let out = sio.output(0).slice::<Complex32>();
+        if out.is_empty() {
+            return Ok(());
+        }
+
+        let (packet, offset) = match self.packet.take() {
+            Some(p) => p,
+            None => match self.dev.packet(self.chan) {
+                Ok(p) => (p, 0),
+                Err(e) => {
+                    self.dev.handle_stream_error(e)?;
+                    io.call_again = true;
+                    return Ok(());
+                }
+            },
+        };
+
+        // spectra and malformed packets have no samples and are consumed
+        let samples = packet.try_samples().unwrap_or(&[]);
+        let samples = &samples[offset.min(samples.len())..];
+        let n = std::cmp::min(out.len(), samples.len());
+        out[0..n].copy_from_slice(&samples[0..n]);
+        sio.output(0).produce(n);
+
+        if n == samples.len() {
+            self.dev.consume(self.chan)?;
+        } else {
+            self.packet = Some((packet, offset + n));
+        }
+
+        io.call_again = true;
+        Ok(())
+    }
+
+    async fn init(
+        &mut self,
+        _sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        self.dev.connect()?;
+        self.dev.start()?;
+        Ok(())
+    }
+
+    async fn deinit(
+        &mut self,
+        _sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        self.dev.stop()?;
+        self.dev.disconnect()?;
+        Ok(())
+    }
+}
+
+/// Aaronia Sink block
+///
+/// Samples are sent in packets of a fixed size. Packets are timestamped with the device clock and
+/// the block waits, if it gets too far ahead of the device.
+///
+/// # Inputs
+///
+/// `in`: IQ samples to transmit on the configured data channel
+///
+/// # Message Inputs
+///
+/// `freq`: Center frequency in Hz, set as `main/centerfreq`
+///
+/// `gain`: Transmit gain in dB, set as `main/transgain`
+///
+/// `config`: [`Pmt::MapStrPmt`] of configuration paths and values
+pub struct AaroniaSink {
+    dev: Device,
+    chan: i32,
+    sample_rate: f64,
+    frequency: f64,
+    buf: Vec<Complex32>,
+    time: f64,
+}
+
+#[allow(clippy::new_ret_no_self)]
+impl AaroniaSink {
+    /// Samples per packet.
+    const PACKET_SIZE: usize = 1024;
+    /// Initial offset of the first packet to the device clock in seconds.
+    const LEAD_TIME: f64 = 0.02;
+    /// Maximum time in seconds, the block sends ahead of the device clock.
+    const MAX_AHEAD: f64 = 0.1;
+
+    /// Create Aaronia Sink block, transmitting IQ samples on data channel `chan`.
+    ///
+    /// The [`Device`] has to be opened and configured for transmission with the given sample
+    /// rate and center frequency.
+    pub fn new(dev: Device, chan: i32, sample_rate: f64, frequency: f64) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("AaroniaSink").blocking().build(),
+            StreamIoBuilder::new().add_input::<Complex32>("in").build(),
+            MessageIoBuilder::new()
+                .add_input("freq", Self::freq_handler)
+                .add_input("gain", Self::gain_handler)
+                .add_input("config", Self::config_handler)
+                .build(),
+            AaroniaSink {
+                dev,
+                chan,
+                sample_rate,
+                frequency,
+                buf: Vec::with_capacity(Self::PACKET_SIZE),
+                time: 0.0,
+            },
+        )
+    }
+
+    #[message_handler]
+    fn freq_handler(
+        &mut self,
+        _io: &mut WorkIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+        p: Pmt,
+    ) -> Result<Pmt> {
+        match pmt_to_f64(&p) {
+            Some(f) => {
+                self.dev.set_float("main/centerfreq", f)?;
+                self.frequency = f;
+                Ok(Pmt::Ok)
+            }
+            None => Ok(Pmt::InvalidValue),
+        }
+    }
+
+    #[message_handler]
+    fn gain_handler(
+        &mut self,
+        _io: &mut WorkIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+        p: Pmt,
+    ) -> Result<Pmt> {
+        match pmt_to_f64(&p) {
+            Some(g) => {
+                self.dev.set_float("main/transgain", g)?;
+                Ok(Pmt::Ok)
+            }
+            None => Ok(Pmt::InvalidValue),
+        }
+    }
+
+    #[message_handler]
+    fn config_handler(
+        &mut self,
+        _io: &mut WorkIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+        p: Pmt,
+    ) -> Result<Pmt> {
+        apply_config(&mut self.dev, &p)
+    }
+
+    fn send(&mut self, end: bool) -> Result<()> {
+        let mut flags = PacketFlags::new();
+        let now = self.dev.clock()?;
+        if self.time < now {
+            self.time = now + Self::LEAD_TIME;
+            flags.set_segment_start();
+        }
+        if end {
+            flags.set_segment_end();
+        }
+        while self.time - self.dev.clock()? > Self::MAX_AHEAD {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        self.dev.send_samples(
+            self.chan,
+            &self.buf,
+            self.time,
+            self.frequency,
+            self.sample_rate,
+            flags,
+        )?;
+        self.time += self.buf.len() as f64 / self.sample_rate;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+#[async_trait]
+impl Kernel for AaroniaSink {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let input = sio.input(0).slice::<Complex32>();
+        let n = std::cmp::min(input.len(), Self::PACKET_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&input[0..n]);
+        sio.input(0).consume(n);
+
+        let finished = sio.input(0).finished() && n == input.len();
+
+        if self.buf.len() == Self::PACKET_SIZE {
+            self.send(false)?;
+            io.call_again = true;
+        } else if finished && !self.buf.is_empty() {
+            self.send(true)?;
+        }
+
+        if finished {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+
+    async fn init(
+        &mut self,
+        _sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        self.dev.connect()?;
+        self.dev.start()?;
+        Ok(())
+    }
+
+    async fn deinit(
+        &mut self,
+        _sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        self.dev.stop()?;
+        self.dev.disconnect()?;
+        Ok(())
+    }
+}
+
+fn apply_config(dev: &mut Device, p: &Pmt) -> Result<Pmt> {
+    let Pmt::MapStrPmt(m) = p else {
+        return Ok(Pmt::InvalidValue);
+    };
+
+    for (path, value) in m.iter() {
+        match value {
+            Pmt::String(s) => dev.set(path, s)?,
+            Pmt::U32(v) => dev.set_int(path, *v)?,
+            Pmt::U64(v) => dev.set_int(path, *v as i64)?,
+            Pmt::Usize(v) => dev.set_int(path, *v as i64)?,
+            Pmt::F32(v) => dev.set_float(path, *v)?,
+            Pmt::F64(v) => dev.set_float(path, *v)?,
+            _ => return Ok(Pmt::InvalidValue),
+        }
+    }
+
+    Ok(Pmt::Ok)
+}
@@ -0,0 +1,315 @@
+use crate::ConfigEntry;
+use crate::ConfigItem;
+use crate::ConfigType;
+use crate::ConfigValue;
+use crate::Device;
+use crate::Error;
+use crate::RxChannel;
+
+const REFLEVEL_PATH: &str = "main/reflevel";
+const PREAMP_PATH: &str = "device/preamp";
+const ATTENUATOR_PATH: &str = "device/attenuator";
+
+/// Gain stage of the receive path, e.g., a preamplifier or attenuator.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GainStage {
+    /// Configuration path, e.g., `device/preamp`.
+    pub path: String,
+    /// Selectable gains in dB, attenuation as negative gain. Empty for continuous stages and
+    /// stages with unknown steps.
+    pub steps: Vec<f64>,
+    /// Current gain in dB, `None` if unknown, e.g., with automatic attenuation.
+    pub gain_db: Option<f64>,
+}
+
+/// Gain stages and reference level of the receive path, returned by [`Device::gain_model()`].
+///
+/// The reference level is the input power that maps to full scale. The device sets the gain
+/// stages to reach it, i.e., a sample of 0 dBFS is `reflevel` dBm independently of the stages,
+/// like with [`Calibration`](crate::measurements::Calibration).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GainModel {
+    /// Reference level in dBm.
+    pub reflevel: f64,
+    /// Minimum reference level in dBm.
+    pub reflevel_min: f64,
+    /// Maximum reference level in dBm.
+    pub reflevel_max: f64,
+    /// Step size of the reference level in dB, zero if continuous.
+    pub reflevel_step: f64,
+    /// Gain stages, found in the configuration tree.
+    pub stages: Vec<GainStage>,
+}
+
+impl GainModel {
+    /// Gain from the input in dBm to the samples in dBFS.
+    ///
+    /// The gain stages are part of the reference level and not added again.
+    pub fn total_gain_db(&self) -> f64 {
+        -self.reflevel
+    }
+
+    /// Sum of the gain stages in dB, i.e., the analog gain in front of the ADC.
+    ///
+    /// Stages with unknown gain are not included.
+    pub fn stage_gain_db(&self) -> f64 {
+        self.stages.iter().filter_map(|s| s.gain_db).sum()
+    }
+
+    /// Input power in dBm that maps to full scale.
+    pub fn full_scale_dbm(&self) -> f64 {
+        -self.total_gain_db()
+    }
+}
+
+/// Gain settings of a receiver, returned by [`Device::rx_gain()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RxGain {
+    /// Reference level in dBm.
+    pub reflevel: f64,
+    /// State of the preamplifier, `None` if the hardware has none.
+    pub preamp: Option<bool>,
+    /// Attenuation in dB, `None` if the hardware has no attenuator or it is unknown.
+    pub attenuation: Option<f64>,
+}
+
+/// Check if a configuration path is a gain stage of the receive path.
+fn is_stage(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or_default().to_lowercase();
+    (name.contains("preamp")
+        || name.contains("amplifier")
+        || name.contains("atten")
+        || name.contains("gain"))
+        && !name.contains("control")
+        && !name.contains("trans")
+}
+
+/// Parse a gain in dB from a label, e.g., `10 dB` or `Preamp (+20dB)`.
+fn parse_db(label: &str) -> Option<f64> {
+    let lower = label.to_lowercase();
+    let end = lower.find("db")?;
+    let head = lower[..end].trim_end();
+    let start = head
+        .rfind(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .map_or(0, |i| i + 1);
+    head[start..].parse().ok()
+}
+
+impl GainStage {
+    fn from_entry(entry: ConfigEntry) -> Option<Self> {
+        // attenuators report their attenuation as positive value
+        let sign = if entry.path.to_lowercase().contains("atten") {
+            -1.0
+        } else {
+            1.0
+        };
+        let (steps, gain_db) = match &entry.value {
+            ConfigItem::Bool(on) => match parse_db(&entry.title) {
+                Some(g) => (
+                    vec![0.0, sign * g.abs()],
+                    Some(if *on { sign * g.abs() } else { 0.0 }),
+                ),
+                None => (Vec::new(), if *on { None } else { Some(0.0) }),
+            },
+            ConfigItem::Enum { value, options, .. } => (
+                options
+                    .iter()
+                    .filter_map(|o| parse_db(o))
+                    .map(|g| sign * g)
+                    .collect(),
+                options
+                    .get(*value as usize)
+                    .and_then(|o| parse_db(o))
+                    .map(|g| sign * g),
+            ),
+            ConfigItem::Number(v) => (Vec::new(), Some(sign * v)),
+            _ => return None,
+        };
+        Some(Self {
+            path: entry.path,
+            steps,
+            gain_db,
+        })
+    }
+}
+
+impl Device {
+    /// Query the gain stages and reference level from the configuration tree.
+    ///
+    /// Stages are switches, enums, and numbers, whose name refers to a preamplifier, amplifier,
+    /// attenuator, or gain of the receive path. Gains of switches are parsed from their title,
+    /// steps of enums from their options, e.g., `10 dB`.
+    pub fn gain_model(&mut self) -> std::result::Result<GainModel, Error> {
+        let reflevel = self.config_entry(REFLEVEL_PATH)?;
+        let ConfigItem::Number(value) = reflevel.value else {
+            return Err(Error::ErrorValueInvalid);
+        };
+        let stages = self
+            .find_config_by(is_stage)?
+            .into_iter()
+            .filter_map(GainStage::from_entry)
+            .collect();
+
+        Ok(GainModel {
+            reflevel: value,
+            reflevel_min: reflevel.min,
+            reflevel_max: reflevel.max,
+            reflevel_step: reflevel.step,
+            stages,
+        })
+    }
+
+    /// Configuration paths of a setting for the receivers of a channel.
+    ///
+    /// Receivers with separate settings have a group per receiver, e.g., `device/rx2/preamp`.
+    /// Otherwise, the receivers share the setting, e.g., `device/preamp`.
+    fn rx_paths(
+        &mut self,
+        channel: RxChannel,
+        path: &str,
+    ) -> std::result::Result<Vec<String>, Error> {
+        let receivers: &[u32] = match channel {
+            RxChannel::Rx1 => &[1],
+            RxChannel::Rx2 => &[2],
+            RxChannel::Both => &[1, 2],
+            RxChannel::Off => return Err(Error::ErrorValueInvalid),
+        };
+        let (group, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let mut paths = Vec::new();
+        for r in receivers {
+            let p = format!("{group}/rx{r}/{name}");
+            let p = if self.config_entry(&p).is_ok() {
+                p
+            } else {
+                path.to_string()
+            };
+            if !paths.contains(&p) {
+                paths.push(p);
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Set the reference level of a receiver in dBm, clamped to its range.
+    ///
+    /// The setting applies while the device is running. Receivers without separate settings
+    /// share the reference level, i.e., setting it for one changes it for both. Returns the
+    /// value that is actually set, read back from the device, of the first receiver of the
+    /// channel.
+    pub fn set_rx_reflevel(
+        &mut self,
+        channel: RxChannel,
+        reflevel: f64,
+    ) -> std::result::Result<f64, Error> {
+        let mut applied = None;
+        for p in self.rx_paths(channel, REFLEVEL_PATH)? {
+            let v = self.set_float_clamped(&p, reflevel)?;
+            applied.get_or_insert(v);
+        }
+        applied.ok_or(Error::ErrorValueInvalid)
+    }
+
+    /// Turn the preamplifier of a receiver on or off.
+    ///
+    /// Returns the state, read back from the device, of the first receiver of the channel, see
+    /// [`set_rx_reflevel()`](Self::set_rx_reflevel).
+    pub fn set_rx_preamp(
+        &mut self,
+        channel: RxChannel,
+        on: bool,
+    ) -> std::result::Result<bool, Error> {
+        let mut applied = None;
+        for p in self.rx_paths(channel, PREAMP_PATH)? {
+            self.set_value(&p, &ConfigValue::Bool(on))?;
+            let v = self.rx_bool(&p)?;
+            applied.get_or_insert(v);
+        }
+        applied.ok_or(Error::ErrorValueInvalid)
+    }
+
+    /// Set the attenuation of a receiver in dB.
+    ///
+    /// Continuous attenuators are clamped to their range, stepped attenuators and switches with
+    /// the attenuation in their title, e.g., `Attenuator (10 dB)`, are set to the closest step.
+    /// Returns the attenuation, read back from the device, of the first receiver of the channel,
+    /// see [`set_rx_reflevel()`](Self::set_rx_reflevel).
+    pub fn set_rx_attenuation(
+        &mut self,
+        channel: RxChannel,
+        attenuation: f64,
+    ) -> std::result::Result<f64, Error> {
+        let mut applied = None;
+        for p in self.rx_paths(channel, ATTENUATOR_PATH)? {
+            let entry = self.config_entry(&p)?;
+            match entry.kind {
+                ConfigType::Number => {
+                    self.set_float_clamped(&p, attenuation)?;
+                }
+                ConfigType::Bool => {
+                    let step = parse_db(&entry.title).ok_or(Error::ErrorValueInvalid)?;
+                    let on = attenuation >= step.abs() / 2.0;
+                    self.set_value(&p, &ConfigValue::Bool(on))?;
+                }
+                ConfigType::Enum => {
+                    let closest = entry
+                        .options
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| !entry.is_disabled(*i))
+                        .filter_map(|(i, o)| Some((i, (parse_db(o)?.abs() - attenuation).abs())))
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .ok_or(Error::ErrorValueInvalid)?;
+                    self.set_int(&p, closest.0 as i64)?;
+                }
+                _ => return Err(Error::ErrorValueInvalid),
+            }
+            let v = self.rx_attenuation(&p)?.ok_or(Error::ErrorValueInvalid)?;
+            applied.get_or_insert(v);
+        }
+        applied.ok_or(Error::ErrorValueInvalid)
+    }
+
+    /// Get the gain settings of the first receiver of a channel.
+    pub fn rx_gain(&mut self, channel: RxChannel) -> std::result::Result<RxGain, Error> {
+        let reflevel = &self.rx_paths(channel, REFLEVEL_PATH)?[0];
+        let reflevel = match self.get(reflevel)? {
+            ConfigItem::Number(v) => v,
+            _ => return Err(Error::ErrorValueInvalid),
+        };
+        let preamp = &self.rx_paths(channel, PREAMP_PATH)?[0];
+        let preamp = match self.rx_bool(preamp) {
+            Ok(on) => Some(on),
+            Err(Error::ErrorNotFound) => None,
+            Err(e) => return Err(e),
+        };
+        let attenuator = &self.rx_paths(channel, ATTENUATOR_PATH)?[0];
+        let attenuation = match self.rx_attenuation(attenuator) {
+            Ok(a) => a,
+            Err(Error::ErrorNotFound) => None,
+            Err(e) => return Err(e),
+        };
+        Ok(RxGain {
+            reflevel,
+            preamp,
+            attenuation,
+        })
+    }
+
+    fn rx_bool(&mut self, path: &str) -> std::result::Result<bool, Error> {
+        match self.get(path)? {
+            ConfigItem::Bool(b) => Ok(b),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+
+    /// Attenuation of an attenuator in dB, `None` if unknown.
+    fn rx_attenuation(&mut self, path: &str) -> std::result::Result<Option<f64>, Error> {
+        let entry = self.config_entry(path)?;
+        Ok(GainStage::from_entry(entry)
+            .and_then(|s| s.gain_db)
+            .map(|g| -g))
+    }
+}
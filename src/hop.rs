@@ -0,0 +1,131 @@
+//! Frequency hopping of transmitted bursts.
+use num_complex::Complex32;
+use std::time::Duration;
+
+use crate::Device;
+use crate::Error;
+use crate::Hz;
+use crate::StreamTime;
+use crate::MIN_LEAD_TIME;
+
+/// Frequency and dwell time of a hop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hop {
+    /// Center frequency in Hz.
+    pub frequency: f64,
+    /// Time until the next hop.
+    pub dwell: Duration,
+}
+
+impl Hop {
+    /// Create a hop.
+    pub fn new(frequency: impl Into<Hz>, dwell: Duration) -> Self {
+        Self {
+            frequency: frequency.into().value(),
+            dwell,
+        }
+    }
+}
+
+/// Transmit a burst on a sequence of frequencies.
+///
+/// Hops are scheduled back to back on the device clock with timed packets. The burst of a hop
+/// starts `settle` after the hop, to give the transmitter time to retune, and has to end within
+/// the dwell time. The sequence repeats after the last hop. The [`Device`] has to be started and
+/// configured to transmit.
+#[derive(Debug, Clone)]
+pub struct Hopper {
+    /// TX data channel (default: 0).
+    pub chan: i32,
+    /// Settle time after retuning (default: 100 µs).
+    pub settle: Duration,
+    /// Maximum time, a burst is sent ahead of the device clock (default: 50 ms).
+    pub lookahead: Duration,
+    burst: Vec<Complex32>,
+    sample_rate: f64,
+    hops: Vec<Hop>,
+    index: usize,
+    next: Option<StreamTime>,
+}
+
+impl Hopper {
+    /// Create a hopper, transmitting `burst` at `sample_rate` on the given hops.
+    pub fn new(burst: Vec<Complex32>, sample_rate: f64, hops: Vec<Hop>) -> Self {
+        assert!(!burst.is_empty(), "burst has to be non-empty");
+        assert!(!hops.is_empty(), "hop sequence has to be non-empty");
+        assert!(sample_rate > 0.0);
+        Self {
+            chan: 0,
+            settle: Duration::from_micros(100),
+            lookahead: Duration::from_millis(50),
+            burst,
+            sample_rate,
+            hops,
+            index: 0,
+            next: None,
+        }
+    }
+
+    /// Hop sequence.
+    pub fn hops(&self) -> &[Hop] {
+        &self.hops
+    }
+
+    /// Duration of the burst.
+    pub fn burst_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.burst.len() as f64 / self.sample_rate)
+    }
+
+    /// Restart the sequence, i.e., the next hop is the first and scheduled relative to the
+    /// device clock.
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.next = None;
+    }
+
+    /// Schedule the next hop and return it.
+    ///
+    /// Blocks until the hop is within `lookahead` of the device clock. Returns
+    /// [`Error::ErrorValueInvalid`], if the burst does not fit in the dwell time after settling,
+    /// and [`Error::TooLate`], if the schedule fell behind the device clock, e.g., because the
+    /// hopper was not called in time. The schedule restarts with the next call after an error.
+    pub fn hop(&mut self, dev: &mut Device) -> std::result::Result<Hop, Error> {
+        let hop = self.hops[self.index];
+        if self.settle + self.burst_duration() > hop.dwell {
+            return Err(Error::ErrorValueInvalid);
+        }
+
+        let now = dev.stream_time()?;
+        let start = match self.next {
+            Some(t) => t,
+            None => StreamTime::from_secs(now.as_secs() + 2.0 * MIN_LEAD_TIME),
+        };
+        let ahead = start.secs_since(now) - self.lookahead.as_secs_f64();
+        if ahead > 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(ahead));
+        }
+
+        let at = StreamTime::from_secs(start.as_secs() + self.settle.as_secs_f64());
+        if let Err(e) = dev.send_at(self.chan, &self.burst, at, hop.frequency, self.sample_rate) {
+            self.reset();
+            return Err(e);
+        }
+
+        self.next = Some(StreamTime::from_secs(
+            start.as_secs() + hop.dwell.as_secs_f64(),
+        ));
+        self.index = (self.index + 1) % self.hops.len();
+        Ok(hop)
+    }
+
+    /// Schedule `hops` hops, or hop forever, if `None`.
+    pub fn run(&mut self, dev: &mut Device, hops: Option<usize>) -> crate::Result {
+        let mut n = 0;
+        while hops.is_none_or(|h| n < h) {
+            self.hop(dev)?;
+            n += 1;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+//! Writers and a player for IQ file formats.
+//!
+//! - [`Cf32Writer`]: interleaved 32-bit float (`.cf32`, GNU Radio `gr_complex`)
+//! - [`Cs16Writer`]: interleaved 16-bit integer with scaling (`.cs16`)
+//! - [`WavWriter`]: two-channel WAV with 32-bit float payload
+//! - [`Cf32Player`]: [`PacketSource`] that transmits a `.cf32` file
+//!
+//! Raw formats have no header, i.e., sample rate and center frequency have to be passed to tools
+//! like inspectrum separately.
+use num_complex::Complex32;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Result;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+
+use crate::Hz;
+use crate::Packet;
+use crate::PacketSource;
+use crate::TxFormat;
+
+/// Byte order of raw formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+/// Writer for IQ samples.
+pub trait IqWriter {
+    /// Write samples.
+    fn write(&mut self, samples: &[Complex32]) -> Result<()>;
+
+    /// Write the IQ samples of a packet.
+    fn write_packet(&mut self, packet: &Packet) -> Result<()> {
+        self.write(packet.samples())
+    }
+
+    /// Flush buffered data and finalize headers.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// Writer for interleaved 32-bit float samples.
+#[derive(Debug)]
+pub struct Cf32Writer<W: Write> {
+    w: W,
+    endian: Endian,
+}
+
+impl Cf32Writer<BufWriter<File>> {
+    /// Create a file with little-endian samples.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(
+            BufWriter::new(File::create(path)?),
+            Endian::Little,
+        ))
+    }
+}
+
+impl<W: Write> Cf32Writer<W> {
+    /// Create a writer with the given byte order.
+    pub fn new(w: W, endian: Endian) -> Self {
+        Self { w, endian }
+    }
+
+    /// Get the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+}
+
+impl<W: Write> IqWriter for Cf32Writer<W> {
+    fn write(&mut self, samples: &[Complex32]) -> Result<()> {
+        for s in samples {
+            let (re, im) = match self.endian {
+                Endian::Little => (s.re.to_le_bytes(), s.im.to_le_bytes()),
+                Endian::Big => (s.re.to_be_bytes(), s.im.to_be_bytes()),
+            };
+            self.w.write_all(&re)?;
+            self.w.write_all(&im)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Writer for interleaved 16-bit integer samples.
+///
+/// Samples are multiplied with `scale` and saturated. The default scale maps full scale, i.e., a
+/// magnitude of one, to `i16::MAX`.
+#[derive(Debug)]
+pub struct Cs16Writer<W: Write> {
+    w: W,
+    endian: Endian,
+    scale: f32,
+    clipped: u64,
+}
+
+impl Cs16Writer<BufWriter<File>> {
+    /// Create a file with little-endian samples and default scaling.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(
+            BufWriter::new(File::create(path)?),
+            Endian::Little,
+        ))
+    }
+}
+
+impl<W: Write> Cs16Writer<W> {
+    /// Create a writer with the given byte order and default scaling.
+    pub fn new(w: W, endian: Endian) -> Self {
+        Self {
+            w,
+            endian,
+            scale: i16::MAX as f32,
+            clipped: 0,
+        }
+    }
+
+    /// Set the scale factor.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Number of values that saturated.
+    pub fn clipped(&self) -> u64 {
+        self.clipped
+    }
+
+    /// Get the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+
+    fn convert(&mut self, v: f32) -> [u8; 2] {
+        let x = (v * self.scale).round();
+        if !(i16::MIN as f32..=i16::MAX as f32).contains(&x) {
+            self.clipped += 1;
+        }
+        let x = x as i16;
+        match self.endian {
+            Endian::Little => x.to_le_bytes(),
+            Endian::Big => x.to_be_bytes(),
+        }
+    }
+}
+
+impl<W: Write> IqWriter for Cs16Writer<W> {
+    fn write(&mut self, samples: &[Complex32]) -> Result<()> {
+        for s in samples {
+            let re = self.convert(s.re);
+            let im = self.convert(s.im);
+            self.w.write_all(&re)?;
+            self.w.write_all(&im)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Writer for WAV files with two 32-bit float channels (I and Q).
+///
+/// The header is written on creation and the sizes are updated by [`finish()`](IqWriter::finish)
+/// or, at the latest, when the writer is dropped.
+#[derive(Debug)]
+pub struct WavWriter<W: Write + Seek> {
+    w: W,
+    samples: u64,
+    finished: bool,
+}
+
+impl WavWriter<BufWriter<File>> {
+    /// Create a file for the given sample rate.
+    pub fn create<P: AsRef<Path>>(path: P, sample_rate: u32) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), sample_rate)
+    }
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    const FORMAT_IEEE_FLOAT: u16 = 3;
+    const CHANNELS: u16 = 2;
+    const BYTES_PER_FRAME: u32 = 8;
+    const HEADER_LEN: u32 = 58;
+
+    /// Create a writer for the given sample rate and write the header.
+    pub fn new(mut w: W, sample_rate: u32) -> Result<Self> {
+        w.write_all(b"RIFF")?;
+        w.write_all(&(Self::HEADER_LEN - 8).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+
+        w.write_all(b"fmt ")?;
+        w.write_all(&18u32.to_le_bytes())?;
+        w.write_all(&Self::FORMAT_IEEE_FLOAT.to_le_bytes())?;
+        w.write_all(&Self::CHANNELS.to_le_bytes())?;
+        w.write_all(&sample_rate.to_le_bytes())?;
+        w.write_all(&(sample_rate * Self::BYTES_PER_FRAME).to_le_bytes())?;
+        w.write_all(&(Self::BYTES_PER_FRAME as u16).to_le_bytes())?;
+        w.write_all(&32u16.to_le_bytes())?;
+        w.write_all(&0u16.to_le_bytes())?;
+
+        // non-PCM formats require a fact chunk with the number of frames
+        w.write_all(b"fact")?;
+        w.write_all(&4u32.to_le_bytes())?;
+        w.write_all(&0u32.to_le_bytes())?;
+
+        w.write_all(b"data")?;
+        w.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Self {
+            w,
+            samples: 0,
+            finished: false,
+        })
+    }
+
+    fn update_header(&mut self) -> Result<()> {
+        let data = (self.samples * Self::BYTES_PER_FRAME as u64).min(u32::MAX as u64) as u32;
+        let pos = self.w.stream_position()?;
+        self.w.seek(SeekFrom::Start(4))?;
+        self.w
+            .write_all(&(Self::HEADER_LEN - 8).saturating_add(data).to_le_bytes())?;
+        self.w.seek(SeekFrom::Start(46))?;
+        self.w
+            .write_all(&(self.samples.min(u32::MAX as u64) as u32).to_le_bytes())?;
+        self.w.seek(SeekFrom::Start(54))?;
+        self.w.write_all(&data.to_le_bytes())?;
+        self.w.seek(SeekFrom::Start(pos))?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> IqWriter for WavWriter<W> {
+    fn write(&mut self, samples: &[Complex32]) -> Result<()> {
+        for s in samples {
+            self.w.write_all(&s.re.to_le_bytes())?;
+            self.w.write_all(&s.im.to_le_bytes())?;
+        }
+        self.samples += samples.len() as u64;
+        self.finished = false;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.update_header()?;
+        self.w.flush()?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Drop for WavWriter<W> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.finish();
+        }
+    }
+}
+
+/// Player for interleaved 32-bit float samples, transmitted with a
+/// [`Transmitter`](crate::Transmitter).
+///
+/// The file is sent in packets of `packet_len` samples, the last packet may be shorter. The stream
+/// ends at the end of the file or at the first read error, which is kept in
+/// [`error()`](Self::error).
+#[derive(Debug)]
+pub struct Cf32Player<R: Read> {
+    r: R,
+    endian: Endian,
+    format: TxFormat,
+    /// Samples per packet (default: 10000).
+    pub packet_len: usize,
+    error: Option<std::io::Error>,
+}
+
+impl Cf32Player<BufReader<File>> {
+    /// Open a file with little-endian samples, played at `frequency` with `sample_rate`.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        frequency: impl Into<Hz>,
+        sample_rate: impl Into<Hz>,
+    ) -> Result<Self> {
+        Ok(Self::new(
+            BufReader::new(File::open(path)?),
+            Endian::Little,
+            frequency,
+            sample_rate,
+        ))
+    }
+}
+
+impl<R: Read> Cf32Player<R> {
+    /// Create a player with the given byte order.
+    pub fn new(r: R, endian: Endian, frequency: impl Into<Hz>, sample_rate: impl Into<Hz>) -> Self {
+        Self {
+            r,
+            endian,
+            format: TxFormat {
+                frequency: frequency.into().value(),
+                sample_rate: sample_rate.into().value(),
+            },
+            packet_len: 10000,
+            error: None,
+        }
+    }
+
+    /// Read error that ended the stream.
+    pub fn error(&self) -> Option<&std::io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Read the next sample, `None` at the end of the file.
+    fn read_sample(&mut self) -> Result<Option<Complex32>> {
+        let mut b = [0u8; 8];
+        match self.r.read_exact(&mut b) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let (re, im) = ([b[0], b[1], b[2], b[3]], [b[4], b[5], b[6], b[7]]);
+        Ok(Some(match self.endian {
+            Endian::Little => Complex32::new(f32::from_le_bytes(re), f32::from_le_bytes(im)),
+            Endian::Big => Complex32::new(f32::from_be_bytes(re), f32::from_be_bytes(im)),
+        }))
+    }
+}
+
+impl<R: Read> PacketSource for Cf32Player<R> {
+    fn next_packet(&mut self, samples: &mut Vec<Complex32>) -> Option<TxFormat> {
+        if self.error.is_some() {
+            return None;
+        }
+        while samples.len() < self.packet_len.max(1) {
+            match self.read_sample() {
+                Ok(Some(s)) => samples.push(s),
+                Ok(None) => break,
+                Err(e) => {
+                    self.error = Some(e);
+                    break;
+                }
+            }
+        }
+        if samples.is_empty() {
+            None
+        } else {
+            Some(self.format)
+        }
+    }
+}
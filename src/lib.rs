@@ -1,54 +1,282 @@
+#[cfg(all(not(feature = "sys-stub"), not(feature = "dlopen")))]
 use aaronia_rtsa_sys as sys;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::Weak;
+use std::time::Duration;
+use std::time::Instant;
+#[cfg(all(not(feature = "sys-stub"), feature = "dlopen"))]
+use sys_dl as sys;
+#[cfg(feature = "sys-stub")]
+use sys_stub as sys;
 use widestring::WideCString;
 
+/// Emit a [`tracing`](https://docs.rs/tracing) event, if the `tracing` feature is enabled.
+macro_rules! event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!($($arg)*);
+    };
+}
+
+/// Call an RTSA library function and map the result code, recording a [`Diagnostic`] with the
+/// given arguments on failure.
+macro_rules! ffi {
+    ($f:ident($($arg:expr),* $(,)?) $(; $($key:ident = $value:expr),+)?) => {{
+        let code = crate::sys::$f($($arg),*);
+        let r = crate::res(code);
+        if let Err(e) = &r {
+            crate::diag::record(stringify!($f), code as u32, e, || {
+                vec![$($((stringify!($key), format!("{:?}", $value))),+)?]
+            });
+        }
+        r
+    }};
+}
+
+mod capabilities;
+pub use capabilities::Capabilities;
+mod clock;
+pub use clock::ClockStatus;
+pub use clock::ClockSync;
+pub use clock::ReferenceClock;
+mod config;
+pub use config::ConfigChange;
+pub use config::ConfigProfile;
+pub use config::ConfigValue;
+mod correction;
+mod details;
+pub use details::DeviceDetails;
+mod diag;
+pub use details::HardwareModel;
+pub use diag::last_diagnostic;
+pub use diag::DetailedError;
+pub use diag::Diagnostic;
+mod devices;
+pub use devices::DeviceIter;
+mod discover;
+pub use discover::ConfigEntry;
+mod file;
+pub use file::FILE_DEVICE_TYPE;
+mod format;
+pub use format::OutputFormat;
+pub use format::PayloadKind;
+mod gain;
+pub use gain::GainModel;
+pub use gain::GainStage;
+pub use gain::RxGain;
+mod paths;
+pub use paths::ConfigPath;
+pub use paths::KNOWN_PATHS;
+mod payload;
+pub use payload::PacketData;
+pub use payload::PacketLayout;
+pub use payload::SpectrumRow;
+pub use payload::SpectrumView;
+mod options;
+pub use options::OpenOptions;
+mod memory;
+pub use memory::recommended_memory;
+pub use memory::MemoryStats;
+mod multichannel;
+pub use multichannel::ChannelSamples;
+pub use multichannel::MultiChannelPacket;
+mod pool;
+pub use pool::BufferPool;
+pub use pool::PoolBuf;
+mod poll;
+pub use poll::CancelToken;
+pub use poll::PollStrategy;
+mod queue;
+mod sample;
+pub use sample::SampleFormat;
+mod select;
+pub use queue::QueueStats;
+pub use select::Select;
+mod shared;
+mod sink;
+mod source;
+pub use shared::suite_running;
+pub use shared::SHARED_DEVICE_TYPE;
+pub use sink::PacketSink;
+pub use source::PacketSource;
+pub use source::Transmitter;
+pub use source::TxFormat;
+pub use source::Watermarks;
+mod startup;
+pub use startup::StartProgress;
+pub use startup::StreamInfo;
+mod status;
+pub use status::StatusEvent;
+pub use status::STATUS_CHANNEL;
+mod throughput;
+pub use throughput::ThroughputReport;
+mod time;
+pub use time::ClockAnchor;
+pub use time::StreamTime;
+mod recover;
+mod units;
+pub use units::Db;
+pub use units::Hz;
+pub use units::Seconds;
+pub use units::Units;
+mod reset;
+mod rfpath;
+pub use rfpath::RfCapabilities;
+pub use rfpath::RfSwitch;
+pub use rfpath::RxChannel;
+mod watch;
+pub use watch::DeviceEvent;
+pub use watch::DeviceWatcher;
+pub mod agc;
+pub mod aggregate;
+pub mod burst;
+pub mod coherent;
+pub mod demod;
+pub mod dsp;
+pub mod hop;
+pub mod io;
+pub mod measurements;
+pub mod monitor;
+pub mod peaks;
+pub mod pipeline;
+pub mod profiles;
+pub mod record;
+pub mod render;
+pub mod ring;
+pub mod runtime;
+pub mod session;
+pub mod siggen;
+pub mod sweep;
+pub mod tee;
+pub mod trigger;
+pub mod tune;
+pub mod vita49;
+
+#[cfg(all(not(feature = "sys"), not(feature = "sys-stub")))]
+compile_error!("either the `sys` or the `sys-stub` feature has to be enabled");
+#[cfg(all(not(feature = "sys-stub"), feature = "dlopen"))]
+mod sys_dl;
+#[cfg(feature = "sys-stub")]
+mod sys_stub;
+#[cfg(feature = "sys-stub")]
+pub use sys_stub::control as stub;
+
+#[cfg(feature = "audio")]
+pub mod audio;
+#[cfg(feature = "crossbeam")]
+pub mod bridge;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "futuresdr")]
+pub mod futuresdr;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "soak")]
+pub mod soak;
+#[cfg(feature = "soapy")]
+pub mod soapy;
+
 /// Version String (`<major>.<minor>`)
 pub fn version() -> String {
     let n = unsafe { sys::AARTSAAPI_Version() };
     format!("{}.{}", n >> 16, n & 0xffff)
 }
 
-static API: Mutex<Option<Api>> = Mutex::new(None);
-
-struct Api {
-    handles: usize,
+/// Minimum time in seconds between the device clock and the start of a packet, scheduled with
+/// [`Device::send_at()`].
+pub const MIN_LEAD_TIME: f64 = 0.005;
+
+static RUNTIME: Mutex<Runtime> = Mutex::new(Runtime {
+    current: Weak::new(),
+    initialized: None,
+    next_id: 0,
+});
+
+/// State of the RTSA library, shared by all [`Rtsa`] instances.
+struct Runtime {
+    /// Runtime, used by the live handles.
+    current: Weak<Rtsa>,
+    /// Runtime, which initialized the library and did not shut it down yet.
+    initialized: Option<u64>,
+    next_id: u64,
 }
 
-impl Api {
-    fn new(mem: Memory) -> Self {
-        unsafe { res(sys::AARTSAAPI_Init(mem.into())).expect("RTSA library initialization failed") }
-        Self { handles: 0 }
-    }
-
-    fn add_handle(&mut self) {
-        self.handles += 1;
-    }
+/// Initialization of the RTSA library, shared by all [`ApiHandle`]s and [`Device`]s.
+///
+/// Each [`ApiHandle`] holds an `Arc<Rtsa>`, and each [`Device`] holds the [`ApiHandle`], it was
+/// created from, i.e., the library stays initialized, while any of them is alive. Teardown is
+/// ordered, also if handles and devices are dropped concurrently on several threads:
+///
+/// 1. A [`Device`] is stopped, disconnected, and closed, as needed.
+/// 2. The library handle of the [`ApiHandle`] or [`Device`] is closed.
+/// 3. The library is shut down with the last reference to the [`Rtsa`].
+///
+/// A new [`ApiHandle`], created while the last reference is dropped on another thread, waits
+/// for the shutdown and initializes the library again.
+#[derive(Debug)]
+pub struct Rtsa {
+    id: u64,
+    mem: Memory,
+}
 
-    fn remove_handle(&mut self) {
-        self.handles -= 1;
+impl Rtsa {
+    /// Get the current runtime or initialize the library.
+    fn get(mem: Memory, strict: bool) -> std::result::Result<Arc<Rtsa>, Error> {
+        let mut rt = RUNTIME.lock().unwrap();
+        if let Some(current) = rt.current.upgrade() {
+            if strict && current.mem != mem {
+                return Err(Error::AlreadyInitialized {
+                    current: current.mem,
+                });
+            }
+            return Ok(current);
+        }
+        // the last reference is dropped on another thread, shut down on its behalf
+        if rt.initialized.take().is_some() {
+            unsafe { ffi!(AARTSAAPI_Shutdown())? };
+        }
+        unsafe { ffi!(AARTSAAPI_Init(mem.into()))? };
+        let id = rt.next_id;
+        rt.next_id += 1;
+        rt.initialized = Some(id);
+        let current = Arc::new(Rtsa { id, mem });
+        rt.current = Arc::downgrade(&current);
+        Ok(current)
     }
 
-    fn handles(&self) -> usize {
-        self.handles
+    /// [`Memory`] size of the library.
+    pub fn memory(&self) -> Memory {
+        self.mem
     }
 }
 
-impl Drop for Api {
+impl Drop for Rtsa {
     fn drop(&mut self) {
-        unsafe { res(sys::AARTSAAPI_Shutdown()).expect("RTSA library shutdown failed") }
+        let mut rt = RUNTIME.lock().unwrap();
+        if rt.initialized == Some(self.id) {
+            rt.initialized = None;
+            unsafe { ffi!(AARTSAAPI_Shutdown()).expect("RTSA library shutdown failed") }
+        }
     }
 }
 
 /// Handle to interface the library
 ///
-/// Internally, all [`ApiHandle`]s use one global library handle, which is dropped when there are
-/// no [`ApiHandle`]s left. Only the first handle, i.e., the one that creates the global library
-/// handle can configure the [`Memory`] size. Later created [`ApiHandle`]s ignore the memory
-/// parameter.
+/// Internally, all [`ApiHandle`]s share one [`Rtsa`] runtime, which shuts the library down when
+/// there are no [`ApiHandle`]s or [`Device`]s left. Only the first handle, i.e., the one that
+/// initializes the library can configure the [`Memory`] size. Later created [`ApiHandle`]s ignore
+/// the memory parameter, use [`ApiHandle::try_with_mem()`] to detect a different size.
 #[derive(Debug)]
 pub struct ApiHandle {
     inner: sys::AARTSAAPI_Handle,
+    // dropped after the library handle is closed
+    rt: Arc<Rtsa>,
 }
 
 impl ApiHandle {
@@ -62,35 +290,50 @@ impl ApiHandle {
     /// The memory size is only considered, if this is the first [`ApiHandle`], i.e. the
     /// one that initializes the underlying RTSA library.
     pub fn with_mem(mem: Memory) -> std::result::Result<Self, Error> {
-        let mut api = API.lock().unwrap();
+        Self::open(mem, false)
+    }
+
+    /// Create [`ApiHandle`] with given [`Memory`] size, failing if the RTSA library is already
+    /// initialized with a different size.
+    ///
+    /// Returns [`Error::AlreadyInitialized`] with the active size, while other [`ApiHandle`]s
+    /// exist, which initialized the library with another size.
+    pub fn try_with_mem(mem: Memory) -> std::result::Result<Self, Error> {
+        Self::open(mem, true)
+    }
 
-        if api.is_none() {
-            *api = Some(Api::new(mem));
+    fn open(mem: Memory, strict: bool) -> std::result::Result<Self, Error> {
+        #[cfg(all(not(feature = "sys-stub"), feature = "dlopen"))]
+        if !sys::loaded() {
+            return Err(Error::LibraryNotFound);
         }
 
+        Self::attach(Rtsa::get(mem, strict)?)
+    }
+
+    /// Open a library handle of the runtime.
+    fn attach(rt: Arc<Rtsa>) -> std::result::Result<Self, Error> {
         let mut h = sys::AARTSAAPI_Handle {
             d: std::ptr::null_mut(),
         };
-        unsafe {
-            match res(sys::AARTSAAPI_Open(&mut h)) {
-                Ok(()) => {
-                    api.as_mut().unwrap().add_handle();
-                    Ok(ApiHandle { inner: h })
-                }
-                Err(e) => {
-                    if api.as_mut().unwrap().handles() == 0 {
-                        *api = None;
-                    }
-                    Err(e)
-                }
-            }
-        }
+        unsafe { ffi!(AARTSAAPI_Open(&mut h))? };
+        Ok(ApiHandle { inner: h, rt })
+    }
+
+    /// [`Memory`] size of the RTSA library, i.e., the size of the first [`ApiHandle`].
+    pub fn memory(&self) -> Memory {
+        self.rt.memory()
+    }
+
+    /// Runtime of the library, shared by all [`ApiHandle`]s and [`Device`]s.
+    pub fn runtime(&self) -> &Arc<Rtsa> {
+        &self.rt
     }
 
     /// Rescan for devices.
     pub fn rescan_devices(&mut self) -> Result {
         loop {
-            let r = unsafe { res(sys::AARTSAAPI_RescanDevices(&mut self.inner, 10000)) };
+            let r = unsafe { ffi!(AARTSAAPI_RescanDevices(&mut self.inner, 10000)) };
             match r {
                 Ok(()) => break Ok(()),
                 Err(Error::Retry) => continue,
@@ -99,39 +342,58 @@ impl ApiHandle {
         }
     }
 
+    /// Rescan devices, waiting at most `timeout` for the scan to complete.
+    ///
+    /// Returns [`Error::Retry`], if the scan did not complete in time.
+    pub fn rescan_devices_timeout(&mut self, timeout: Duration) -> Result {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let ms = remaining.as_millis().min(i32::MAX as u128) as _;
+            let r = unsafe { ffi!(AARTSAAPI_RescanDevices(&mut self.inner, ms)) };
+            match r {
+                Err(Error::Retry) if !remaining.is_zero() => continue,
+                r => return r,
+            }
+        }
+    }
+
+    /// Rescan devices, until the scan completes or `token` is cancelled.
+    ///
+    /// Returns [`Error::Cancelled`], if the token is cancelled before the scan completed.
+    pub fn rescan_devices_cancellable(&mut self, token: &CancelToken) -> Result {
+        loop {
+            if token.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            match self.rescan_devices_timeout(Duration::from_millis(100)) {
+                Err(Error::Retry) => continue,
+                r => return r,
+            }
+        }
+    }
+
+    /// Start or continue a device rescan without blocking.
+    ///
+    /// Returns [`Error::Retry`], while the scan is in progress.
+    pub fn try_rescan(&mut self) -> Result {
+        unsafe { ffi!(AARTSAAPI_RescanDevices(&mut self.inner, 0)) }
+    }
+
     /// Reset all devices.
     pub fn reset_devices(&mut self) -> Result {
-        unsafe { res(sys::AARTSAAPI_ResetDevices(&mut self.inner)) }
+        unsafe { ffi!(AARTSAAPI_ResetDevices(&mut self.inner)) }
     }
 
     /// Get a list with information about all detected devices.
     pub fn devices(&mut self) -> std::result::Result<Vec<DeviceInfo>, Error> {
-        let mut devices = Vec::new();
-        let device_type = WideCString::from_str_truncate("spectranv6");
-
-        for i in 0.. {
-            let mut di = DeviceInfo::new();
-            match unsafe {
-                res(sys::AARTSAAPI_EnumDevice(
-                    &mut self.inner,
-                    device_type.as_ptr(),
-                    i,
-                    &mut di.inner,
-                ))
-            } {
-                Ok(()) => devices.push(di),
-                Err(Error::Empty) => break,
-                Err(e) => return Err(e),
-            }
-        }
-
-        Ok(devices)
+        self.iter_devices("spectranv6").collect()
     }
 
     /// Get the first detected [`Device`].
     pub fn get_device(&mut self) -> std::result::Result<Device, Error> {
         let devs = self.devices()?;
-        if let Some(d) = devs.get(0) {
+        if let Some(d) = devs.first() {
             self.get_this_device(d)
         } else {
             Err(Error::Empty)
@@ -142,21 +404,14 @@ impl ApiHandle {
     ///
     /// The [DeviceInfo] can be get from the [devices()](Self::devices) function.
     pub fn get_this_device(&mut self, info: &DeviceInfo) -> std::result::Result<Device, Error> {
-        Device::new(info)
+        Device::new(info, &self.rt)
     }
 }
 
 impl Drop for ApiHandle {
     fn drop(&mut self) {
         unsafe {
-            res(sys::AARTSAAPI_Close(&mut self.inner)).expect("error dropping API handle");
-        }
-
-        let mut api = API.lock().unwrap();
-
-        api.as_mut().unwrap().remove_handle();
-        if api.as_mut().unwrap().handles() == 0 {
-            *api = None;
+            ffi!(AARTSAAPI_Close(&mut self.inner)).expect("error dropping API handle");
         }
     }
 }
@@ -198,16 +453,21 @@ impl ConfigInfo {
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum DeviceStatus {
+/// Lifecycle status of a [`Device`] handle, see [`Device::status()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceStatus {
+    /// Not opened.
     Uninit,
+    /// Opened, see [`Device::open()`].
     Opened,
+    /// Connected, see [`Device::connect()`].
     Connected,
+    /// Started, see [`Device::start()`].
     Started,
 }
 
 /// Device state can be queried with [`Device::state()`]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DeviceState {
     Idle,
     Connecting,
@@ -251,90 +511,157 @@ pub struct Device {
     api: ApiHandle,
     status: DeviceStatus,
     serial: WideCString,
+    device_type: &'static str,
+    profile: ConfigProfile,
+    auto_recover: bool,
+    poll: PollStrategy,
+    queues: HashMap<i32, queue::QueueTracker>,
+    select_next: usize,
+    correction: Option<correction::Correction>,
+    capabilities: Option<Capabilities>,
 }
 
 impl Device {
-    fn new(info: &DeviceInfo) -> std::result::Result<Self, Error> {
+    fn new(info: &DeviceInfo, rt: &Arc<Rtsa>) -> std::result::Result<Self, Error> {
         Ok(Device {
             inner: sys::AARTSAAPI_Device {
                 d: std::ptr::null_mut(),
             },
-            api: ApiHandle::new()?,
+            api: ApiHandle::attach(rt.clone())?,
             status: DeviceStatus::Uninit,
             serial: WideCString::from_vec_truncate(info.inner.serialNumber),
+            device_type: "spectranv6/raw",
+            profile: ConfigProfile::new(),
+            auto_recover: false,
+            poll: PollStrategy::default(),
+            queues: HashMap::new(),
+            select_next: 0,
+            correction: None,
+            capabilities: None,
+        })
+    }
+
+    /// Get the lifecycle [`DeviceStatus`] of the handle.
+    pub fn status(&self) -> DeviceStatus {
+        self.status
+    }
+
+    /// Runtime of the library, kept initialized while the device is alive, see [`Rtsa`].
+    pub fn runtime(&self) -> &Arc<Rtsa> {
+        self.api.runtime()
+    }
+
+    /// Check the lifecycle status before a transition.
+    ///
+    /// With the `strict-state` feature, debug builds panic instead of returning
+    /// [`Error::WrongState`].
+    fn expect_status(&self, expected: DeviceStatus) -> Result {
+        if self.status == expected {
+            return Ok(());
+        }
+        if cfg!(all(debug_assertions, feature = "strict-state")) {
+            panic!(
+                "wrong device state: expected {expected:?}, actual {:?}",
+                self.status
+            );
+        }
+        Err(Error::WrongState {
+            expected,
+            actual: self.status,
         })
     }
 
     /// Open the [`Device`] for exclusive use.
     ///
     /// This allocates the required data structures and prepares the configuration settings, but
-    /// will not access the hardware.
+    /// will not access the hardware. Returns [`Error::DeviceInUse`], if the device is held by
+    /// another application, e.g., RTSA-Suite PRO.
     pub fn open(&mut self) -> Result {
-        assert_eq!(self.status, DeviceStatus::Uninit);
-        let device_type = WideCString::from_str_truncate("spectranv6/raw");
+        self.expect_status(DeviceStatus::Uninit)?;
+        self.capabilities = None;
+        let device_type = WideCString::from_str_truncate(self.device_type);
 
-        unsafe {
-            res(sys::AARTSAAPI_OpenDevice(
+        let r = unsafe {
+            ffi!(AARTSAAPI_OpenDevice(
                 &mut self.api.inner,
                 &mut self.inner,
                 device_type.as_ptr(),
                 self.serial.as_ptr(),
-            ))?;
+            ); serial = self.serial.to_string_lossy(), device_type = self.device_type)
+        };
+        match r {
+            Err(Error::ErrorBusy) => return Err(Self::in_use_error(&self.serial)),
+            r => r?,
         }
 
         self.status = DeviceStatus::Opened;
+        event!(tracing::Level::INFO, serial = %self.serial.display(), "open");
 
         Ok(())
     }
 
     /// Close the [`Device`] for exclusive use.
     pub fn close(&mut self) -> Result {
-        assert_eq!(self.status, DeviceStatus::Opened);
+        self.expect_status(DeviceStatus::Opened)?;
         unsafe {
-            res(sys::AARTSAAPI_CloseDevice(
+            ffi!(AARTSAAPI_CloseDevice(
                 &mut self.api.inner,
                 &mut self.inner,
-            ))?
+            ); serial = self.serial.to_string_lossy())?
         }
         self.status = DeviceStatus::Uninit;
+        event!(tracing::Level::INFO, serial = %self.serial.display(), "close");
         Ok(())
     }
 
     /// Connect to the [`Device`].
     pub fn connect(&mut self) -> Result {
-        assert_eq!(self.status, DeviceStatus::Opened);
-        unsafe { res(sys::AARTSAAPI_ConnectDevice(&mut self.inner))? }
+        self.expect_status(DeviceStatus::Opened)?;
+        unsafe {
+            ffi!(AARTSAAPI_ConnectDevice(&mut self.inner); serial = self.serial.to_string_lossy())?
+        }
         self.status = DeviceStatus::Connected;
+        event!(tracing::Level::INFO, serial = %self.serial.display(), "connect");
         Ok(())
     }
 
     /// Disconnect from the [`Device`].
     pub fn disconnect(&mut self) -> Result {
-        assert_eq!(self.status, DeviceStatus::Connected);
-        unsafe { res(sys::AARTSAAPI_ConnectDevice(&mut self.inner))? }
+        self.expect_status(DeviceStatus::Connected)?;
+        unsafe {
+            ffi!(AARTSAAPI_DisconnectDevice(&mut self.inner);
+                serial = self.serial.to_string_lossy())?
+        }
         self.status = DeviceStatus::Opened;
+        event!(tracing::Level::INFO, serial = %self.serial.display(), "disconnect");
         Ok(())
     }
 
     /// Start data acqusition from the [`Device] / data transmission to the [`Device`].
     pub fn start(&mut self) -> Result {
-        assert_eq!(self.status, DeviceStatus::Connected);
-        unsafe { res(sys::AARTSAAPI_StartDevice(&mut self.inner))? }
+        self.expect_status(DeviceStatus::Connected)?;
+        unsafe {
+            ffi!(AARTSAAPI_StartDevice(&mut self.inner); serial = self.serial.to_string_lossy())?
+        }
         self.status = DeviceStatus::Started;
+        event!(tracing::Level::INFO, serial = %self.serial.display(), "start");
         Ok(())
     }
 
     /// Stop data acqusition from the [`Device`] / data transmission to the [`Device`].
     pub fn stop(&mut self) -> Result {
-        assert_eq!(self.status, DeviceStatus::Started);
-        unsafe { res(sys::AARTSAAPI_StopDevice(&mut self.inner))? }
+        self.expect_status(DeviceStatus::Started)?;
+        unsafe {
+            ffi!(AARTSAAPI_StopDevice(&mut self.inner); serial = self.serial.to_string_lossy())?
+        }
         self.status = DeviceStatus::Connected;
+        event!(tracing::Level::INFO, serial = %self.serial.display(), "stop");
         Ok(())
     }
 
     /// Get [`DeviceState`] from the [`Device`].
     pub fn state(&mut self) -> std::result::Result<DeviceState, Error> {
-        let res = unsafe { res(sys::AARTSAAPI_GetDeviceState(&mut self.inner)) };
+        let res = unsafe { ffi!(AARTSAAPI_GetDeviceState(&mut self.inner)) };
         match res {
             Ok(()) => Err(Error::Error),
             Err(e) => e.try_into(),
@@ -347,14 +674,14 @@ impl Device {
         let mut node = Config::new();
         let path = WideCString::from_str_truncate(path.as_ref());
 
-        unsafe { res(sys::AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
         unsafe {
-            res(sys::AARTSAAPI_ConfigFind(
+            ffi!(AARTSAAPI_ConfigFind(
                 &mut self.inner,
                 &mut root.inner,
                 &mut node.inner,
                 path.as_ptr(),
-            ))?
+            ); path = path.to_string_lossy())?
         };
 
         let (_, item) = self.parse_item(&mut node)?;
@@ -364,160 +691,360 @@ impl Device {
 
     /// Set [`Device`] configuration parameter as string.
     pub fn set<S1: AsRef<str>, S2: AsRef<str>>(&mut self, path: S1, value: S2) -> Result {
+        let record = (
+            path.as_ref().to_string(),
+            ConfigValue::String(value.as_ref().to_string()),
+        );
         let path = WideCString::from_str_truncate(path.as_ref());
         let value = WideCString::from_str_truncate(value.as_ref());
 
         let mut root = Config::new();
         let mut node = Config::new();
 
-        unsafe { res(sys::AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
         unsafe {
-            res(sys::AARTSAAPI_ConfigFind(
+            ffi!(AARTSAAPI_ConfigFind(
                 &mut self.inner,
                 &mut root.inner,
                 &mut node.inner,
                 path.as_ptr(),
-            ))?
+            ); path = path.to_string_lossy())?
         };
         unsafe {
-            res(sys::AARTSAAPI_ConfigSetString(
+            ffi!(AARTSAAPI_ConfigSetString(
                 &mut self.inner,
                 &mut node.inner,
                 value.as_ptr(),
-            ))?
+            ); value = value.to_string_lossy())?
         };
 
+        event!(
+            tracing::Level::DEBUG,
+            serial = %self.serial.display(),
+            path = %record.0,
+            value = %record.1,
+            "set"
+        );
+        self.profile.set(record.0, record.1);
         Ok(())
     }
 
     /// Set [`Device`] configuration parameter as float.
+    ///
+    /// Returns [`Error::WarningValueAdjusted`], if the device rounded the value. The rounded value
+    /// is applied and recorded in [`last_config()`](Self::last_config).
     pub fn set_float<S1: AsRef<str>, F: Into<f64>>(&mut self, path: S1, value: F) -> Result {
+        let value = value.into();
+        let record = path.as_ref().to_string();
         let path = WideCString::from_str_truncate(path.as_ref());
 
         let mut root = Config::new();
         let mut node = Config::new();
 
-        unsafe { res(sys::AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
         unsafe {
-            res(sys::AARTSAAPI_ConfigFind(
+            ffi!(AARTSAAPI_ConfigFind(
                 &mut self.inner,
                 &mut root.inner,
                 &mut node.inner,
                 path.as_ptr(),
-            ))?
+            ); path = path.to_string_lossy())?
         };
-        unsafe {
-            res(sys::AARTSAAPI_ConfigSetFloat(
+        let r = unsafe {
+            ffi!(AARTSAAPI_ConfigSetFloat(
                 &mut self.inner,
                 &mut node.inner,
-                value.into(),
-            ))?
+                value,
+            ); value = value)
+        };
+        // the device rounded the value, record the one that is applied
+        let value = match r {
+            Ok(()) => value,
+            Err(Error::WarningValueAdjusted) => {
+                let mut applied = value;
+                unsafe {
+                    ffi!(AARTSAAPI_ConfigGetFloat(
+                        &mut self.inner,
+                        &mut node.inner,
+                        &mut applied,
+                    ))?
+                };
+                applied
+            }
+            Err(e) => return Err(e),
         };
 
-        Ok(())
+        event!(
+            tracing::Level::DEBUG,
+            serial = %self.serial.display(),
+            path = %record,
+            value,
+            "set_float"
+        );
+        self.profile.set(record, ConfigValue::Float(value));
+        r
     }
 
     /// Set [`Device`] configuration parameter as integer.
     pub fn set_int<S1: AsRef<str>, F: Into<i64>>(&mut self, path: S1, value: F) -> Result {
+        let value = value.into();
+        let record = path.as_ref().to_string();
         let path = WideCString::from_str_truncate(path.as_ref());
 
         let mut root = Config::new();
         let mut node = Config::new();
 
-        unsafe { res(sys::AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
         unsafe {
-            res(sys::AARTSAAPI_ConfigFind(
+            ffi!(AARTSAAPI_ConfigFind(
                 &mut self.inner,
                 &mut root.inner,
                 &mut node.inner,
                 path.as_ptr(),
-            ))?
+            ); path = path.to_string_lossy())?
         };
         unsafe {
-            res(sys::AARTSAAPI_ConfigSetInteger(
+            ffi!(AARTSAAPI_ConfigSetInteger(
                 &mut self.inner,
                 &mut node.inner,
-                value.into(),
-            ))?
+                value,
+            ); value = value)?
         };
 
+        event!(
+            tracing::Level::DEBUG,
+            serial = %self.serial.display(),
+            path = %record,
+            value,
+            "set_int"
+        );
+        self.profile.set(record, ConfigValue::Int(value));
         Ok(())
     }
 
+    /// Set [`Device`] configuration parameter as float, clamped to its range.
+    ///
+    /// The value is clamped to the minimum and maximum of the parameter and rounded to its step
+    /// size. Returns the value that is actually set, read back from the device.
+    pub fn set_float_clamped<S: AsRef<str>>(
+        &mut self,
+        path: S,
+        value: impl Into<f64>,
+    ) -> std::result::Result<f64, Error> {
+        let entry = self.config_entry(path.as_ref())?;
+        if entry.kind != ConfigType::Number {
+            return Err(Error::ErrorValueInvalid);
+        }
+
+        let mut v = value.into();
+        if entry.min <= entry.max {
+            v = v.clamp(entry.min, entry.max);
+        }
+        if entry.step > 0.0 {
+            v = entry.min + ((v - entry.min) / entry.step).round() * entry.step;
+            if v > entry.max {
+                v -= entry.step;
+            }
+            if entry.min <= entry.max {
+                v = v.clamp(entry.min, entry.max);
+            }
+        }
+
+        match self.set_float(path.as_ref(), v) {
+            Ok(()) | Err(Error::WarningValueAdjusted) => {}
+            Err(e) => return Err(e),
+        }
+
+        match self.get(path)? {
+            ConfigItem::Number(n) => Ok(n),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+
     /// Query [`Packet`] queue of [`Device`] data channel.
     pub fn packets_avail(&mut self, chan: i32) -> std::result::Result<usize, Error> {
         let mut n = 0i32;
-        unsafe { res(sys::AARTSAAPI_AvailPackets(&mut self.inner, chan, &mut n))? };
+        unsafe { ffi!(AARTSAAPI_AvailPackets(&mut self.inner, chan, &mut n); chan = chan)? };
         Ok(n as usize)
     }
 
     /// Get [`Packet`] from the [`Device`].
     ///
-    /// This call is blocking, polling the queue every 5ms, in case it is empty.
+    /// This call is blocking, polling the queue according to the [`PollStrategy`] of the device
+    /// (default: every 5ms), in case it is empty. The packet stays at the head of the queue
+    /// until it is consumed, i.e., getting a packet again without [`consume()`](Self::consume)
+    /// returns the same packet.
     pub fn packet(&mut self, chan: i32) -> std::result::Result<Packet, Error> {
+        self.packet_with(chan, self.poll)
+    }
+
+    /// Get [`Packet`] from the [`Device`], polling with the given [`PollStrategy`].
+    pub fn packet_with(
+        &mut self,
+        chan: i32,
+        strategy: PollStrategy,
+    ) -> std::result::Result<Packet, Error> {
+        self.check_channel(chan)?;
         let mut packet = Packet::new();
+        let mut poller = poll::Poller::new(strategy);
 
         loop {
             let ret = unsafe {
-                res(sys::AARTSAAPI_GetPacket(
+                ffi!(AARTSAAPI_GetPacket(
                     &mut self.inner,
                     chan,
                     0,
                     &mut packet.inner,
-                ))
+                ); chan = chan)
             };
             match ret {
-                Ok(_) => return Ok(packet),
-                Err(Error::Empty) => {
-                    std::thread::sleep(std::time::Duration::from_millis(5));
+                Ok(_) => {
+                    packet.kind = self.known_output_format().and_then(|f| f.payload(chan));
+                    self.track_packet(chan, &packet);
+                    self.correct_packet(chan, &packet);
+                    return Ok(packet);
                 }
+                Err(Error::Empty) => poller.wait(),
                 Err(e) => return Err(e),
             }
         }
     }
 
+    /// Get [`Packet`] from the [`Device`], until `token` is cancelled.
+    ///
+    /// Blocks like [`packet()`](Self::packet) and checks the token between polls. Returns
+    /// [`Error::Cancelled`], if the token is cancelled, while the queue is empty.
+    pub fn packet_cancellable(
+        &mut self,
+        chan: i32,
+        token: &CancelToken,
+    ) -> std::result::Result<Packet, Error> {
+        let mut poller = poll::Poller::new(self.poll);
+        loop {
+            if token.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            match self.try_packet(chan) {
+                Err(Error::Empty) => poller.wait(),
+                r => return r,
+            }
+        }
+    }
+
+    /// Set the [`PollStrategy`], used by [`packet()`](Self::packet).
+    pub fn set_poll_strategy(&mut self, strategy: PollStrategy) {
+        self.poll = strategy;
+    }
+
+    /// Get the [`PollStrategy`], used by [`packet()`](Self::packet).
+    pub fn poll_strategy(&self) -> PollStrategy {
+        self.poll
+    }
+
     /// Try to get a [`Packet`] from the [`Device`] data channel.
     ///
     /// This call is non-blocking.
     pub fn try_packet(&mut self, chan: i32) -> std::result::Result<Packet, Error> {
+        self.check_channel(chan)?;
         let mut packet = Packet::new();
 
         unsafe {
-            res(sys::AARTSAAPI_GetPacket(
+            ffi!(AARTSAAPI_GetPacket(
                 &mut self.inner,
                 chan,
                 0,
                 &mut packet.inner,
-            ))
-        }
-        .map(|_| packet)
+            ); chan = chan)?
+        };
+        packet.kind = self.known_output_format().and_then(|f| f.payload(chan));
+        self.track_packet(chan, &packet);
+        self.correct_packet(chan, &packet);
+        Ok(packet)
     }
 
     /// Send a [`Packet`] to the [`Device`] data channel.
     pub fn send_packet(&mut self, chan: i32, packet: &Packet) -> Result {
         unsafe {
-            res(sys::AARTSAAPI_SendPacket(
+            ffi!(AARTSAAPI_SendPacket(
                 &mut self.inner,
                 chan,
                 &packet.inner,
-            ))
+            ); chan = chan)
+        }
+    }
+
+    /// Send IQ samples to the [`Device`] data channel.
+    ///
+    /// The samples are transmitted from `start_time` (in device clock time) with the given
+    /// sample rate and center frequency. The samples are copied by the RTSA library, i.e., the
+    /// buffer can be reused once the call returns.
+    pub fn send_samples(
+        &mut self,
+        chan: i32,
+        samples: &[num_complex::Complex32],
+        start_time: f64,
+        frequency: f64,
+        sample_rate: f64,
+        flags: PacketFlags,
+    ) -> Result {
+        let packet = Packet::from_samples(samples, start_time, frequency, sample_rate, flags);
+        self.send_packet(chan, &packet)
+    }
+
+    /// Send IQ samples to the [`Device`] data channel, transmitting them exactly at `at`.
+    ///
+    /// The packet is marked as a segment of its own, i.e., it is transmitted as a burst. Returns
+    /// [`Error::TooLate`], if `at` is less than [`MIN_LEAD_TIME`] seconds ahead of the device
+    /// clock, since the samples would be transmitted late or dropped.
+    pub fn send_at(
+        &mut self,
+        chan: i32,
+        samples: &[num_complex::Complex32],
+        at: StreamTime,
+        frequency: f64,
+        sample_rate: f64,
+    ) -> Result {
+        if at.secs_since(self.stream_time()?) < MIN_LEAD_TIME {
+            return Err(Error::TooLate);
         }
+        let mut flags = PacketFlags::new();
+        flags.set_segment_start().set_segment_end();
+        self.send_samples(chan, samples, at.as_secs(), frequency, sample_rate, flags)
     }
 
     /// Consume a [`Packet`] from a [`Device`] data channel.
+    ///
+    /// The data of packets, received from the channel, is invalid afterwards.
     pub fn consume(&mut self, chan: i32) -> Result {
-        unsafe { res(sys::AARTSAAPI_ConsumePackets(&mut self.inner, chan, 1)) }
+        self.consume_n(chan, 1)
+    }
+
+    /// Consume `n` [`Packet`]s from a [`Device`] data channel at once.
+    ///
+    /// This allows readers to batch the consumption, e.g., after processing all
+    /// [available](Self::packets_avail) packets. Consuming no packets is a no-op.
+    pub fn consume_n(&mut self, chan: i32, n: usize) -> Result {
+        if n == 0 {
+            return Ok(());
+        }
+        let n = i32::try_from(n).map_err(|_| Error::ErrorInvalidParameter)?;
+        unsafe { ffi!(AARTSAAPI_ConsumePackets(&mut self.inner, chan, n); chan = chan, n = n) }
+    }
+
+    /// Consume all [`Packet`]s, available in the queue of a [`Device`] data channel, and return
+    /// their number.
+    ///
+    /// This call does not wait for packets, i.e., it returns zero for an empty queue.
+    pub fn consume_all(&mut self, chan: i32) -> std::result::Result<usize, Error> {
+        let n = self.packets_avail(chan)?;
+        self.consume_n(chan, n)?;
+        Ok(n)
     }
 
     /// Get [`Device`] clock time.
     pub fn clock(&mut self) -> std::result::Result<f64, Error> {
         let mut val = 0.0f64;
-        unsafe {
-            res(sys::AARTSAAPI_GetMasterStreamTime(
-                &mut self.inner,
-                &mut val,
-            ))?
-        };
+        unsafe { ffi!(AARTSAAPI_GetMasterStreamTime(&mut self.inner, &mut val,))? };
         Ok(val)
     }
 
@@ -526,7 +1053,7 @@ impl Device {
         let mut conf = HashMap::<String, ConfigItem>::new();
         let mut root = Config::new();
 
-        unsafe { res(sys::AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
 
         let (name, item) = self.parse_item(&mut root)?;
         conf.insert(name, item);
@@ -542,12 +1069,7 @@ impl Device {
 
         let mut root = Config::new();
 
-        unsafe {
-            res(sys::AARTSAAPI_ConfigHealth(
-                &mut self.inner,
-                &mut root.inner,
-            ))?
-        };
+        unsafe { ffi!(AARTSAAPI_ConfigHealth(&mut self.inner, &mut root.inner,))? };
 
         let (name, item) = self.parse_item(&mut root)?;
         conf.insert(name, item);
@@ -557,6 +1079,88 @@ impl Device {
         Ok(())
     }
 
+    /// Collect all leaves of the health tree with their full paths.
+    pub(crate) fn health_leaves(
+        &mut self,
+    ) -> std::result::Result<Vec<(String, ConfigItem)>, Error> {
+        let mut root = Config::new();
+        unsafe { ffi!(AARTSAAPI_ConfigHealth(&mut self.inner, &mut root.inner,))? };
+
+        let mut leaves = Vec::new();
+        self.config_leaves(&mut root, "", &mut leaves)?;
+        Ok(leaves)
+    }
+
+    /// Collect all leaves of the configuration tree with their full paths.
+    pub(crate) fn root_leaves(&mut self) -> std::result::Result<Vec<(String, ConfigItem)>, Error> {
+        let mut root = Config::new();
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+
+        let mut leaves = Vec::new();
+        self.config_leaves(&mut root, "", &mut leaves)?;
+        Ok(leaves)
+    }
+
+    /// Collect all leaves of the configuration tree below `group` with their full paths.
+    ///
+    /// Leaves are returned in the order of the tree.
+    fn config_leaves(
+        &mut self,
+        group: &mut Config,
+        prefix: &str,
+        out: &mut Vec<(String, ConfigItem)>,
+    ) -> Result {
+        let mut node = Config::new();
+        let mut r = unsafe {
+            ffi!(AARTSAAPI_ConfigFirst(
+                &mut self.inner,
+                &mut group.inner,
+                &mut node.inner,
+            ))
+        };
+
+        loop {
+            match r {
+                Ok(()) => {}
+                Err(Error::Empty) => break,
+                Err(e) => return Err(e),
+            }
+
+            let mut info = ConfigInfo::new();
+            unsafe {
+                ffi!(AARTSAAPI_ConfigGetInfo(
+                    &mut self.inner,
+                    &mut node.inner,
+                    &mut info.inner,
+                ))?
+            };
+            let name = WideCString::from_vec_truncate(info.inner.name).to_string_lossy();
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}/{name}")
+            };
+
+            match ConfigType::from(info.inner.type_) {
+                ConfigType::Group => self.config_leaves(&mut node, &path, out)?,
+                _ => {
+                    let (_, item) = self.parse_item(&mut node)?;
+                    out.push((path, item));
+                }
+            }
+
+            r = unsafe {
+                ffi!(AARTSAAPI_ConfigNext(
+                    &mut self.inner,
+                    &mut group.inner,
+                    &mut node.inner,
+                ))
+            };
+        }
+
+        Ok(())
+    }
+
     fn parse_item(
         &mut self,
         node: &mut Config,
@@ -564,19 +1168,19 @@ impl Device {
         let mut info = ConfigInfo::new();
 
         unsafe {
-            res(sys::AARTSAAPI_ConfigGetInfo(
+            ffi!(AARTSAAPI_ConfigGetInfo(
                 &mut self.inner,
                 &mut node.inner,
                 &mut info.inner,
             ))?
         };
 
-        let item = match info.inner.type_ {
-            sys::AARTSAAPI_ConfigType_AARTSAAPI_CONFIG_TYPE_BLOB => ConfigItem::Blob,
-            sys::AARTSAAPI_ConfigType_AARTSAAPI_CONFIG_TYPE_BOOL => {
+        let item = match ConfigType::from(info.inner.type_) {
+            ConfigType::Blob => ConfigItem::Blob,
+            ConfigType::Bool => {
                 let mut val = 0i64;
                 match unsafe {
-                    res(sys::AARTSAAPI_ConfigGetInteger(
+                    ffi!(AARTSAAPI_ConfigGetInteger(
                         &mut self.inner,
                         &mut node.inner,
                         &mut val,
@@ -587,7 +1191,7 @@ impl Device {
                     Err(e) => return Err(e),
                 }
             }
-            sys::AARTSAAPI_ConfigType_AARTSAAPI_CONFIG_TYPE_ENUM => {
+            ConfigType::Enum => {
                 let s = WideCString::from_vec_truncate(info.inner.options)
                     .to_string_lossy()
                     .split(';')
@@ -596,18 +1200,22 @@ impl Device {
 
                 let mut val = 0i64;
                 unsafe {
-                    res(sys::AARTSAAPI_ConfigGetInteger(
+                    ffi!(AARTSAAPI_ConfigGetInteger(
                         &mut self.inner,
                         &mut node.inner,
                         &mut val,
                     ))?
                 }
-                ConfigItem::Enum(val, s)
+                ConfigItem::Enum {
+                    value: val,
+                    options: s,
+                    disabled: info.inner.disabledOptions,
+                }
             }
-            sys::AARTSAAPI_ConfigType_AARTSAAPI_CONFIG_TYPE_NUMBER => {
+            ConfigType::Number => {
                 let mut num = 0.0f64;
                 unsafe {
-                    res(sys::AARTSAAPI_ConfigGetFloat(
+                    ffi!(AARTSAAPI_ConfigGetFloat(
                         &mut self.inner,
                         &mut node.inner,
                         &mut num,
@@ -615,15 +1223,15 @@ impl Device {
                 };
                 ConfigItem::Number(num)
             }
-            sys::AARTSAAPI_ConfigType_AARTSAAPI_CONFIG_TYPE_STRING => ConfigItem::String(
+            ConfigType::String => ConfigItem::String(
                 WideCString::from_vec_truncate(info.inner.options).to_string_lossy(),
             ),
-            sys::AARTSAAPI_ConfigType_AARTSAAPI_CONFIG_TYPE_GROUP => {
+            ConfigType::Group => {
                 let mut items = HashMap::new();
                 let mut n = Config::new();
 
                 unsafe {
-                    res(sys::AARTSAAPI_ConfigFirst(
+                    ffi!(AARTSAAPI_ConfigFirst(
                         &mut self.inner,
                         &mut node.inner,
                         &mut n.inner,
@@ -635,7 +1243,7 @@ impl Device {
 
                 loop {
                     match unsafe {
-                        res(sys::AARTSAAPI_ConfigNext(
+                        ffi!(AARTSAAPI_ConfigNext(
                             &mut self.inner,
                             &mut node.inner,
                             &mut n.inner,
@@ -652,7 +1260,7 @@ impl Device {
 
                 ConfigItem::Group(items)
             }
-            _ => ConfigItem::Other,
+            ConfigType::Other => ConfigItem::Other,
         };
 
         Ok((
@@ -662,6 +1270,10 @@ impl Device {
     }
 }
 
+// The RTSA library does not rely on thread-local state, so device handles can be moved between
+// threads.
+unsafe impl Send for Device {}
+
 impl std::fmt::Debug for Device {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Device")
@@ -674,17 +1286,43 @@ impl std::fmt::Debug for Device {
 
 /// [`Device`] configuration parameter.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConfigItem {
+    /// Binary parameter, e.g., calibration data.
+    ///
+    /// The RTSA API only provides float, integer, and string accessors, so blob contents cannot
+    /// be read or written.
     Blob,
     Bool(bool),
     Button,
-    Enum(i64, Vec<String>),
+    /// Selection of one of several options.
+    Enum {
+        /// Index of the selected option.
+        value: i64,
+        /// Options of the parameter.
+        options: Vec<String>,
+        /// Options that the device currently rejects, bit `i` (LSB first) disables option `i`,
+        /// see [`is_disabled()`](Self::is_disabled).
+        disabled: u64,
+    },
     Group(HashMap<String, ConfigItem>),
     Number(f64),
     Other,
     String(String),
 }
 
+impl ConfigItem {
+    /// Check if option `i` of an enum parameter is currently disabled.
+    ///
+    /// Returns `false` for other parameters and options beyond the 64 bits of the mask.
+    pub fn is_disabled(&self, i: usize) -> bool {
+        match self {
+            ConfigItem::Enum { disabled, .. } => discover::option_disabled(*disabled, i),
+            _ => false,
+        }
+    }
+}
+
 impl Drop for Device {
     fn drop(&mut self) {
         match self.status {
@@ -743,6 +1381,59 @@ impl DeviceInfo {
     }
 }
 
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "DeviceInfo")]
+struct SerdeDeviceInfo {
+    serial: String,
+    ready: bool,
+    boost: bool,
+    superspeed: bool,
+    active: bool,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DeviceInfo {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        SerdeDeviceInfo {
+            serial: self.serial(),
+            ready: self.ready(),
+            boost: self.boost(),
+            superspeed: self.superspeed(),
+            active: self.active(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DeviceInfo {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let i = SerdeDeviceInfo::deserialize(deserializer)?;
+        let mut di = DeviceInfo::new();
+        let serial = WideCString::from_str_truncate(&i.serial);
+        let n = di.inner.serialNumber.len() - 1;
+        for (d, s) in di
+            .inner
+            .serialNumber
+            .iter_mut()
+            .zip(serial.as_slice().iter().take(n))
+        {
+            *d = *s;
+        }
+        di.inner.ready = i.ready;
+        di.inner.boost = i.boost;
+        di.inner.superspeed = i.superspeed;
+        di.inner.active = i.active;
+        Ok(di)
+    }
+}
+
 impl std::fmt::Debug for DeviceInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("DeviceInfo")
@@ -764,8 +1455,11 @@ impl std::fmt::Debug for DeviceInfo {
 #[derive(Debug)]
 pub struct Packet {
     inner: sys::AARTSAAPI_Packet,
+    kind: Option<PayloadKind>,
 }
 
+unsafe impl Send for Packet {}
+
 impl Packet {
     fn new() -> Self {
         Self {
@@ -785,9 +1479,34 @@ impl Packet {
                 stride: 0,
                 fp32: std::ptr::null_mut(),
             },
+            kind: None,
         }
     }
 
+    /// Create a TX packet, pointing to `samples`.
+    ///
+    /// The packet must not outlive the samples.
+    pub(crate) fn from_samples(
+        samples: &[num_complex::Complex32],
+        start_time: f64,
+        frequency: f64,
+        sample_rate: f64,
+        flags: PacketFlags,
+    ) -> Self {
+        let mut packet = Packet::new();
+        packet.inner.flags = flags.into();
+        packet.inner.startTime = start_time;
+        packet.inner.endTime = start_time + samples.len() as f64 / sample_rate;
+        packet.inner.startFrequency = frequency;
+        packet.inner.stepFrequency = sample_rate;
+        packet.inner.num = samples.len() as _;
+        packet.inner.total = samples.len() as _;
+        packet.inner.size = 2;
+        packet.inner.stride = 2;
+        packet.inner.fp32 = samples.as_ptr() as _;
+        packet
+    }
+
     /// Get stream ID.
     pub fn stream_id(&self) -> u64 {
         self.inner.streamID
@@ -842,14 +1561,85 @@ impl Packet {
         unsafe { std::slice::from_raw_parts(self.inner.fp32 as _, self.inner.num as _) }
     }
 
-    /// Get spectrum data from packet.
+    /// Get spectrum data of the first row, see [`spectra_rows()`](Self::spectra_rows).
     pub fn spectrum(&self) -> &'static [f32] {
         unsafe { std::slice::from_raw_parts(self.inner.fp32 as _, self.inner.size as _) }
     }
+
+    /// Get packet metadata, i.e., all fields except the payload.
+    pub fn meta(&self) -> PacketMeta {
+        PacketMeta {
+            stream_id: self.stream_id(),
+            flags: self.flags(),
+            start_time: self.start_time(),
+            end_time: self.end_time(),
+            start_frequency: self.start_frequency(),
+            step_frequency: self.step_frequency(),
+            span_frequency: self.span_frequency(),
+            rbw_frequency: self.rbw_frequency(),
+            num: self.num(),
+            total: self.total(),
+            size: self.size(),
+            stride: self.stride(),
+        }
+    }
+
+    /// Copy spectrum data of the first row and metadata from packet.
+    pub fn to_spectrum(&self) -> Spectrum {
+        Spectrum {
+            start_time: self.start_time(),
+            end_time: self.end_time(),
+            start_frequency: self.start_frequency(),
+            step_frequency: self.step_frequency(),
+            rbw_frequency: self.rbw_frequency(),
+            data: Vec::from(self.spectrum()),
+        }
+    }
+}
+
+/// Metadata of a [`Packet`].
+///
+/// In contrast to the [`Packet`], it does not reference library memory and can be kept after
+/// the packet is consumed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PacketMeta {
+    pub stream_id: u64,
+    pub flags: PacketFlags,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub start_frequency: f64,
+    pub step_frequency: f64,
+    pub span_frequency: f64,
+    pub rbw_frequency: f64,
+    pub num: i64,
+    pub total: i64,
+    pub size: i64,
+    pub stride: i64,
+}
+
+/// Spectrum, copied from a spectra [`Packet`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spectrum {
+    /// Start time of the spectrum.
+    pub start_time: f64,
+    /// End time of the spectrum.
+    pub end_time: f64,
+    /// Frequency of the first bin.
+    pub start_frequency: f64,
+    /// Frequency spacing of the bins.
+    pub step_frequency: f64,
+    /// Resolution bandwidth.
+    pub rbw_frequency: f64,
+    /// Power of the bins.
+    pub data: Vec<f32>,
 }
 
-#[derive(Debug, Clone)]
-enum ConfigType {
+/// Type of a [`Device`] configuration parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConfigType {
     Other,
     Group,
     Blob,
@@ -874,7 +1664,10 @@ impl From<std::os::raw::c_uint> for ConfigType {
 }
 
 /// Options for memory sizes, used by the RTSA library.
-#[derive(Debug, Clone)]
+///
+/// Use [`recommended_memory()`] to choose a size for a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Memory {
     Small,
     Medium,
@@ -905,7 +1698,12 @@ impl From<Memory> for u32 {
 }
 
 /// Packet Flags
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "u64", into = "u64")
+)]
 pub struct PacketFlags {
     v: u64,
 }
@@ -1032,12 +1830,76 @@ pub enum Error {
     #[error("Error Value Malformed")]
     ErrorValueMalformed,
 
+    #[error("RTSA library not found")]
+    LibraryNotFound,
+    #[error(
+        "RTSA library not found in {}, install RTSA-Suite PRO or set RTSA_DIR to its directory",
+        .searched.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    LibraryNotLocated { searched: Vec<std::path::PathBuf> },
+    #[error("Failed to load RTSA library {}: {reason}", .path.display())]
+    LibraryLoad {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+    #[error(
+        "RTSA library {} has version {version}, expected major version {expected}, install a \
+         matching RTSA-Suite PRO",
+        .path.display()
+    )]
+    LibraryVersion {
+        path: std::path::PathBuf,
+        version: String,
+        expected: u32,
+    },
+    #[error("Transmit time too close or in the past")]
+    TooLate,
+    #[error("Operation cancelled")]
+    Cancelled,
+    #[error("RTSA library already initialized with memory size {current:?}")]
+    AlreadyInitialized { current: Memory },
+    #[error("Wrong device state: expected {expected:?}, actual {actual:?}")]
+    WrongState {
+        expected: DeviceStatus,
+        actual: DeviceStatus,
+    },
+    #[error(
+        "Device {serial} is in use by another application{}, close it or attach through \
+         RTSA-Suite with Device::open_shared()",
+        if *.suite_running { " (RTSA-Suite is running)" } else { "" }
+    )]
+    DeviceInUse { serial: String, suite_running: bool },
+    #[error("Invalid option {value:?} for {path}, valid options: {}", .options.join(", "))]
+    InvalidOption {
+        path: String,
+        value: String,
+        options: Vec<String>,
+    },
+    #[error(
+        "Option {value:?} of {path} is disabled, enabled options: {}",
+        .options.join(", ")
+    )]
+    OptionDisabled {
+        path: String,
+        value: String,
+        options: Vec<String>,
+    },
+    #[error("Audio output: {0}")]
+    Audio(String),
+    #[error("Profile {name:?}: {reason}")]
+    Profile { name: String, reason: String },
+    #[error("Session {}: {reason}", .dir.display())]
+    Session {
+        dir: std::path::PathBuf,
+        reason: String,
+    },
+
     #[error("Undocumented")]
     Undocumented,
 }
 
 fn res(r: sys::AARTSAAPI_Result) -> Result {
-    match r {
+    let result = match r {
         0x00000000 => Ok(()),
         0x00000001 => Err(Error::Empty),
         0x00000002 => Err(Error::Retry),
@@ -1068,6 +1930,17 @@ fn res(r: sys::AARTSAAPI_Result) -> Result {
         0x8000000b => Err(Error::ErrorMissingPathsFile),
         0x8000000c => Err(Error::ErrorValueInvalid),
         0x8000000d => Err(Error::ErrorValueMalformed),
+        #[cfg(all(not(feature = "sys-stub"), feature = "dlopen"))]
+        sys::LIBRARY_NOT_FOUND => Err(Error::LibraryNotFound),
         _ => Err(Error::Undocumented),
+    };
+
+    #[cfg(feature = "tracing")]
+    match r {
+        0x8000_0000.. => tracing::warn!(code = format_args!("{r:#010x}"), ?result, "RTSA error"),
+        0x4000_0000.. => tracing::debug!(code = format_args!("{r:#010x}"), ?result, "RTSA warning"),
+        _ => {}
     }
+
+    result
 }
@@ -0,0 +1,377 @@
+//! Power, RSSI, and signal quality measurements on IQ samples.
+//!
+//! Power values in dBFS are relative to a full-scale sample with magnitude one. The device
+//! scales IQ samples, such that full scale corresponds to the configured reference level, i.e.,
+//! absolute power in dBm is the dBFS value plus `main/reflevel`. [`Calibration`] captures this
+//! conversion for a device configuration.
+use num_complex::Complex32;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::ConfigItem;
+use crate::Device;
+use crate::Error;
+use crate::Packet;
+
+/// Convert linear power to dB.
+fn db(p: f32) -> f32 {
+    10.0 * p.log10()
+}
+
+/// Mean power of the samples (linear).
+pub fn power(samples: &[Complex32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().map(|s| s.norm_sqr()).sum::<f32>() / samples.len() as f32
+}
+
+/// Received signal strength, i.e., mean power of the samples in dBFS.
+pub fn rssi(samples: &[Complex32]) -> f32 {
+    db(power(samples))
+}
+
+/// Mean power in dBFS for consecutive, non-overlapping windows of `window` samples.
+///
+/// A trailing window with less than `window` samples is included.
+pub fn windowed_rssi(samples: &[Complex32], window: usize) -> Vec<f32> {
+    assert!(window > 0, "window has to be non-empty");
+    samples.chunks(window).map(rssi).collect()
+}
+
+/// Conversion of IQ samples to absolute power.
+///
+/// Derived from the reference level of the device, i.e., a full-scale sample corresponds to
+/// `reflevel` dBm. The `offset` is added to all values and can be used to compensate external
+/// gains or losses, e.g., of cables, attenuators, or antennas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    /// Reference level in dBm.
+    pub reflevel: f32,
+    /// Additional offset in dB.
+    pub offset: f32,
+}
+
+impl Calibration {
+    /// Create a calibration for the given reference level.
+    pub fn new(reflevel: f32) -> Self {
+        Self {
+            reflevel,
+            offset: 0.0,
+        }
+    }
+
+    /// Create a calibration from the current `main/reflevel` of the device.
+    pub fn from_device(dev: &mut Device) -> std::result::Result<Self, Error> {
+        match dev.get("main/reflevel")? {
+            ConfigItem::Number(n) => Ok(Self::new(n as f32)),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+
+    /// Set the additional offset in dB.
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Convert a power value in dBFS to dBm.
+    pub fn dbfs_to_dbm(&self, dbfs: f32) -> f32 {
+        dbfs + self.reflevel + self.offset
+    }
+
+    /// Power of a sample in dBm.
+    pub fn sample_dbm(&self, sample: Complex32) -> f32 {
+        self.dbfs_to_dbm(db(sample.norm_sqr()))
+    }
+
+    /// Power of the samples in dBm.
+    pub fn samples_dbm(&self, samples: &[Complex32]) -> Vec<f32> {
+        samples.iter().map(|s| self.sample_dbm(*s)).collect()
+    }
+
+    /// Mean power of the samples in dBm.
+    pub fn power_dbm(&self, samples: &[Complex32]) -> f32 {
+        self.dbfs_to_dbm(rssi(samples))
+    }
+
+    /// Power of a sample in mW.
+    pub fn sample_mw(&self, sample: Complex32) -> f32 {
+        sample.norm_sqr() * 10f32.powf((self.reflevel + self.offset) / 10.0)
+    }
+}
+
+impl Packet {
+    /// Get the power of the IQ samples in dBm.
+    pub fn samples_dbm(&self, calibration: &Calibration) -> Vec<f32> {
+        calibration.samples_dbm(self.samples())
+    }
+}
+
+/// Result of a [`Device::measure_rssi()`] measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RssiMeasurement {
+    /// Mean power in dBFS.
+    pub mean_dbfs: f32,
+    /// Peak sample power in dBFS.
+    pub peak_dbfs: f32,
+    /// Mean power in dBm.
+    pub mean_dbm: f32,
+    /// Peak sample power in dBm.
+    pub peak_dbm: f32,
+    /// Reference level in dBm, used for the conversion.
+    pub reflevel: f32,
+    /// Number of samples, considered in the measurement.
+    pub samples: usize,
+}
+
+impl Device {
+    /// Measure mean and peak power on data channel `chan` for the given duration.
+    ///
+    /// The [`Device`] has to be started and configured to output IQ samples.
+    pub fn measure_rssi(
+        &mut self,
+        chan: i32,
+        duration: Duration,
+    ) -> std::result::Result<RssiMeasurement, Error> {
+        let cal = Calibration::from_device(self)?;
+
+        let mut sum = 0.0f64;
+        let mut peak = 0.0f32;
+        let mut n = 0usize;
+        let start = Instant::now();
+
+        while start.elapsed() < duration {
+            let p = self.packet(chan)?;
+            for s in p.samples() {
+                let pow = s.norm_sqr();
+                sum += pow as f64;
+                peak = peak.max(pow);
+            }
+            n += p.samples().len();
+            self.consume(chan)?;
+        }
+
+        let mean = if n > 0 { (sum / n as f64) as f32 } else { 0.0 };
+        let mean_dbfs = db(mean);
+        let peak_dbfs = db(peak);
+
+        Ok(RssiMeasurement {
+            mean_dbfs,
+            peak_dbfs,
+            mean_dbm: cal.dbfs_to_dbm(mean_dbfs),
+            peak_dbm: cal.dbfs_to_dbm(peak_dbfs),
+            reflevel: cal.reflevel,
+            samples: n,
+        })
+    }
+}
+
+/// Maximum DC offset relative to the RMS level in dB, accepted by [`IqHealth`].
+const MAX_DC_DBC: f64 = -30.0;
+/// Maximum gain imbalance in dB, accepted by [`IqHealth`].
+const MAX_GAIN_IMBALANCE_DB: f64 = 1.0;
+/// Maximum phase imbalance in degrees, accepted by [`IqHealth`].
+const MAX_PHASE_IMBALANCE_DEG: f64 = 5.0;
+/// Maximum fraction of clipped samples, accepted by [`IqHealth`].
+const MAX_CLIP_RATIO: f64 = 1e-4;
+
+/// Statistics of an IQ stream to check the signal quality, i.e., DC offset, I/Q imbalance,
+/// clipping, and RMS level.
+///
+/// Samples are accumulated with [`push()`](Self::push) until the statistics are
+/// [`reset()`](Self::reset).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IqStats {
+    /// Magnitude of the I or Q component, at or above which a sample counts as clipped
+    /// (default: 0.99).
+    pub clip_level: f32,
+    count: usize,
+    clipped: usize,
+    peak: f32,
+    sum_i: f64,
+    sum_q: f64,
+    sum_ii: f64,
+    sum_qq: f64,
+    sum_iq: f64,
+}
+
+impl Default for IqStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IqStats {
+    /// Create empty statistics.
+    pub fn new() -> Self {
+        Self {
+            clip_level: 0.99,
+            count: 0,
+            clipped: 0,
+            peak: 0.0,
+            sum_i: 0.0,
+            sum_q: 0.0,
+            sum_ii: 0.0,
+            sum_qq: 0.0,
+            sum_iq: 0.0,
+        }
+    }
+
+    /// Add samples to the statistics.
+    pub fn push(&mut self, samples: &[Complex32]) {
+        for s in samples {
+            let (i, q) = (s.re as f64, s.im as f64);
+            self.sum_i += i;
+            self.sum_q += q;
+            self.sum_ii += i * i;
+            self.sum_qq += q * q;
+            self.sum_iq += i * q;
+            self.peak = self.peak.max(s.norm_sqr());
+            if s.re.abs() >= self.clip_level || s.im.abs() >= self.clip_level {
+                self.clipped += 1;
+            }
+        }
+        self.count += samples.len();
+    }
+
+    /// Add the IQ samples of a [`Packet`] to the statistics.
+    pub fn push_packet(&mut self, packet: &Packet) {
+        self.push(packet.samples());
+    }
+
+    /// Remove all samples.
+    pub fn reset(&mut self) {
+        *self = Self {
+            clip_level: self.clip_level,
+            ..Self::new()
+        };
+    }
+
+    /// Number of samples.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Number of clipped samples.
+    pub fn clipped(&self) -> usize {
+        self.clipped
+    }
+
+    /// Fraction of clipped samples.
+    pub fn clip_ratio(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.clipped as f64 / self.count as f64
+        }
+    }
+
+    /// Mean of the samples, i.e., the DC offset.
+    pub fn dc_offset(&self) -> Complex32 {
+        let n = self.count.max(1) as f64;
+        Complex32::new((self.sum_i / n) as f32, (self.sum_q / n) as f32)
+    }
+
+    /// RMS magnitude of the samples, including DC.
+    pub fn rms(&self) -> f32 {
+        let n = self.count.max(1) as f64;
+        ((self.sum_ii + self.sum_qq) / n).sqrt() as f32
+    }
+
+    /// Mean power of the samples in dBFS.
+    pub fn rms_dbfs(&self) -> f32 {
+        db(self.rms() * self.rms())
+    }
+
+    /// Peak sample power in dBFS.
+    pub fn peak_dbfs(&self) -> f32 {
+        db(self.peak)
+    }
+
+    /// Power of the DC offset relative to the mean power in dB.
+    pub fn dc_dbc(&self) -> f32 {
+        db(self.dc_offset().norm_sqr() / (self.rms() * self.rms()))
+    }
+
+    /// Variances of I and Q and their covariance, i.e., without DC.
+    fn moments(&self) -> (f64, f64, f64) {
+        let n = self.count.max(1) as f64;
+        let (mi, mq) = (self.sum_i / n, self.sum_q / n);
+        (
+            self.sum_ii / n - mi * mi,
+            self.sum_qq / n - mq * mq,
+            self.sum_iq / n - mi * mq,
+        )
+    }
+
+    /// Power ratio of the I and Q components in dB, zero for a balanced signal.
+    pub fn gain_imbalance_db(&self) -> f32 {
+        let (ii, qq, _) = self.moments();
+        (10.0 * (ii / qq).log10()) as f32
+    }
+
+    /// Deviation of the I and Q components from quadrature in degrees, zero for a balanced
+    /// signal.
+    pub fn phase_imbalance_deg(&self) -> f32 {
+        let (ii, qq, iq) = self.moments();
+        (iq / (ii * qq).sqrt()).clamp(-1.0, 1.0).asin().to_degrees() as f32
+    }
+}
+
+/// Result of a [`Device::iq_health_check()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IqHealth {
+    /// Statistics of the received samples.
+    pub stats: IqStats,
+    /// DC offset is below -30 dBc.
+    pub dc_ok: bool,
+    /// Gain imbalance is below 1 dB and phase imbalance below 5°.
+    pub balance_ok: bool,
+    /// Less than 0.01% of the samples are clipped.
+    pub clipping_ok: bool,
+}
+
+impl IqHealth {
+    /// Evaluate the statistics.
+    pub fn new(stats: IqStats) -> Self {
+        Self {
+            stats,
+            dc_ok: (stats.dc_dbc() as f64) < MAX_DC_DBC,
+            balance_ok: (stats.gain_imbalance_db().abs() as f64) < MAX_GAIN_IMBALANCE_DB
+                && (stats.phase_imbalance_deg().abs() as f64) < MAX_PHASE_IMBALANCE_DEG,
+            clipping_ok: stats.clip_ratio() < MAX_CLIP_RATIO,
+        }
+    }
+
+    /// Check if all checks passed.
+    pub fn is_ok(&self) -> bool {
+        self.stats.count() > 0 && self.dc_ok && self.balance_ok && self.clipping_ok
+    }
+}
+
+impl Device {
+    /// Collect [`IqStats`] on data channel `chan` for the given duration and check them.
+    ///
+    /// The [`Device`] has to be started and configured to output IQ samples. The check expects
+    /// a signal at the input, e.g., a test tone, since imbalance is not defined for silence.
+    pub fn iq_health_check(
+        &mut self,
+        chan: i32,
+        duration: Duration,
+    ) -> std::result::Result<IqHealth, Error> {
+        let mut stats = IqStats::new();
+        let start = Instant::now();
+
+        while start.elapsed() < duration {
+            let p = self.packet(chan)?;
+            stats.push_packet(&p);
+            self.consume(chan)?;
+        }
+
+        Ok(IqHealth::new(stats))
+    }
+}
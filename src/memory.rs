@@ -0,0 +1,116 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::ApiHandle;
+use crate::Memory;
+
+/// Bytes of the packets, available in the tracked queues of all devices.
+static BUFFERED: AtomicUsize = AtomicUsize::new(0);
+/// Number of tracked queues of all devices.
+static TRACKED: AtomicUsize = AtomicUsize::new(0);
+
+/// Memory usage of the RTSA library, returned by [`ApiHandle::memory_stats()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MemoryStats {
+    /// [`Memory`] size of the RTSA library.
+    pub memory: Memory,
+    /// Number of [`ApiHandle`]s, including the ones of the [`Device`](crate::Device)s, and other
+    /// references to the [`Rtsa`](crate::Rtsa) runtime.
+    pub handles: usize,
+    /// Nominal packet buffer of the memory size in bytes, see [`Memory::buffer_size()`].
+    pub capacity: usize,
+    /// Estimated bytes of the packets, waiting in the queues, `None` if no queue is tracked.
+    ///
+    /// The RTSA library does not report its buffer usage. The estimate is the number of
+    /// available packets times the payload size of the last packet of each data channel, whose
+    /// [`QueueStats`](crate::QueueStats) are tracked, i.e., which were read by this process.
+    pub buffered: Option<usize>,
+}
+
+impl MemoryStats {
+    /// Fraction of the nominal packet buffer, used by the queues.
+    pub fn utilization(&self) -> Option<f64> {
+        self.buffered
+            .map(|b| b as f64 / self.capacity.max(1) as f64)
+    }
+}
+
+impl Memory {
+    /// Nominal packet buffer of the memory size in bytes.
+    ///
+    /// The RTSA library does not report its allocation. The sizes are the guidance, this crate
+    /// uses for [`recommended_memory()`]: 256 MiB for small, 1 GiB for medium, 4 GiB for large,
+    /// and 16 GiB for ludicrous.
+    pub fn buffer_size(&self) -> usize {
+        const MIB: usize = 1 << 20;
+        match self {
+            Memory::Small => 256 * MIB,
+            Memory::Medium => 1024 * MIB,
+            Memory::Large => 4096 * MIB,
+            Memory::Ludicrous => 16384 * MIB,
+        }
+    }
+}
+
+/// Recommend the smallest [`Memory`] size, whose buffer holds `duration` of complex `f32`
+/// samples at `sample_rate` with 100% headroom for spectra and further channels.
+///
+/// The duration is the longest time, the application might not read packets, e.g., while
+/// processing or writing to disk. Returns [`Memory::Ludicrous`], if no size is sufficient.
+pub fn recommended_memory(sample_rate: f64, duration: Duration) -> Memory {
+    let bytes = sample_rate.max(0.0)
+        * duration.as_secs_f64()
+        * std::mem::size_of::<num_complex::Complex32>() as f64;
+    [Memory::Small, Memory::Medium, Memory::Large]
+        .into_iter()
+        .find(|m| 2.0 * bytes <= m.buffer_size() as f64)
+        .unwrap_or(Memory::Ludicrous)
+}
+
+impl ApiHandle {
+    /// Query the [`MemoryStats`] of the RTSA library.
+    pub fn memory_stats(&self) -> MemoryStats {
+        let handles = Arc::strong_count(self.runtime());
+        let memory = self.memory();
+        let buffered =
+            (TRACKED.load(Ordering::Relaxed) > 0).then(|| BUFFERED.load(Ordering::Relaxed));
+        MemoryStats {
+            memory,
+            handles,
+            capacity: memory.buffer_size(),
+            buffered,
+        }
+    }
+}
+
+/// Contribution of a tracked queue to the [`MemoryStats`], released on drop.
+#[derive(Debug)]
+pub(crate) struct BufferedBytes(usize);
+
+impl BufferedBytes {
+    pub(crate) fn set(&mut self, bytes: usize) {
+        if bytes >= self.0 {
+            BUFFERED.fetch_add(bytes - self.0, Ordering::Relaxed);
+        } else {
+            BUFFERED.fetch_sub(self.0 - bytes, Ordering::Relaxed);
+        }
+        self.0 = bytes;
+    }
+}
+
+impl Default for BufferedBytes {
+    fn default() -> Self {
+        TRACKED.fetch_add(1, Ordering::Relaxed);
+        Self(0)
+    }
+}
+
+impl Drop for BufferedBytes {
+    fn drop(&mut self) {
+        self.set(0);
+        TRACKED.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,57 @@
+//! Export of device health and stream statistics through the [`metrics`](::metrics) facade.
+//!
+//! The application installs a recorder, e.g., `metrics-exporter-prometheus`, and calls
+//! [`Device::publish_metrics()`] periodically. All metrics have a `device` label with the serial
+//! number. Published metrics are:
+//!
+//! - `rtsa_health{item}`: numeric and boolean items of the health tree
+//! - `rtsa_temperature_celsius{sensor}`: health items with `temp` in their name
+//! - `rtsa_queue_available{channel}`, `rtsa_queue_high_water{channel}`: packets in the queue
+//! - `rtsa_packets_total{channel}`, `rtsa_drops_total{channel}`: received packets and gaps
+//! - `rtsa_dropped_seconds{channel}`: duration of the gaps
+//! - `rtsa_sample_rate_hertz{channel}`: sample rate of the last packet
+//!
+//! Queue metrics are published for all channels that were read through the [`Device`].
+use ::metrics::counter;
+use ::metrics::gauge;
+
+use crate::ConfigItem;
+use crate::Device;
+use crate::Error;
+
+impl Device {
+    /// Publish health and queue statistics as metrics.
+    pub fn publish_metrics(&mut self) -> std::result::Result<(), Error> {
+        let device = self.serial.to_string_lossy();
+
+        for (path, item) in self.health_leaves()? {
+            let v = match item {
+                ConfigItem::Number(v) => v,
+                ConfigItem::Bool(b) => b as u8 as f64,
+                _ => continue,
+            };
+            if path.to_lowercase().contains("temp") {
+                let labels = [("device", device.clone()), ("sensor", path.clone())];
+                gauge!("rtsa_temperature_celsius", &labels).set(v);
+            }
+            gauge!("rtsa_health", "device" => device.clone(), "item" => path).set(v);
+        }
+
+        let mut chans: Vec<i32> = self.queues.keys().copied().collect();
+        chans.sort_unstable();
+        for chan in chans {
+            let s = self.queue_stats(chan)?;
+            let labels = [("device", device.clone()), ("channel", chan.to_string())];
+            gauge!("rtsa_queue_available", &labels).set(s.available as f64);
+            gauge!("rtsa_queue_high_water", &labels).set(s.high_water as f64);
+            counter!("rtsa_packets_total", &labels).absolute(s.packets);
+            counter!("rtsa_drops_total", &labels).absolute(s.drops);
+            gauge!("rtsa_dropped_seconds", &labels).set(s.dropped_time);
+            if let Some(rate) = s.sample_rate {
+                gauge!("rtsa_sample_rate_hertz", &labels).set(rate);
+            }
+        }
+
+        Ok(())
+    }
+}
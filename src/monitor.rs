@@ -0,0 +1,300 @@
+//! Spectrum occupancy monitoring of frequency bands.
+use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::sweep::Sweeper;
+use crate::Device;
+use crate::Error;
+use crate::Hz;
+
+/// Frequency band, monitored by a [`Monitor`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Band {
+    /// Name of the band, used in the logs.
+    pub name: String,
+    /// Start frequency in Hz.
+    pub start_frequency: f64,
+    /// Stop frequency in Hz.
+    pub stop_frequency: f64,
+}
+
+impl Band {
+    /// Create a band.
+    pub fn new<S: Into<String>>(
+        name: S,
+        start_frequency: impl Into<Hz>,
+        stop_frequency: impl Into<Hz>,
+    ) -> Self {
+        let (start_frequency, stop_frequency) = (
+            start_frequency.into().value(),
+            stop_frequency.into().value(),
+        );
+        assert!(stop_frequency > start_frequency);
+        Self {
+            name: name.into(),
+            start_frequency,
+            stop_frequency,
+        }
+    }
+}
+
+/// Occupancy statistics of a [`Band`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Occupancy {
+    /// Monitored band.
+    pub band: Band,
+    /// Number of scans.
+    pub scans: u64,
+    /// Number of scans with a bin above the threshold.
+    pub occupied: u64,
+    /// Maximum power in dBm, observed so far.
+    pub max_power: f32,
+    /// Power of the strongest bin in the last scan in dBm.
+    pub last_power: f32,
+    /// Time of the last scan with a bin above the threshold.
+    pub last_seen: Option<SystemTime>,
+}
+
+impl Occupancy {
+    fn new(band: Band) -> Self {
+        Self {
+            band,
+            scans: 0,
+            occupied: 0,
+            max_power: f32::NEG_INFINITY,
+            last_power: f32::NEG_INFINITY,
+            last_seen: None,
+        }
+    }
+
+    /// Fraction of scans, in which the band was occupied.
+    pub fn duty_cycle(&self) -> f64 {
+        if self.scans == 0 {
+            0.0
+        } else {
+            self.occupied as f64 / self.scans as f64
+        }
+    }
+}
+
+/// Destination of the occupancy statistics, written by [`Monitor::run()`] after every scan.
+pub trait OccupancyLog {
+    /// Log the statistics of all bands at `time`.
+    fn log(&mut self, time: SystemTime, stats: &[Occupancy]) -> std::io::Result<()>;
+}
+
+pub(crate) fn unix_secs(t: SystemTime) -> f64 {
+    t.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+const CSV_HEADER: &str = concat!(
+    "time,band,start_frequency,stop_frequency,",
+    "scans,duty_cycle,power,max_power,last_seen"
+);
+
+/// Log statistics as CSV with one row per band and scan.
+#[derive(Debug)]
+pub struct CsvLog<W: Write> {
+    writer: W,
+    header: bool,
+}
+
+impl<W: Write> CsvLog<W> {
+    /// Create a log, writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header: false,
+        }
+    }
+
+    /// Get the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> OccupancyLog for CsvLog<W> {
+    fn log(&mut self, time: SystemTime, stats: &[Occupancy]) -> std::io::Result<()> {
+        if !self.header {
+            writeln!(self.writer, "{CSV_HEADER}")?;
+            self.header = true;
+        }
+        for s in stats {
+            writeln!(
+                self.writer,
+                "{:.3},{},{},{},{},{:.4},{:.1},{:.1},{}",
+                unix_secs(time),
+                s.band.name,
+                s.band.start_frequency,
+                s.band.stop_frequency,
+                s.scans,
+                s.duty_cycle(),
+                s.last_power,
+                s.max_power,
+                s.last_seen
+                    .map(|t| format!("{:.3}", unix_secs(t)))
+                    .unwrap_or_default(),
+            )?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Log statistics as JSON lines with one object per band and scan.
+#[derive(Debug)]
+pub struct JsonLog<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLog<W> {
+    /// Create a log, writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Get the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Format a float as JSON number, i.e., `null` for infinite values.
+pub(crate) fn json_number(v: f64) -> String {
+    if v.is_finite() {
+        format!("{v}")
+    } else {
+        "null".to_string()
+    }
+}
+
+impl<W: Write> OccupancyLog for JsonLog<W> {
+    fn log(&mut self, time: SystemTime, stats: &[Occupancy]) -> std::io::Result<()> {
+        for s in stats {
+            let name = s.band.name.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(
+                self.writer,
+                concat!(
+                    "{{\"time\":{:.3},\"band\":\"{}\",\"start_frequency\":{},",
+                    "\"stop_frequency\":{},\"scans\":{},\"duty_cycle\":{:.4},\"power\":{},",
+                    "\"max_power\":{},\"last_seen\":{}}}"
+                ),
+                unix_secs(time),
+                name,
+                s.band.start_frequency,
+                s.band.stop_frequency,
+                s.scans,
+                s.duty_cycle(),
+                json_number(s.last_power as f64),
+                json_number(s.max_power as f64),
+                s.last_seen
+                    .map(|t| format!("{:.3}", unix_secs(t)))
+                    .unwrap_or_else(|| "null".to_string()),
+            )?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Periodically scan frequency bands and track their occupancy.
+///
+/// A band counts as occupied in a scan if any bin of its spectrum exceeds the threshold. The
+/// [`Device`] has to be started and configured to output spectra. Bands wider than `step` are
+/// swept, see [`Sweeper`].
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    /// Occupancy threshold in dBm.
+    pub threshold: f32,
+    /// Time between the start of two scans (default: 1s).
+    pub interval: Duration,
+    /// Frequency span, used from each spectrum (default: 20 MHz).
+    pub step: f64,
+    /// Spectra data channel (default: 2).
+    pub chan: i32,
+    stats: Vec<Occupancy>,
+}
+
+impl Monitor {
+    /// Create a monitor for the given bands and threshold in dBm.
+    pub fn new(bands: Vec<Band>, threshold: f32) -> Self {
+        Self {
+            threshold,
+            interval: Duration::from_secs(1),
+            step: 20e6,
+            chan: 2,
+            stats: bands.into_iter().map(Occupancy::new).collect(),
+        }
+    }
+
+    /// Statistics of all bands.
+    pub fn stats(&self) -> &[Occupancy] {
+        &self.stats
+    }
+
+    /// Reset the statistics.
+    pub fn reset(&mut self) {
+        for s in self.stats.iter_mut() {
+            *s = Occupancy::new(s.band.clone());
+        }
+    }
+
+    /// Scan all bands once and update the statistics.
+    pub fn scan(&mut self, dev: &mut Device) -> std::result::Result<&[Occupancy], Error> {
+        for s in self.stats.iter_mut() {
+            let step = self
+                .step
+                .min(s.band.stop_frequency - s.band.start_frequency);
+            let mut sweeper = Sweeper::new(s.band.start_frequency, s.band.stop_frequency, step);
+            sweeper.chan = self.chan;
+            let trace = sweeper.sweep(dev, |_| {})?;
+
+            let power = trace
+                .data
+                .iter()
+                .copied()
+                .filter(|v| !v.is_nan())
+                .fold(f32::NEG_INFINITY, f32::max);
+
+            s.scans += 1;
+            s.last_power = power;
+            s.max_power = s.max_power.max(power);
+            if power > self.threshold {
+                s.occupied += 1;
+                s.last_seen = Some(SystemTime::now());
+            }
+        }
+        Ok(&self.stats)
+    }
+
+    /// Scan every `interval` and write the statistics to `log`.
+    ///
+    /// Runs `scans` times or forever, if `None`.
+    pub fn run<L: OccupancyLog>(
+        &mut self,
+        dev: &mut Device,
+        log: &mut L,
+        scans: Option<usize>,
+    ) -> std::result::Result<(), Error> {
+        let mut n = 0;
+        while scans.is_none_or(|s| n < s) {
+            let start = Instant::now();
+            self.scan(dev)?;
+            log.log(SystemTime::now(), &self.stats)
+                .map_err(|_| Error::Error)?;
+            n += 1;
+            if let Some(rest) = self.interval.checked_sub(start.elapsed()) {
+                if scans.is_none_or(|s| n < s) {
+                    std::thread::sleep(rest);
+                }
+            }
+        }
+        Ok(())
+    }
+}
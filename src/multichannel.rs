@@ -0,0 +1,126 @@
+use num_complex::Complex32;
+
+use crate::Packet;
+
+/// View on a [`Packet`] with interleaved IQ samples of multiple receive channels.
+///
+/// With both Rx1 and Rx2 enabled, the device interleaves the samples of the channels, i.e., each
+/// stride of the packet holds one IQ sample per channel. The view uses the `size` and `stride`
+/// fields of the packet to split the samples per channel.
+#[derive(Debug, Clone, Copy)]
+pub struct MultiChannelPacket<'a> {
+    packet: &'a Packet,
+}
+
+impl<'a> MultiChannelPacket<'a> {
+    /// Create a view on the packet.
+    pub fn new(packet: &'a Packet) -> Self {
+        Self { packet }
+    }
+
+    /// Get the underlying packet.
+    pub fn packet(&self) -> &'a Packet {
+        self.packet
+    }
+
+    /// Number of interleaved channels.
+    pub fn channels(&self) -> usize {
+        let size = self.packet.size().max(1);
+        (self.packet.stride() / size).max(1) as usize
+    }
+
+    /// Number of samples per channel.
+    pub fn len(&self) -> usize {
+        self.packet.num() as usize
+    }
+
+    /// Check if the packet holds no samples.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the samples of channel `ch`.
+    ///
+    /// Panics, if `ch` is not smaller than [`channels()`](Self::channels).
+    pub fn samples_ch(&self, ch: usize) -> ChannelSamples<'a> {
+        let channels = self.channels();
+        assert!(
+            ch < channels,
+            "channel {ch} out of range ({channels} channels)"
+        );
+        let data = if self.packet.inner.fp32.is_null() {
+            &[]
+        } else {
+            unsafe {
+                std::slice::from_raw_parts(
+                    self.packet.inner.fp32 as *const Complex32,
+                    self.len() * channels,
+                )
+            }
+        };
+        ChannelSamples {
+            data,
+            offset: ch,
+            step: channels,
+        }
+    }
+}
+
+impl Packet {
+    /// Get a view on the packet that splits interleaved samples of multiple channels.
+    pub fn multi_channel(&self) -> MultiChannelPacket<'_> {
+        MultiChannelPacket::new(self)
+    }
+}
+
+/// Strided IQ samples of one channel, returned by [`MultiChannelPacket::samples_ch()`].
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelSamples<'a> {
+    data: &'a [Complex32],
+    offset: usize,
+    step: usize,
+}
+
+impl<'a> ChannelSamples<'a> {
+    /// Number of samples.
+    pub fn len(&self) -> usize {
+        self.data.len() / self.step
+    }
+
+    /// Check if there are no samples.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get sample `i`.
+    pub fn get(&self, i: usize) -> Option<Complex32> {
+        if i < self.len() {
+            Some(self.data[i * self.step + self.offset])
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the samples.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = Complex32> + 'a {
+        let data = self.data;
+        let (offset, step) = (self.offset, self.step);
+        (0..self.len()).map(move |i| data[i * step + offset])
+    }
+
+    /// Copy the samples into `out`, truncating to its length.
+    ///
+    /// Returns the number of copied samples.
+    pub fn copy_to(&self, out: &mut [Complex32]) -> usize {
+        let n = self.len().min(out.len());
+        for (o, s) in out.iter_mut().zip(self.iter()) {
+            *o = s;
+        }
+        n
+    }
+
+    /// Copy the samples into a vector.
+    pub fn to_vec(&self) -> Vec<Complex32> {
+        self.iter().collect()
+    }
+}
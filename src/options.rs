@@ -0,0 +1,61 @@
+use crate::ConfigValue;
+use crate::Device;
+use crate::Result;
+
+/// Options that are applied when the [`Device`] is opened, see [`Device::open_with()`].
+///
+/// Options that are `None` keep the device default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpenOptions {
+    /// Boost mode, i.e., draw more power from the USB port (`device/boost`).
+    pub boost: Option<bool>,
+    /// USB compatibility mode for hosts or cables with unstable SuperSpeed links
+    /// (`device/usbcompatibility`).
+    pub usb_compatibility: Option<bool>,
+}
+
+impl OpenOptions {
+    /// Create options that keep all device defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request boost mode.
+    pub fn boost(mut self, boost: bool) -> Self {
+        self.boost = Some(boost);
+        self
+    }
+
+    /// Request USB compatibility mode.
+    pub fn usb_compatibility(mut self, usb_compatibility: bool) -> Self {
+        self.usb_compatibility = Some(usb_compatibility);
+        self
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (&'static str, ConfigValue)> {
+        [
+            ("device/boost", self.boost),
+            ("device/usbcompatibility", self.usb_compatibility),
+        ]
+        .into_iter()
+        .filter_map(|(p, v)| v.map(|v| (p, ConfigValue::Bool(v))))
+    }
+}
+
+impl Device {
+    /// Open the [`Device`] for exclusive use and apply the [`OpenOptions`].
+    ///
+    /// The options are applied before the device is connected. If an option cannot be applied,
+    /// the device is closed again and the error is returned.
+    pub fn open_with(&mut self, options: &OpenOptions) -> Result {
+        self.open()?;
+        for (path, value) in options.entries() {
+            if let Err(e) = self.set_value(path, &value) {
+                self.close()?;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use crate::ConfigItem;
+use crate::ConfigType;
+use crate::ConfigValue;
+use crate::Device;
+use crate::Error;
+use crate::Result;
+
+/// Configuration path with a known type, created with [`config_path!`](crate::config_path).
+///
+/// Paths dereference to strings, i.e., they can be passed to all methods that take a
+/// configuration path, like [`Device::set()`]. [`Device::get_typed()`] and
+/// [`Device::set_typed()`] check values against the type of the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConfigPath {
+    path: &'static str,
+    kind: ConfigType,
+}
+
+/// Check if two strings are equal in const context.
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+impl ConfigPath {
+    const fn new(path: &'static str, kind: ConfigType) -> Self {
+        Self { path, kind }
+    }
+
+    /// Look up a path in [`KNOWN_PATHS`].
+    pub const fn lookup(path: &str) -> Option<ConfigPath> {
+        let mut i = 0;
+        while i < KNOWN_PATHS.len() {
+            if str_eq(KNOWN_PATHS[i].path, path) {
+                return Some(KNOWN_PATHS[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// Path as string, e.g., `main/centerfreq`.
+    pub const fn as_str(&self) -> &'static str {
+        self.path
+    }
+
+    /// Type of the parameter.
+    pub const fn kind(&self) -> ConfigType {
+        self.kind
+    }
+
+    /// Check if a value can be written to the parameter.
+    pub fn accepts(&self, value: &ConfigValue) -> bool {
+        match self.kind {
+            ConfigType::Number => matches!(value, ConfigValue::Float(_) | ConfigValue::Int(_)),
+            ConfigType::Bool => matches!(value, ConfigValue::Bool(_)),
+            ConfigType::Enum => matches!(value, ConfigValue::String(_) | ConfigValue::Int(_)),
+            ConfigType::String => matches!(value, ConfigValue::String(_)),
+            _ => false,
+        }
+    }
+}
+
+impl AsRef<str> for ConfigPath {
+    fn as_ref(&self) -> &str {
+        self.path
+    }
+}
+
+impl std::ops::Deref for ConfigPath {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.path
+    }
+}
+
+impl std::fmt::Display for ConfigPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.path)
+    }
+}
+
+/// Known configuration paths of the Spectran V6 raw device.
+///
+/// The list covers the documented parameters of the `spectranv6/raw` device type. It is not
+/// exhaustive, since the configuration tree depends on the hardware and firmware; other
+/// parameters are accessed with runtime strings.
+pub const KNOWN_PATHS: &[ConfigPath] = &[
+    ConfigPath::new("main/centerfreq", ConfigType::Number),
+    ConfigPath::new("main/decimation", ConfigType::Enum),
+    ConfigPath::new("main/reflevel", ConfigType::Number),
+    ConfigPath::new("main/spanfreq", ConfigType::Number),
+    ConfigPath::new("main/transgain", ConfigType::Number),
+    ConfigPath::new("device/amplifier", ConfigType::Bool),
+    ConfigPath::new("device/antenna", ConfigType::Enum),
+    ConfigPath::new("device/attenuator", ConfigType::Bool),
+    ConfigPath::new("device/boost", ConfigType::Bool),
+    ConfigPath::new("device/gaincontrol", ConfigType::Enum),
+    ConfigPath::new("device/outputformat", ConfigType::Enum),
+    ConfigPath::new("device/preamp", ConfigType::Bool),
+    ConfigPath::new("device/receiverchannel", ConfigType::Enum),
+    ConfigPath::new("device/receiverclock", ConfigType::Enum),
+    ConfigPath::new("device/referenceclock", ConfigType::Enum),
+    ConfigPath::new("device/transmittermode", ConfigType::Enum),
+    ConfigPath::new("device/usbcompatibility", ConfigType::Bool),
+    ConfigPath::new("device/fft0/fftaggregate", ConfigType::Number),
+    ConfigPath::new("device/fft0/fftbinsize", ConfigType::Number),
+    ConfigPath::new("device/fft0/fftmergemode", ConfigType::Enum),
+    ConfigPath::new("device/fft0/fftrbwfreq", ConfigType::Number),
+    ConfigPath::new("device/fft0/fftsize", ConfigType::Number),
+    ConfigPath::new("device/fft0/fftsizemode", ConfigType::Enum),
+    ConfigPath::new("device/fft0/fftwindow", ConfigType::Enum),
+    ConfigPath::new("device/fft1/fftaggregate", ConfigType::Number),
+    ConfigPath::new("device/fft1/fftbinsize", ConfigType::Number),
+    ConfigPath::new("device/fft1/fftmergemode", ConfigType::Enum),
+    ConfigPath::new("device/fft1/fftrbwfreq", ConfigType::Number),
+    ConfigPath::new("device/fft1/fftsize", ConfigType::Number),
+    ConfigPath::new("device/fft1/fftsizemode", ConfigType::Enum),
+    ConfigPath::new("device/fft1/fftwindow", ConfigType::Enum),
+];
+
+/// Create a [`ConfigPath`] from a string literal, checked against [`KNOWN_PATHS`] at compile
+/// time.
+///
+/// Unknown paths, e.g., typos like `main/centrefreq`, fail to compile. Parameters that are not in
+/// the list are accessed with runtime strings.
+#[macro_export]
+macro_rules! config_path {
+    ($path:literal) => {{
+        const PATH: $crate::ConfigPath = match $crate::ConfigPath::lookup($path) {
+            Some(p) => p,
+            None => panic!(concat!("unknown configuration path: ", $path)),
+        };
+        PATH
+    }};
+}
+
+impl Device {
+    /// Get the value of a parameter as [`ConfigValue`].
+    ///
+    /// Returns [`Error::ErrorValueInvalid`], if the device reports a different type than
+    /// the path.
+    pub fn get_typed(&mut self, path: ConfigPath) -> std::result::Result<ConfigValue, Error> {
+        let item = self.get(path)?;
+        let matches = matches!(
+            (path.kind(), &item),
+            (ConfigType::Number, ConfigItem::Number(_))
+                | (ConfigType::Bool, ConfigItem::Bool(_))
+                | (ConfigType::Enum, ConfigItem::Enum { .. })
+                | (ConfigType::String, ConfigItem::String(_))
+        );
+        match ConfigValue::from_item(&item) {
+            Some(v) if matches => Ok(v),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+
+    /// Set a parameter to a [`ConfigValue`] of the type of the path.
+    ///
+    /// Returns [`Error::ErrorValueInvalid`] without accessing the device, if the value does not
+    /// match the type, see [`ConfigPath::accepts()`], and [`Error::OptionDisabled`], if it selects
+    /// a disabled option of an enum parameter.
+    pub fn set_typed(&mut self, path: ConfigPath, value: &ConfigValue) -> Result {
+        if !path.accepts(value) {
+            return Err(Error::ErrorValueInvalid);
+        }
+        self.check_capabilities(&path, value)?;
+        if path.kind() == ConfigType::Enum {
+            self.check_enabled(&path, value)?;
+        }
+        self.set_value(path, value)
+    }
+}
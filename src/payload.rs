@@ -0,0 +1,251 @@
+use num_complex::Complex32;
+
+use crate::Error;
+use crate::Packet;
+use crate::PayloadKind;
+
+/// Typed payload of a [`Packet`], returned by [`Packet::data()`].
+#[derive(Debug, Clone, Copy)]
+pub enum PacketData<'a> {
+    /// IQ samples.
+    Iq(&'a [Complex32]),
+    /// Spectrum.
+    Spectrum(SpectrumView<'a>),
+    /// Raw ADC samples.
+    Raw(&'a [f32]),
+}
+
+/// Spectrum data of a [`Packet`] with its frequency axis.
+#[derive(Debug, Clone, Copy)]
+pub struct SpectrumView<'a> {
+    /// Frequency of the first bin.
+    pub start_frequency: f64,
+    /// Frequency step between bins.
+    pub step_frequency: f64,
+    /// Resolution bandwidth.
+    pub rbw_frequency: f64,
+    /// Bins in dBm.
+    pub data: &'a [f32],
+}
+
+impl SpectrumView<'_> {
+    /// Frequency of bin `i`.
+    pub fn frequency(&self, i: usize) -> f64 {
+        self.start_frequency + i as f64 * self.step_frequency
+    }
+
+    /// Iterate over frequencies and values of the bins.
+    pub fn bins(&self) -> impl Iterator<Item = (f64, f32)> + '_ {
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (self.frequency(i), *v))
+    }
+}
+
+/// One FFT row of a spectra [`Packet`], returned by [`Packet::spectra_rows()`].
+#[derive(Debug, Clone, Copy)]
+pub struct SpectrumRow<'a> {
+    /// Index of the row in the packet.
+    pub index: usize,
+    /// Start time of the row, interpolated between packet start and end time.
+    pub start_time: f64,
+    /// End time of the row.
+    pub end_time: f64,
+    /// Bins of the row with their frequency axis.
+    pub spectrum: SpectrumView<'a>,
+}
+
+/// Interpretation of the `num`, `size`, and `stride` fields of a [`Packet`], returned by
+/// [`Packet::layout()`].
+///
+/// - IQ: `num` samples per channel, `size` floats per sample, i.e., two, and `stride` floats
+///   between two samples, i.e., `stride / size` interleaved channels
+/// - spectra: `num` rows of `size` bins, which are `stride` floats apart
+/// - raw: `num` rows of `stride` floats
+///
+/// `total` is reported as is, it is not needed to read the payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketLayout {
+    /// Kind of payload.
+    pub kind: PayloadKind,
+    /// Number of rows, i.e., spectra of a spectra packet. IQ packets have one row.
+    pub rows: usize,
+    /// Items per row, i.e., IQ samples per channel, bins per spectrum, or raw samples.
+    pub row_len: usize,
+    /// Floats between the start of two rows, or two samples for IQ packets.
+    pub stride: usize,
+    /// Number of interleaved channels of IQ packets, one otherwise.
+    pub channels: usize,
+    /// Total number of items, as reported by the device.
+    pub total: usize,
+    /// Floats of the payload, i.e., the readable length.
+    pub floats: usize,
+    valid: bool,
+}
+
+impl PacketLayout {
+    /// Bytes of the payload.
+    pub fn bytes(&self) -> usize {
+        self.floats * std::mem::size_of::<f32>()
+    }
+
+    /// Check if the fields are consistent, i.e., not negative and rows are not overlapping.
+    ///
+    /// Inconsistent layouts have no readable payload.
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+}
+
+impl Packet {
+    /// Interpret the `num`, `size`, and `stride` fields for the [`PayloadKind`] of the packet.
+    pub fn layout(&self) -> PacketLayout {
+        let kind = self.payload_kind();
+        let fields = [self.num(), self.size(), self.stride(), self.total()];
+        let mut valid = fields.iter().all(|f| *f >= 0);
+        let [num, size, stride, total] = fields.map(|f| f.max(0) as usize);
+        // contiguous rows, if the stride is not set
+        let stride = if stride == 0 { size } else { stride };
+
+        let (rows, row_len, channels) = match kind {
+            PayloadKind::Iq => {
+                valid &= size == 2 && stride % size == 0;
+                (1, num, (stride / size.max(1)).max(1))
+            }
+            PayloadKind::Spectrum => {
+                valid &= stride >= size;
+                (num, size, 1)
+            }
+            PayloadKind::Raw => (num, stride, 1),
+        };
+        let floats = match kind {
+            PayloadKind::Iq | PayloadKind::Raw => num.checked_mul(stride),
+            PayloadKind::Spectrum if num == 0 => Some(0),
+            PayloadKind::Spectrum => (num - 1)
+                .checked_mul(stride)
+                .and_then(|n| n.checked_add(size)),
+        };
+        // slices are limited to isize::MAX bytes
+        let bytes = floats.and_then(|n| n.checked_mul(std::mem::size_of::<f32>()));
+        valid &= bytes.is_some_and(|b| b <= isize::MAX as usize);
+        valid &= floats == Some(0) || !self.inner.fp32.is_null();
+
+        PacketLayout {
+            kind,
+            rows,
+            row_len,
+            stride,
+            channels,
+            total,
+            floats: if valid { floats.unwrap_or(0) } else { 0 },
+            valid,
+        }
+    }
+
+    /// Get the payload as floats, bounded by the [`layout()`](Self::layout).
+    ///
+    /// Returns [`Error::ErrorInvalidSize`], if the layout is inconsistent.
+    pub fn try_payload(&self) -> std::result::Result<&[f32], Error> {
+        let layout = self.layout();
+        if !layout.is_valid() {
+            return Err(Error::ErrorInvalidSize);
+        }
+        if layout.floats == 0 {
+            return Ok(&[]);
+        }
+        Ok(unsafe { std::slice::from_raw_parts(self.inner.fp32 as *const f32, layout.floats) })
+    }
+
+    /// Get the IQ samples, i.e., the samples of all channels interleaved.
+    ///
+    /// Returns [`Error::ErrorInvalidParameter`] for other payloads and
+    /// [`Error::ErrorInvalidSize`], if the layout is inconsistent.
+    pub fn try_samples(&self) -> std::result::Result<&[Complex32], Error> {
+        if self.payload_kind() != PayloadKind::Iq {
+            return Err(Error::ErrorInvalidParameter);
+        }
+        let floats = self.try_payload()?;
+        if floats.is_empty() {
+            return Ok(&[]);
+        }
+        let n = floats.len() / 2;
+        Ok(unsafe { std::slice::from_raw_parts(floats.as_ptr() as *const Complex32, n) })
+    }
+
+    /// Get the bins of spectrum `row`, see [`spectra_rows()`](Self::spectra_rows).
+    ///
+    /// Returns [`Error::ErrorInvalidParameter`] for other payloads and rows that are out of
+    /// range and [`Error::ErrorInvalidSize`], if the layout is inconsistent.
+    pub fn try_spectrum_row(&self, row: usize) -> std::result::Result<&[f32], Error> {
+        let layout = self.layout();
+        if layout.kind != PayloadKind::Spectrum || row >= layout.rows {
+            return Err(Error::ErrorInvalidParameter);
+        }
+        let start = row * layout.stride;
+        Ok(&self.try_payload()?[start..start + layout.row_len])
+    }
+
+    /// Split the payload of a spectra packet into its FFT rows.
+    ///
+    /// A packet holds `num` rows of `size` bins, which are `stride` floats apart, see
+    /// [`layout()`](Self::layout). The packet duration is split evenly between the rows. Rows
+    /// that fail the validation of [`try_spectrum_row()`](Self::try_spectrum_row) are skipped,
+    /// i.e., packets with an inconsistent layout and other payloads have no rows.
+    pub fn spectra_rows(&self) -> impl Iterator<Item = SpectrumRow<'_>> + '_ {
+        let layout = self.layout();
+        let num = match layout.kind {
+            PayloadKind::Spectrum => layout.rows,
+            _ => 0,
+        };
+        let start = self.start_time();
+        let duration = (self.end_time() - start) / num.max(1) as f64;
+
+        (0..num).filter_map(move |i| {
+            Some(SpectrumRow {
+                index: i,
+                start_time: start + i as f64 * duration,
+                end_time: start + (i + 1) as f64 * duration,
+                spectrum: SpectrumView {
+                    start_frequency: self.start_frequency(),
+                    step_frequency: self.step_frequency(),
+                    rbw_frequency: self.rbw_frequency(),
+                    data: self.try_spectrum_row(i).ok()?,
+                },
+            })
+        })
+    }
+
+    /// Kind of payload, determined from the data channel and [`OutputFormat`](crate::OutputFormat).
+    ///
+    /// If the output format was not set through the [`Device`](crate::Device) handle, the kind is
+    /// inferred from the packet layout, i.e., IQ packets have two floats per sample.
+    pub fn payload_kind(&self) -> PayloadKind {
+        match self.kind {
+            Some(k) => k,
+            None if self.size() == 2 => PayloadKind::Iq,
+            None => PayloadKind::Spectrum,
+        }
+    }
+
+    /// Get the typed payload of the packet.
+    ///
+    /// Spectra packets return their first row, see [`spectra_rows()`](Self::spectra_rows) for
+    /// all rows. Packets with an inconsistent [`layout()`](Self::layout) have no readable
+    /// payload and return an empty [`PacketData::Raw`].
+    pub fn data(&self) -> PacketData<'_> {
+        let data = match self.payload_kind() {
+            PayloadKind::Iq => self.try_samples().map(PacketData::Iq),
+            PayloadKind::Spectrum => self.try_spectrum_row(0).map(|data| {
+                PacketData::Spectrum(SpectrumView {
+                    start_frequency: self.start_frequency(),
+                    step_frequency: self.step_frequency(),
+                    rbw_frequency: self.rbw_frequency(),
+                    data,
+                })
+            }),
+            PayloadKind::Raw => self.try_payload().map(PacketData::Raw),
+        };
+        data.unwrap_or(PacketData::Raw(&[]))
+    }
+}
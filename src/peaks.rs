@@ -0,0 +1,274 @@
+//! Peak detection on spectra and tracking of emitters across time.
+//!
+//! - [`find_peaks()`] returns the local maxima of a spectrum above a threshold
+//! - [`PeakTracker`] associates the peaks of consecutive spectra with emitters and reports,
+//!   when they appear and disappear
+use std::time::Duration;
+
+use crate::Packet;
+use crate::PayloadKind;
+use crate::Spectrum;
+use crate::SpectrumView;
+
+/// Local maximum of a spectrum, returned by [`find_peaks()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Peak {
+    /// Index of the bin.
+    pub bin: usize,
+    /// Frequency in Hz, interpolated between the neighboring bins.
+    pub frequency: f64,
+    /// Level in dBm, interpolated between the neighboring bins.
+    pub level: f32,
+}
+
+/// Find the local maxima of a spectrum above `threshold` in dBm.
+///
+/// Plateaus report their first bin. Peaks closer than `min_separation` Hz to a stronger peak
+/// are dropped. The peaks are sorted by frequency.
+pub fn find_peaks(spectrum: &SpectrumView, threshold: f32, min_separation: f64) -> Vec<Peak> {
+    let data = spectrum.data;
+    let mut peaks = Vec::new();
+    for (i, &b) in data.iter().enumerate() {
+        let a = if i > 0 {
+            data[i - 1]
+        } else {
+            f32::NEG_INFINITY
+        };
+        let c = data.get(i + 1).copied().unwrap_or(f32::NEG_INFINITY);
+        if b < threshold || b <= a || b < c {
+            continue;
+        }
+
+        // parabolic interpolation of the maximum
+        let (mut offset, mut level) = (0.0, b);
+        let denom = a - 2.0 * b + c;
+        if a.is_finite() && c.is_finite() && denom < 0.0 {
+            offset = 0.5 * (a - c) / denom;
+            level = b - 0.25 * (a - c) * offset;
+        }
+        peaks.push(Peak {
+            bin: i,
+            frequency: spectrum.start_frequency
+                + (i as f64 + offset as f64) * spectrum.step_frequency,
+            level,
+        });
+    }
+
+    if min_separation > 0.0 {
+        let mut strongest: Vec<Peak> = Vec::with_capacity(peaks.len());
+        peaks.sort_by(|a, b| b.level.total_cmp(&a.level));
+        for p in peaks {
+            if strongest
+                .iter()
+                .all(|s| (s.frequency - p.frequency).abs() >= min_separation)
+            {
+                strongest.push(p);
+            }
+        }
+        peaks = strongest;
+        peaks.sort_by(|a, b| a.frequency.total_cmp(&b.frequency));
+    }
+    peaks
+}
+
+/// Emitter, tracked by a [`PeakTracker`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Emitter {
+    /// Identifier, unique for the tracker.
+    pub id: u64,
+    /// Frequency of the last detection in Hz.
+    pub frequency: f64,
+    /// Level of the last detection in dBm.
+    pub level: f32,
+    /// Lowest frequency, the emitter was detected at, in Hz.
+    pub min_frequency: f64,
+    /// Highest frequency, the emitter was detected at, in Hz.
+    pub max_frequency: f64,
+    /// Maximum level in dBm.
+    pub max_level: f32,
+    /// Start time of the spectrum with the first detection.
+    pub first_seen: f64,
+    /// End time of the spectrum with the last detection.
+    pub last_seen: f64,
+    /// Number of spectra, the emitter was detected in.
+    pub detections: u64,
+}
+
+impl Emitter {
+    /// Time between the first and the last detection in seconds.
+    pub fn duration(&self) -> f64 {
+        self.last_seen - self.first_seen
+    }
+
+    /// Frequency drift between the lowest and the highest detection in Hz.
+    pub fn drift(&self) -> f64 {
+        self.max_frequency - self.min_frequency
+    }
+}
+
+/// Change of the emitters, reported by a [`PeakTracker`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PeakEvent {
+    /// Emitter was detected in `confirm` spectra.
+    Appeared(Emitter),
+    /// Emitter was not detected for longer than `hold`.
+    Disappeared(Emitter),
+}
+
+/// Tracker of emitters across spectra.
+///
+/// The tracker finds the peaks of each spectrum with [`find_peaks()`] and associates them with
+/// the closest emitter within `tolerance` Hz of its last frequency, strongest peaks first.
+/// Emitters may therefore drift by up to `tolerance` between two detections. Unmatched peaks
+/// start new emitters, which are reported once they were detected `confirm` times. Emitters
+/// that are not detected for longer than `hold` are reported as disappeared.
+#[derive(Debug, Clone)]
+pub struct PeakTracker {
+    /// Peak threshold in dBm.
+    pub threshold: f32,
+    /// Maximum frequency change between two detections of an emitter in Hz (default: 50k).
+    pub tolerance: f64,
+    /// Minimum distance of a peak to stronger peaks in Hz (default: 0).
+    pub min_separation: f64,
+    /// Time without detection, after which an emitter disappears (default: 500 ms).
+    pub hold: Duration,
+    /// Number of detections, before an emitter is reported (default: 1).
+    pub confirm: u64,
+    emitters: Vec<Emitter>,
+    next_id: u64,
+}
+
+impl PeakTracker {
+    /// Create a tracker with a peak threshold in dBm.
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            tolerance: 50e3,
+            min_separation: 0.0,
+            hold: Duration::from_millis(500),
+            confirm: 1,
+            emitters: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Reported emitters that did not disappear yet.
+    pub fn emitters(&self) -> impl Iterator<Item = &Emitter> + '_ {
+        self.emitters
+            .iter()
+            .filter(|e| e.detections >= self.confirm)
+    }
+
+    /// Process a [`Spectrum`] and return the changes of the emitters.
+    pub fn process(&mut self, spectrum: &Spectrum) -> Vec<PeakEvent> {
+        let view = SpectrumView {
+            start_frequency: spectrum.start_frequency,
+            step_frequency: spectrum.step_frequency,
+            rbw_frequency: spectrum.rbw_frequency,
+            data: &spectrum.data,
+        };
+        self.process_view(&view, spectrum.start_time, spectrum.end_time)
+    }
+
+    /// Process all FFT rows of a spectra [`Packet`]. Packets with other payloads are ignored.
+    pub fn process_packet(&mut self, packet: &Packet) -> Vec<PeakEvent> {
+        if packet.payload_kind() != PayloadKind::Spectrum {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        for row in packet.spectra_rows() {
+            events.extend(self.process_view(&row.spectrum, row.start_time, row.end_time));
+        }
+        events
+    }
+
+    /// Process the bins of a spectrum from `start_time` to `end_time`.
+    pub fn process_view(
+        &mut self,
+        spectrum: &SpectrumView,
+        start_time: f64,
+        end_time: f64,
+    ) -> Vec<PeakEvent> {
+        let mut events = Vec::new();
+        let mut peaks = find_peaks(spectrum, self.threshold, self.min_separation);
+        peaks.sort_by(|a, b| b.level.total_cmp(&a.level));
+
+        let mut matched = vec![false; self.emitters.len()];
+        for p in peaks {
+            let closest = self
+                .emitters
+                .iter()
+                .enumerate()
+                .filter(|(i, e)| {
+                    !matched[*i] && (e.frequency - p.frequency).abs() <= self.tolerance
+                })
+                .min_by(|(_, a), (_, b)| {
+                    let da = (a.frequency - p.frequency).abs();
+                    let db = (b.frequency - p.frequency).abs();
+                    da.total_cmp(&db)
+                })
+                .map(|(i, _)| i);
+
+            let e = match closest {
+                Some(i) => {
+                    matched[i] = true;
+                    let e = &mut self.emitters[i];
+                    e.frequency = p.frequency;
+                    e.level = p.level;
+                    e.min_frequency = e.min_frequency.min(p.frequency);
+                    e.max_frequency = e.max_frequency.max(p.frequency);
+                    e.max_level = e.max_level.max(p.level);
+                    e.last_seen = end_time;
+                    e.detections += 1;
+                    e
+                }
+                None => {
+                    self.emitters.push(Emitter {
+                        id: self.next_id,
+                        frequency: p.frequency,
+                        level: p.level,
+                        min_frequency: p.frequency,
+                        max_frequency: p.frequency,
+                        max_level: p.level,
+                        first_seen: start_time,
+                        last_seen: end_time,
+                        detections: 1,
+                    });
+                    self.next_id += 1;
+                    matched.push(true);
+                    self.emitters.last_mut().unwrap()
+                }
+            };
+            if e.detections == self.confirm.max(1) {
+                events.push(PeakEvent::Appeared(e.clone()));
+            }
+        }
+
+        let hold = self.hold.as_secs_f64();
+        let confirm = self.confirm;
+        self.emitters.retain(|e| {
+            if start_time - e.last_seen <= hold {
+                return true;
+            }
+            if e.detections >= confirm {
+                events.push(PeakEvent::Disappeared(e.clone()));
+            }
+            false
+        });
+        events
+    }
+
+    /// Report all emitters as disappeared, e.g., at the end of a recording, and reset the
+    /// tracker.
+    pub fn flush(&mut self) -> Vec<PeakEvent> {
+        let confirm = self.confirm;
+        self.emitters
+            .drain(..)
+            .filter(|e| e.detections >= confirm)
+            .map(PeakEvent::Disappeared)
+            .collect()
+    }
+}
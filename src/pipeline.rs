@@ -0,0 +1,347 @@
+//! Multi-threaded processing of packets with a receive thread and worker threads.
+use num_complex::Complex32;
+use std::collections::VecDeque;
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::Device;
+use crate::Error;
+use crate::Packet;
+use crate::PacketData;
+use crate::PacketMeta;
+
+/// What to do when the queue of a stage is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Wait until the stage has room, i.e., back-pressure to the device queue.
+    #[default]
+    Block,
+    /// Drop the new packet.
+    DropNewest,
+    /// Drop the oldest queued packet.
+    DropOldest,
+}
+
+/// Payload of an [`OwnedPacket`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Payload {
+    /// IQ samples.
+    Iq(Vec<Complex32>),
+    /// Spectrum bins in dBm.
+    Spectrum(Vec<f32>),
+    /// Raw ADC samples.
+    Raw(Vec<f32>),
+}
+
+/// Packet with metadata and payload, copied from the device queue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedPacket {
+    /// Packet metadata.
+    pub meta: PacketMeta,
+    /// Packet payload.
+    pub payload: Payload,
+}
+
+impl From<&Packet> for OwnedPacket {
+    fn from(p: &Packet) -> Self {
+        let payload = match p.data() {
+            PacketData::Iq(s) => Payload::Iq(s.to_vec()),
+            PacketData::Spectrum(s) => Payload::Spectrum(s.data.to_vec()),
+            PacketData::Raw(s) => Payload::Raw(s.to_vec()),
+        };
+        Self {
+            meta: p.meta(),
+            payload,
+        }
+    }
+}
+
+/// Bounded queue of packets with a [`DropPolicy`].
+pub(crate) struct Queue {
+    items: Mutex<VecDeque<Arc<OwnedPacket>>>,
+    cond: Condvar,
+    capacity: usize,
+    policy: DropPolicy,
+    closed: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl Queue {
+    pub(crate) fn new(capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(capacity)),
+            cond: Condvar::new(),
+            capacity,
+            policy,
+            closed: AtomicBool::new(false),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Add a packet. Packets are discarded, once the queue is closed.
+    pub(crate) fn push(&self, p: Arc<OwnedPacket>) {
+        let mut items = self.items.lock().unwrap();
+        if self.closed.load(Ordering::Acquire) {
+            return;
+        }
+        if items.len() >= self.capacity {
+            match self.policy {
+                DropPolicy::Block => {
+                    while items.len() >= self.capacity && !self.closed.load(Ordering::Acquire) {
+                        items = self.cond.wait(items).unwrap();
+                    }
+                    if self.closed.load(Ordering::Acquire) {
+                        return;
+                    }
+                }
+                DropPolicy::DropNewest => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                DropPolicy::DropOldest => {
+                    items.pop_front();
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        items.push_back(p);
+        self.cond.notify_all();
+    }
+
+    /// Get the next packet, or `None` if the queue is closed and drained.
+    pub(crate) fn pop(&self) -> Option<Arc<OwnedPacket>> {
+        let mut items = self.items.lock().unwrap();
+        loop {
+            if let Some(p) = items.pop_front() {
+                self.cond.notify_all();
+                return Some(p);
+            }
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            items = self.cond.wait(items).unwrap();
+        }
+    }
+
+    /// Get the next packet, waiting at most `timeout`.
+    pub(crate) fn pop_timeout(&self, timeout: Duration) -> Option<Arc<OwnedPacket>> {
+        let deadline = Instant::now() + timeout;
+        let mut items = self.items.lock().unwrap();
+        loop {
+            if let Some(p) = items.pop_front() {
+                self.cond.notify_all();
+                return Some(p);
+            }
+            let now = Instant::now();
+            if self.closed.load(Ordering::Acquire) || now >= deadline {
+                return None;
+            }
+            items = self.cond.wait_timeout(items, deadline - now).unwrap().0;
+        }
+    }
+
+    /// Get the next packet without waiting.
+    pub(crate) fn try_pop(&self) -> Option<Arc<OwnedPacket>> {
+        let p = self.items.lock().unwrap().pop_front();
+        if p.is_some() {
+            self.cond.notify_all();
+        }
+        p
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn close(&self) {
+        let _items = self.items.lock().unwrap();
+        self.closed.store(true, Ordering::Release);
+        self.cond.notify_all();
+    }
+}
+
+type Work = Box<dyn FnMut(&OwnedPacket) + Send>;
+
+enum Stage {
+    Single(Work),
+    Pool(usize, Arc<dyn Fn(&OwnedPacket) + Send + Sync>),
+}
+
+/// Builder of a [`Pipeline`], returned by [`Pipeline::builder()`].
+pub struct PipelineBuilder {
+    chan: i32,
+    capacity: usize,
+    policy: DropPolicy,
+    stages: Vec<Stage>,
+}
+
+impl PipelineBuilder {
+    /// Set the queue capacity of the stages in packets (default: 16).
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set the [`DropPolicy`] of the stages (default: [`DropPolicy::Block`]).
+    pub fn drop_policy(mut self, policy: DropPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Add a stage with one worker thread that gets every packet in order.
+    pub fn stage<F: FnMut(&OwnedPacket) + Send + 'static>(mut self, f: F) -> Self {
+        self.stages.push(Stage::Single(Box::new(f)));
+        self
+    }
+
+    /// Add a stage with a pool of `workers` threads that share the packets.
+    ///
+    /// Every packet is processed by one of the workers, i.e., packets are processed out of order.
+    pub fn pool<F: Fn(&OwnedPacket) + Send + Sync + 'static>(
+        mut self,
+        workers: usize,
+        f: F,
+    ) -> Self {
+        self.stages.push(Stage::Pool(workers.max(1), Arc::new(f)));
+        self
+    }
+
+    /// Start receiving from the [`Device`] and processing the packets.
+    ///
+    /// The [`Device`] has to be started. It is returned by [`Pipeline::stop()`].
+    pub fn start(self, mut dev: Device) -> Pipeline {
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut queues = Vec::new();
+        let mut workers = Vec::new();
+
+        for stage in self.stages {
+            let q = Arc::new(Queue::new(self.capacity, self.policy));
+            match stage {
+                Stage::Single(mut f) => {
+                    let q = q.clone();
+                    workers.push(std::thread::spawn(move || {
+                        while let Some(p) = q.pop() {
+                            f(&p);
+                        }
+                    }));
+                }
+                Stage::Pool(n, f) => {
+                    for _ in 0..n {
+                        let q = q.clone();
+                        let f = f.clone();
+                        workers.push(std::thread::spawn(move || {
+                            while let Some(p) = q.pop() {
+                                f(&p);
+                            }
+                        }));
+                    }
+                }
+            }
+            queues.push(q);
+        }
+
+        let chan = self.chan;
+        let s = stop.clone();
+        let q = queues.clone();
+        let receiver = std::thread::spawn(move || {
+            let r = Pipeline::run(&mut dev, chan, &s, &q);
+            for q in &q {
+                q.close();
+            }
+            r.map(|_| dev)
+        });
+
+        Pipeline {
+            stop,
+            queues,
+            receiver: Some(receiver),
+            workers,
+        }
+    }
+}
+
+/// Receive packets in a background thread and process them in worker threads.
+///
+/// The receive thread copies each packet from the device queue and hands it to all stages via
+/// bounded queues, so the device queue is not stalled by slow processing. Stages either have one
+/// worker, getting all packets in order, or a pool of workers sharing the load.
+pub struct Pipeline {
+    stop: Arc<AtomicBool>,
+    queues: Vec<Arc<Queue>>,
+    receiver: Option<JoinHandle<std::result::Result<Device, Error>>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Pipeline {
+    /// Create a [`PipelineBuilder`] for data channel `chan`.
+    pub fn builder(chan: i32) -> PipelineBuilder {
+        PipelineBuilder {
+            chan,
+            capacity: 16,
+            policy: DropPolicy::default(),
+            stages: Vec::new(),
+        }
+    }
+
+    fn run(dev: &mut Device, chan: i32, stop: &AtomicBool, queues: &[Arc<Queue>]) -> crate::Result {
+        dev.drive(chan, stop, |p| {
+            let p = Arc::new(OwnedPacket::from(p));
+            for q in queues {
+                q.push(p.clone());
+            }
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Number of packets dropped by each stage, in the order the stages were added.
+    pub fn dropped(&self) -> Vec<u64> {
+        self.queues.iter().map(|q| q.dropped()).collect()
+    }
+
+    /// Stop receiving, wait for the workers to process the queued packets, and get the
+    /// [`Device`] back.
+    pub fn stop(mut self) -> std::result::Result<Device, Error> {
+        self.stop.store(true, Ordering::Release);
+        let dev = self
+            .receiver
+            .take()
+            .unwrap()
+            .join()
+            .map_err(|_| Error::Error)?;
+        for w in self.workers.drain(..) {
+            w.join().map_err(|_| Error::Error)?;
+        }
+        dev
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        for q in &self.queues {
+            q.close();
+        }
+        if let Some(h) = self.receiver.take() {
+            let _ = h.join();
+        }
+        for w in self.workers.drain(..) {
+            let _ = w.join();
+        }
+    }
+}
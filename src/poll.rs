@@ -0,0 +1,184 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Strategy to wait for packets, if the queue of a data channel is empty.
+///
+/// Used by [`Device::packet()`](crate::Device::packet) and can be configured per device with
+/// [`Device::set_poll_strategy()`](crate::Device::set_poll_strategy) or per call with
+/// [`Device::packet_with()`](crate::Device::packet_with).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PollStrategy {
+    /// Poll again immediately, i.e., lowest latency, but one core is fully loaded.
+    BusySpin,
+    /// Yield to the scheduler between polls.
+    Yield,
+    /// Sleep for a fixed interval between polls.
+    Fixed(Duration),
+    /// Sleep with exponentially increasing interval, starting from `min` up to `max`.
+    ///
+    /// Intervals are at least 1 µs, i.e., a `min` of zero does not busy-spin.
+    Backoff {
+        /// Initial interval.
+        min: Duration,
+        /// Maximum interval.
+        max: Duration,
+    },
+}
+
+/// Shortest interval of [`PollStrategy::Backoff`].
+const MIN_BACKOFF: Duration = Duration::from_micros(1);
+
+impl Default for PollStrategy {
+    fn default() -> Self {
+        PollStrategy::Fixed(Duration::from_millis(5))
+    }
+}
+
+/// Token to abort blocking calls from another thread, e.g., on shutdown.
+///
+/// Clones share the state, i.e., cancelling one cancels all. Blocking calls like
+/// [`Device::packet_cancellable()`](crate::Device::packet_cancellable) and
+/// [`ApiHandle::rescan_devices_cancellable()`](crate::ApiHandle::rescan_devices_cancellable)
+/// check the token between polls and return [`Error::Cancelled`](crate::Error::Cancelled).
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the blocking calls, waiting on this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Check if the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// State of a [`PollStrategy`] while waiting for one packet.
+pub(crate) struct Poller {
+    strategy: PollStrategy,
+    interval: Duration,
+}
+
+impl Poller {
+    pub(crate) fn new(strategy: PollStrategy) -> Self {
+        let interval = match strategy {
+            PollStrategy::Backoff { min, .. } => min.max(MIN_BACKOFF),
+            _ => Duration::ZERO,
+        };
+        Self { strategy, interval }
+    }
+
+    /// Wait before the next poll.
+    pub(crate) fn wait(&mut self) {
+        match self.strategy {
+            PollStrategy::BusySpin => std::hint::spin_loop(),
+            PollStrategy::Yield => std::thread::yield_now(),
+            _ => std::thread::sleep(self.next_interval()),
+        }
+    }
+
+    /// Interval until the next poll, zero for strategies that do not sleep.
+    pub(crate) fn next_interval(&mut self) -> Duration {
+        match self.strategy {
+            PollStrategy::BusySpin | PollStrategy::Yield => Duration::ZERO,
+            PollStrategy::Fixed(d) => d,
+            PollStrategy::Backoff { max, .. } => {
+                let d = self.interval;
+                self.interval = std::cmp::min(self.interval * 2, max).max(MIN_BACKOFF);
+                d
+            }
+        }
+    }
+}
+
+/// Wakers, waiting for a deadline, served by one background thread.
+struct Timer {
+    waiting: Mutex<Vec<(Instant, Waker)>>,
+    cond: Condvar,
+}
+
+impl Timer {
+    fn get() -> &'static Timer {
+        static TIMER: OnceLock<Timer> = OnceLock::new();
+        static THREAD: OnceLock<()> = OnceLock::new();
+        let timer = TIMER.get_or_init(|| Timer {
+            waiting: Mutex::new(Vec::new()),
+            cond: Condvar::new(),
+        });
+        THREAD.get_or_init(|| {
+            std::thread::Builder::new()
+                .name("rtsa-timer".to_string())
+                .spawn(move || timer.run())
+                .expect("failed to spawn timer thread");
+        });
+        timer
+    }
+
+    fn run(&self) {
+        let mut waiting = self.waiting.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            waiting.retain(|(deadline, waker)| {
+                if *deadline <= now {
+                    waker.wake_by_ref();
+                    false
+                } else {
+                    true
+                }
+            });
+            waiting = match waiting.iter().map(|(d, _)| *d).min() {
+                Some(d) => self.cond.wait_timeout(waiting, d - now).unwrap().0,
+                None => self.cond.wait(waiting).unwrap(),
+            };
+        }
+    }
+}
+
+/// Future that completes after a deadline, independent of the async runtime.
+pub(crate) struct Sleep {
+    deadline: Instant,
+}
+
+impl Sleep {
+    pub(crate) fn new(duration: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + duration,
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        let timer = Timer::get();
+        timer
+            .waiting
+            .lock()
+            .unwrap()
+            .push((self.deadline, cx.waker().clone()));
+        timer.cond.notify_one();
+        Poll::Pending
+    }
+}
@@ -0,0 +1,158 @@
+use num_complex::Complex32;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::Device;
+use crate::Error;
+use crate::PacketMeta;
+
+/// Floats per cache line.
+const LINE: usize = 16;
+
+/// Cache line of payload storage.
+#[derive(Clone, Copy)]
+#[repr(C, align(64))]
+struct Line([f32; LINE]);
+
+/// Pool of reusable, cacheline-aligned payload buffers.
+///
+/// Buffers are taken with [`get()`](Self::get) and return to the pool, when the [`PoolBuf`] is
+/// dropped. The pool is cheap to clone and can be shared between threads.
+#[derive(Clone)]
+pub struct BufferPool {
+    free: Arc<Mutex<Vec<Vec<Line>>>>,
+    capacity: usize,
+    max_free: usize,
+}
+
+impl BufferPool {
+    /// Create a pool of buffers for `capacity` floats, keeping at most `max_free` unused buffers.
+    pub fn new(capacity: usize, max_free: usize) -> Self {
+        Self {
+            free: Arc::new(Mutex::new(Vec::with_capacity(max_free))),
+            capacity,
+            max_free,
+        }
+    }
+
+    /// Get an empty buffer, reusing a returned one if available.
+    pub fn get(&self) -> PoolBuf {
+        let storage = self
+            .free
+            .lock()
+            .unwrap()
+            .pop()
+            .unwrap_or_else(|| vec![Line([0.0; LINE]); self.capacity.div_ceil(LINE)]);
+        PoolBuf {
+            storage,
+            len: 0,
+            meta: None,
+            pool: self.clone(),
+        }
+    }
+
+    /// Number of unused buffers in the pool.
+    pub fn available(&self) -> usize {
+        self.free.lock().unwrap().len()
+    }
+}
+
+impl std::fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("capacity", &self.capacity)
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+/// Buffer of a [`BufferPool`], holding the payload and metadata of a packet.
+pub struct PoolBuf {
+    storage: Vec<Line>,
+    len: usize,
+    meta: Option<PacketMeta>,
+    pool: BufferPool,
+}
+
+impl PoolBuf {
+    /// Metadata of the copied packet, `None` if the buffer is empty.
+    pub fn meta(&self) -> Option<&PacketMeta> {
+        self.meta.as_ref()
+    }
+
+    /// Payload as floats.
+    pub fn as_f32(&self) -> &[f32] {
+        // SAFETY: lines are arrays of floats without padding
+        unsafe { std::slice::from_raw_parts(self.storage.as_ptr() as *const f32, self.len) }
+    }
+
+    /// Payload as IQ samples.
+    pub fn samples(&self) -> &[Complex32] {
+        // SAFETY: Complex32 is repr(C) with two floats and lines are 64 byte aligned
+        unsafe {
+            std::slice::from_raw_parts(self.storage.as_ptr() as *const Complex32, self.len / 2)
+        }
+    }
+
+    /// Number of floats in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copy a payload into the buffer, growing it if needed.
+    fn fill(&mut self, data: &[f32], meta: PacketMeta) {
+        let lines = data.len().div_ceil(LINE);
+        if self.storage.len() < lines {
+            self.storage.resize(lines, Line([0.0; LINE]));
+        }
+        // SAFETY: storage holds at least `data.len()` floats
+        let dst = unsafe {
+            std::slice::from_raw_parts_mut(self.storage.as_mut_ptr() as *mut f32, data.len())
+        };
+        dst.copy_from_slice(data);
+        self.len = data.len();
+        self.meta = Some(meta);
+    }
+}
+
+impl std::fmt::Debug for PoolBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PoolBuf")
+            .field("len", &self.len)
+            .field("meta", &self.meta)
+            .finish()
+    }
+}
+
+impl Drop for PoolBuf {
+    fn drop(&mut self) {
+        let mut free = self.pool.free.lock().unwrap();
+        if free.len() < self.pool.max_free {
+            free.push(std::mem::take(&mut self.storage));
+        }
+    }
+}
+
+impl Device {
+    /// Copy the next [`Packet`](crate::Packet) of data channel `chan` into a pooled buffer and
+    /// consume it.
+    ///
+    /// Blocks like [`packet()`](Self::packet). The buffer holds the full payload, i.e., IQ
+    /// samples or spectrum bins, and the packet metadata.
+    pub fn packet_into(&mut self, chan: i32, buf: &mut PoolBuf) -> std::result::Result<(), Error> {
+        let p = self.packet(chan)?;
+        let n = (p.num() * p.stride()).max(0) as usize;
+        let data = if n == 0 || p.inner.fp32.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(p.inner.fp32 as *const f32, n) }
+        };
+        buf.fill(data, p.meta());
+        self.consume(chan)
+    }
+}
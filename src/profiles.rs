@@ -0,0 +1,233 @@
+//! Named configuration presets, stored in a user configuration directory.
+//!
+//! - save the configuration of a set up [`Device`] with [`Device::save_profile()`]
+//! - restore it on any device with [`Device::apply_profile()`], e.g., `"fm-broadcast"`
+//! - share profiles by copying the files, one `<name>.profile` per profile
+//!
+//! Profiles are text files with one `path = value` entry per line, applied in order. Values are
+//! `true`/`false`, integers, floats (with a `.` or exponent), or strings in double quotes.
+//! Unquoted words are read as strings, e.g., `device/receiverchannel = Rx1`, and lines starting
+//! with `#` are comments.
+use std::fs;
+use std::io::Error as IoError;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::ConfigProfile;
+use crate::ConfigValue;
+use crate::Device;
+use crate::Error;
+use crate::Result;
+
+/// File extension of profiles.
+pub const EXTENSION: &str = "profile";
+
+/// Directory of the user profiles.
+///
+/// This is `RTSA_PROFILE_DIR`, if set, or the `aaronia-rtsa/profiles` directory in the user
+/// configuration directory, i.e., `XDG_CONFIG_HOME` or `~/.config` on Linux and macOS and
+/// `%APPDATA%` on Windows.
+pub fn user_dir() -> Option<PathBuf> {
+    if let Some(d) = std::env::var_os("RTSA_PROFILE_DIR").filter(|d| !d.is_empty()) {
+        return Some(d.into());
+    }
+
+    #[cfg(not(windows))]
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".config")));
+    #[cfg(windows)]
+    let config = std::env::var_os("APPDATA").map(PathBuf::from);
+
+    config.map(|c| c.join("aaronia-rtsa").join("profiles"))
+}
+
+/// Directory of named [`ConfigProfile`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileStore {
+    dir: PathBuf,
+}
+
+impl ProfileStore {
+    /// Store in the given directory, which is created when a profile is saved.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store in the [user directory](user_dir).
+    pub fn user() -> std::io::Result<Self> {
+        user_dir().map(Self::new).ok_or_else(|| {
+            IoError::new(
+                ErrorKind::NotFound,
+                "no configuration directory, set RTSA_PROFILE_DIR",
+            )
+        })
+    }
+
+    /// Directory of the store.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File of a profile.
+    ///
+    /// Names consist of letters, digits, `-`, `_`, and `.` and must not start with a `.`.
+    pub fn path(&self, name: &str) -> std::io::Result<PathBuf> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("invalid profile name {name:?}"),
+            ));
+        }
+        Ok(self.dir.join(format!("{name}.{EXTENSION}")))
+    }
+
+    /// Names of the stored profiles in alphabetical order.
+    ///
+    /// A missing directory has no profiles.
+    pub fn list(&self) -> std::io::Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(e) => e,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut names = Vec::new();
+        for e in entries {
+            let p = e?.path();
+            if p.extension().is_some_and(|e| e == EXTENSION) {
+                if let Some(n) = p.file_stem().and_then(|n| n.to_str()) {
+                    names.push(n.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Check if a profile is stored.
+    pub fn contains(&self, name: &str) -> bool {
+        self.path(name).is_ok_and(|p| p.is_file())
+    }
+
+    /// Load a profile.
+    pub fn load(&self, name: &str) -> std::io::Result<ConfigProfile> {
+        parse(&fs::read_to_string(self.path(name)?)?)
+    }
+
+    /// Save a profile, replacing a stored profile with the same name.
+    pub fn save(&self, name: &str, profile: &ConfigProfile) -> std::io::Result<()> {
+        let path = self.path(name)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(path, format(profile))
+    }
+
+    /// Remove a profile.
+    pub fn remove(&self, name: &str) -> std::io::Result<()> {
+        fs::remove_file(self.path(name)?)
+    }
+}
+
+/// Write a profile in the text format of the [module documentation](self).
+pub fn format(profile: &ConfigProfile) -> String {
+    let mut s = String::new();
+    for (path, value) in profile.iter() {
+        let v = match value {
+            ConfigValue::Bool(b) => b.to_string(),
+            ConfigValue::Int(i) => i.to_string(),
+            // Debug keeps the `.` of integral floats, e.g., `1000000000.0`
+            ConfigValue::Float(f) => format!("{f:?}"),
+            ConfigValue::String(s) => {
+                format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+        };
+        s.push_str(&format!("{path} = {v}\n"));
+    }
+    s
+}
+
+/// Read a profile in the text format of the [module documentation](self).
+pub fn parse(s: &str) -> std::io::Result<ConfigProfile> {
+    let mut profile = ConfigProfile::new();
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |reason: &str| {
+            IoError::new(ErrorKind::InvalidData, format!("line {}: {reason}", i + 1))
+        };
+        let (path, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid("expected `=`"))?;
+        let path = path.trim();
+        if path.is_empty() {
+            return Err(invalid("missing path"));
+        }
+        profile.set(
+            path,
+            parse_value(value.trim()).ok_or_else(|| invalid("invalid value"))?,
+        );
+    }
+    Ok(profile)
+}
+
+/// Parse a value of the text format.
+fn parse_value(v: &str) -> Option<ConfigValue> {
+    if let Some(q) = v.strip_prefix('"') {
+        let mut s = String::new();
+        let mut chars = q.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => s.push(chars.next()?),
+                '"' => return chars.as_str().is_empty().then_some(ConfigValue::String(s)),
+                c => s.push(c),
+            }
+        }
+        return None;
+    }
+    match v {
+        "" => None,
+        "true" => Some(ConfigValue::Bool(true)),
+        "false" => Some(ConfigValue::Bool(false)),
+        _ => Some(
+            v.parse()
+                .map(ConfigValue::Int)
+                .or_else(|_| v.parse().map(ConfigValue::Float))
+                .unwrap_or_else(|_| ConfigValue::String(v.to_string())),
+        ),
+    }
+}
+
+impl Device {
+    /// Apply a named profile of the [user directory](user_dir).
+    ///
+    /// Returns [`Error::Profile`], if the profile cannot be loaded.
+    pub fn apply_profile(&mut self, name: &str) -> Result {
+        let profile = ProfileStore::user()
+            .and_then(|s| s.load(name))
+            .map_err(|e| profile_error(name, e))?;
+        self.apply_config(&profile)
+    }
+
+    /// Save the current configuration as named profile in the [user directory](user_dir).
+    pub fn save_profile(&mut self, name: &str) -> Result {
+        let profile = self.export_config()?;
+        ProfileStore::user()
+            .and_then(|s| s.save(name, &profile))
+            .map_err(|e| profile_error(name, e))
+    }
+}
+
+fn profile_error(name: &str, e: IoError) -> Error {
+    Error::Profile {
+        name: name.to_string(),
+        reason: e.to_string(),
+    }
+}
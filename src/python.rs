@@ -0,0 +1,399 @@
+//! Python bindings with [pyo3](https://pyo3.rs).
+//!
+//! The `aaronia_rtsa` extension module exposes the safe API to Python:
+//!
+//! - `ApiHandle`, `DeviceInfo`, and `Device` with the lifecycle of the Rust types
+//! - `Device.get()` and `Device.set()`, checking values of known paths like
+//!   [`Device::get_typed()`] and [`Device::set_typed()`]
+//! - `Packet` with its metadata and the payload as NumPy arrays, viewing the packet buffer of the
+//!   library without copying it
+//!
+//! Errors are raised as `aaronia_rtsa.RtsaError`. Build the module with
+//! [maturin](https://www.maturin.rs), e.g., `maturin develop --release --features python`, which
+//! builds the crate as `cdylib`.
+//!
+//! ```python
+//! import aaronia_rtsa
+//!
+//! api = aaronia_rtsa.ApiHandle()
+//! api.rescan_devices()
+//! dev = api.get_device()
+//! dev.open()
+//! dev.connect()
+//! dev.set("main/centerfreq", 2.4e9)
+//! dev.start()
+//! packet = dev.packet(0)
+//! samples = packet.samples().copy()
+//! dev.consume(0)
+//! ```
+use numpy::ndarray::ArrayView1;
+use numpy::PyArray1;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::exceptions::PyTypeError;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::IntoPyObjectExt;
+
+use crate::poll::Poller;
+use crate::ApiHandle;
+use crate::ConfigPath;
+use crate::ConfigValue;
+use crate::Device;
+use crate::DeviceInfo;
+use crate::Error;
+use crate::Memory;
+use crate::Packet;
+
+create_exception!(
+    aaronia_rtsa,
+    RtsaError,
+    PyException,
+    "Error of the RTSA library or the device."
+);
+
+impl From<Error> for PyErr {
+    fn from(e: Error) -> Self {
+        RtsaError::new_err(e.to_string())
+    }
+}
+
+fn parse_memory(name: &str) -> PyResult<Memory> {
+    match name.to_lowercase().as_str() {
+        "small" => Ok(Memory::Small),
+        "medium" => Ok(Memory::Medium),
+        "large" => Ok(Memory::Large),
+        "ludicrous" => Ok(Memory::Ludicrous),
+        _ => Err(PyValueError::new_err(format!(
+            "invalid memory size {name:?}, valid sizes: small, medium, large, ludicrous"
+        ))),
+    }
+}
+
+/// Convert a Python value to a [`ConfigValue`].
+fn config_value(value: &Bound<'_, PyAny>) -> PyResult<ConfigValue> {
+    // bool is a subclass of int in Python
+    if let Ok(b) = value.cast_exact::<pyo3::types::PyBool>() {
+        return Ok(ConfigValue::Bool(b.is_true()));
+    }
+    if let Ok(i) = value.extract::<i64>() {
+        return Ok(ConfigValue::Int(i));
+    }
+    if let Ok(f) = value.extract::<f64>() {
+        return Ok(ConfigValue::Float(f));
+    }
+    if let Ok(s) = value.extract::<String>() {
+        return Ok(ConfigValue::String(s));
+    }
+    Err(PyTypeError::new_err("expected bool, int, float, or str"))
+}
+
+/// View a slice as read-only NumPy array, keeping `container` alive.
+fn view<'py, T: numpy::Element>(
+    data: &[T],
+    container: Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyArray1<T>>> {
+    let py = container.py();
+    // raise ImportError instead of a panic without NumPy
+    py.import("numpy")?;
+    let array = unsafe { PyArray1::borrow_from_array(&ArrayView1::from(data), container) };
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("write", false)?;
+    array.call_method("setflags", (), Some(&kwargs))?;
+    Ok(array)
+}
+
+/// Handle to interface the library, see [`ApiHandle`].
+#[pyclass(name = "ApiHandle", module = "aaronia_rtsa", unsendable)]
+struct PyApiHandle {
+    inner: ApiHandle,
+}
+
+#[pymethods]
+impl PyApiHandle {
+    #[new]
+    #[pyo3(signature = (memory = "medium"))]
+    fn new(memory: &str) -> PyResult<Self> {
+        Ok(Self {
+            inner: ApiHandle::with_mem(parse_memory(memory)?)?,
+        })
+    }
+
+    /// Memory size of the library, e.g., `medium`.
+    #[getter]
+    fn memory(&self) -> String {
+        format!("{:?}", self.inner.memory()).to_lowercase()
+    }
+
+    /// Rescan for devices.
+    fn rescan_devices(&mut self) -> PyResult<()> {
+        Ok(self.inner.rescan_devices()?)
+    }
+
+    /// Information about all detected devices.
+    fn devices(&mut self) -> PyResult<Vec<PyDeviceInfo>> {
+        Ok(self
+            .inner
+            .devices()?
+            .into_iter()
+            .map(|inner| PyDeviceInfo { inner })
+            .collect())
+    }
+
+    /// Get the first detected device.
+    fn get_device(&mut self) -> PyResult<PyDevice> {
+        Ok(PyDevice {
+            inner: self.inner.get_device()?,
+        })
+    }
+
+    /// Get a specific device, identified by its `DeviceInfo`.
+    fn get_this_device(&mut self, info: &PyDeviceInfo) -> PyResult<PyDevice> {
+        Ok(PyDevice {
+            inner: self.inner.get_this_device(&info.inner)?,
+        })
+    }
+}
+
+/// Information about a device, see [`DeviceInfo`].
+#[pyclass(name = "DeviceInfo", module = "aaronia_rtsa", unsendable)]
+struct PyDeviceInfo {
+    inner: DeviceInfo,
+}
+
+#[pymethods]
+impl PyDeviceInfo {
+    #[getter]
+    fn serial(&self) -> String {
+        self.inner.serial()
+    }
+
+    #[getter]
+    fn ready(&self) -> bool {
+        self.inner.ready()
+    }
+
+    #[getter]
+    fn boost(&self) -> bool {
+        self.inner.boost()
+    }
+
+    #[getter]
+    fn superspeed(&self) -> bool {
+        self.inner.superspeed()
+    }
+
+    #[getter]
+    fn active(&self) -> bool {
+        self.inner.active()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DeviceInfo(serial={:?})", self.inner.serial())
+    }
+}
+
+/// Device, see [`Device`].
+#[pyclass(name = "Device", module = "aaronia_rtsa", unsendable)]
+struct PyDevice {
+    inner: Device,
+}
+
+#[pymethods]
+impl PyDevice {
+    fn open(&mut self) -> PyResult<()> {
+        Ok(self.inner.open()?)
+    }
+
+    fn close(&mut self) -> PyResult<()> {
+        Ok(self.inner.close()?)
+    }
+
+    fn connect(&mut self, py: Python<'_>) -> PyResult<()> {
+        let dev = &mut self.inner;
+        Ok(py.detach(|| dev.connect())?)
+    }
+
+    fn disconnect(&mut self) -> PyResult<()> {
+        Ok(self.inner.disconnect()?)
+    }
+
+    fn start(&mut self, py: Python<'_>) -> PyResult<()> {
+        let dev = &mut self.inner;
+        Ok(py.detach(|| dev.start())?)
+    }
+
+    fn stop(&mut self) -> PyResult<()> {
+        Ok(self.inner.stop()?)
+    }
+
+    /// Lifecycle state, e.g., `started`.
+    #[getter]
+    fn status(&self) -> String {
+        format!("{:?}", self.inner.status()).to_lowercase()
+    }
+
+    /// Get a configuration parameter as bool, int, float, or str.
+    ///
+    /// Enum parameters return the selected option.
+    fn get<'py>(&mut self, py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyAny>> {
+        let value = match ConfigPath::lookup(path) {
+            Some(p) => self.inner.get_typed(p)?,
+            None => ConfigValue::from_item(&self.inner.get(path)?)
+                .ok_or_else(|| PyTypeError::new_err(format!("{path} holds no value")))?,
+        };
+        match value {
+            ConfigValue::Bool(b) => b.into_bound_py_any(py),
+            ConfigValue::Int(i) => i.into_bound_py_any(py),
+            ConfigValue::Float(f) => f.into_bound_py_any(py),
+            ConfigValue::String(s) => s.into_bound_py_any(py),
+        }
+    }
+
+    /// Set a configuration parameter to a bool, int, float, or str.
+    ///
+    /// Enum parameters are set by option or index.
+    fn set(&mut self, path: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        let value = config_value(value)?;
+        match ConfigPath::lookup(path) {
+            Some(p) => self.inner.set_typed(p, &value)?,
+            None => self.inner.set_value(path, &value)?,
+        }
+        Ok(())
+    }
+
+    /// Number of packets, available in the queue of a data channel.
+    fn packets_avail(&mut self, chan: i32) -> PyResult<usize> {
+        Ok(self.inner.packets_avail(chan)?)
+    }
+
+    /// Get the packet at the head of the queue of a data channel, waiting for it.
+    ///
+    /// The wait is interrupted by signals, e.g., Ctrl-C.
+    fn packet(&mut self, py: Python<'_>, chan: i32) -> PyResult<PyPacket> {
+        let mut poller = Poller::new(self.inner.poll_strategy());
+        loop {
+            match self.inner.try_packet(chan) {
+                Err(Error::Empty) => {
+                    py.check_signals()?;
+                    let poller = &mut poller;
+                    py.detach(|| poller.wait());
+                }
+                r => return Ok(PyPacket { inner: r? }),
+            }
+        }
+    }
+
+    /// Get the packet at the head of the queue of a data channel, `None` if it is empty.
+    fn try_packet(&mut self, chan: i32) -> PyResult<Option<PyPacket>> {
+        match self.inner.try_packet(chan) {
+            Ok(inner) => Ok(Some(PyPacket { inner })),
+            Err(Error::Empty) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Remove the packet at the head of the queue of a data channel.
+    ///
+    /// Arrays of the packet are invalid afterwards.
+    fn consume(&mut self, chan: i32) -> PyResult<()> {
+        Ok(self.inner.consume(chan)?)
+    }
+}
+
+/// Packet of a data channel, see [`Packet`].
+///
+/// The arrays of the payload view the packet buffer of the library, i.e., they are valid, until
+/// the packet is consumed. Copy them, e.g., with `samples().copy()`, to keep them.
+#[pyclass(name = "Packet", module = "aaronia_rtsa", unsendable)]
+struct PyPacket {
+    inner: Packet,
+}
+
+#[pymethods]
+impl PyPacket {
+    #[getter]
+    fn start_time(&self) -> f64 {
+        self.inner.start_time()
+    }
+
+    #[getter]
+    fn end_time(&self) -> f64 {
+        self.inner.end_time()
+    }
+
+    #[getter]
+    fn start_frequency(&self) -> f64 {
+        self.inner.start_frequency()
+    }
+
+    #[getter]
+    fn step_frequency(&self) -> f64 {
+        self.inner.step_frequency()
+    }
+
+    #[getter]
+    fn span_frequency(&self) -> f64 {
+        self.inner.span_frequency()
+    }
+
+    /// Sample rate in Hz, `None` if unknown.
+    #[getter]
+    fn sample_rate(&self) -> Option<f64> {
+        self.inner.sample_rate()
+    }
+
+    /// IQ samples as `complex64` array.
+    fn samples<'py>(
+        slf: Bound<'py, Self>,
+    ) -> PyResult<Bound<'py, PyArray1<num_complex::Complex32>>> {
+        let data = {
+            let packet = slf.borrow();
+            let samples = packet.inner.try_samples()?;
+            // the buffer is owned by the library, not the borrow of the packet
+            unsafe { std::slice::from_raw_parts(samples.as_ptr(), samples.len()) }
+        };
+        view(data, slf.into_any())
+    }
+
+    /// Row of a spectra packet as `float32` array in dBm.
+    #[pyo3(signature = (row = 0))]
+    fn spectrum<'py>(slf: Bound<'py, Self>, row: usize) -> PyResult<Bound<'py, PyArray1<f32>>> {
+        let data = {
+            let packet = slf.borrow();
+            let bins = packet.inner.try_spectrum_row(row)?;
+            unsafe { std::slice::from_raw_parts(bins.as_ptr(), bins.len()) }
+        };
+        view(data, slf.into_any())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Packet(start_time={}, start_frequency={}, num={})",
+            self.inner.start_time(),
+            self.inner.start_frequency(),
+            self.inner.num()
+        )
+    }
+}
+
+/// Version of the RTSA library (`<major>.<minor>`).
+#[pyfunction]
+fn version() -> String {
+    crate::version()
+}
+
+/// The `aaronia_rtsa` extension module.
+///
+/// Applications that embed Python register it with [`pyo3::append_to_inittab!`].
+#[pymodule]
+pub fn aaronia_rtsa(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("RtsaError", m.py().get_type::<RtsaError>())?;
+    m.add_class::<PyApiHandle>()?;
+    m.add_class::<PyDeviceInfo>()?;
+    m.add_class::<PyDevice>()?;
+    m.add_class::<PyPacket>()?;
+    m.add_function(wrap_pyfunction!(version, m)?)?;
+    Ok(())
+}
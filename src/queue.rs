@@ -0,0 +1,110 @@
+use crate::memory::BufferedBytes;
+use crate::Device;
+use crate::Error;
+use crate::Packet;
+
+/// Statistics of a data channel queue, returned by [`Device::queue_stats()`].
+///
+/// The statistics are tracked continuously while packets are fetched with
+/// [`Device::packet()`] or [`Device::try_packet()`]. Drops are detected as gaps between the end
+/// time of a packet and the start time of the next one.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueueStats {
+    /// Packets currently available in the queue.
+    pub available: usize,
+    /// Maximum number of available packets, observed so far.
+    pub high_water: usize,
+    /// Number of received packets.
+    pub packets: u64,
+    /// Number of detected gaps in the stream.
+    pub drops: u64,
+    /// Total duration of the detected gaps in seconds.
+    pub dropped_time: f64,
+    /// Sample rate of the last packet in Hz.
+    pub sample_rate: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct QueueTracker {
+    stats: QueueStats,
+    last: Option<(f64, f64)>,
+    packet_bytes: usize,
+    buffered: BufferedBytes,
+}
+
+impl QueueTracker {
+    fn update_available(&mut self, available: usize) {
+        self.stats.available = available;
+        self.stats.high_water = self.stats.high_water.max(available);
+        self.buffered.set(available * self.packet_bytes);
+    }
+
+    fn update_packet(&mut self, packet: &Packet) {
+        let (start, end) = (packet.start_time(), packet.end_time());
+        if let Some((last_start, last_end)) = self.last {
+            // the same packet is returned until it is consumed
+            if start == last_start {
+                return;
+            }
+            // tolerate jitter of a fraction of the packet duration
+            let gap = start - last_end;
+            if gap > (end - start) * 0.01 {
+                self.stats.drops += 1;
+                self.stats.dropped_time += gap;
+            }
+        }
+        self.stats.packets += 1;
+        self.stats.sample_rate = packet.sample_rate();
+        self.packet_bytes = packet.layout().bytes();
+        self.buffered.set(self.stats.available * self.packet_bytes);
+        self.last = Some((start, end));
+    }
+}
+
+impl Device {
+    /// Get the [`QueueStats`] of data channel `chan`.
+    pub fn queue_stats(&mut self, chan: i32) -> std::result::Result<QueueStats, Error> {
+        let available = self.packets_avail(chan)?;
+        let tracker = self.queues.entry(chan).or_default();
+        tracker.update_available(available);
+        Ok(tracker.stats)
+    }
+
+    /// Reset the [`QueueStats`] of data channel `chan`.
+    pub fn reset_queue_stats(&mut self, chan: i32) {
+        self.queues.remove(&chan);
+    }
+
+    /// Update the queue tracking with a fetched packet.
+    pub(crate) fn track_packet(&mut self, chan: i32, packet: &Packet) {
+        let available = self.packets_avail(chan).ok();
+        let tracker = self.queues.entry(chan).or_default();
+        if let Some(a) = available {
+            tracker.update_available(a);
+        }
+        #[cfg(feature = "tracing")]
+        let before = tracker.stats;
+        tracker.update_packet(packet);
+
+        #[cfg(feature = "tracing")]
+        {
+            let after = tracker.stats;
+            let serial = self.serial.display();
+            if after.drops > before.drops {
+                let gap = after.dropped_time - before.dropped_time;
+                tracing::warn!(serial = %serial, chan, gap, "stream gap");
+            }
+            if after.packets > before.packets {
+                tracing::trace!(
+                    serial = %serial,
+                    chan,
+                    packets = after.packets,
+                    available = after.available,
+                    start_time = packet.start_time(),
+                    "packet"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,474 @@
+//! Continuous IQ recording to segmented files.
+//!
+//! A [`Recorder`] writes interleaved little-endian samples in a [`SampleFormat`], e.g., 32-bit
+//! float (`.cf32`), to a sequence of segment files `<prefix>-00000.cf32`, `<prefix>-00001.cf32`,
+//! ... in a directory, rotating them by size or stream time. The index file `<prefix>.index.csv`
+//! has a line for the start of every segment and every discontinuity of the stream, i.e., a gap
+//! in time or a change of frequency or sample rate, with the columns
+//!
+//! - `segment`: number of the segment
+//! - `file`: file name of the segment
+//! - `sample`: index of the first sample in the recording
+//! - `offset`: index of the first sample in the segment
+//! - `time`: stream time of the first sample in seconds
+//! - `frequency`: center frequency in Hz
+//! - `sample_rate`: sample rate in Hz
+//!
+//! On Linux, segments are preallocated and written with `O_DIRECT` from aligned buffers to
+//! sustain high data rates. Both fall back to regular writes, if the file system does not
+//! support them.
+use num_complex::Complex32;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Result;
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::Packet;
+use crate::PacketMeta;
+use crate::PacketSink;
+use crate::SampleFormat;
+use crate::StreamTime;
+
+/// Alignment and granularity of direct writes.
+const BLOCK: usize = 4096;
+/// Size of the write buffer.
+const BUFFER_BLOCKS: usize = 1024;
+
+/// Aligned block of the write buffer.
+#[derive(Clone, Copy)]
+#[repr(C, align(4096))]
+struct Block([u8; BLOCK]);
+
+/// View blocks as bytes.
+fn as_bytes(blocks: &mut [Block]) -> &mut [u8] {
+    // SAFETY: blocks are byte arrays without padding
+    unsafe { std::slice::from_raw_parts_mut(blocks.as_mut_ptr() as *mut u8, BLOCK * blocks.len()) }
+}
+
+/// Segment file, written through an aligned buffer.
+struct SegmentFile {
+    file: File,
+    buf: Vec<Block>,
+    len: usize,
+    written: u64,
+    direct: bool,
+}
+
+impl SegmentFile {
+    fn create(path: &Path, direct: bool, preallocate: Option<u64>) -> Result<Self> {
+        let (file, direct) = Self::open(path, direct)?;
+        #[cfg(target_os = "linux")]
+        if let Some(n) = preallocate {
+            use std::os::unix::io::AsRawFd;
+            // best effort, not all file systems support it
+            unsafe { libc::posix_fallocate(file.as_raw_fd(), 0, n as libc::off_t) };
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = preallocate;
+
+        Ok(Self {
+            file,
+            buf: vec![Block([0; BLOCK]); BUFFER_BLOCKS],
+            len: 0,
+            written: 0,
+            direct,
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn open(path: &Path, direct: bool) -> Result<(File, bool)> {
+        use std::os::unix::fs::OpenOptionsExt;
+        if direct {
+            let f = std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .custom_flags(libc::O_DIRECT)
+                .open(path);
+            if let Ok(f) = f {
+                return Ok((f, true));
+            }
+        }
+        Ok((File::create(path)?, false))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn open(path: &Path, _direct: bool) -> Result<(File, bool)> {
+        Ok((File::create(path)?, false))
+    }
+
+    /// Write the full blocks of the buffer.
+    fn write_blocks(&mut self) -> Result<()> {
+        let n = self.len / BLOCK * BLOCK;
+        if n == 0 {
+            return Ok(());
+        }
+        let bytes = as_bytes(&mut self.buf);
+        self.file.write_all(&bytes[..n])?;
+        bytes.copy_within(n..self.len, 0);
+        self.len -= n;
+        self.written += n as u64;
+        Ok(())
+    }
+
+    /// Write the remaining data and truncate the preallocated space.
+    fn finish(mut self) -> Result<()> {
+        self.write_blocks()?;
+        if self.len > 0 {
+            #[cfg(target_os = "linux")]
+            if self.direct {
+                use std::os::unix::io::AsRawFd;
+                // the tail is not block-aligned, i.e., it has to bypass O_DIRECT
+                let fd = self.file.as_raw_fd();
+                unsafe {
+                    let flags = libc::fcntl(fd, libc::F_GETFL);
+                    libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_DIRECT);
+                }
+            }
+            self.file.write_all(&as_bytes(&mut self.buf)[..self.len])?;
+            self.written += self.len as u64;
+            self.len = 0;
+        }
+        self.file.set_len(self.written)?;
+        self.file.flush()
+    }
+}
+
+impl Write for SegmentFile {
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        let cap = BLOCK * self.buf.len();
+        if self.len == cap {
+            self.write_blocks()?;
+        }
+        let n = data.len().min(cap - self.len);
+        as_bytes(&mut self.buf)[self.len..self.len + n].copy_from_slice(&data[..n]);
+        self.len += n;
+        Ok(n)
+    }
+
+    /// Write the full blocks of the buffer, the tail is written by [`SegmentFile::finish()`].
+    fn flush(&mut self) -> Result<()> {
+        self.write_blocks()
+    }
+}
+
+/// Writer of a segment, optionally compressed.
+enum SegmentWriter {
+    Plain(SegmentFile),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, SegmentFile>),
+}
+
+impl SegmentWriter {
+    fn write_all(&mut self, data: &[u8]) -> Result<()> {
+        match self {
+            SegmentWriter::Plain(w) => w.write_all(data),
+            #[cfg(feature = "zstd")]
+            SegmentWriter::Zstd(w) => w.write_all(data),
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            SegmentWriter::Plain(w) => w.finish(),
+            #[cfg(feature = "zstd")]
+            SegmentWriter::Zstd(w) => w.finish()?.finish(),
+        }
+    }
+}
+
+/// Open segment.
+struct Segment {
+    writer: SegmentWriter,
+    file: String,
+    samples: u64,
+    start: f64,
+}
+
+/// Stream parameters of the last written samples.
+#[derive(Clone, Copy)]
+struct Position {
+    end: f64,
+    frequency: f64,
+    sample_rate: f64,
+}
+
+/// Writer of continuous IQ streams to segmented files with an index, see the [module
+/// documentation](self).
+///
+/// Settings take effect at the next segment. The current segment is finished, when the recorder
+/// is dropped, but errors are only reported by [`finish()`](Self::finish).
+///
+/// As [`PacketSink`], the recorder writes the IQ samples of all packets and stops receiving at the
+/// first write error, which is returned by [`finish()`](Self::finish).
+pub struct Recorder {
+    /// Maximum size of a segment in bytes of uncompressed samples (default: 1 GiB).
+    pub segment_size: Option<u64>,
+    /// Maximum stream time of a segment (default: `None`).
+    pub segment_duration: Option<Duration>,
+    /// Preallocate segments of `segment_size` bytes (default: `true`).
+    pub preallocate: bool,
+    /// Write with `O_DIRECT`, if supported (default: `true`).
+    pub direct_io: bool,
+    /// Format of the samples in the segments (default: [`SampleFormat::F32`]).
+    pub format: SampleFormat,
+    /// Compress segments with zstd at the given level (default: `None`).
+    #[cfg(feature = "zstd")]
+    pub compression: Option<i32>,
+    dir: PathBuf,
+    prefix: String,
+    index: BufWriter<File>,
+    segment: Option<Segment>,
+    segments: usize,
+    samples: u64,
+    position: Option<Position>,
+    bytes: Vec<u8>,
+    clipped: u64,
+    error: Option<std::io::Error>,
+}
+
+impl Recorder {
+    /// Create a recorder, writing to files starting with `prefix` in `dir`.
+    ///
+    /// The directory is created, if it does not exist.
+    pub fn new<P: AsRef<Path>>(dir: P, prefix: &str) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        let mut index = BufWriter::new(File::create(dir.join(format!("{prefix}.index.csv")))?);
+        writeln!(
+            index,
+            "segment,file,sample,offset,time,frequency,sample_rate"
+        )?;
+
+        Ok(Self {
+            segment_size: Some(1 << 30),
+            segment_duration: None,
+            preallocate: true,
+            direct_io: true,
+            format: SampleFormat::F32,
+            #[cfg(feature = "zstd")]
+            compression: None,
+            dir,
+            prefix: prefix.to_string(),
+            index,
+            segment: None,
+            segments: 0,
+            samples: 0,
+            position: None,
+            bytes: Vec::new(),
+            clipped: 0,
+            error: None,
+        })
+    }
+
+    /// Number of started segments.
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    /// Number of recorded samples.
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Number of values that saturated in the conversion to an integer [`SampleFormat`].
+    pub fn clipped(&self) -> u64 {
+        self.clipped
+    }
+
+    /// Write the IQ samples of a [`Packet`].
+    pub fn write_packet(&mut self, packet: &Packet) -> Result<()> {
+        let sample_rate = packet.sample_rate().unwrap_or(packet.step_frequency());
+        let frequency = packet.start_frequency() + packet.span_frequency() / 2.0;
+        self.write(
+            packet.samples(),
+            packet.start_stream_time(),
+            frequency,
+            sample_rate,
+        )
+    }
+
+    /// Write IQ samples, starting at `time`.
+    pub fn write(
+        &mut self,
+        samples: &[Complex32],
+        time: StreamTime,
+        frequency: f64,
+        sample_rate: f64,
+    ) -> Result<()> {
+        let discontinuity = match self.position {
+            Some(p) => {
+                (time.as_secs() - p.end).abs() > 0.5 / sample_rate
+                    || p.frequency != frequency
+                    || p.sample_rate != sample_rate
+            }
+            None => true,
+        };
+
+        let mut time = time.as_secs();
+        let mut samples = samples;
+        let mut mark = discontinuity;
+        while !samples.is_empty() {
+            let n = self.segment_capacity(time, sample_rate);
+            if n == 0 {
+                self.finish_segment()?;
+                continue;
+            }
+            if self.segment.is_none() {
+                self.start_segment(time)?;
+                mark = true;
+            }
+            if mark {
+                self.write_index(time, frequency, sample_rate)?;
+                mark = false;
+            }
+
+            let (chunk, rest) = samples.split_at(n.min(samples.len()));
+            self.write_samples(chunk)?;
+            samples = rest;
+            time += chunk.len() as f64 / sample_rate;
+        }
+
+        self.position = Some(Position {
+            end: time,
+            frequency,
+            sample_rate,
+        });
+        Ok(())
+    }
+
+    /// Finish the current segment and flush the index.
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.finish_segment()?;
+        self.index.flush()
+    }
+
+    /// Number of samples that fit in the current segment, or a new one if there is none.
+    fn segment_capacity(&self, time: f64, sample_rate: f64) -> usize {
+        let (samples, start) = match &self.segment {
+            Some(s) => (s.samples, s.start),
+            None => (0, time),
+        };
+        let mut n = usize::MAX;
+        if let Some(size) = self.segment_size {
+            let max = (size / self.format.sample_bytes() as u64).max(1);
+            n = n.min(max.saturating_sub(samples) as usize);
+        }
+        if let Some(d) = self.segment_duration {
+            let rest = d.as_secs_f64() - (time - start);
+            let max = (rest * sample_rate).ceil().max(0.0) as usize;
+            n = n.min(if samples == 0 { max.max(1) } else { max });
+        }
+        n
+    }
+
+    fn start_segment(&mut self, time: f64) -> Result<()> {
+        let file = format!(
+            "{}-{:05}.{}",
+            self.prefix,
+            self.segments,
+            self.format.extension()
+        );
+        #[cfg(feature = "zstd")]
+        let file = match self.compression {
+            Some(_) => format!("{file}.zst"),
+            None => file,
+        };
+        let preallocate = self
+            .segment_size
+            .filter(|_| self.preallocate)
+            .map(|s| s.div_ceil(BLOCK as u64) * BLOCK as u64);
+        let f = SegmentFile::create(&self.dir.join(&file), self.direct_io, preallocate)?;
+
+        #[cfg(feature = "zstd")]
+        let writer = match self.compression {
+            Some(level) => SegmentWriter::Zstd(zstd::Encoder::new(f, level)?),
+            None => SegmentWriter::Plain(f),
+        };
+        #[cfg(not(feature = "zstd"))]
+        let writer = SegmentWriter::Plain(f);
+
+        self.segment = Some(Segment {
+            writer,
+            file,
+            samples: 0,
+            start: time,
+        });
+        self.segments += 1;
+        Ok(())
+    }
+
+    fn finish_segment(&mut self) -> Result<()> {
+        if let Some(s) = self.segment.take() {
+            s.writer.finish()?;
+            self.index.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write_index(&mut self, time: f64, frequency: f64, sample_rate: f64) -> Result<()> {
+        let s = self.segment.as_ref().unwrap();
+        writeln!(
+            self.index,
+            "{},{},{},{},{:.9},{},{}",
+            self.segments - 1,
+            s.file,
+            self.samples,
+            s.samples,
+            time,
+            frequency,
+            sample_rate
+        )
+    }
+
+    fn write_samples(&mut self, samples: &[Complex32]) -> Result<()> {
+        self.bytes.clear();
+        self.clipped += self.format.encode(samples, &mut self.bytes);
+        let s = self.segment.as_mut().unwrap();
+        s.writer.write_all(&self.bytes)?;
+        s.samples += samples.len() as u64;
+        self.samples += samples.len() as u64;
+        Ok(())
+    }
+}
+
+impl PacketSink for Recorder {
+    fn on_packet(&mut self, meta: &PacketMeta, samples: &[Complex32]) -> ControlFlow<()> {
+        if samples.is_empty() {
+            return ControlFlow::Continue(());
+        }
+        let sample_rate = meta.sample_rate().unwrap_or(meta.step_frequency);
+        let frequency = meta.start_frequency + meta.span_frequency / 2.0;
+        match self.write(samples, meta.start_stream_time(), frequency, sample_rate) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(e) => {
+                self.error = Some(e);
+                ControlFlow::Break(())
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder")
+            .field("dir", &self.dir)
+            .field("prefix", &self.prefix)
+            .field("segments", &self.segments)
+            .field("samples", &self.samples)
+            .finish()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
@@ -0,0 +1,78 @@
+use crate::ConfigProfile;
+use crate::Device;
+use crate::DeviceStatus;
+use crate::Error;
+use crate::Result;
+
+impl Device {
+    /// Configuration, set through this handle, i.e., the profile that is reapplied by
+    /// [`recover()`](Self::recover).
+    pub fn last_config(&self) -> &ConfigProfile {
+        &self.profile
+    }
+
+    /// Recover the [`Device`] after a connection loss, e.g., a USB reset.
+    ///
+    /// The device is stopped, disconnected, and closed, ignoring errors, since the hardware might
+    /// be gone. It is then reopened with the stored serial number, the last known configuration
+    /// is reapplied, and the device is brought back into its previous state.
+    pub fn recover(&mut self) -> Result {
+        let target = self.status;
+
+        if self.status == DeviceStatus::Started {
+            let _ = self.stop();
+            self.status = DeviceStatus::Connected;
+        }
+        if self.status == DeviceStatus::Connected {
+            let _ = self.disconnect();
+            self.status = DeviceStatus::Opened;
+        }
+        if self.status == DeviceStatus::Opened {
+            let _ = self.close();
+            self.status = DeviceStatus::Uninit;
+        }
+
+        let _ = self.api.rescan_devices();
+        self.open()?;
+        let profile = self.profile.clone();
+        self.apply_config(&profile)?;
+
+        if matches!(target, DeviceStatus::Connected | DeviceStatus::Started) {
+            self.connect()?;
+        }
+        if target == DeviceStatus::Started {
+            self.start()?;
+        }
+
+        Ok(())
+    }
+
+    /// Enable or disable automatic recovery in the streaming helpers.
+    ///
+    /// If enabled, helpers that stream continuously, like [`RingCapture`](crate::ring::RingCapture)
+    /// or the FutureSDR source, call [`recover()`](Self::recover) on connection errors instead of
+    /// returning them.
+    pub fn set_auto_recover(&mut self, enabled: bool) {
+        self.auto_recover = enabled;
+    }
+
+    /// Check if automatic recovery is enabled.
+    pub fn auto_recover(&self) -> bool {
+        self.auto_recover
+    }
+
+    /// Try to recover from a streaming error, if automatic recovery is enabled.
+    ///
+    /// Returns the error, if it is not caused by a connection loss or recovery fails.
+    pub(crate) fn handle_stream_error(&mut self, e: Error) -> Result {
+        let recoverable = matches!(
+            e,
+            Error::ErrorBusy | Error::ErrorNotConnected | Error::ErrorNotOpen
+        );
+        if self.auto_recover && recoverable {
+            self.recover()
+        } else {
+            Err(e)
+        }
+    }
+}
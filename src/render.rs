@@ -0,0 +1,491 @@
+//! Color-mapped spectrogram images of spectra, e.g., to inspect a capture without plotting tools.
+//!
+//! - collect spectra in a [`Waterfall`], e.g., with [`Waterfall::push_packet()`]
+//! - render the waterfall with a [`Spectrogram`] into an RGB [`Image`] with frequency and time
+//!   axes, and save it as PNG with the `png` feature
+//! - for live views, [`Spectrogram::render_row()`] maps a single spectrum to a row of pixels
+//!
+//! The newest spectrum is at the top of the image.
+use std::collections::VecDeque;
+
+use crate::Packet;
+use crate::PayloadKind;
+use crate::SpectrumView;
+
+/// Colormap of a [`Spectrogram`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Colormap {
+    /// Perceptually uniform blue-green-yellow map of matplotlib.
+    #[default]
+    Viridis,
+    /// Perceptually uniform black-red-yellow map of matplotlib.
+    Inferno,
+    /// Black to white.
+    Grayscale,
+}
+
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 81, 139],
+    [44, 113, 142],
+    [33, 144, 141],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+
+const INFERNO: [[u8; 3]; 9] = [
+    [0, 0, 4],
+    [31, 12, 72],
+    [85, 15, 109],
+    [136, 34, 106],
+    [186, 54, 85],
+    [227, 89, 51],
+    [249, 140, 10],
+    [249, 201, 50],
+    [252, 255, 164],
+];
+
+const GRAYSCALE: [[u8; 3]; 2] = [[0, 0, 0], [255, 255, 255]];
+
+impl Colormap {
+    /// Map a value in `[0, 1]` to a color. Values outside the range are clamped.
+    pub fn color(&self, value: f32) -> [u8; 3] {
+        let lut: &[[u8; 3]] = match self {
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Inferno => &INFERNO,
+            Colormap::Grayscale => &GRAYSCALE,
+        };
+        let v = if value.is_nan() {
+            0.0
+        } else {
+            value.clamp(0.0, 1.0)
+        };
+        let x = v * (lut.len() - 1) as f32;
+        let i = (x as usize).min(lut.len() - 2);
+        let t = x - i as f32;
+        let mut c = [0; 3];
+        for (k, c) in c.iter_mut().enumerate() {
+            let a = lut[i][k] as f32;
+            let b = lut[i + 1][k] as f32;
+            *c = (a + (b - a) * t).round() as u8;
+        }
+        c
+    }
+}
+
+/// Buffer of the most recent spectra with a common frequency axis.
+///
+/// If the frequency axis or the number of bins changes, e.g., after retuning, the buffer is
+/// cleared.
+#[derive(Debug, Clone)]
+pub struct Waterfall {
+    capacity: usize,
+    start_frequency: f64,
+    step_frequency: f64,
+    rows: VecDeque<(f64, Vec<f32>)>,
+}
+
+impl Waterfall {
+    /// Create a buffer that holds up to `capacity` spectra.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            start_frequency: 0.0,
+            step_frequency: 0.0,
+            rows: VecDeque::new(),
+        }
+    }
+
+    /// Add a spectrum, starting at `time`, dropping the oldest spectrum, if the buffer is full.
+    pub fn push(&mut self, time: f64, spectrum: &SpectrumView) {
+        if spectrum.start_frequency != self.start_frequency
+            || spectrum.step_frequency != self.step_frequency
+            || self
+                .rows
+                .front()
+                .is_some_and(|r| r.1.len() != spectrum.data.len())
+        {
+            self.rows.clear();
+            self.start_frequency = spectrum.start_frequency;
+            self.step_frequency = spectrum.step_frequency;
+        }
+        if self.rows.len() == self.capacity {
+            self.rows.pop_front();
+        }
+        self.rows.push_back((time, spectrum.data.to_vec()));
+    }
+
+    /// Add all FFT rows of a spectra [`Packet`]. Packets with other payloads are ignored.
+    pub fn push_packet(&mut self, packet: &Packet) {
+        if packet.payload_kind() != PayloadKind::Spectrum {
+            return;
+        }
+        for row in packet.spectra_rows() {
+            self.push(row.start_time, &row.spectrum);
+        }
+    }
+
+    /// Maximum number of spectra.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of spectra.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Check if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Number of bins of the spectra.
+    pub fn bins(&self) -> usize {
+        self.rows.front().map_or(0, |r| r.1.len())
+    }
+
+    /// Frequency of the first bin.
+    pub fn start_frequency(&self) -> f64 {
+        self.start_frequency
+    }
+
+    /// Frequency step between bins.
+    pub fn step_frequency(&self) -> f64 {
+        self.step_frequency
+    }
+
+    /// Iterate over the start times and bins of the spectra, newest first.
+    pub fn rows(&self) -> impl Iterator<Item = (f64, &[f32])> + '_ {
+        self.rows.iter().rev().map(|(t, r)| (*t, r.as_slice()))
+    }
+
+    /// Remove all spectra.
+    pub fn clear(&mut self) {
+        self.rows.clear();
+    }
+}
+
+/// RGB image with 8 bits per channel, rendered by a [`Spectrogram`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    /// Width in pixels.
+    pub width: usize,
+    /// Height in pixels.
+    pub height: usize,
+    /// Pixels, row by row, with three bytes per pixel.
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; width * height * 3],
+        }
+    }
+
+    /// Color of the pixel at column `x` and row `y`.
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        let i = (y * self.width + x) * 3;
+        [self.data[i], self.data[i + 1], self.data[i + 2]]
+    }
+
+    fn set(&mut self, x: usize, y: usize, c: [u8; 3]) {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 3;
+            self.data[i..i + 3].copy_from_slice(&c);
+        }
+    }
+
+    /// Pixels of row `y`.
+    pub fn row(&self, y: usize) -> &[u8] {
+        &self.data[y * self.width * 3..(y + 1) * self.width * 3]
+    }
+
+    /// Encode the image as PNG.
+    #[cfg(feature = "png")]
+    pub fn write_png<W: std::io::Write>(&self, w: W) -> std::io::Result<()> {
+        let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+        writer
+            .write_image_data(&self.data)
+            .map_err(std::io::Error::other)
+    }
+
+    /// Save the image as PNG file.
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        let w = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_png(w)
+    }
+}
+
+/// Renderer of color-mapped spectrograms.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrogram {
+    /// Colormap (default: viridis).
+    pub colormap: Colormap,
+    /// Power in dBm at the lower end of the colormap, `None` for the minimum of the data
+    /// (default).
+    pub min: Option<f32>,
+    /// Power in dBm at the upper end of the colormap, `None` for the maximum of the data
+    /// (default).
+    pub max: Option<f32>,
+    /// Width of the plot area in pixels, `None` for one column per bin (default). If there are
+    /// more bins than columns, a column shows the maximum of its bins.
+    pub width: Option<usize>,
+    /// Annotate the frequency and time axes (default: true).
+    pub axes: bool,
+}
+
+impl Default for Spectrogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Space left of the plot for time labels.
+const LEFT: usize = 76;
+/// Space right of the plot, since frequency labels are centered on their ticks.
+const RIGHT: usize = 40;
+/// Space above the plot.
+const TOP: usize = 6;
+/// Space below the plot for frequency labels.
+const BOTTOM: usize = 20;
+/// Length of the ticks.
+const TICK: usize = 4;
+/// Scale of the 3x5 font.
+const SCALE: usize = 2;
+/// Width of a character, including spacing.
+const ADVANCE: usize = 4 * SCALE;
+/// Color of axes and labels.
+const FOREGROUND: [u8; 3] = [220, 220, 220];
+
+/// Glyphs of the characters in the labels, five rows of three pixels.
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        'k' => [0b100, 0b101, 0b110, 0b101, 0b101],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'G' => [0b111, 0b100, 0b101, 0b101, 0b111],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'z' => [0b000, 0b111, 0b010, 0b100, 0b111],
+        's' => [0b000, 0b011, 0b100, 0b001, 0b110],
+        _ => [0; 5],
+    }
+}
+
+fn text(img: &mut Image, x: usize, y: usize, s: &str) {
+    for (i, c) in s.chars().enumerate() {
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    for d in 0..SCALE * SCALE {
+                        img.set(
+                            x + i * ADVANCE + col * SCALE + d % SCALE,
+                            y + row * SCALE + d / SCALE,
+                            FOREGROUND,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Step of 1, 2, or 5 times a power of ten, so that `span` has about `count` ticks.
+fn tick_step(span: f64, count: usize) -> f64 {
+    let raw = span / count.max(1) as f64;
+    let mag = 10f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * mag)
+        .find(|s| *s >= raw)
+        .unwrap_or(10.0 * mag)
+}
+
+/// Number of decimals to distinguish ticks that are `step` apart.
+fn decimals(step: f64) -> usize {
+    (-step.log10().floor()).clamp(0.0, 6.0) as usize
+}
+
+fn frequency_label(f: f64, step: f64) -> String {
+    let (unit, suffix) = match f.abs().max(step) {
+        x if x >= 1e9 => (1e9, "GHz"),
+        x if x >= 1e6 => (1e6, "MHz"),
+        x if x >= 1e3 => (1e3, "kHz"),
+        _ => (1.0, "Hz"),
+    };
+    format!("{:.*}{suffix}", decimals(step / unit), f / unit)
+}
+
+impl Spectrogram {
+    /// Create a renderer with default settings.
+    pub fn new() -> Self {
+        Self {
+            colormap: Colormap::Viridis,
+            min: None,
+            max: None,
+            width: None,
+            axes: true,
+        }
+    }
+
+    /// Range of the colormap for `data`.
+    fn range<'a>(&self, data: impl Iterator<Item = &'a f32>) -> (f32, f32) {
+        let (lo, hi) = match (self.min, self.max) {
+            (Some(lo), Some(hi)) => (lo, hi),
+            _ => {
+                let (lo, hi) = data
+                    .filter(|v| v.is_finite())
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+                        (lo.min(*v), hi.max(*v))
+                    });
+                (self.min.unwrap_or(lo), self.max.unwrap_or(hi))
+            }
+        };
+        if lo.is_finite() && hi.is_finite() {
+            (lo, hi.max(lo + 1e-3))
+        } else {
+            (0.0, 1.0)
+        }
+    }
+
+    fn map_row(&self, data: &[f32], width: usize, (lo, hi): (f32, f32), out: &mut [u8]) {
+        let n = data.len();
+        if n == 0 {
+            return;
+        }
+        for x in 0..width {
+            let a = x * n / width;
+            let b = ((x + 1) * n / width).max(a + 1).min(n);
+            let v = data[a..b].iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+            let c = self.colormap.color((v - lo) / (hi - lo));
+            out[x * 3..x * 3 + 3].copy_from_slice(&c);
+        }
+    }
+
+    /// Map a spectrum to a row of RGB pixels without axes, e.g., to scroll a live view.
+    ///
+    /// The row is [`width`](Self::width) pixels wide. For a consistent color scale between
+    /// rows, set [`min`](Self::min) and [`max`](Self::max); otherwise, the range of the row is
+    /// used.
+    pub fn render_row(&self, data: &[f32]) -> Vec<u8> {
+        let width = self.width.unwrap_or(data.len());
+        let mut row = vec![0; width * 3];
+        if !data.is_empty() {
+            self.map_row(data, width, self.range(data.iter()), &mut row);
+        }
+        row
+    }
+
+    /// Render the spectra of a [`Waterfall`], newest at the top, one row of pixels per spectrum.
+    pub fn render(&self, waterfall: &Waterfall) -> Image {
+        let bins = waterfall.bins();
+        let width = self.width.unwrap_or(bins).max(1);
+        let height = waterfall.len().max(1);
+        let (x0, y0) = if self.axes { (LEFT, TOP) } else { (0, 0) };
+        let mut img = if self.axes {
+            Image::new(LEFT + width + RIGHT, TOP + height + BOTTOM)
+        } else {
+            Image::new(width, height)
+        };
+
+        let range = self.range(waterfall.rows().flat_map(|(_, r)| r.iter()));
+        let mut row = vec![0; width * 3];
+        for (y, (_, data)) in waterfall.rows().enumerate() {
+            self.map_row(data, width, range, &mut row);
+            let i = ((y0 + y) * img.width + x0) * 3;
+            img.data[i..i + width * 3].copy_from_slice(&row);
+        }
+
+        if self.axes {
+            self.draw_axes(&mut img, waterfall, width, height);
+        }
+        img
+    }
+
+    fn draw_axes(&self, img: &mut Image, waterfall: &Waterfall, width: usize, height: usize) {
+        let (x0, y0) = (LEFT, TOP);
+        let bottom = y0 + height;
+        for x in x0 - 1..x0 + width {
+            img.set(x, bottom, FOREGROUND);
+        }
+        for y in y0..=bottom {
+            img.set(x0 - 1, y, FOREGROUND);
+        }
+
+        // frequency axis, bins are centered on their columns
+        let bins = waterfall.bins();
+        if bins > 0 {
+            let step = waterfall.step_frequency();
+            let first = waterfall.start_frequency() - step / 2.0;
+            let span = bins as f64 * step;
+            if span > 0.0 {
+                let tick = tick_step(span, width / 100);
+                let ticks = ((first + span) / tick).floor() as i64 - (first / tick).ceil() as i64;
+                for i in 0..=ticks {
+                    let f = ((first / tick).ceil() + i as f64) * tick;
+                    let x = x0 + ((f - first) / span * width as f64) as usize;
+                    for y in bottom..bottom + TICK {
+                        img.set(x, y, FOREGROUND);
+                    }
+                    let label = frequency_label(f, tick);
+                    let w = label.len() * ADVANCE;
+                    text(img, x.saturating_sub(w / 2), bottom + TICK + 2, &label);
+                }
+            }
+        }
+
+        // time axis, relative to the newest spectrum
+        let times: Vec<f64> = waterfall.rows().map(|(t, _)| t).collect();
+        if let (Some(newest), Some(oldest)) = (times.first(), times.last()) {
+            let span = newest - oldest;
+            if span > 0.0 && height > 1 {
+                let tick = tick_step(span, height / 40);
+                for i in 0..=(span / tick).floor() as usize {
+                    let dt = i as f64 * tick;
+                    // row, whose start time is closest to the tick
+                    let y = times
+                        .iter()
+                        .enumerate()
+                        .min_by(|a, b| {
+                            let da = (newest - a.1 - dt).abs();
+                            let db = (newest - b.1 - dt).abs();
+                            da.total_cmp(&db)
+                        })
+                        .map_or(0, |(i, _)| i);
+                    for x in x0 - 1 - TICK..x0 - 1 {
+                        img.set(x, y0 + y, FOREGROUND);
+                    }
+                    let label = match i {
+                        0 => "0s".to_string(),
+                        _ => format!("-{:.*}s", decimals(tick), dt),
+                    };
+                    let w = label.len() * ADVANCE;
+                    let ty = (y0 + y).saturating_sub(5 * SCALE / 2);
+                    text(img, (x0 - 2 - TICK).saturating_sub(w), ty, &label);
+                }
+            }
+        }
+    }
+}
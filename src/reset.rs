@@ -0,0 +1,127 @@
+use std::time::Duration;
+use std::time::Instant;
+use widestring::WideCString;
+
+use crate::Config;
+use crate::ConfigProfile;
+use crate::Device;
+use crate::DeviceStatus;
+use crate::Error;
+use crate::Result;
+
+/// Button that resets the hardware of the device.
+const RESET_PATH: &str = "device/reset";
+/// Button that reboots the firmware of the device.
+const REBOOT_PATH: &str = "device/reboot";
+/// Maximum time for the device to show up again after a reset or reboot.
+const REENUMERATION_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum time for the device to leave the bus after a reset or reboot. Devices that are still
+/// listed afterwards are assumed to have re-enumerated between two rescans.
+const LEAVE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Interval between rescans, while waiting for the device.
+const RESCAN_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Device {
+    /// Reset the hardware of the [`Device`].
+    ///
+    /// The device has to be connected or started; a running stream is stopped. The reset drops
+    /// the connection and restores the default configuration. The handle is closed, the device is
+    /// re-enumerated, and the handle is reopened, i.e., it is [`Opened`](DeviceStatus::Opened)
+    /// afterwards and has to be configured and connected again. The stored configuration of
+    /// [`last_config()`](Self::last_config) is cleared.
+    ///
+    /// Returns [`Error::Retry`], if the device did not show up again within 10 seconds.
+    pub fn reset(&mut self) -> Result {
+        self.press_and_reenumerate(RESET_PATH)?;
+        self.profile = ConfigProfile::new();
+        Ok(())
+    }
+
+    /// Reboot the firmware of the [`Device`].
+    ///
+    /// The device has to be connected or started. It leaves the bus during the reboot. The handle
+    /// is closed, the device is re-enumerated, and the handle is reopened. Like with
+    /// [`recover()`](Self::recover), the last known configuration is reapplied and the device is
+    /// brought back into its previous state, i.e., a started device is started again.
+    ///
+    /// Returns [`Error::Retry`], if the device did not show up again within 10 seconds.
+    pub fn reboot(&mut self) -> Result {
+        let target = self.status;
+        self.press_and_reenumerate(REBOOT_PATH)?;
+
+        let profile = self.profile.clone();
+        self.apply_config(&profile)?;
+        self.connect()?;
+        if target == DeviceStatus::Started {
+            self.start()?;
+        }
+        Ok(())
+    }
+
+    /// Press a reset button and reopen the device, once it is enumerated again.
+    fn press_and_reenumerate(&mut self, path: &str) -> Result {
+        if self.status == DeviceStatus::Started {
+            self.stop()?;
+        }
+        self.expect_status(DeviceStatus::Connected)?;
+        self.press(path)?;
+        event!(tracing::Level::INFO, serial = %self.serial.display(), path, "press");
+
+        // the connection is gone, so errors are expected
+        let _ = self.disconnect();
+        self.status = DeviceStatus::Opened;
+        let _ = self.close();
+        self.status = DeviceStatus::Uninit;
+
+        // the device may still be listed from before the press, so wait until it left the bus,
+        // before waiting for it to come back
+        let serial = self.serial.to_string_lossy();
+        let start = Instant::now();
+        let mut left = false;
+        loop {
+            let remaining = REENUMERATION_TIMEOUT.saturating_sub(start.elapsed());
+            match self.api.rescan_devices_timeout(remaining) {
+                Ok(()) => {
+                    let devices = self.api.devices()?;
+                    if !devices.iter().any(|d| d.serial() == serial) {
+                        left = true;
+                    } else if left || start.elapsed() >= LEAVE_TIMEOUT {
+                        break;
+                    }
+                }
+                Err(Error::Retry) => {}
+                Err(e) => return Err(e),
+            }
+            if start.elapsed() >= REENUMERATION_TIMEOUT {
+                return Err(Error::Retry);
+            }
+            std::thread::sleep(RESCAN_INTERVAL);
+        }
+
+        self.open()
+    }
+
+    /// Press a button, without recording it in the configuration profile.
+    fn press(&mut self, path: &str) -> Result {
+        let path = WideCString::from_str_truncate(path);
+        let mut root = Config::new();
+        let mut node = Config::new();
+
+        unsafe { ffi!(AARTSAAPI_ConfigRoot(&mut self.inner, &mut root.inner))? };
+        unsafe {
+            ffi!(AARTSAAPI_ConfigFind(
+                &mut self.inner,
+                &mut root.inner,
+                &mut node.inner,
+                path.as_ptr(),
+            ); path = path.to_string_lossy())?
+        };
+        unsafe {
+            ffi!(AARTSAAPI_ConfigSetInteger(
+                &mut self.inner,
+                &mut node.inner,
+                1,
+            ))
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::ConfigItem;
+use crate::ConfigValue;
+use crate::Device;
+use crate::Error;
+use crate::Result;
+
+const CHANNEL_PATH: &str = "device/receiverchannel";
+const ANTENNA_PATH: &str = "device/antenna";
+
+/// Receiver channel, selected with `device/receiverchannel`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RxChannel {
+    /// First receiver.
+    Rx1,
+    /// Second receiver.
+    Rx2,
+    /// Both receivers, IQ samples on data channels 0 and 1.
+    Both,
+    /// Receivers off.
+    Off,
+}
+
+impl RxChannel {
+    /// Value of the `device/receiverchannel` configuration parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RxChannel::Rx1 => "Rx1",
+            RxChannel::Rx2 => "Rx2",
+            RxChannel::Both => "Rx12",
+            RxChannel::Off => "Rx Off",
+        }
+    }
+
+    fn from_option(s: &str) -> Option<Self> {
+        [
+            RxChannel::Rx1,
+            RxChannel::Rx2,
+            RxChannel::Both,
+            RxChannel::Off,
+        ]
+        .into_iter()
+        .find(|c| c.as_str().eq_ignore_ascii_case(s))
+    }
+}
+
+/// Switchable component of the RF path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RfSwitch {
+    /// Amplifier, `device/amplifier`.
+    Amplifier,
+    /// Low-noise preamplifier, `device/preamp`.
+    Preamp,
+    /// Attenuator, `device/attenuator`.
+    Attenuator,
+}
+
+impl RfSwitch {
+    /// Configuration path of the switch.
+    pub fn path(&self) -> &'static str {
+        match self {
+            RfSwitch::Amplifier => "device/amplifier",
+            RfSwitch::Preamp => "device/preamp",
+            RfSwitch::Attenuator => "device/attenuator",
+        }
+    }
+}
+
+/// RF path options, supported by the connected hardware, returned by
+/// [`Device::rf_capabilities()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RfCapabilities {
+    /// Selectable receiver channels.
+    pub rx_channels: Vec<RxChannel>,
+    /// Selectable antenna inputs.
+    pub antennas: Vec<String>,
+    /// Available switches.
+    pub switches: Vec<RfSwitch>,
+}
+
+impl Device {
+    /// Select the [`RxChannel`].
+    ///
+    /// Returns [`Error::OptionDisabled`], if the channel is not available on the device.
+    pub fn set_rx_channel(&mut self, channel: RxChannel) -> Result {
+        let value = ConfigValue::String(channel.as_str().to_string());
+        self.check_capabilities(CHANNEL_PATH, &value)?;
+        self.check_enabled(CHANNEL_PATH, &value)?;
+        self.set(CHANNEL_PATH, channel.as_str())
+    }
+
+    /// Get the selected [`RxChannel`].
+    pub fn rx_channel(&mut self) -> std::result::Result<RxChannel, Error> {
+        self.enum_option(CHANNEL_PATH)
+            .and_then(|s| RxChannel::from_option(&s).ok_or(Error::ErrorValueInvalid))
+    }
+
+    /// Select the antenna input by name (case-insensitive), see [`RfCapabilities::antennas`].
+    ///
+    /// Returns [`Error::OptionDisabled`], if the input is not available on the device.
+    pub fn set_rx_antenna(&mut self, antenna: &str) -> Result {
+        let entry = self.config_entry(ANTENNA_PATH)?;
+        let option = entry
+            .options
+            .iter()
+            .find(|o| o.eq_ignore_ascii_case(antenna))
+            .ok_or(Error::ErrorValueInvalid)?
+            .clone();
+        self.check_enabled(ANTENNA_PATH, &ConfigValue::String(option.clone()))?;
+        self.set(ANTENNA_PATH, option)
+    }
+
+    /// Get the selected antenna input.
+    pub fn rx_antenna(&mut self) -> std::result::Result<String, Error> {
+        self.enum_option(ANTENNA_PATH)
+    }
+
+    /// Turn an [`RfSwitch`] on or off.
+    pub fn set_rf_switch(&mut self, switch: RfSwitch, on: bool) -> Result {
+        self.set_value(switch.path(), &ConfigValue::Bool(on))
+    }
+
+    /// Get the state of an [`RfSwitch`].
+    pub fn rf_switch(&mut self, switch: RfSwitch) -> std::result::Result<bool, Error> {
+        match self.get(switch.path())? {
+            ConfigItem::Bool(b) => Ok(b),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+
+    /// Query the RF path options of the connected hardware variant.
+    ///
+    /// Options that are not in the configuration tree are not supported.
+    pub fn rf_capabilities(&mut self) -> std::result::Result<RfCapabilities, Error> {
+        let mut caps = RfCapabilities::default();
+
+        if let Ok(e) = self.config_entry(CHANNEL_PATH) {
+            caps.rx_channels = e
+                .options
+                .iter()
+                .filter_map(|o| RxChannel::from_option(o))
+                .collect();
+        }
+        if let Ok(e) = self.config_entry(ANTENNA_PATH) {
+            caps.antennas = e.options;
+        }
+        for s in [RfSwitch::Amplifier, RfSwitch::Preamp, RfSwitch::Attenuator] {
+            if self.config_entry(s.path()).is_ok() {
+                caps.switches.push(s);
+            }
+        }
+
+        Ok(caps)
+    }
+
+    /// Get the selected option of an enum parameter.
+    pub(crate) fn enum_option(&mut self, path: &str) -> std::result::Result<String, Error> {
+        match self.get(path)? {
+            ConfigItem::Enum { value, options, .. } => options
+                .get(value as usize)
+                .cloned()
+                .ok_or(Error::ErrorValueInvalid),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+}
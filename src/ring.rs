@@ -0,0 +1,177 @@
+//! Continuous capture into a ring buffer with snapshots of the recent history.
+use num_complex::Complex32;
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::Device;
+use crate::Error;
+
+fn pack(s: Complex32) -> u64 {
+    s.re.to_bits() as u64 | (s.im.to_bits() as u64) << 32
+}
+
+fn unpack(v: u64) -> Complex32 {
+    Complex32::new(f32::from_bits(v as u32), f32::from_bits((v >> 32) as u32))
+}
+
+struct Shared {
+    buf: Box<[AtomicU64]>,
+    written: AtomicU64,
+    sample_rate: AtomicU64,
+    start_time: AtomicU64,
+    stop: AtomicBool,
+    running: AtomicBool,
+}
+
+/// Samples, returned by [`RingCapture::snapshot()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// Device clock time of the first sample.
+    pub start_time: f64,
+    /// Sample rate in Hz.
+    pub sample_rate: f64,
+    /// IQ samples.
+    pub samples: Vec<Complex32>,
+}
+
+/// Continuously capture IQ samples into a ring buffer.
+///
+/// A background thread consumes all packets of a data channel and writes the samples into a
+/// lock-free ring buffer. [`snapshot()`](Self::snapshot) can be called at any time to get the
+/// samples around the current point in time, without stalling the device queue.
+///
+/// Sample times assume a continuous stream, i.e., they are derived from the time of the first
+/// sample and the sample rate. With [`Device::set_auto_recover()`], the capture continues after
+/// a connection loss, but the stream has a gap.
+pub struct RingCapture {
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<std::result::Result<Device, Error>>>,
+}
+
+impl RingCapture {
+    /// Start capturing from data channel `chan` into a ring buffer of `capacity` samples.
+    ///
+    /// The [`Device`] has to be started and configured to output IQ samples. It is returned by
+    /// [`stop()`](Self::stop).
+    pub fn start(mut dev: Device, chan: i32, capacity: usize) -> Self {
+        assert!(capacity > 0);
+        let shared = Arc::new(Shared {
+            buf: (0..capacity).map(|_| AtomicU64::new(0)).collect(),
+            written: AtomicU64::new(0),
+            sample_rate: AtomicU64::new(0f64.to_bits()),
+            start_time: AtomicU64::new(0f64.to_bits()),
+            stop: AtomicBool::new(false),
+            running: AtomicBool::new(true),
+        });
+
+        let s = shared.clone();
+        let handle = std::thread::spawn(move || {
+            let r = Self::run(&mut dev, chan, &s);
+            s.running.store(false, Ordering::Release);
+            r.map(|_| dev)
+        });
+
+        Self {
+            shared,
+            handle: Some(handle),
+        }
+    }
+
+    fn run(dev: &mut Device, chan: i32, s: &Shared) -> crate::Result {
+        let cap = s.buf.len() as u64;
+        let mut written = 0u64;
+
+        dev.drive(chan, &s.stop, |p| {
+            let samples = p.samples();
+            if let (0, Some(rate)) = (written, p.sample_rate()) {
+                s.start_time
+                    .store(p.start_time().to_bits(), Ordering::Relaxed);
+                s.sample_rate.store(rate.to_bits(), Ordering::Relaxed);
+            }
+            for x in samples {
+                s.buf[(written % cap) as usize].store(pack(*x), Ordering::Relaxed);
+                written += 1;
+            }
+            s.written.store(written, Ordering::Release);
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Get the samples from `before` the current point in time until `after` it.
+    ///
+    /// The call blocks until the samples after the current point in time are captured.
+    pub fn snapshot(
+        &self,
+        before: Duration,
+        after: Duration,
+    ) -> std::result::Result<Snapshot, Error> {
+        let s = &self.shared;
+        let cap = s.buf.len() as u64;
+
+        let now = s.written.load(Ordering::Acquire);
+        if now == 0 {
+            return Err(Error::Empty);
+        }
+        let sample_rate = f64::from_bits(s.sample_rate.load(Ordering::Relaxed));
+        let start_time = f64::from_bits(s.start_time.load(Ordering::Relaxed));
+
+        let n_before = (before.as_secs_f64() * sample_rate).round() as u64;
+        let n_after = (after.as_secs_f64() * sample_rate).round() as u64;
+        if n_before + n_after > cap {
+            return Err(Error::ErrorBufferSize);
+        }
+
+        let start = now.saturating_sub(n_before);
+        let end = now + n_after;
+        while s.written.load(Ordering::Acquire) < end {
+            if !s.running.load(Ordering::Acquire) {
+                return Err(Error::ErrorNotConnected);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let samples = (start..end)
+            .map(|i| unpack(s.buf[(i % cap) as usize].load(Ordering::Relaxed)))
+            .collect();
+
+        // the writer might have overtaken us while copying
+        if s.written.load(Ordering::Acquire) > start + cap {
+            return Err(Error::ErrorBufferSize);
+        }
+
+        Ok(Snapshot {
+            start_time: start_time + start as f64 / sample_rate,
+            sample_rate,
+            samples,
+        })
+    }
+
+    /// Total number of captured samples.
+    pub fn captured(&self) -> u64 {
+        self.shared.written.load(Ordering::Acquire)
+    }
+
+    /// Stop capturing and get the [`Device`] back.
+    pub fn stop(mut self) -> std::result::Result<Device, Error> {
+        self.shared.stop.store(true, Ordering::Release);
+        self.handle
+            .take()
+            .unwrap()
+            .join()
+            .map_err(|_| Error::Error)?
+    }
+}
+
+impl Drop for RingCapture {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Release);
+        if let Some(h) = self.handle.take() {
+            let _ = h.join();
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Location of the RTSA library at runtime.
+//!
+//! Applications often build, since the SDK is found through `RTSA_DIR`, but fail at runtime,
+//! since the library is not in the search path of the dynamic loader. [`locate()`] searches the
+//! standard install locations and checks the version of the library. With the `dlopen` feature,
+//! it loads the library from the discovered path. The SDK header is still needed to build with
+//! `dlopen`, since the bindings are generated at build time.
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::Error;
+
+/// File name of the RTSA library.
+#[cfg(not(windows))]
+pub const LIBRARY_NAME: &str = "libAaroniaRTSAAPI.so";
+/// File name of the RTSA library.
+#[cfg(windows)]
+pub const LIBRARY_NAME: &str = "AaroniaRTSAAPI.dll";
+
+/// Major version of the RTSA API, the bindings are made for.
+pub const ABI_MAJOR: u32 = 1;
+
+/// RTSA library, found by [`locate()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Library {
+    /// Path of the library.
+    ///
+    /// Without the `dlopen` feature, this is the first candidate on disk. The dynamic loader
+    /// might have linked another copy, e.g., from its cache, which is the one that
+    /// [`version`](Self::version) is read from.
+    pub path: PathBuf,
+    /// Version of the library (`<major>.<minor>`).
+    pub version: String,
+}
+
+/// Directories that are searched for the library, in order.
+///
+/// These are the directories in `RTSA_DIR`, the default install location of RTSA-Suite PRO, and
+/// the search path of the dynamic loader, i.e., `LD_LIBRARY_PATH` on Linux and `PATH` on
+/// Windows.
+pub fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(d) = std::env::var_os("RTSA_DIR") {
+        dirs.extend(std::env::split_paths(&d));
+    }
+
+    #[cfg(not(windows))]
+    {
+        if let Some(home) = std::env::var_os("HOME") {
+            dirs.push(Path::new(&home).join("Aaronia/RTSA/Aaronia-RTSA-Suite-PRO"));
+        }
+        if let Some(d) = std::env::var_os("LD_LIBRARY_PATH") {
+            dirs.extend(std::env::split_paths(&d));
+        }
+        dirs.push("/usr/local/lib".into());
+        dirs.push("/usr/lib".into());
+    }
+    #[cfg(windows)]
+    {
+        let programs =
+            std::env::var_os("ProgramFiles").unwrap_or_else(|| r"C:\Program Files".into());
+        dirs.push(Path::new(&programs).join(r"Aaronia AG\Aaronia RTSA-Suite PRO"));
+        if let Some(d) = std::env::var_os("PATH") {
+            dirs.extend(std::env::split_paths(&d));
+        }
+    }
+
+    dirs.retain(|d| !d.as_os_str().is_empty());
+    let mut seen = std::collections::HashSet::new();
+    dirs.retain(|d| seen.insert(d.clone()));
+    dirs
+}
+
+/// Paths of all copies of the library in the [search directories](search_dirs), in order.
+pub fn candidates() -> Vec<PathBuf> {
+    search_dirs()
+        .into_iter()
+        .map(|d| d.join(LIBRARY_NAME))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// Find the RTSA library and check its version.
+///
+/// Returns [`Error::LibraryNotLocated`] with the searched directories, if there is no library,
+/// and [`Error::LibraryVersion`], if its major version is not [`ABI_MAJOR`]. With the `dlopen`
+/// feature, the first candidate that loads is used for all further calls, unless a library is
+/// loaded already; otherwise, the version is the one of the linked library, see [`version()`],
+/// and the path is only the first candidate, not necessarily the linked copy.
+///
+/// [`version()`]: crate::version()
+pub fn locate() -> std::result::Result<Library, Error> {
+    let found = candidates();
+
+    #[cfg(all(not(feature = "sys-stub"), feature = "dlopen"))]
+    let path = match crate::sys::loaded_path() {
+        Some(p) => p.to_path_buf(),
+        None => load(&found)?,
+    };
+    #[cfg(not(all(not(feature = "sys-stub"), feature = "dlopen")))]
+    let path = match found.into_iter().next() {
+        Some(p) => p,
+        None => {
+            return Err(Error::LibraryNotLocated {
+                searched: search_dirs(),
+            })
+        }
+    };
+
+    check(path)
+}
+
+/// Load the first candidate that loads.
+#[cfg(all(not(feature = "sys-stub"), feature = "dlopen"))]
+fn load(found: &[PathBuf]) -> std::result::Result<PathBuf, Error> {
+    let mut error = None;
+    for p in found {
+        match crate::sys::load_from(p) {
+            Ok(l) => return Ok(l.to_path_buf()),
+            Err(e) => {
+                error.get_or_insert(Error::LibraryLoad {
+                    path: p.clone(),
+                    reason: e.to_string(),
+                });
+            }
+        }
+    }
+    Err(error.unwrap_or_else(|| Error::LibraryNotLocated {
+        searched: search_dirs(),
+    }))
+}
+
+/// Check the version of the library in use.
+fn check(path: PathBuf) -> std::result::Result<Library, Error> {
+    let n = unsafe { crate::sys::AARTSAAPI_Version() };
+    let version = crate::version();
+    if n >> 16 != ABI_MAJOR {
+        return Err(Error::LibraryVersion {
+            path,
+            version,
+            expected: ABI_MAJOR,
+        });
+    }
+    Ok(Library { path, version })
+}
@@ -0,0 +1,113 @@
+use num_complex::Complex32;
+
+use crate::Error;
+
+/// Format of IQ samples, written by the streaming helpers, e.g., the
+/// [`Recorder`](crate::record::Recorder).
+///
+/// The RTSA API delivers 32-bit float samples only, i.e., integer formats are converted in
+/// software. Full scale, i.e., a magnitude of one, maps to the maximum integer value; larger
+/// values saturate. Samples are interleaved and little-endian.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SampleFormat {
+    /// 32-bit float (`cf32`).
+    #[default]
+    F32,
+    /// 16-bit integer (`cs16`).
+    I16,
+    /// 8-bit integer (`cs8`).
+    I8,
+}
+
+impl SampleFormat {
+    /// Bytes per complex sample.
+    pub fn sample_bytes(&self) -> usize {
+        match self {
+            SampleFormat::F32 => 8,
+            SampleFormat::I16 => 4,
+            SampleFormat::I8 => 2,
+        }
+    }
+
+    /// Common file extension, e.g., `cs16`.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SampleFormat::F32 => "cf32",
+            SampleFormat::I16 => "cs16",
+            SampleFormat::I8 => "cs8",
+        }
+    }
+
+    /// Append the encoded samples to `out` and return the number of values that saturated.
+    pub fn encode(&self, samples: &[Complex32], out: &mut Vec<u8>) -> u64 {
+        out.reserve(samples.len() * self.sample_bytes());
+        let values = samples.iter().flat_map(|s| [s.re, s.im]);
+        let mut clipped = 0;
+        match self {
+            SampleFormat::F32 => values.for_each(|v| out.extend_from_slice(&v.to_le_bytes())),
+            SampleFormat::I16 => {
+                for v in values {
+                    let (x, c) = saturate(v, i16::MAX as f32);
+                    clipped += c as u64;
+                    out.extend_from_slice(&(x as i16).to_le_bytes());
+                }
+            }
+            SampleFormat::I8 => {
+                for v in values {
+                    let (x, c) = saturate(v, i8::MAX as f32);
+                    clipped += c as u64;
+                    out.push(x as i8 as u8);
+                }
+            }
+        }
+        clipped
+    }
+
+    /// Decode samples, ignoring a trailing incomplete sample.
+    pub fn decode(&self, bytes: &[u8]) -> Vec<Complex32> {
+        let n = self.sample_bytes() / 2;
+        let values: Vec<f32> = bytes
+            .chunks_exact(n)
+            .map(|b| match self {
+                SampleFormat::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                SampleFormat::I16 => i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32,
+                SampleFormat::I8 => b[0] as i8 as f32 / i8::MAX as f32,
+            })
+            .collect();
+        values
+            .chunks_exact(2)
+            .map(|v| Complex32::new(v[0], v[1]))
+            .collect()
+    }
+}
+
+/// Scale and round a value, returning the value, saturated symmetrically at `max`, and whether
+/// it saturated.
+fn saturate(v: f32, max: f32) -> (f32, bool) {
+    let x = (v * max).round();
+    (x.clamp(-max, max), x.abs() > max)
+}
+
+impl std::fmt::Display for SampleFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SampleFormat::F32 => "f32",
+            SampleFormat::I16 => "i16",
+            SampleFormat::I8 => "i8",
+        })
+    }
+}
+
+impl std::str::FromStr for SampleFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Error> {
+        match s.to_lowercase().as_str() {
+            "f32" | "cf32" => Ok(SampleFormat::F32),
+            "i16" | "cs16" => Ok(SampleFormat::I16),
+            "i8" | "cs8" => Ok(SampleFormat::I8),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use crate::poll::Poller;
+use crate::poll::Sleep;
+use crate::Device;
+use crate::Error;
+use crate::Packet;
+use crate::PollStrategy;
+
+impl Device {
+    /// Wait for the first of the data channels `chans` with a [`Packet`] and return the channel
+    /// with its packet.
+    ///
+    /// Channels are checked in turn, starting after the channel of the last selected packet, so
+    /// that a busy channel does not starve the others. Blocks like [`packet()`](Self::packet),
+    /// polling with the [`PollStrategy`] of the device, if all queues are empty.
+    pub fn select(&mut self, chans: &[i32]) -> std::result::Result<(i32, Packet), Error> {
+        self.select_with(chans, self.poll)
+    }
+
+    /// Wait for the first of the data channels `chans` with a [`Packet`], polling with the given
+    /// [`PollStrategy`].
+    pub fn select_with(
+        &mut self,
+        chans: &[i32],
+        strategy: PollStrategy,
+    ) -> std::result::Result<(i32, Packet), Error> {
+        let mut poller = Poller::new(strategy);
+        loop {
+            match self.try_select(chans) {
+                Err(Error::Empty) => poller.wait(),
+                r => return r,
+            }
+        }
+    }
+
+    /// Get a [`Packet`] of the first of the data channels `chans` with data.
+    ///
+    /// This call is non-blocking and returns [`Error::Empty`], if all queues are empty.
+    pub fn try_select(&mut self, chans: &[i32]) -> std::result::Result<(i32, Packet), Error> {
+        if chans.is_empty() {
+            return Err(Error::ErrorInvalidChannel);
+        }
+        for i in 0..chans.len() {
+            let n = (self.select_next + i) % chans.len();
+            match self.try_packet(chans[n]) {
+                Ok(p) => {
+                    self.select_next = n + 1;
+                    return Ok((chans[n], p));
+                }
+                Err(Error::Empty) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Error::Empty)
+    }
+
+    /// Async variant of [`select()`](Self::select).
+    ///
+    /// The future works with any async runtime. While all queues are empty, it sleeps according
+    /// to the [`PollStrategy`] of the device on a shared timer thread.
+    pub fn select_async<'a>(&'a mut self, chans: &'a [i32]) -> Select<'a> {
+        let poller = Poller::new(self.poll);
+        Select {
+            dev: self,
+            chans,
+            poller,
+            sleep: None,
+        }
+    }
+}
+
+/// Future, returned by [`Device::select_async()`].
+pub struct Select<'a> {
+    dev: &'a mut Device,
+    chans: &'a [i32],
+    poller: Poller,
+    sleep: Option<Sleep>,
+}
+
+impl Future for Select<'_> {
+    type Output = std::result::Result<(i32, Packet), Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            if let Some(sleep) = this.sleep.as_mut() {
+                match Pin::new(sleep).poll(cx) {
+                    Poll::Ready(()) => this.sleep = None,
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+            match this.dev.try_select(this.chans) {
+                Err(Error::Empty) => {
+                    let d = this.poller.next_interval();
+                    if d.is_zero() {
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    this.sleep = Some(Sleep::new(d));
+                }
+                r => return Poll::Ready(r),
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Select<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Select")
+            .field("chans", &self.chans)
+            .finish()
+    }
+}
@@ -0,0 +1,224 @@
+//! Stream IQ samples or spectra to TCP clients, e.g., GNU Radio or Python scripts.
+//!
+//! Every packet is sent as a frame with a 40 byte header, followed by the payload. All values are
+//! little-endian.
+//!
+//! | Offset | Type     | Field                                                         |
+//! |--------|----------|---------------------------------------------------------------|
+//! | 0      | `[u8;4]` | Magic `RTSA`                                                  |
+//! | 4      | `u16`    | Version (1)                                                   |
+//! | 6      | `u16`    | Kind, see [`FrameKind`]                                       |
+//! | 8      | `u32`    | Number of items, i.e., complex samples or bins                |
+//! | 12     | `u32`    | Reserved                                                      |
+//! | 16     | `f64`    | Center frequency (IQ) or frequency of the first bin (spectrum) |
+//! | 24     | `f64`    | Sample rate (IQ) or bin spacing (spectrum) in Hz              |
+//! | 32     | `f64`    | Stream time of the first item in seconds                      |
+//!
+//! IQ payloads are interleaved samples in the [`SampleFormat`] of the server, spectra are `f32` in
+//! dBm.
+use num_complex::Complex32;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::Packet;
+use crate::PacketData;
+use crate::PacketMeta;
+use crate::PacketSink;
+use crate::SampleFormat;
+
+/// Magic bytes at the start of every frame.
+pub const MAGIC: [u8; 4] = *b"RTSA";
+/// Version of the frame format.
+pub const VERSION: u16 = 1;
+/// Size of the frame header in bytes.
+pub const HEADER_SIZE: usize = 40;
+
+/// Kind of the frame payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum FrameKind {
+    /// Interleaved IQ samples, `f32`.
+    Iq = 0,
+    /// Spectrum bins in dBm.
+    Spectrum = 1,
+    /// Interleaved IQ samples, `i16` with full scale at `i16::MAX`.
+    IqI16 = 2,
+    /// Interleaved IQ samples, `i8` with full scale at `i8::MAX`.
+    IqI8 = 3,
+}
+
+/// Serve packets to all connected TCP clients.
+///
+/// Clients that do not keep up, i.e., block a write longer than the write timeout, or that
+/// close the connection, are dropped.
+pub struct Server {
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    addr: std::net::SocketAddr,
+    buf: Vec<u8>,
+    format: SampleFormat,
+}
+
+impl Server {
+    /// Listen for clients on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> std::io::Result<Self> {
+        Self::bind_with_timeout(addr, Duration::from_millis(100))
+    }
+
+    /// Listen for clients on `addr`, dropping clients that block a write longer than `timeout`.
+    pub fn bind_with_timeout<A: ToSocketAddrs>(
+        addr: A,
+        timeout: Duration,
+    ) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let c = clients.clone();
+        let s = stop.clone();
+        let handle = std::thread::spawn(move || {
+            while !s.load(Ordering::Acquire) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let ok = stream.set_nonblocking(false).is_ok()
+                            && stream.set_nodelay(true).is_ok()
+                            && stream.set_write_timeout(Some(timeout)).is_ok();
+                        if ok {
+                            c.lock().unwrap().push(stream);
+                        }
+                    }
+                    Err(_) => std::thread::sleep(Duration::from_millis(20)),
+                }
+            }
+        });
+
+        Ok(Self {
+            clients,
+            stop,
+            handle: Some(handle),
+            addr,
+            buf: Vec::new(),
+            format: SampleFormat::F32,
+        })
+    }
+
+    /// Local address of the listening socket.
+    pub fn local_addr(&self) -> std::net::SocketAddr {
+        self.addr
+    }
+
+    /// Set the [`SampleFormat`] of IQ frames (default: [`SampleFormat::F32`]).
+    ///
+    /// Integer formats halve or quarter the bandwidth to the clients.
+    pub fn set_sample_format(&mut self, format: SampleFormat) {
+        self.format = format;
+    }
+
+    /// Get the [`SampleFormat`] of IQ frames.
+    pub fn sample_format(&self) -> SampleFormat {
+        self.format
+    }
+
+    /// Number of connected clients.
+    pub fn clients(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /// Send the IQ samples or spectrum of a packet to all clients.
+    ///
+    /// Raw ADC packets are ignored.
+    pub fn send_packet(&mut self, packet: &Packet) {
+        let time = packet.start_time();
+        match packet.data() {
+            PacketData::Iq(s) => {
+                let center = packet.start_frequency() + packet.span_frequency() / 2.0;
+                let rate = packet.sample_rate().unwrap_or(packet.span_frequency());
+                self.send_samples(s, center, rate, time);
+            }
+            PacketData::Spectrum(s) => {
+                self.send_spectrum(s.data, s.start_frequency, s.step_frequency, time);
+            }
+            PacketData::Raw(_) => {}
+        }
+    }
+
+    /// Send IQ samples to all clients.
+    pub fn send_samples(&mut self, samples: &[Complex32], frequency: f64, rate: f64, time: f64) {
+        let kind = match self.format {
+            SampleFormat::F32 => FrameKind::Iq,
+            SampleFormat::I16 => FrameKind::IqI16,
+            SampleFormat::I8 => FrameKind::IqI8,
+        };
+        self.header(kind, samples.len(), frequency, rate, time);
+        self.format.encode(samples, &mut self.buf);
+        self.broadcast();
+    }
+
+    /// Send spectrum bins in dBm to all clients.
+    pub fn send_spectrum(&mut self, bins: &[f32], start: f64, step: f64, time: f64) {
+        self.header(FrameKind::Spectrum, bins.len(), start, step, time);
+        for b in bins {
+            self.buf.extend_from_slice(&b.to_le_bytes());
+        }
+        self.broadcast();
+    }
+
+    fn header(&mut self, kind: FrameKind, n: usize, frequency: f64, rate: f64, time: f64) {
+        self.buf.clear();
+        self.buf.extend_from_slice(&MAGIC);
+        self.buf.extend_from_slice(&VERSION.to_le_bytes());
+        self.buf.extend_from_slice(&(kind as u16).to_le_bytes());
+        self.buf.extend_from_slice(&(n as u32).to_le_bytes());
+        self.buf.extend_from_slice(&0u32.to_le_bytes());
+        self.buf.extend_from_slice(&frequency.to_le_bytes());
+        self.buf.extend_from_slice(&rate.to_le_bytes());
+        self.buf.extend_from_slice(&time.to_le_bytes());
+    }
+
+    fn broadcast(&mut self) {
+        let buf = &self.buf;
+        self.clients
+            .lock()
+            .unwrap()
+            .retain_mut(|c| c.write_all(buf).is_ok());
+    }
+}
+
+/// Sends the IQ samples or spectra of all packets.
+impl PacketSink for Server {
+    fn on_packet(&mut self, meta: &PacketMeta, samples: &[Complex32]) -> ControlFlow<()> {
+        if !samples.is_empty() {
+            let center = meta.start_frequency + meta.span_frequency / 2.0;
+            let rate = meta.sample_rate().unwrap_or(meta.span_frequency);
+            self.send_samples(samples, center, rate, meta.start_time);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn on_device_packet(&mut self, packet: &Packet) -> ControlFlow<()> {
+        self.send_packet(packet);
+        ControlFlow::Continue(())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(h) = self.handle.take() {
+            let _ = h.join();
+        }
+    }
+}
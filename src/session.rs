@@ -0,0 +1,434 @@
+//! Self-describing captures, i.e., a directory with the IQ samples, the configuration, and the
+//! metadata of a recording.
+//!
+//! - describe the capture with a [`Session`], i.e., a configuration, [`Annotations`], and the
+//!   duration
+//! - add [`PacketSink`]s, e.g., for live processing, with [`Session::add_sink()`]
+//! - record into a directory with [`Session::run()`], which starts and stops the [`Device`]
+//!
+//! The directory contains
+//!
+//! - `config.json`: all configuration parameters at the start, see [`Device::export_config()`]
+//! - `<name>.sigmf-data`: the IQ samples as interleaved little-endian 32-bit floats
+//! - `<name>.sigmf-meta`: [SigMF](https://sigmf.org) metadata with the annotations and the
+//!   device, and one capture per discontinuity of the stream, i.e., a gap in time or a change of
+//!   frequency or sample rate
+//! - `health.jsonl`: the health tree, sampled periodically, as JSON lines
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use crate::monitor::json_number;
+use crate::monitor::unix_secs;
+use crate::poll::Poller;
+use crate::ConfigItem;
+use crate::ConfigProfile;
+use crate::ConfigValue;
+use crate::Device;
+use crate::DeviceStatus;
+use crate::Error;
+use crate::PacketSink;
+
+/// Description of a capture by the operator.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Annotations {
+    /// Name of the operator, `core:author` in SigMF.
+    pub operator: Option<String>,
+    /// Location of the capture, e.g., a site name or coordinates.
+    pub location: Option<String>,
+    /// Free-text notes, `core:description` in SigMF.
+    pub notes: Option<String>,
+}
+
+/// Result of a [`Session`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionReport {
+    /// Directory of the capture.
+    pub dir: PathBuf,
+    /// Wall-clock duration of the capture.
+    pub duration: Duration,
+    /// Number of recorded samples.
+    pub samples: u64,
+    /// Number of SigMF captures, i.e., continuous parts of the stream.
+    pub captures: usize,
+    /// Number of health samples.
+    pub health_samples: u64,
+}
+
+/// Capture session, see the [module documentation](self).
+pub struct Session {
+    /// Base name of the SigMF files (default: `capture`).
+    pub name: String,
+    /// Configuration, applied before the device is connected (default: none, i.e., the current
+    /// configuration).
+    pub config: Option<ConfigProfile>,
+    /// Description of the capture.
+    pub annotations: Annotations,
+    /// Data channel (default: 0).
+    pub chan: i32,
+    /// Duration of the capture (default: none, i.e., until stopped).
+    pub duration: Option<Duration>,
+    /// Time between two health samples (default: 10 s).
+    pub health_interval: Duration,
+    sinks: Vec<Box<dyn PacketSink>>,
+}
+
+impl Session {
+    /// Create a session without configuration and annotations.
+    pub fn new() -> Self {
+        Self {
+            name: "capture".to_string(),
+            config: None,
+            annotations: Annotations::default(),
+            chan: 0,
+            duration: None,
+            health_interval: Duration::from_secs(10),
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Pass the packets also to a [`PacketSink`].
+    ///
+    /// The capture stops, when a sink returns
+    /// [`ControlFlow::Break`](std::ops::ControlFlow::Break).
+    pub fn add_sink<S: PacketSink + 'static>(&mut self, sink: S) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    /// Record into the directory `dir` for the duration of the session.
+    ///
+    /// See [`run_until()`](Self::run_until).
+    pub fn run<P: AsRef<Path>>(
+        &mut self,
+        dev: &mut Device,
+        dir: P,
+    ) -> std::result::Result<SessionReport, Error> {
+        self.run_until(dev, dir, &AtomicBool::new(false))
+    }
+
+    /// Record into the directory `dir` for the duration of the session or until `stop` is set,
+    /// e.g., by a Ctrl-C handler.
+    ///
+    /// The [`Device`] has to be opened. It is connected and started, if it is not yet, and
+    /// returned to its previous status afterwards. The directory is created, if it does not
+    /// exist. File errors are returned as [`Error::Session`]. The metadata is written also if
+    /// the capture fails.
+    pub fn run_until<P: AsRef<Path>>(
+        &mut self,
+        dev: &mut Device,
+        dir: P,
+        stop: &AtomicBool,
+    ) -> std::result::Result<SessionReport, Error> {
+        let dir = dir.as_ref();
+        let io = |e: std::io::Error| session_error(dir, e);
+        std::fs::create_dir_all(dir).map_err(io)?;
+
+        if let Some(config) = &self.config {
+            dev.apply_config(config)?;
+        }
+        let status = dev.status();
+        if status == DeviceStatus::Opened {
+            dev.connect()?;
+        }
+        std::fs::write(dir.join("config.json"), config_json(&dev.export_config()?)).map_err(io)?;
+        let details = dev.info()?;
+        if status != DeviceStatus::Started {
+            dev.start()?;
+        }
+
+        let mut data = File::create(dir.join(format!("{}.sigmf-data", self.name)))
+            .map(BufWriter::new)
+            .map_err(io)?;
+        let mut health = File::create(dir.join("health.jsonl"))
+            .map(BufWriter::new)
+            .map_err(io)?;
+        let start = Instant::now();
+        let mut recording = Recording::default();
+        let streams = Streams {
+            dir,
+            data: &mut data,
+            health: &mut health,
+        };
+        let result = self.capture(dev, stop, start, streams, &mut recording);
+
+        // restore the status, before reporting errors
+        let mut restore = Ok(());
+        if status != DeviceStatus::Started {
+            restore = dev.stop();
+        }
+        if status == DeviceStatus::Opened && restore.is_ok() {
+            restore = dev.disconnect();
+        }
+
+        let meta = self.meta_json(&details.serial, details.model_name.as_deref(), &recording);
+        std::fs::write(dir.join(format!("{}.sigmf-meta", self.name)), meta).map_err(io)?;
+        result?;
+        restore?;
+
+        Ok(SessionReport {
+            dir: dir.to_path_buf(),
+            duration: start.elapsed(),
+            samples: recording.samples,
+            captures: recording.captures.len(),
+            health_samples: recording.health_samples,
+        })
+    }
+
+    /// Receive loop, writing the samples and health samples.
+    fn capture(
+        &mut self,
+        dev: &mut Device,
+        stop: &AtomicBool,
+        start: Instant,
+        streams: Streams,
+        recording: &mut Recording,
+    ) -> std::result::Result<(), Error> {
+        let Streams { dir, data, health } = streams;
+        let dir_error = |e: std::io::Error| session_error(dir, e);
+        let mut poller = Poller::new(dev.poll_strategy());
+        let mut next_health = start;
+        loop {
+            let now = Instant::now();
+            if self.duration.is_some_and(|d| now - start >= d) || stop.load(Ordering::Acquire) {
+                break;
+            }
+            if now >= next_health {
+                next_health = now + self.health_interval;
+                if let Ok(leaves) = dev.health_leaves() {
+                    recording.health_samples += 1;
+                    writeln!(health, "{}", health_json(&leaves))
+                        .and_then(|_| health.flush())
+                        .map_err(dir_error)?;
+                }
+            }
+
+            let p = match dev.try_packet(self.chan) {
+                Ok(p) => p,
+                Err(Error::Empty) => {
+                    poller.wait();
+                    continue;
+                }
+                Err(e) => {
+                    dev.handle_stream_error(e)?;
+                    continue;
+                }
+            };
+            poller = Poller::new(dev.poll_strategy());
+
+            let samples = p.samples();
+            if let (false, Some(rate)) = (samples.is_empty(), p.sample_rate()) {
+                recording.mark(p.start_time(), p.start_frequency(), rate);
+                for s in samples {
+                    data.write_all(&s.re.to_le_bytes())
+                        .and_then(|_| data.write_all(&s.im.to_le_bytes()))
+                        .map_err(dir_error)?;
+                }
+                recording.advance(samples.len());
+            }
+            let flow = self.sinks.on_device_packet(&p);
+            dev.consume(self.chan)?;
+            if flow.is_break() {
+                break;
+            }
+        }
+        data.flush().map_err(dir_error)
+    }
+
+    /// SigMF metadata of the recording.
+    fn meta_json(&self, serial: &str, model: Option<&str>, recording: &Recording) -> String {
+        let mut global = vec![
+            "\"core:datatype\":\"cf32_le\"".to_string(),
+            "\"core:version\":\"1.0.0\"".to_string(),
+            format!(
+                "\"core:recorder\":\"aaronia-rtsa {}\"",
+                env!("CARGO_PKG_VERSION")
+            ),
+            format!(
+                "\"core:hw\":{}",
+                json_string(&format!("{} {serial}", model.unwrap_or("Aaronia RTSA")))
+            ),
+        ];
+        if let Some(rate) = recording.captures.first().map(|c| c.sample_rate) {
+            global.push(format!("\"core:sample_rate\":{}", json_number(rate)));
+        }
+        let a = &self.annotations;
+        for (key, value) in [
+            ("core:author", &a.operator),
+            ("core:description", &a.notes),
+            ("rtsa:location", &a.location),
+        ] {
+            if let Some(v) = value {
+                global.push(format!("\"{key}\":{}", json_string(v)));
+            }
+        }
+
+        let captures: Vec<String> = recording
+            .captures
+            .iter()
+            .map(|c| {
+                format!(
+                    concat!(
+                        "{{\"core:sample_start\":{},\"core:frequency\":{},",
+                        "\"rtsa:sample_rate\":{},\"rtsa:time\":{}}}"
+                    ),
+                    c.sample_start,
+                    json_number(c.frequency),
+                    json_number(c.sample_rate),
+                    json_number(c.time),
+                )
+            })
+            .collect();
+        format!(
+            "{{\"global\":{{{}}},\"captures\":[{}],\"annotations\":[]}}\n",
+            global.join(","),
+            captures.join(",")
+        )
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Session")
+            .field("name", &self.name)
+            .field("config", &self.config)
+            .field("annotations", &self.annotations)
+            .field("chan", &self.chan)
+            .field("duration", &self.duration)
+            .field("health_interval", &self.health_interval)
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
+
+/// Output files of a running session.
+struct Streams<'a> {
+    dir: &'a Path,
+    data: &'a mut BufWriter<File>,
+    health: &'a mut BufWriter<File>,
+}
+
+/// Continuous part of the stream.
+#[derive(Debug, Clone, Copy)]
+struct Capture {
+    sample_start: u64,
+    time: f64,
+    frequency: f64,
+    sample_rate: f64,
+}
+
+/// Captures of a recording.
+#[derive(Debug, Default)]
+struct Recording {
+    captures: Vec<Capture>,
+    samples: u64,
+    end: f64,
+    health_samples: u64,
+}
+
+impl Recording {
+    /// Start a capture, if the samples do not continue the last one.
+    fn mark(&mut self, time: f64, frequency: f64, sample_rate: f64) {
+        let continues = self.captures.last().is_some_and(|c| {
+            c.frequency == frequency
+                && c.sample_rate == sample_rate
+                && (time - self.end).abs() <= 0.5 / sample_rate
+        });
+        if !continues {
+            self.captures.push(Capture {
+                sample_start: self.samples,
+                time,
+                frequency,
+                sample_rate,
+            });
+            self.end = time;
+        }
+    }
+
+    /// Advance the end of the recording by `n` samples.
+    fn advance(&mut self, n: usize) {
+        if let Some(c) = self.captures.last() {
+            self.end += n as f64 / c.sample_rate;
+        }
+        self.samples += n as u64;
+    }
+}
+
+fn session_error(dir: &Path, e: std::io::Error) -> Error {
+    Error::Session {
+        dir: dir.to_path_buf(),
+        reason: e.to_string(),
+    }
+}
+
+/// Format a string as JSON string.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Configuration as JSON object with one member per path.
+fn config_json(profile: &ConfigProfile) -> String {
+    let members: Vec<String> = profile
+        .iter()
+        .map(|(path, value)| {
+            let v = match value {
+                ConfigValue::Bool(b) => b.to_string(),
+                ConfigValue::Int(i) => i.to_string(),
+                ConfigValue::Float(f) => json_number(*f),
+                ConfigValue::String(s) => json_string(s),
+            };
+            format!("{}:{v}", json_string(path))
+        })
+        .collect();
+    format!("{{{}}}\n", members.join(","))
+}
+
+/// Health sample as JSON object with the wall-clock time.
+fn health_json(leaves: &[(String, ConfigItem)]) -> String {
+    let items: Vec<String> = leaves
+        .iter()
+        .filter_map(|(path, item)| {
+            let v = match item {
+                ConfigItem::Number(v) => json_number(*v),
+                ConfigItem::Bool(b) => b.to_string(),
+                ConfigItem::String(s) => json_string(s),
+                ConfigItem::Enum { value, options, .. } => {
+                    json_string(options.get(*value as usize)?)
+                }
+                _ => return None,
+            };
+            Some(format!("{}:{v}", json_string(path)))
+        })
+        .collect();
+    format!(
+        "{{\"time\":{:.3},\"items\":{{{}}}}}",
+        unix_secs(SystemTime::now()),
+        items.join(",")
+    )
+}
@@ -0,0 +1,65 @@
+use widestring::WideCString;
+
+use crate::Device;
+use crate::Error;
+
+/// Device type of devices that are managed by a running RTSA-Suite.
+///
+/// Devices of this type are attached through the suite instead of being claimed exclusively,
+/// i.e., they can be used while the suite holds the hardware.
+pub const SHARED_DEVICE_TYPE: &str = "spectranv6/suite";
+
+/// Check if an RTSA-Suite process is running on this machine.
+///
+/// Only supported on Linux, where the process list is read from `/proc`. Returns `false` on other
+/// platforms.
+pub fn suite_running() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let Ok(procs) = std::fs::read_dir("/proc") else {
+            return false;
+        };
+        procs.flatten().any(|p| {
+            std::fs::read(p.path().join("cmdline")).is_ok_and(|cmd| {
+                String::from_utf8_lossy(&cmd)
+                    .to_lowercase()
+                    .contains("rtsa-suite")
+            })
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        false
+    }
+}
+
+impl Device {
+    /// Open the [`Device`] through a running RTSA-Suite.
+    ///
+    /// Instead of claiming the hardware exclusively, the device is attached as
+    /// [`SHARED_DEVICE_TYPE`], i.e., it can be used while RTSA-Suite PRO is running. Returns
+    /// [`Error::ErrorNotFound`], if the suite does not manage the device.
+    pub fn open_shared(&mut self) -> crate::Result {
+        self.expect_status(crate::DeviceStatus::Uninit)?;
+        let device_type = self.device_type;
+        self.device_type = SHARED_DEVICE_TYPE;
+        let r = self.open();
+        if r.is_err() {
+            self.device_type = device_type;
+        }
+        r
+    }
+
+    /// Check if the device is attached through RTSA-Suite, see [`Device::open_shared()`].
+    pub fn is_shared(&self) -> bool {
+        self.device_type == SHARED_DEVICE_TYPE
+    }
+
+    /// Error for an open that failed, because the device is busy.
+    pub(crate) fn in_use_error(serial: &WideCString) -> Error {
+        Error::DeviceInUse {
+            serial: serial.to_string_lossy(),
+            suite_running: suite_running(),
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Test signal generation for the TX path.
+//!
+//! A [`SignalGenerator`] produces a [`Waveform`] as continuous IQ samples, either into buffers
+//! or as a stream of timestamped [`TxPacket`]s that can be passed to
+//! [`Device::send_packet()`](crate::Device::send_packet). A [`PacketStream`] is also a
+//! [`PacketSource`] for a paced [`Transmitter`](crate::Transmitter).
+use num_complex::Complex32;
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use crate::Hz;
+use crate::Packet;
+use crate::PacketFlags;
+use crate::PacketSource;
+use crate::StreamTime;
+use crate::TxFormat;
+
+/// Test waveform. Frequencies are offsets from the center frequency in Hz.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Waveform {
+    /// Continuous wave at `offset`.
+    Cw {
+        /// Frequency offset.
+        offset: f64,
+    },
+    /// Tones of equal amplitude at `offsets`.
+    Multitone {
+        /// Frequency offsets.
+        offsets: Vec<f64>,
+    },
+    /// Linear chirp from `start` to `stop`, repeated every `period`.
+    Chirp {
+        /// Start frequency offset.
+        start: f64,
+        /// Stop frequency offset.
+        stop: f64,
+        /// Duration of a sweep.
+        period: Duration,
+    },
+    /// Complex additive white Gaussian noise.
+    Noise,
+    /// QPSK with Gray mapping and rectangular pulses, modulated with a PRBS-9 sequence.
+    Qpsk {
+        /// Symbol rate in Bd.
+        symbol_rate: f64,
+    },
+}
+
+/// Generator of a [`Waveform`].
+///
+/// The generator keeps its phase between calls, i.e., consecutive buffers form a continuous
+/// signal.
+#[derive(Debug, Clone)]
+pub struct SignalGenerator {
+    /// Peak amplitude of the signal, or RMS amplitude for noise (default: 0.5, i.e., -6 dBFS).
+    pub amplitude: f32,
+    waveform: Waveform,
+    sample_rate: f64,
+    sample: u64,
+    phases: Vec<f64>,
+    rng: u64,
+    prbs: u16,
+    symbol: Option<(u64, Complex32)>,
+}
+
+impl SignalGenerator {
+    /// Create a generator for the given sample rate.
+    pub fn new(waveform: Waveform, sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+        let tones = match &waveform {
+            Waveform::Multitone { offsets } => offsets.len(),
+            _ => 1,
+        };
+        Self {
+            amplitude: 0.5,
+            waveform,
+            sample_rate,
+            sample: 0,
+            phases: vec![0.0; tones],
+            rng: 0x853c_49e6_748f_ea9b,
+            prbs: 0x1ff,
+            symbol: None,
+        }
+    }
+
+    /// Set the seed of the noise generator.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = seed.max(1);
+        self
+    }
+
+    /// Waveform of the generator.
+    pub fn waveform(&self) -> &Waveform {
+        &self.waveform
+    }
+
+    /// Sample rate of the generator.
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Fill `out` with the next samples.
+    pub fn fill(&mut self, out: &mut [Complex32]) {
+        for o in out.iter_mut() {
+            *o = self.next_sample() * self.amplitude;
+            self.sample += 1;
+        }
+    }
+
+    /// Generate the next `n` samples.
+    pub fn generate(&mut self, n: usize) -> Vec<Complex32> {
+        let mut v = vec![Complex32::new(0.0, 0.0); n];
+        self.fill(&mut v);
+        v
+    }
+
+    /// Turn the generator into a stream of packets with `len` samples at `frequency`, starting
+    /// at `start`.
+    pub fn packets(self, frequency: impl Into<Hz>, len: usize, start: StreamTime) -> PacketStream {
+        assert!(len > 0, "packets have to be non-empty");
+        PacketStream {
+            generator: self,
+            frequency: frequency.into().value(),
+            len,
+            time: start.as_secs(),
+            first: true,
+        }
+    }
+
+    /// Next sample with unit amplitude.
+    fn next_sample(&mut self) -> Complex32 {
+        let rate = self.sample_rate;
+        match &self.waveform {
+            Waveform::Cw { offset } => Self::tone(&mut self.phases[0], *offset / rate),
+            Waveform::Multitone { offsets } => {
+                let n = offsets.len().max(1) as f32;
+                offsets
+                    .iter()
+                    .zip(self.phases.iter_mut())
+                    .map(|(f, p)| Self::tone(p, f / rate))
+                    .sum::<Complex32>()
+                    / n
+            }
+            Waveform::Chirp {
+                start,
+                stop,
+                period,
+            } => {
+                let len = (period.as_secs_f64() * rate).max(1.0);
+                let pos = (self.sample as f64 % len) / len;
+                let f = start + (stop - start) * pos;
+                Self::tone(&mut self.phases[0], f / rate)
+            }
+            Waveform::Noise => {
+                // Box-Muller with unit power
+                let u1 = self.uniform().max(f64::MIN_POSITIVE);
+                let u2 = self.uniform();
+                let r = (-u1.ln()).sqrt();
+                let (s, c) = (2.0 * PI * u2).sin_cos();
+                Complex32::new((r * c) as f32, (r * s) as f32)
+            }
+            Waveform::Qpsk { symbol_rate } => {
+                let index = (self.sample as f64 * symbol_rate / rate) as u64;
+                match self.symbol {
+                    Some((i, s)) if i == index => s,
+                    _ => {
+                        let (b0, b1) = (self.prbs_bit(), self.prbs_bit());
+                        let a = std::f32::consts::FRAC_1_SQRT_2;
+                        let s = Complex32::new(if b0 { -a } else { a }, if b1 { -a } else { a });
+                        self.symbol = Some((index, s));
+                        s
+                    }
+                }
+            }
+        }
+    }
+
+    /// Advance a phase accumulator by `step` cycles and return the unit phasor before the step.
+    fn tone(phase: &mut f64, step: f64) -> Complex32 {
+        let s = Complex32::from_polar(1.0, (2.0 * PI * *phase) as f32);
+        *phase = (*phase + step).rem_euclid(1.0);
+        s
+    }
+
+    /// Uniform random number in `[0, 1)` from a xorshift64* generator.
+    fn uniform(&mut self) -> f64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        (self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Next bit of the PRBS-9 sequence, i.e., x^9 + x^5 + 1.
+    fn prbs_bit(&mut self) -> bool {
+        let bit = ((self.prbs >> 8) ^ (self.prbs >> 4)) & 1;
+        self.prbs = ((self.prbs << 1) | bit) & 0x1ff;
+        bit == 1
+    }
+}
+
+/// IQ samples with a TX packet, pointing to them, returned by [`PacketStream`].
+///
+/// Dereferences to [`Packet`], i.e., it can be passed to
+/// [`Device::send_packet()`](crate::Device::send_packet).
+pub struct TxPacket {
+    // the packet points to the heap buffer of the samples, which is never modified
+    packet: Packet,
+    _samples: Vec<Complex32>,
+}
+
+impl std::ops::Deref for TxPacket {
+    type Target = Packet;
+
+    fn deref(&self) -> &Packet {
+        &self.packet
+    }
+}
+
+impl std::fmt::Debug for TxPacket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TxPacket")
+            .field("meta", &self.packet.meta())
+            .finish()
+    }
+}
+
+/// Endless stream of consecutive [`TxPacket`]s of a [`SignalGenerator`].
+///
+/// The first packet starts a segment.
+#[derive(Debug, Clone)]
+pub struct PacketStream {
+    generator: SignalGenerator,
+    frequency: f64,
+    len: usize,
+    time: f64,
+    first: bool,
+}
+
+impl PacketStream {
+    /// Start time of the next packet.
+    pub fn next_time(&self) -> StreamTime {
+        StreamTime::from_secs(self.time)
+    }
+}
+
+impl Iterator for PacketStream {
+    type Item = TxPacket;
+
+    fn next(&mut self) -> Option<TxPacket> {
+        let samples = self.generator.generate(self.len);
+        let mut flags = PacketFlags::new();
+        if self.first {
+            flags.set_segment_start();
+            self.first = false;
+        }
+        let rate = self.generator.sample_rate;
+        let packet = Packet::from_samples(&samples, self.time, self.frequency, rate, flags);
+        self.time += self.len as f64 / rate;
+        Some(TxPacket {
+            packet,
+            _samples: samples,
+        })
+    }
+}
+
+/// Packets for a [`Transmitter`](crate::Transmitter), which ignores the start times of the
+/// stream and schedules the packets on the device clock.
+impl PacketSource for PacketStream {
+    fn next_packet(&mut self, samples: &mut Vec<Complex32>) -> Option<TxFormat> {
+        samples.resize(self.len, Complex32::new(0.0, 0.0));
+        self.generator.fill(samples);
+        self.first = false;
+        let sample_rate = self.generator.sample_rate;
+        self.time += self.len as f64 / sample_rate;
+        Some(TxFormat {
+            frequency: self.frequency,
+            sample_rate,
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use num_complex::Complex32;
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use crate::poll::Poller;
+use crate::Device;
+use crate::Error;
+use crate::Packet;
+use crate::PacketData;
+use crate::PacketMeta;
+use crate::Result;
+
+/// Consumer of the packets of a data channel, driven by [`Device::run_sink()`].
+///
+/// File writers, network streamers, DSP chains, and user code implement the trait to share one
+/// receive loop. Closures `FnMut(&PacketMeta, &[Complex32]) -> ControlFlow<()>` are sinks, pairs
+/// `(A, B)` and vectors of boxed sinks pass every packet to all of their sinks.
+pub trait PacketSink {
+    /// Handle the metadata and IQ samples of a packet, i.e., an empty slice for spectra and raw
+    /// packets.
+    ///
+    /// Return [`ControlFlow::Break`] to stop receiving.
+    fn on_packet(&mut self, meta: &PacketMeta, samples: &[Complex32]) -> ControlFlow<()>;
+
+    /// Handle a packet of the device queue.
+    ///
+    /// Calls [`on_packet()`](Self::on_packet) by default. Sinks override it to access the
+    /// payload of spectra and raw packets.
+    fn on_device_packet(&mut self, packet: &Packet) -> ControlFlow<()> {
+        let samples = match packet.data() {
+            PacketData::Iq(s) => s,
+            _ => &[],
+        };
+        self.on_packet(&packet.meta(), samples)
+    }
+}
+
+impl<F: FnMut(&PacketMeta, &[Complex32]) -> ControlFlow<()>> PacketSink for F {
+    fn on_packet(&mut self, meta: &PacketMeta, samples: &[Complex32]) -> ControlFlow<()> {
+        self(meta, samples)
+    }
+}
+
+/// Both sinks get every packet. Receiving stops, if one of them breaks.
+impl<A: PacketSink, B: PacketSink> PacketSink for (A, B) {
+    fn on_packet(&mut self, meta: &PacketMeta, samples: &[Complex32]) -> ControlFlow<()> {
+        let a = self.0.on_packet(meta, samples);
+        let b = self.1.on_packet(meta, samples);
+        if a.is_break() || b.is_break() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+
+    fn on_device_packet(&mut self, packet: &Packet) -> ControlFlow<()> {
+        let a = self.0.on_device_packet(packet);
+        let b = self.1.on_device_packet(packet);
+        if a.is_break() || b.is_break() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+/// All sinks get every packet. Receiving stops, if one of them breaks.
+impl<S: PacketSink + ?Sized> PacketSink for Vec<Box<S>> {
+    fn on_packet(&mut self, meta: &PacketMeta, samples: &[Complex32]) -> ControlFlow<()> {
+        let mut flow = ControlFlow::Continue(());
+        for s in self.iter_mut() {
+            if s.on_packet(meta, samples).is_break() {
+                flow = ControlFlow::Break(());
+            }
+        }
+        flow
+    }
+
+    fn on_device_packet(&mut self, packet: &Packet) -> ControlFlow<()> {
+        let mut flow = ControlFlow::Continue(());
+        for s in self.iter_mut() {
+            if s.on_device_packet(packet).is_break() {
+                flow = ControlFlow::Break(());
+            }
+        }
+        flow
+    }
+}
+
+impl Device {
+    /// Receive the packets of data channel `chan` and pass them to a [`PacketSink`], until it
+    /// returns [`ControlFlow::Break`].
+    ///
+    /// Packets are consumed, after the sink handled them. The loop polls with the
+    /// [`PollStrategy`](crate::PollStrategy) of the device, while the queue is empty. With
+    /// [`set_auto_recover()`](Self::set_auto_recover), it continues after a connection loss.
+    pub fn run_sink<S: PacketSink + ?Sized>(&mut self, chan: i32, sink: &mut S) -> Result {
+        self.drive(chan, &AtomicBool::new(false), |p| sink.on_device_packet(p))
+    }
+
+    /// Receive loop of [`run_sink()`](Self::run_sink) and the receive threads, stopping also,
+    /// when `stop` is set.
+    pub(crate) fn drive<F: FnMut(&Packet) -> ControlFlow<()>>(
+        &mut self,
+        chan: i32,
+        stop: &AtomicBool,
+        mut f: F,
+    ) -> Result {
+        let mut poller = Poller::new(self.poll_strategy());
+        while !stop.load(Ordering::Acquire) {
+            let p = match self.try_packet(chan) {
+                Ok(p) => p,
+                Err(Error::Empty) => {
+                    poller.wait();
+                    continue;
+                }
+                Err(e) => {
+                    self.handle_stream_error(e)?;
+                    continue;
+                }
+            };
+            poller = Poller::new(self.poll_strategy());
+
+            let flow = f(&p);
+            self.consume(chan)?;
+            if flow.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
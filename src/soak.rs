@@ -0,0 +1,342 @@
+//! Long-running stream tests, e.g., to check that a setup sustains 24/7 capture.
+//!
+//! - configure, connect, and start the [`Device`]
+//! - run a [`Soak`] test for hours with [`Soak::run()`] or until a flag is set with
+//!   [`Soak::run_until()`]
+//! - check the [`SoakReport`] with drop, gap, and health statistics, e.g., store it with
+//!   [`SoakReport::to_json()`]
+//!
+//! With a `log` path, the test captures its events as JSON lines, i.e., one object per gap,
+//! health sample, and stream error with the wall-clock time, so that drops can be correlated
+//! with system logs afterwards.
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use crate::monitor::json_number;
+use crate::monitor::unix_secs;
+use crate::poll::Poller;
+use crate::ConfigItem;
+use crate::Device;
+use crate::Error;
+use crate::QueueStats;
+
+/// Result of a [`Soak`] test.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SoakReport {
+    /// Wall-clock duration of the test.
+    pub duration: Duration,
+    /// Number of received packets.
+    pub packets: u64,
+    /// Number of received samples.
+    pub samples: u64,
+    /// Nominal sample rate of the stream in Hz, `None` if no packet was received.
+    pub sample_rate: Option<f64>,
+    /// Number of detected gaps in the stream, see [`QueueStats`](crate::QueueStats).
+    pub drops: u64,
+    /// Total duration of the detected gaps in seconds.
+    pub dropped_time: f64,
+    /// Duration of the longest gap in seconds.
+    pub longest_gap: f64,
+    /// Maximum number of packets in the queue.
+    pub high_water: usize,
+    /// Longest wall-clock time without a packet.
+    pub longest_stall: Duration,
+    /// Number of stream errors, e.g., connection losses.
+    pub errors: u64,
+    /// Number of errors, the device recovered from, see
+    /// [`Device::set_auto_recover()`](crate::Device::set_auto_recover).
+    pub recoveries: u64,
+    /// Number of health samples.
+    pub health_samples: u64,
+    /// Highest value of each temperature sensor, i.e., health items with `temp` in their name.
+    pub peak_temperatures: Vec<(String, f64)>,
+    /// Error that stopped the event log, e.g., a full disk.
+    pub log_error: Option<String>,
+}
+
+impl SoakReport {
+    /// Check if the setup sustained the stream, i.e., packets were received without gaps and
+    /// errors.
+    pub fn passed(&self) -> bool {
+        self.packets > 0 && self.drops == 0 && self.errors == 0
+    }
+
+    /// Highest temperature of all sensors.
+    pub fn peak_temperature(&self) -> Option<f64> {
+        self.peak_temperatures
+            .iter()
+            .map(|(_, t)| *t)
+            .max_by(f64::total_cmp)
+    }
+
+    /// Format the report as JSON object.
+    pub fn to_json(&self) -> String {
+        let temperatures: Vec<String> = self
+            .peak_temperatures
+            .iter()
+            .map(|(s, t)| format!("\"{}\":{}", escape(s), json_number(*t)))
+            .collect();
+        format!(
+            concat!(
+                "{{\"passed\":{},\"duration\":{:.3},\"packets\":{},\"samples\":{},",
+                "\"sample_rate\":{},\"drops\":{},\"dropped_time\":{},\"longest_gap\":{},",
+                "\"high_water\":{},\"longest_stall\":{:.3},\"errors\":{},\"recoveries\":{},",
+                "\"health_samples\":{},\"peak_temperatures\":{{{}}},\"log_error\":{}}}"
+            ),
+            self.passed(),
+            self.duration.as_secs_f64(),
+            self.packets,
+            self.samples,
+            self.sample_rate
+                .map(json_number)
+                .unwrap_or_else(|| "null".to_string()),
+            self.drops,
+            json_number(self.dropped_time),
+            json_number(self.longest_gap),
+            self.high_water,
+            self.longest_stall.as_secs_f64(),
+            self.errors,
+            self.recoveries,
+            self.health_samples,
+            temperatures.join(","),
+            self.log_error
+                .as_ref()
+                .map(|e| format!("\"{}\"", escape(e)))
+                .unwrap_or_else(|| "null".to_string()),
+        )
+    }
+}
+
+/// Escape a string for JSON.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Long-running stream test, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Soak {
+    /// Duration of the test.
+    pub duration: Duration,
+    /// Data channel (default: 0).
+    pub chan: i32,
+    /// Time between two health samples (default: 60 s).
+    pub health_interval: Duration,
+    /// File for the event log, JSON lines (default: none).
+    pub log: Option<PathBuf>,
+}
+
+impl Soak {
+    /// Create a test that runs for `duration`.
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            chan: 0,
+            health_interval: Duration::from_secs(60),
+            log: None,
+        }
+    }
+
+    /// Receive the stream of the started [`Device`] for the duration of the test.
+    pub fn run(&self, dev: &mut Device) -> std::result::Result<SoakReport, Error> {
+        self.run_until(dev, &AtomicBool::new(false))
+    }
+
+    /// Receive the stream for the duration of the test or until `stop` is set, e.g., by a
+    /// Ctrl-C handler.
+    ///
+    /// Stream errors end the test, unless the device recovers from them. Failing to create or
+    /// write the log does not end the test, it is reported in [`SoakReport::log_error`].
+    pub fn run_until(
+        &self,
+        dev: &mut Device,
+        stop: &AtomicBool,
+    ) -> std::result::Result<SoakReport, Error> {
+        let mut log = self.log.as_deref().map(EventLog::create);
+        let chan = self.chan;
+        dev.reset_queue_stats(chan);
+
+        let start = Instant::now();
+        let mut last_packet = start;
+        let mut next_health = start;
+        let mut poller = Poller::new(dev.poll_strategy());
+        let mut report = SoakReport {
+            duration: Duration::ZERO,
+            packets: 0,
+            samples: 0,
+            sample_rate: None,
+            drops: 0,
+            dropped_time: 0.0,
+            longest_gap: 0.0,
+            high_water: 0,
+            longest_stall: Duration::ZERO,
+            errors: 0,
+            recoveries: 0,
+            health_samples: 0,
+            peak_temperatures: Vec::new(),
+            log_error: None,
+        };
+        let mut last = QueueStats::default();
+
+        let result = loop {
+            let now = Instant::now();
+            if now - start >= self.duration || stop.load(Ordering::Acquire) {
+                break Ok(());
+            }
+            report.longest_stall = report.longest_stall.max(now - last_packet);
+
+            if now >= next_health {
+                next_health = now + self.health_interval;
+                if let Ok(health) = dev.health_leaves() {
+                    report.health_samples += 1;
+                    update_temperatures(&mut report.peak_temperatures, &health);
+                    if let Some(l) = &mut log {
+                        l.health(&health);
+                    }
+                }
+            }
+
+            let p = match dev.try_packet(chan) {
+                Ok(p) => p,
+                Err(Error::Empty) => {
+                    poller.wait();
+                    continue;
+                }
+                Err(e) => {
+                    report.errors += 1;
+                    if let Some(l) = &mut log {
+                        l.error(&e);
+                    }
+                    match dev.handle_stream_error(e) {
+                        Ok(()) => {
+                            report.recoveries += 1;
+                            continue;
+                        }
+                        Err(e) => break Err(e),
+                    }
+                }
+            };
+            poller = Poller::new(dev.poll_strategy());
+            last_packet = Instant::now();
+            report.samples += p.num().max(0) as u64;
+            let time = p.start_time();
+            dev.consume(chan)?;
+
+            let stats = dev.queue_stats(chan)?;
+            if stats.drops > last.drops {
+                let gap = stats.dropped_time - last.dropped_time;
+                report.longest_gap = report.longest_gap.max(gap);
+                if let Some(l) = &mut log {
+                    l.gap(time, gap);
+                }
+            }
+            last = stats;
+        };
+
+        let stats = dev.queue_stats(chan)?;
+        report.duration = start.elapsed();
+        report.packets = stats.packets;
+        report.sample_rate = stats.sample_rate;
+        report.drops = stats.drops;
+        report.dropped_time = stats.dropped_time;
+        report.high_water = stats.high_water;
+        if let Some(l) = &mut log {
+            l.finish(&report);
+            report.log_error = l.error.as_ref().map(|e| e.to_string());
+        }
+        result.map(|_| report)
+    }
+}
+
+/// Raise the peak temperatures with the temperature sensors of a health sample.
+fn update_temperatures(peaks: &mut Vec<(String, f64)>, health: &[(String, ConfigItem)]) {
+    for (path, item) in health {
+        let ConfigItem::Number(v) = item else {
+            continue;
+        };
+        if !path.to_lowercase().contains("temp") {
+            continue;
+        }
+        match peaks.iter_mut().find(|(p, _)| p == path) {
+            Some((_, peak)) => *peak = peak.max(*v),
+            None => peaks.push((path.clone(), *v)),
+        }
+    }
+}
+
+/// JSON lines log of the events of a test.
+///
+/// The first write error is kept and later events are dropped.
+struct EventLog {
+    writer: Option<BufWriter<File>>,
+    error: Option<std::io::Error>,
+}
+
+impl EventLog {
+    fn create(path: &Path) -> Self {
+        match File::create(path) {
+            Ok(f) => Self {
+                writer: Some(BufWriter::new(f)),
+                error: None,
+            },
+            Err(e) => Self {
+                writer: None,
+                error: Some(e),
+            },
+        }
+    }
+
+    fn write(&mut self, event: &str, fields: &str) {
+        let Some(w) = &mut self.writer else {
+            return;
+        };
+        let time = unix_secs(SystemTime::now());
+        let r = writeln!(w, "{{\"time\":{time:.3},\"event\":\"{event}\"{fields}}}")
+            .and_then(|_| w.flush());
+        if let Err(e) = r {
+            self.writer = None;
+            self.error = Some(e);
+        }
+    }
+
+    fn gap(&mut self, stream_time: f64, gap: f64) {
+        let fields = format!(
+            ",\"stream_time\":{},\"gap\":{}",
+            json_number(stream_time),
+            json_number(gap)
+        );
+        self.write("gap", &fields);
+    }
+
+    fn health(&mut self, health: &[(String, ConfigItem)]) {
+        let items: Vec<String> = health
+            .iter()
+            .filter_map(|(p, i)| {
+                let v = match i {
+                    ConfigItem::Number(v) => json_number(*v),
+                    ConfigItem::Bool(b) => b.to_string(),
+                    _ => return None,
+                };
+                Some(format!("\"{}\":{v}", escape(p)))
+            })
+            .collect();
+        self.write("health", &format!(",\"items\":{{{}}}", items.join(",")));
+    }
+
+    fn error(&mut self, e: &Error) {
+        let fields = format!(",\"error\":\"{}\"", escape(&e.to_string()));
+        self.write("error", &fields);
+    }
+
+    fn finish(&mut self, report: &SoakReport) {
+        self.write("report", &format!(",\"report\":{}", report.to_json()));
+    }
+}
@@ -0,0 +1,312 @@
+//! SoapySDR-style adapter for a [`Device`].
+//!
+//! [`SoapyDevice`] mirrors the SoapySDR device API (directions, channels, antennas, gains,
+//! frequencies, sample rates, and streams) and translates the calls into RTSA configuration
+//! paths, so applications written against SoapySDR can be ported with minimal changes.
+use num_complex::Complex32;
+
+use crate::ConfigItem;
+use crate::Device;
+use crate::Error;
+use crate::PacketFlags;
+use crate::Result;
+
+/// Stream direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Rx,
+    Tx,
+}
+
+/// Range of values, e.g., for gains and frequencies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+}
+
+impl Range {
+    fn new(min: f64, max: f64, step: f64) -> Self {
+        Self { min, max, step }
+    }
+
+    fn contains(&self, v: f64) -> bool {
+        v >= self.min && v <= self.max
+    }
+}
+
+/// Receiver clocks in Hz, in the order of the `device/receiverclock` options.
+const CLOCKS: [f64; 4] = [92e6, 122e6, 184e6, 245e6];
+/// Decimation factors, in the order of the `main/decimation` options.
+const DECIMATIONS: [f64; 10] = [1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0, 512.0];
+/// Reference level that corresponds to a gain of 0 dB.
+const REFLEVEL_MAX: f64 = -8.0;
+/// Maximum samples per TX packet.
+const TX_MTU: usize = 1024;
+
+/// SoapySDR-style wrapper around an opened [`Device`].
+#[derive(Debug)]
+pub struct SoapyDevice {
+    dev: Device,
+    tx_time: f64,
+    rx_offset: usize,
+}
+
+impl SoapyDevice {
+    /// Wrap an opened [`Device`].
+    pub fn new(dev: Device) -> Self {
+        Self {
+            dev,
+            tx_time: 0.0,
+            rx_offset: 0,
+        }
+    }
+
+    /// Get the wrapped [`Device`].
+    pub fn inner(&mut self) -> &mut Device {
+        &mut self.dev
+    }
+
+    /// Get the number of channels.
+    pub fn num_channels(&self, dir: Direction) -> usize {
+        match dir {
+            Direction::Rx => 2,
+            Direction::Tx => 1,
+        }
+    }
+
+    fn check_channel(&self, dir: Direction, chan: usize) -> Result {
+        if chan < self.num_channels(dir) {
+            Ok(())
+        } else {
+            Err(Error::ErrorInvalidChannel)
+        }
+    }
+
+    /// List available antennas.
+    pub fn list_antennas(
+        &self,
+        dir: Direction,
+        chan: usize,
+    ) -> std::result::Result<Vec<String>, Error> {
+        self.check_channel(dir, chan)?;
+        match dir {
+            Direction::Rx => Ok(vec!["RX1".to_string(), "RX2".to_string()]),
+            Direction::Tx => Ok(vec!["TX1".to_string()]),
+        }
+    }
+
+    /// Select antenna, mapped to `device/receiverchannel`.
+    pub fn set_antenna(&mut self, dir: Direction, chan: usize, name: &str) -> Result {
+        self.check_channel(dir, chan)?;
+        match (dir, name.to_uppercase().as_str()) {
+            (Direction::Rx, "RX1") => self.dev.set("device/receiverchannel", "Rx1"),
+            (Direction::Rx, "RX2") => self.dev.set("device/receiverchannel", "Rx2"),
+            (Direction::Tx, "TX1") => Ok(()),
+            _ => Err(Error::ErrorInvalidParameter),
+        }
+    }
+
+    /// Get selected antenna.
+    pub fn antenna(&mut self, dir: Direction, chan: usize) -> std::result::Result<String, Error> {
+        self.check_channel(dir, chan)?;
+        match dir {
+            Direction::Rx => match self.dev.get("device/receiverchannel")? {
+                ConfigItem::Enum { value, options, .. } => options
+                    .get(value as usize)
+                    .map(|s| s.to_uppercase())
+                    .ok_or(Error::ErrorValueInvalid),
+                _ => Err(Error::ErrorValueInvalid),
+            },
+            Direction::Tx => Ok("TX1".to_string()),
+        }
+    }
+
+    /// Get gain range in dB.
+    pub fn gain_range(&self, dir: Direction, chan: usize) -> std::result::Result<Range, Error> {
+        self.check_channel(dir, chan)?;
+        match dir {
+            Direction::Rx => Ok(Range::new(0.0, 30.0, 1.0)),
+            Direction::Tx => Ok(Range::new(-100.0, 10.0, 1.0)),
+        }
+    }
+
+    /// Set gain in dB.
+    ///
+    /// RX gain is mapped to `main/reflevel`, TX gain to `main/transgain`.
+    pub fn set_gain(&mut self, dir: Direction, chan: usize, gain: f64) -> Result {
+        if !self.gain_range(dir, chan)?.contains(gain) {
+            return Err(Error::ErrorInvalidParameter);
+        }
+        match dir {
+            Direction::Rx => self.dev.set_float("main/reflevel", REFLEVEL_MAX - gain),
+            Direction::Tx => self.dev.set_float("main/transgain", gain),
+        }
+    }
+
+    /// Get gain in dB.
+    pub fn gain(&mut self, dir: Direction, chan: usize) -> std::result::Result<f64, Error> {
+        self.check_channel(dir, chan)?;
+        match dir {
+            Direction::Rx => Ok(REFLEVEL_MAX - self.number("main/reflevel")?),
+            Direction::Tx => self.number("main/transgain"),
+        }
+    }
+
+    /// Get frequency range in Hz.
+    pub fn frequency_range(
+        &self,
+        dir: Direction,
+        chan: usize,
+    ) -> std::result::Result<Range, Error> {
+        self.check_channel(dir, chan)?;
+        Ok(Range::new(193e6, 6e9, 0.0))
+    }
+
+    /// Set center frequency in Hz, mapped to `main/centerfreq`.
+    pub fn set_frequency(&mut self, dir: Direction, chan: usize, freq: f64) -> Result {
+        if !self.frequency_range(dir, chan)?.contains(freq) {
+            return Err(Error::ErrorInvalidParameter);
+        }
+        self.dev.set_float("main/centerfreq", freq)
+    }
+
+    /// Get center frequency in Hz.
+    pub fn frequency(&mut self, dir: Direction, chan: usize) -> std::result::Result<f64, Error> {
+        self.check_channel(dir, chan)?;
+        self.number("main/centerfreq")
+    }
+
+    /// List supported sample rates in Hz.
+    pub fn list_sample_rates(
+        &self,
+        dir: Direction,
+        chan: usize,
+    ) -> std::result::Result<Vec<f64>, Error> {
+        self.check_channel(dir, chan)?;
+        let mut rates: Vec<f64> = CLOCKS
+            .iter()
+            .flat_map(|c| DECIMATIONS.iter().map(move |d| c / d))
+            .collect();
+        rates.sort_by(|a, b| a.total_cmp(b));
+        rates.dedup();
+        Ok(rates)
+    }
+
+    /// Set sample rate in Hz, mapped to `device/receiverclock` and `main/decimation`.
+    ///
+    /// The rate has to be one of the [supported rates](Self::list_sample_rates).
+    pub fn set_sample_rate(&mut self, dir: Direction, chan: usize, rate: f64) -> Result {
+        self.check_channel(dir, chan)?;
+        for (c, clock) in CLOCKS.iter().enumerate() {
+            for (d, dec) in DECIMATIONS.iter().enumerate() {
+                if (rate - clock / dec).abs() < 1e-3 {
+                    self.dev.set_int("device/receiverclock", c as i64)?;
+                    return self.dev.set_int("main/decimation", d as i64);
+                }
+            }
+        }
+        Err(Error::ErrorInvalidParameter)
+    }
+
+    /// Get sample rate in Hz.
+    pub fn sample_rate(&mut self, dir: Direction, chan: usize) -> std::result::Result<f64, Error> {
+        self.check_channel(dir, chan)?;
+        let clock = match self.dev.get("device/receiverclock")? {
+            ConfigItem::Enum { value: i, .. } => CLOCKS.get(i as usize),
+            _ => None,
+        };
+        let dec = match self.dev.get("main/decimation")? {
+            ConfigItem::Enum { value: i, .. } => DECIMATIONS.get(i as usize),
+            _ => None,
+        };
+        match (clock, dec) {
+            (Some(c), Some(d)) => Ok(c / d),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+
+    /// Activate the stream, i.e., connect and start the [`Device`].
+    pub fn activate_stream(&mut self) -> Result {
+        self.dev.connect()?;
+        self.dev.start()?;
+        self.tx_time = 0.0;
+        self.rx_offset = 0;
+        Ok(())
+    }
+
+    /// Deactivate the stream, i.e., stop and disconnect the [`Device`].
+    pub fn deactivate_stream(&mut self) -> Result {
+        self.dev.stop()?;
+        self.dev.disconnect()
+    }
+
+    /// Read IQ samples from RX channel `chan`, blocking until the buffer is full.
+    pub fn read_stream(
+        &mut self,
+        chan: usize,
+        buf: &mut [Complex32],
+    ) -> std::result::Result<usize, Error> {
+        self.check_channel(Direction::Rx, chan)?;
+        let chan = chan as i32;
+
+        let mut i = 0;
+        while i < buf.len() {
+            let p = self.dev.packet(chan)?;
+            let cur = &p.samples()[self.rx_offset..];
+            let n = std::cmp::min(buf.len() - i, cur.len());
+            buf[i..i + n].copy_from_slice(&cur[0..n]);
+            i += n;
+            if n == cur.len() {
+                self.dev.consume(chan)?;
+                self.rx_offset = 0;
+            } else {
+                self.rx_offset += n;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    /// Write IQ samples to TX channel `chan`.
+    ///
+    /// Consecutive writes are scheduled back-to-back. If the stream fell behind the device
+    /// clock, a new burst is started. Set `end_burst` to mark the end of a burst.
+    pub fn write_stream(
+        &mut self,
+        chan: usize,
+        buf: &[Complex32],
+        end_burst: bool,
+    ) -> std::result::Result<usize, Error> {
+        self.check_channel(Direction::Tx, chan)?;
+        let rate = self.sample_rate(Direction::Rx, 0)?;
+        let freq = self.number("main/centerfreq")?;
+
+        let chunks = buf.chunks(TX_MTU);
+        let n_chunks = chunks.len();
+        for (i, c) in chunks.enumerate() {
+            let mut flags = PacketFlags::new();
+            let now = self.dev.clock()?;
+            if self.tx_time < now {
+                self.tx_time = now;
+                flags.set_segment_start();
+            }
+            if end_burst && i + 1 == n_chunks {
+                flags.set_segment_end();
+            }
+            self.dev
+                .send_samples(chan as i32, c, self.tx_time, freq, rate, flags)?;
+            self.tx_time += c.len() as f64 / rate;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn number(&mut self, path: &str) -> std::result::Result<f64, Error> {
+        match self.dev.get(path)? {
+            ConfigItem::Number(n) => Ok(n),
+            _ => Err(Error::ErrorValueInvalid),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use num_complex::Complex32;
+use std::time::Duration;
+
+use crate::poll::Poller;
+use crate::Device;
+use crate::Error;
+use crate::PacketFlags;
+use crate::Result;
+use crate::StreamTime;
+use crate::MIN_LEAD_TIME;
+
+/// Center frequency and sample rate of the samples of a [`PacketSource`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TxFormat {
+    /// Center frequency in Hz.
+    pub frequency: f64,
+    /// Sample rate in Hz.
+    pub sample_rate: f64,
+}
+
+/// Producer of IQ samples for the transmitter, driven by a [`Transmitter`].
+///
+/// Signal generators, file players, modulators, and user code implement the trait to share one
+/// transmit loop, which takes care of timing. Closures
+/// `FnMut(&mut Vec<Complex32>) -> Option<TxFormat>` are sources.
+pub trait PacketSource {
+    /// Append the IQ samples of the next packet to `samples`, which is empty, and return their
+    /// [`TxFormat`].
+    ///
+    /// Return `None` at the end of the stream.
+    fn next_packet(&mut self, samples: &mut Vec<Complex32>) -> Option<TxFormat>;
+}
+
+impl<F: FnMut(&mut Vec<Complex32>) -> Option<TxFormat>> PacketSource for F {
+    fn next_packet(&mut self, samples: &mut Vec<Complex32>) -> Option<TxFormat> {
+        self(samples)
+    }
+}
+
+/// Bounds of the depth of the TX queue in packets, see [`Transmitter::watermarks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Watermarks {
+    /// Depth, below which the queue runs low, i.e., the stream is about to underrun.
+    pub low: usize,
+    /// Depth, at which the transmitter waits, until the queue drained to `low`.
+    pub high: usize,
+}
+
+/// Transmit the packets of a [`PacketSource`], paced by the device clock.
+///
+/// Packets are scheduled back to back on the device clock, i.e., the stream is continuous. The
+/// first packet starts shortly after the device clock and a new segment starts, whenever the
+/// frequency or sample rate changes. The transmitter sleeps, while the queued samples reach more
+/// than `lookahead` ahead of the device clock, which bounds the depth of the TX queue. If the
+/// source does not keep up and the schedule falls behind the device clock, the stream restarts
+/// with a new segment and the underrun is counted. The [`Device`] has to be started and configured
+/// to transmit.
+///
+/// With [`watermarks`](Self::watermarks), the transmitter also monitors the depth of the TX
+/// queue, i.e., [`Device::packets_avail()`] of the TX channel, after the first packet of a
+/// segment. It does not request the next packet from the source, while the queue is full, and
+/// counts the packets, sent while the queue ran low.
+#[derive(Debug, Clone)]
+pub struct Transmitter {
+    /// TX data channel (default: 0).
+    pub chan: i32,
+    /// Maximum time, samples are sent ahead of the device clock, at least twice
+    /// [`MIN_LEAD_TIME`](crate::MIN_LEAD_TIME) (default: 50 ms).
+    pub lookahead: Duration,
+    /// Bounds of the TX queue depth (default: none, i.e., paced by the lookahead only).
+    pub watermarks: Option<Watermarks>,
+    next: Option<StreamTime>,
+    format: Option<TxFormat>,
+    samples: Vec<Complex32>,
+    packets: u64,
+    underruns: u64,
+    low: u64,
+    depth: Option<usize>,
+}
+
+impl Default for Transmitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transmitter {
+    /// Create a transmitter with default settings.
+    pub fn new() -> Self {
+        Self {
+            chan: 0,
+            lookahead: Duration::from_millis(50),
+            watermarks: None,
+            next: None,
+            format: None,
+            samples: Vec::new(),
+            packets: 0,
+            underruns: 0,
+            low: 0,
+            depth: None,
+        }
+    }
+
+    /// Get the next packet from `source` and send it, once it is within `lookahead` of the
+    /// device clock and the TX queue is below the high watermark.
+    ///
+    /// Returns `false` at the end of the stream.
+    pub fn send_next<S: PacketSource + ?Sized>(
+        &mut self,
+        dev: &mut Device,
+        source: &mut S,
+    ) -> std::result::Result<bool, Error> {
+        // before the first packet, the queue holds no packets of this transmitter
+        if let (Some(w), Some(_)) = (self.watermarks, self.next) {
+            self.wait_for_queue(dev, w)?;
+        }
+        self.samples.clear();
+        let format = match source.next_packet(&mut self.samples) {
+            Some(f) => f,
+            None => return Ok(false),
+        };
+        if self.samples.is_empty() {
+            return Ok(true);
+        }
+        if format.sample_rate <= 0.0 {
+            return Err(Error::ErrorInvalidParameter);
+        }
+
+        let mut now = dev.stream_time()?;
+        let mut flags = PacketFlags::new();
+        let start = match self.next {
+            Some(t) if self.format == Some(format) && t.secs_since(now) >= MIN_LEAD_TIME => t,
+            Some(_) if self.format == Some(format) => {
+                self.underruns += 1;
+                event!(
+                    tracing::Level::WARN,
+                    underruns = self.underruns,
+                    "tx underrun"
+                );
+                flags.set_segment_start();
+                StreamTime::from_secs(now.as_secs() + 2.0 * MIN_LEAD_TIME)
+            }
+            _ => {
+                flags.set_segment_start();
+                StreamTime::from_secs(now.as_secs() + 2.0 * MIN_LEAD_TIME)
+            }
+        };
+
+        let lookahead = self.lookahead.as_secs_f64().max(2.0 * MIN_LEAD_TIME);
+        let ahead = start.secs_since(now) - lookahead;
+        if ahead > 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(ahead));
+            now = dev.stream_time()?;
+        }
+        if start.secs_since(now) < 0.0 {
+            self.reset();
+            return Err(Error::TooLate);
+        }
+
+        dev.send_samples(
+            self.chan,
+            &self.samples,
+            start.as_secs(),
+            format.frequency,
+            format.sample_rate,
+            flags,
+        )?;
+        self.next = Some(start.sample_time(self.samples.len() as i64, format.sample_rate));
+        self.format = Some(format);
+        self.packets += 1;
+        Ok(true)
+    }
+
+    /// Send the packets of `source` until the end of the stream.
+    pub fn run<S: PacketSource + ?Sized>(&mut self, dev: &mut Device, source: &mut S) -> Result {
+        while self.send_next(dev, source)? {}
+        Ok(())
+    }
+
+    /// Start time of the next packet, `None` before the first packet.
+    pub fn next_time(&self) -> Option<StreamTime> {
+        self.next
+    }
+
+    /// Number of sent packets.
+    pub fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    /// Number of times, the schedule fell behind the device clock and restarted.
+    pub fn underruns(&self) -> u64 {
+        self.underruns
+    }
+
+    /// Number of packets, sent while the TX queue was below the low watermark.
+    pub fn low_water(&self) -> u64 {
+        self.low
+    }
+
+    /// Depth of the TX queue before the last packet, `None` without watermarks.
+    pub fn queue_depth(&self) -> Option<usize> {
+        self.depth
+    }
+
+    /// Wait, while the TX queue is at the high watermark, until it drained to the low watermark.
+    fn wait_for_queue(&mut self, dev: &mut Device, w: Watermarks) -> Result {
+        let mut depth = dev.packets_avail(self.chan)?;
+        if depth >= w.high {
+            let low = w.low.min(w.high.saturating_sub(1));
+            let mut poller = Poller::new(dev.poll_strategy());
+            while depth > low {
+                poller.wait();
+                depth = dev.packets_avail(self.chan)?;
+            }
+        } else if depth < w.low {
+            self.low += 1;
+            event!(tracing::Level::DEBUG, depth, "tx queue low");
+        }
+        self.depth = Some(depth);
+        Ok(())
+    }
+
+    /// Restart the schedule, i.e., the next packet starts a new segment relative to the device
+    /// clock.
+    pub fn reset(&mut self) {
+        self.next = None;
+        self.format = None;
+    }
+}
+
+impl Device {
+    /// Transmit the packets of a [`PacketSource`] on data channel `chan` until the end of the
+    /// stream, paced by a [`Transmitter`] with default settings.
+    pub fn run_source<S: PacketSource + ?Sized>(&mut self, chan: i32, source: &mut S) -> Result {
+        let mut tx = Transmitter::new();
+        tx.chan = chan;
+        tx.run(self, source)
+    }
+}
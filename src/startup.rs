@@ -0,0 +1,95 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::poll::Poller;
+use crate::Device;
+use crate::DeviceState;
+use crate::Error;
+use crate::PayloadKind;
+use crate::StreamTime;
+
+/// Progress of a [`Device::start_and_wait()`], passed to the progress callback, when the
+/// device state changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StartProgress {
+    /// State, reported by the device.
+    pub state: DeviceState,
+    /// Time since the start.
+    pub elapsed: Duration,
+}
+
+/// Properties of a started stream, returned by [`Device::start_and_wait()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamInfo {
+    /// Data channel of the first packet.
+    pub chan: i32,
+    /// Actual sample rate in Hz, derived from the first packet, `None` for spectra.
+    pub sample_rate: Option<f64>,
+    /// Start frequency of the first packet, i.e., the center frequency for IQ samples.
+    pub start_frequency: f64,
+    /// Timestamp of the first packet.
+    pub first_timestamp: StreamTime,
+    /// Time from the start until the first packet was available.
+    pub startup: Duration,
+}
+
+impl Device {
+    /// Start the [`Device`] and wait until data arrives.
+    ///
+    /// [`start()`](Device::start) returns before data flows and the first packets may take
+    /// hundreds of milliseconds. This call polls the device state according to the
+    /// [`PollStrategy`](crate::PollStrategy), calls `progress` for every state change, e.g.,
+    /// `Starting` and `Running`, and returns once a packet is available on the first data
+    /// channel of the output format (channel 0, if the format was not set through this handle).
+    /// The packet is not consumed.
+    ///
+    /// Returns [`Error::Retry`], if no packet arrived within `timeout`. The device stays started
+    /// in this case.
+    pub fn start_and_wait<F: FnMut(&StartProgress)>(
+        &mut self,
+        timeout: Duration,
+        mut progress: F,
+    ) -> std::result::Result<StreamInfo, Error> {
+        let chan = self.known_output_format().map_or(0, |f| f.channels()[0]);
+        let start = Instant::now();
+        self.start()?;
+
+        let mut poller = Poller::new(self.poll);
+        let mut last = None;
+        loop {
+            let state = self.state()?;
+            if last != Some(state) {
+                last = Some(state);
+                progress(&StartProgress {
+                    state,
+                    elapsed: start.elapsed(),
+                });
+            }
+            if state == DeviceState::Running {
+                match self.try_packet(chan) {
+                    Ok(p) => {
+                        // the packet is fetched again by the caller
+                        self.reset_queue_stats(chan);
+                        return Ok(StreamInfo {
+                            chan,
+                            sample_rate: match p.payload_kind() {
+                                PayloadKind::Spectrum => None,
+                                _ => p.sample_rate(),
+                            },
+                            start_frequency: p.start_frequency(),
+                            first_timestamp: p.start_stream_time(),
+                            startup: start.elapsed(),
+                        });
+                    }
+                    Err(Error::Empty) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            if start.elapsed() >= timeout {
+                return Err(Error::Retry);
+            }
+            poller.wait();
+        }
+    }
+}
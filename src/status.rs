@@ -0,0 +1,141 @@
+use crate::Device;
+use crate::Error;
+use crate::Packet;
+use crate::StreamTime;
+
+/// Data channel of the status stream of the raw device.
+///
+/// Status packets are delivered independently of the [`OutputFormat`](crate::OutputFormat).
+pub const STATUS_CHANNEL: i32 = 3;
+
+const KIND_OVERLOAD: u32 = 1;
+const KIND_TEMPERATURE: u32 = 2;
+const KIND_PPS: u32 = 3;
+
+/// Device condition, reported on the [`STATUS_CHANNEL`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StatusEvent {
+    /// Overload state of the receivers changed.
+    Overload {
+        /// Time of the change.
+        time: StreamTime,
+        /// Bitmask of the overloaded receivers, i.e., bit 0 for Rx1 and bit 1 for Rx2.
+        receivers: u32,
+    },
+    /// Temperature of the device.
+    Temperature {
+        /// Time of the measurement.
+        time: StreamTime,
+        /// Temperature in °C.
+        celsius: f32,
+    },
+    /// Pulse of the GPS PPS input.
+    Pps {
+        /// Time of the pulse.
+        time: StreamTime,
+    },
+    /// Status record of unknown kind.
+    Other {
+        /// Time of the record.
+        time: StreamTime,
+        /// Kind of the record.
+        kind: u32,
+        /// Value of the record.
+        value: f32,
+    },
+}
+
+impl StatusEvent {
+    /// Time of the event on the device clock.
+    pub fn time(&self) -> StreamTime {
+        match self {
+            StatusEvent::Overload { time, .. }
+            | StatusEvent::Temperature { time, .. }
+            | StatusEvent::Pps { time }
+            | StatusEvent::Other { time, .. } => *time,
+        }
+    }
+
+    /// Check if any receiver is overloaded, i.e., the event is an [`StatusEvent::Overload`] with
+    /// a receiver set.
+    pub fn is_overload(&self) -> bool {
+        matches!(self, StatusEvent::Overload { receivers, .. } if *receivers != 0)
+    }
+
+    fn parse(kind: u32, value: f32, time: StreamTime) -> Self {
+        match kind {
+            KIND_OVERLOAD => StatusEvent::Overload {
+                time,
+                receivers: value as u32,
+            },
+            KIND_TEMPERATURE => StatusEvent::Temperature {
+                time,
+                celsius: value,
+            },
+            KIND_PPS => StatusEvent::Pps { time },
+            _ => StatusEvent::Other { time, kind, value },
+        }
+    }
+}
+
+impl Packet {
+    /// Parse the records of a status packet.
+    ///
+    /// A status packet holds `num` records of a kind and a value, which are `stride` floats
+    /// apart. The packet duration is split evenly between the records.
+    pub fn status_events(&self) -> Vec<StatusEvent> {
+        let num = self.num().max(0) as usize;
+        let stride = (self.stride().max(0) as usize).max(2);
+        if num == 0 || self.size() < 2 || self.inner.fp32.is_null() {
+            return Vec::new();
+        }
+        let data = unsafe {
+            std::slice::from_raw_parts(self.inner.fp32 as *const f32, (num - 1) * stride + 2)
+        };
+        let start = self.start_time();
+        let step = (self.end_time() - start) / num as f64;
+
+        data.chunks(stride)
+            .enumerate()
+            .map(|(i, r)| {
+                let time = StreamTime::from_secs(start + i as f64 * step);
+                StatusEvent::parse(r[0] as u32, r[1], time)
+            })
+            .collect()
+    }
+}
+
+impl Device {
+    /// Read all status events that are available on the [`STATUS_CHANNEL`].
+    ///
+    /// This call is non-blocking. The status packets are consumed.
+    pub fn status_events(&mut self) -> std::result::Result<Vec<StatusEvent>, Error> {
+        let mut events = Vec::new();
+        loop {
+            let mut packet = Packet::new();
+            let ret = unsafe {
+                ffi!(AARTSAAPI_GetPacket(
+                    &mut self.inner,
+                    STATUS_CHANNEL,
+                    0,
+                    &mut packet.inner,
+                ); chan = STATUS_CHANNEL)
+            };
+            match ret {
+                Ok(_) => {
+                    events.extend(packet.status_events());
+                    unsafe {
+                        ffi!(AARTSAAPI_ConsumePackets(
+                            &mut self.inner,
+                            STATUS_CHANNEL,
+                            1,
+                        ); chan = STATUS_CHANNEL)?
+                    };
+                }
+                Err(Error::Empty) => return Ok(events),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
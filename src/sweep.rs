@@ -0,0 +1,142 @@
+//! Wideband frequency sweeps by retuning the center frequency.
+use crate::Device;
+use crate::Error;
+use crate::Hz;
+use crate::Spectrum;
+
+/// Progress of a [`Sweeper::sweep()`], passed to the progress callback after each step.
+#[derive(Debug)]
+pub struct SweepProgress<'a> {
+    /// Index of the finished step.
+    pub step: usize,
+    /// Total number of steps.
+    pub steps: usize,
+    /// Center frequency of the finished step.
+    pub center_frequency: f64,
+    /// Spectrum, received for this step.
+    pub spectrum: &'a Spectrum,
+}
+
+/// Sweep the center frequency across a span and stitch the spectra into one trace.
+///
+/// The [`Device`] has to be started and configured to output spectra. For every step, the
+/// sweeper sets `main/centerfreq`, waits for spectra at the new frequency, and copies the bins
+/// within `step` Hz around the center into the output trace. The span per step should,
+/// therefore, be smaller than the span of the device spectra to cut off the filter roll-off.
+#[derive(Debug, Clone)]
+pub struct Sweeper {
+    /// Start frequency of the sweep.
+    pub start_frequency: f64,
+    /// Stop frequency of the sweep.
+    pub stop_frequency: f64,
+    /// Frequency span, used from each step.
+    pub step: f64,
+    /// Spectra data channel (default: 2).
+    pub chan: i32,
+    /// Number of spectra to discard after retuning (default: 1).
+    pub settle_packets: usize,
+}
+
+impl Sweeper {
+    /// Create a sweeper for the given frequency range and span per step.
+    pub fn new(
+        start_frequency: impl Into<Hz>,
+        stop_frequency: impl Into<Hz>,
+        step: impl Into<Hz>,
+    ) -> Self {
+        let (start_frequency, stop_frequency, step) = (
+            start_frequency.into().value(),
+            stop_frequency.into().value(),
+            step.into().value(),
+        );
+        assert!(stop_frequency > start_frequency);
+        assert!(step > 0.0);
+        Self {
+            start_frequency,
+            stop_frequency,
+            step,
+            chan: 2,
+            settle_packets: 1,
+        }
+    }
+
+    /// Center frequencies of the steps.
+    pub fn centers(&self) -> Vec<f64> {
+        let steps = ((self.stop_frequency - self.start_frequency) / self.step).ceil() as usize;
+        (0..steps)
+            .map(|i| self.start_frequency + (i as f64 + 0.5) * self.step)
+            .collect()
+    }
+
+    /// Run the sweep, calling `progress` after every step.
+    pub fn sweep<F: FnMut(&SweepProgress)>(
+        &self,
+        dev: &mut Device,
+        mut progress: F,
+    ) -> std::result::Result<Spectrum, Error> {
+        let centers = self.centers();
+        let mut trace: Option<Spectrum> = None;
+
+        for (i, center) in centers.iter().enumerate() {
+            dev.set_float("main/centerfreq", *center)?;
+            let s = self.receive(dev, *center)?;
+
+            let t = trace.get_or_insert_with(|| {
+                let bins = ((self.stop_frequency - self.start_frequency) / s.step_frequency).ceil()
+                    as usize;
+                Spectrum {
+                    start_time: s.start_time,
+                    end_time: s.end_time,
+                    start_frequency: self.start_frequency,
+                    step_frequency: s.step_frequency,
+                    rbw_frequency: s.rbw_frequency,
+                    data: vec![f32::NAN; bins],
+                }
+            });
+
+            let lo = center - self.step / 2.0;
+            let hi = center + self.step / 2.0;
+            for (b, v) in s.data.iter().enumerate() {
+                let f = s.start_frequency + b as f64 * s.step_frequency;
+                if f < lo || f >= hi {
+                    continue;
+                }
+                let idx = ((f - t.start_frequency) / t.step_frequency).round();
+                if idx >= 0.0 && (idx as usize) < t.data.len() {
+                    t.data[idx as usize] = *v;
+                }
+            }
+            t.end_time = s.end_time;
+
+            progress(&SweepProgress {
+                step: i,
+                steps: centers.len(),
+                center_frequency: *center,
+                spectrum: &s,
+            });
+        }
+
+        trace.ok_or(Error::Empty)
+    }
+
+    /// Receive a spectrum, tuned to the given center frequency.
+    fn receive(&self, dev: &mut Device, center: f64) -> std::result::Result<Spectrum, Error> {
+        let mut skip = self.settle_packets;
+        loop {
+            let p = dev.packet(self.chan)?;
+            let tolerance = p.step_frequency().max(1.0);
+            let tuned = (p.start_frequency() + p.span_frequency() / 2.0 - center).abs() < tolerance;
+            let s = p.to_spectrum();
+            dev.consume(self.chan)?;
+
+            if !tuned {
+                continue;
+            }
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            return Ok(s);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Runtime loading of the RTSA library, enabled with the `dlopen` feature.
+//!
+//! Types and constants are taken from `aaronia-rtsa-sys`. The functions are shadowed by wrappers
+//! that call into the library, loaded on first use. If the library cannot be loaded, the
+//! wrappers return [`LIBRARY_NOT_FOUND`].
+//!
+//! The bindings are still generated from the SDK header at build time, i.e., the build machine
+//! needs the header, but not the library, and the machines that run the application need neither.
+#![allow(non_snake_case)]
+#![allow(clippy::missing_safety_doc)]
+
+pub use aaronia_rtsa_sys::*;
+use std::os::raw::c_int;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::runtime;
+
+/// Result code, returned if the library is not loaded.
+pub const LIBRARY_NOT_FOUND: AARTSAAPI_Result = 0xffff_ffff;
+
+macro_rules! functions {
+    ($(fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        struct Functions {
+            _lib: libloading::Library,
+            path: PathBuf,
+            $($name: unsafe extern "C" fn($($ty),*) -> $ret,)*
+        }
+
+        impl Functions {
+            unsafe fn load(path: &Path) -> Result<Self, libloading::Error> {
+                let lib = libloading::Library::new(path)?;
+                Ok(Self {
+                    $($name: *lib.get(concat!(stringify!($name), "\0").as_bytes())?,)*
+                    _lib: lib,
+                    path: path.into(),
+                })
+            }
+        }
+
+        $(
+            pub unsafe fn $name($($arg: $ty),*) -> $ret {
+                match functions() {
+                    Some(f) => (f.$name)($($arg),*),
+                    None => LIBRARY_NOT_FOUND,
+                }
+            }
+        )*
+    };
+}
+
+functions! {
+    fn AARTSAAPI_Init(memory: u32) -> AARTSAAPI_Result;
+    fn AARTSAAPI_Shutdown() -> AARTSAAPI_Result;
+    fn AARTSAAPI_Open(handle: *mut AARTSAAPI_Handle) -> AARTSAAPI_Result;
+    fn AARTSAAPI_Close(handle: *mut AARTSAAPI_Handle) -> AARTSAAPI_Result;
+    fn AARTSAAPI_RescanDevices(handle: *mut AARTSAAPI_Handle, timeout: c_int) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ResetDevices(handle: *mut AARTSAAPI_Handle) -> AARTSAAPI_Result;
+    fn AARTSAAPI_EnumDevice(
+        handle: *mut AARTSAAPI_Handle,
+        type_: *const wchar_t,
+        index: i32,
+        dinfo: *mut AARTSAAPI_DeviceInfo
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_OpenDevice(
+        handle: *mut AARTSAAPI_Handle,
+        dhandle: *mut AARTSAAPI_Device,
+        type_: *const wchar_t,
+        serial_number: *const wchar_t
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_CloseDevice(
+        handle: *mut AARTSAAPI_Handle,
+        dhandle: *mut AARTSAAPI_Device
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConnectDevice(dhandle: *mut AARTSAAPI_Device) -> AARTSAAPI_Result;
+    fn AARTSAAPI_DisconnectDevice(dhandle: *mut AARTSAAPI_Device) -> AARTSAAPI_Result;
+    fn AARTSAAPI_StartDevice(dhandle: *mut AARTSAAPI_Device) -> AARTSAAPI_Result;
+    fn AARTSAAPI_StopDevice(dhandle: *mut AARTSAAPI_Device) -> AARTSAAPI_Result;
+    fn AARTSAAPI_GetDeviceState(dhandle: *mut AARTSAAPI_Device) -> AARTSAAPI_Result;
+    fn AARTSAAPI_AvailPackets(
+        dhandle: *mut AARTSAAPI_Device,
+        channel: i32,
+        num: *mut i32
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_GetPacket(
+        dhandle: *mut AARTSAAPI_Device,
+        channel: i32,
+        index: i32,
+        packet: *mut AARTSAAPI_Packet
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConsumePackets(
+        dhandle: *mut AARTSAAPI_Device,
+        channel: i32,
+        num: i32
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_GetMasterStreamTime(
+        dhandle: *mut AARTSAAPI_Device,
+        stime: *mut f64
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_SendPacket(
+        dhandle: *mut AARTSAAPI_Device,
+        channel: i32,
+        packet: *const AARTSAAPI_Packet
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigRoot(
+        dhandle: *mut AARTSAAPI_Device,
+        config: *mut AARTSAAPI_Config
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigHealth(
+        dhandle: *mut AARTSAAPI_Device,
+        config: *mut AARTSAAPI_Config
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigFirst(
+        dhandle: *mut AARTSAAPI_Device,
+        group: *mut AARTSAAPI_Config,
+        config: *mut AARTSAAPI_Config
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigNext(
+        dhandle: *mut AARTSAAPI_Device,
+        group: *mut AARTSAAPI_Config,
+        config: *mut AARTSAAPI_Config
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigFind(
+        dhandle: *mut AARTSAAPI_Device,
+        group: *mut AARTSAAPI_Config,
+        config: *mut AARTSAAPI_Config,
+        name: *const wchar_t
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigGetInfo(
+        dhandle: *mut AARTSAAPI_Device,
+        config: *mut AARTSAAPI_Config,
+        cinfo: *mut AARTSAAPI_ConfigInfo
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigSetFloat(
+        dhandle: *mut AARTSAAPI_Device,
+        config: *mut AARTSAAPI_Config,
+        value: f64
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigGetFloat(
+        dhandle: *mut AARTSAAPI_Device,
+        config: *mut AARTSAAPI_Config,
+        value: *mut f64
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigSetString(
+        dhandle: *mut AARTSAAPI_Device,
+        config: *mut AARTSAAPI_Config,
+        value: *const wchar_t
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigSetInteger(
+        dhandle: *mut AARTSAAPI_Device,
+        config: *mut AARTSAAPI_Config,
+        value: i64
+    ) -> AARTSAAPI_Result;
+    fn AARTSAAPI_ConfigGetInteger(
+        dhandle: *mut AARTSAAPI_Device,
+        config: *mut AARTSAAPI_Config,
+        value: *mut i64
+    ) -> AARTSAAPI_Result;
+}
+
+// The version is not a result code, so it is looked up separately and reported as 0.0 if the
+// library is missing.
+pub unsafe fn AARTSAAPI_Version() -> u32 {
+    match functions() {
+        Some(f) => match f
+            ._lib
+            .get::<unsafe extern "C" fn() -> u32>(b"AARTSAAPI_Version\0")
+        {
+            Ok(v) => v(),
+            Err(_) => 0,
+        },
+        None => 0,
+    }
+}
+
+static FUNCTIONS: OnceLock<Functions> = OnceLock::new();
+
+/// Get the loaded library or load it from the candidates of [`runtime::candidates()`] or the
+/// system search path.
+///
+/// If the library cannot be loaded, the search is repeated on the next call.
+fn functions() -> Option<&'static Functions> {
+    if let Some(f) = FUNCTIONS.get() {
+        return Some(f);
+    }
+    let mut candidates = runtime::candidates();
+    candidates.push(runtime::LIBRARY_NAME.into());
+    let f = candidates
+        .iter()
+        .find_map(|p| unsafe { Functions::load(p) }.ok())?;
+    Some(FUNCTIONS.get_or_init(|| f))
+}
+
+/// Load the library from `path`, unless a library is loaded already.
+///
+/// Returns the path of the loaded library.
+pub fn load_from(path: &Path) -> Result<&'static Path, libloading::Error> {
+    if let Some(f) = FUNCTIONS.get() {
+        return Ok(&f.path);
+    }
+    let f = unsafe { Functions::load(path) }?;
+    Ok(&FUNCTIONS.get_or_init(|| f).path)
+}
+
+/// Path of the loaded library, without trying to load it.
+pub fn loaded_path() -> Option<&'static Path> {
+    FUNCTIONS.get().map(|f| f.path.as_path())
+}
+
+/// Check if the library could be loaded.
+pub fn loaded() -> bool {
+    functions().is_some()
+}
@@ -0,0 +1,1408 @@
+//! In-crate stub of the RTSA library, enabled with the `sys-stub` feature.
+//!
+//! The stub implements the C API surface that is used by the wrapper with a simulated device,
+//! so that the crate can be tested without the proprietary library. Simulated devices have a
+//! small configuration and health tree and produce a tone as IQ samples or a spectrum with a
+//! peak in the center.
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::HashMap;
+use std::os::raw::c_int;
+use std::os::raw::c_uint;
+use std::os::raw::c_void;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::Instant;
+use widestring::WideCStr;
+
+#[cfg(not(windows))]
+pub type wchar_t = u32;
+#[cfg(windows)]
+pub type wchar_t = u16;
+pub type AARTSAAPI_Result = u32;
+pub type AARTSAAPI_ConfigType = c_uint;
+
+pub const AARTSAAPI_MEMORY_SMALL: u32 = 0;
+pub const AARTSAAPI_MEMORY_MEDIUM: u32 = 1;
+pub const AARTSAAPI_MEMORY_LARGE: u32 = 2;
+pub const AARTSAAPI_MEMORY_LUDICROUS: u32 = 3;
+pub const AARTSAAPI_PACKET_STREAM_START: u32 = 1;
+pub const AARTSAAPI_PACKET_STREAM_END: u32 = 2;
+pub const AARTSAAPI_PACKET_SEGMENT_START: u32 = 4;
+pub const AARTSAAPI_PACKET_SEGMENT_END: u32 = 8;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct AARTSAAPI_Handle {
+    pub d: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct AARTSAAPI_Device {
+    pub d: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct AARTSAAPI_Config {
+    pub d: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct AARTSAAPI_DeviceInfo {
+    pub cbsize: i64,
+    pub serialNumber: [wchar_t; 120],
+    pub ready: bool,
+    pub boost: bool,
+    pub superspeed: bool,
+    pub active: bool,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct AARTSAAPI_Packet {
+    pub cbsize: i64,
+    pub streamID: u64,
+    pub flags: u64,
+    pub startTime: f64,
+    pub endTime: f64,
+    pub startFrequency: f64,
+    pub stepFrequency: f64,
+    pub spanFrequency: f64,
+    pub rbwFrequency: f64,
+    pub num: i64,
+    pub total: i64,
+    pub size: i64,
+    pub stride: i64,
+    pub fp32: *mut f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct AARTSAAPI_ConfigInfo {
+    pub cbsize: i64,
+    pub name: [wchar_t; 80],
+    pub title: [wchar_t; 120],
+    pub type_: AARTSAAPI_ConfigType,
+    pub minValue: f64,
+    pub maxValue: f64,
+    pub stepValue: f64,
+    pub unit: [wchar_t; 10],
+    pub options: [wchar_t; 1000],
+    pub disabledOptions: u64,
+}
+
+const OK: u32 = 0x00000000;
+const EMPTY: u32 = 0x00000001;
+const RETRY: u32 = 0x00000002;
+const WARNING_VALUE_ADJUSTED: u32 = 0x40000001;
+const IDLE: u32 = 0x10000000;
+const CONNECTED: u32 = 0x10000002;
+const RUNNING: u32 = 0x10000004;
+const ERROR_NOT_INITIALIZED: u32 = 0x80000001;
+const ERROR_NOT_FOUND: u32 = 0x80000002;
+const ERROR_BUSY: u32 = 0x80000003;
+const ERROR_NOT_OPEN: u32 = 0x80000004;
+const ERROR_NOT_CONNECTED: u32 = 0x80000005;
+const ERROR_INVALID_CONFIG: u32 = 0x80000006;
+const ERROR_BUFFER_SIZE: u32 = 0x80000007;
+const ERROR_INVALID_CHANNEL: u32 = 0x80000008;
+const ERROR_VALUE_INVALID: u32 = 0x8000000c;
+
+const GROUP: c_uint = 1;
+const NUMBER: c_uint = 3;
+const BOOL: c_uint = 4;
+const ENUM: c_uint = 5;
+const STRING: c_uint = 6;
+
+/// Samples or bins per packet.
+const PACKET_LEN: usize = 1024;
+/// Packets that are reported as available while running.
+const QUEUE_LEN: i32 = 8;
+const CLOCKS: [f64; 4] = [92e6, 122e6, 184e6, 245e6];
+
+#[derive(Debug, Clone)]
+enum Value {
+    None,
+    Float(f64),
+    Int(i64),
+    String(String),
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    name: &'static str,
+    title: &'static str,
+    kind: c_uint,
+    value: Value,
+    options: &'static [&'static str],
+    disabled: u64,
+    /// Number of the following sets that fail.
+    failures: usize,
+    min: f64,
+    max: f64,
+    /// Step of numbers, values in between are rounded with a warning.
+    step: f64,
+    children: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Idle,
+    Connected,
+    Running,
+}
+
+struct Channel {
+    data: Vec<f32>,
+    time: f64,
+    current: bool,
+}
+
+struct DevState {
+    serial: String,
+    status: Status,
+    lost: bool,
+    nodes: Vec<Node>,
+    health: usize,
+    channels: HashMap<i32, Channel>,
+    started: Instant,
+    spectra_rows: usize,
+    /// Override of the `num` field and null payload of malformed data packets.
+    malformed: Option<(Option<i64>, bool)>,
+    /// Number of polls of data channels that found no packet.
+    empty_polls: usize,
+    status_records: std::collections::VecDeque<(f64, f32, f32)>,
+    status_data: Vec<f32>,
+    /// End times of the sent packets, `None` until the first packet is sent after the start.
+    tx_queue: Option<Vec<f64>>,
+}
+
+struct Stub {
+    initialized: bool,
+    /// Open library handles.
+    handles: usize,
+    devices: Vec<String>,
+    open: HashMap<usize, DevState>,
+    next_id: usize,
+    calls: Vec<String>,
+    sent: HashMap<String, Vec<(f64, f64, usize)>>,
+    rescan_retries: usize,
+    suite: Vec<String>,
+    /// Devices that reset or reboot with the number of rescans until they reappear. They are
+    /// still listed in the first rescan and missing in the second.
+    rebooting: Vec<(String, usize)>,
+}
+
+static STUB: Mutex<Option<Stub>> = Mutex::new(None);
+
+fn stub() -> MutexGuard<'static, Option<Stub>> {
+    let mut s = STUB.lock().unwrap_or_else(|e| e.into_inner());
+    if s.is_none() {
+        *s = Some(Stub {
+            initialized: false,
+            handles: 0,
+            devices: vec![control::DEFAULT_SERIAL.to_string()],
+            open: HashMap::new(),
+            next_id: 1,
+            calls: Vec::new(),
+            sent: HashMap::new(),
+            rescan_retries: 0,
+            suite: Vec::new(),
+            rebooting: Vec::new(),
+        });
+    }
+    s
+}
+
+fn log(s: &mut Stub, call: &str) {
+    s.calls.push(call.to_string());
+}
+
+fn write_wide(dst: &mut [wchar_t], s: &str) {
+    let n = dst.len() - 1;
+    let mut i = 0;
+    for c in widestring::WideString::from_str(s)
+        .as_slice()
+        .iter()
+        .take(n)
+    {
+        dst[i] = *c;
+        i += 1;
+    }
+    dst[i] = 0;
+}
+
+unsafe fn read_wide(s: *const wchar_t) -> String {
+    WideCStr::from_ptr_str(s).to_string_lossy()
+}
+
+fn leaf(name: &'static str, kind: c_uint, value: Value) -> Node {
+    Node {
+        name,
+        title: name,
+        kind,
+        value,
+        options: &[],
+        disabled: 0,
+        failures: 0,
+        min: f64::MIN,
+        max: f64::MAX,
+        step: 0.0,
+        children: Vec::new(),
+    }
+}
+
+fn number(name: &'static str, value: f64, min: f64, max: f64) -> Node {
+    Node {
+        min,
+        max,
+        ..leaf(name, NUMBER, Value::Float(value))
+    }
+}
+
+fn enumeration(name: &'static str, value: i64, options: &'static [&'static str]) -> Node {
+    Node {
+        options,
+        ..leaf(name, ENUM, Value::Int(value))
+    }
+}
+
+fn tree() -> (Vec<Node>, usize) {
+    let mut nodes = Vec::new();
+    let mut add = |n: Node| {
+        nodes.push(n);
+        nodes.len() - 1
+    };
+
+    let root = add(leaf("root", GROUP, Value::None));
+    let main = add(leaf("main", GROUP, Value::None));
+    let centerfreq = add(number("centerfreq", 2.44e9, 193e6, 6e9));
+    let reflevel = add(number("reflevel", -20.0, -100.0, 10.0));
+    let transgain = add(Node {
+        step: 0.5,
+        ..number("transgain", 0.0, -100.0, 10.0)
+    });
+    let decimation = add(enumeration(
+        "decimation",
+        0,
+        &[
+            "Full", "1 / 2", "1 / 4", "1 / 8", "1 / 16", "1 / 32", "1 / 64", "1 / 128", "1 / 256",
+            "1 / 512",
+        ],
+    ));
+    let device = add(leaf("device", GROUP, Value::None));
+    let receiverchannel = add(enumeration(
+        "receiverchannel",
+        0,
+        &["Rx1", "Rx2", "Rx12", "Rx1+Rx2", "Rx Off"],
+    ));
+    let antenna = add(enumeration("antenna", 0, &["RF1", "RF2"]));
+    let preamp = add(Node {
+        title: "Preamplifier (+20 dB)",
+        ..leaf("preamp", BOOL, Value::Int(0))
+    });
+    let outputformat = add(enumeration(
+        "outputformat",
+        0,
+        &["iq", "spectra", "both", "raw"],
+    ));
+    let receiverclock = add(enumeration(
+        "receiverclock",
+        0,
+        &["92MHz", "122MHz", "184MHz", "245MHz"],
+    ));
+    let referenceclock = add(enumeration(
+        "referenceclock",
+        0,
+        &["Internal", "External 10MHz", "GPS"],
+    ));
+    let gaincontrol = add(enumeration("gaincontrol", 0, &["manual", "peak", "power"]));
+    let name = add(leaf("name", STRING, Value::String("stub".into())));
+    let calibrate = add(leaf("calibrate", BOOL, Value::None));
+    let reset = add(leaf("reset", BOOL, Value::None));
+    let reboot = add(leaf("reboot", BOOL, Value::None));
+    let boost = add(leaf("boost", BOOL, Value::Int(0)));
+    let usbcompatibility = add(leaf("usbcompatibility", BOOL, Value::Int(0)));
+
+    let health = add(leaf("health", GROUP, Value::None));
+    let temperature = add(number("temperature", 40.0, -40.0, 120.0));
+    let overload = add(leaf("overload", BOOL, Value::Int(0)));
+    let reflock = add(leaf("reflock", BOOL, Value::Int(1)));
+    let product = add(leaf(
+        "productname",
+        STRING,
+        Value::String("SPECTRAN V6 ECO".into()),
+    ));
+    let firmware = add(leaf("firmware", STRING, Value::String("1.4.2".into())));
+    let calibdate = add(leaf(
+        "calibdate",
+        STRING,
+        Value::String("2024-03-01".into()),
+    ));
+    let options = add(leaf("options", STRING, Value::String("RTBW245, TX".into())));
+
+    nodes[root].children = vec![main, device];
+    nodes[main].children = vec![centerfreq, reflevel, transgain, decimation];
+    nodes[device].children = vec![
+        receiverchannel,
+        antenna,
+        preamp,
+        outputformat,
+        receiverclock,
+        referenceclock,
+        gaincontrol,
+        name,
+        calibrate,
+        reset,
+        reboot,
+        boost,
+        usbcompatibility,
+    ];
+    nodes[health].children = vec![
+        temperature,
+        overload,
+        reflock,
+        product,
+        firmware,
+        calibdate,
+        options,
+    ];
+
+    (nodes, health)
+}
+
+impl DevState {
+    fn new(serial: String) -> Self {
+        let (nodes, health) = tree();
+        Self {
+            serial,
+            status: Status::Idle,
+            lost: false,
+            nodes,
+            health,
+            channels: HashMap::new(),
+            started: Instant::now(),
+            tx_queue: None,
+            spectra_rows: 1,
+            malformed: None,
+            empty_polls: 0,
+            status_records: Default::default(),
+            status_data: Vec::new(),
+        }
+    }
+
+    fn find(&self, path: &str) -> Option<usize> {
+        let mut node = 0;
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            node = *self.nodes[node]
+                .children
+                .iter()
+                .find(|c| self.nodes[**c].name == part)?;
+        }
+        Some(node)
+    }
+
+    fn enum_value(&self, path: &str) -> usize {
+        match self.find(path).map(|n| &self.nodes[n].value) {
+            Some(Value::Int(i)) => *i as usize,
+            _ => 0,
+        }
+    }
+
+    fn float_value(&self, path: &str) -> f64 {
+        match self.find(path).map(|n| &self.nodes[n].value) {
+            Some(Value::Float(f)) => *f,
+            _ => 0.0,
+        }
+    }
+
+    fn sample_rate(&self) -> f64 {
+        let clock = CLOCKS[self
+            .enum_value("device/receiverclock")
+            .min(CLOCKS.len() - 1)];
+        clock / (1 << self.enum_value("main/decimation")) as f64
+    }
+
+    /// Payload of a channel with the configured output format: `Some(true)` for IQ samples,
+    /// `Some(false)` for spectra.
+    fn payload(&self, chan: i32) -> Option<bool> {
+        match (self.enum_value("device/outputformat"), chan) {
+            (0 | 2 | 3, 0 | 1) => Some(true),
+            (1 | 2, 2) => Some(false),
+            _ => None,
+        }
+    }
+
+    fn fill(&mut self, chan: i32, iq: bool, packet: &mut AARTSAAPI_Packet) {
+        let rate = self.sample_rate();
+        let center = self.float_value("main/centerfreq");
+        let reflevel = self.float_value("main/reflevel") as f32;
+        // Rx1+Rx2 interleaves the samples of both receivers on the first data channel
+        let interleaved = if iq && chan == 0 && self.enum_value("device/receiverchannel") == 3 {
+            2
+        } else {
+            1
+        };
+        let ch = self.channels.entry(chan).or_insert_with(|| Channel {
+            data: Vec::new(),
+            time: 0.0,
+            current: false,
+        });
+
+        let rows = if iq { 1 } else { self.spectra_rows };
+        // pad multi-row spectra to test the stride
+        let stride = if rows > 1 { PACKET_LEN + 8 } else { PACKET_LEN };
+        let duration = (rows * PACKET_LEN) as f64 / rate;
+        if !ch.current {
+            ch.data.clear();
+            if iq {
+                // tone at an eighth of the sample rate, 6 dB below full scale, with a phase
+                // offset on the second receiver
+                let n0 = (ch.time * rate).round() as usize;
+                for i in 0..PACKET_LEN {
+                    for rx in 0..interleaved {
+                        let offset = if chan == 1 || rx == 1 {
+                            control::RX2_PHASE
+                        } else {
+                            0.0
+                        };
+                        let phase =
+                            2.0 * std::f32::consts::PI * ((n0 + i) % 8) as f32 / 8.0 + offset;
+                        ch.data.push(0.5 * phase.cos());
+                        ch.data.push(0.5 * phase.sin());
+                    }
+                }
+            } else {
+                for r in 0..rows {
+                    for i in 0..stride {
+                        let v = if i >= PACKET_LEN {
+                            f32::NAN
+                        } else if i == PACKET_LEN / 2 {
+                            reflevel - 6.0 - r as f32
+                        } else {
+                            -120.0
+                        };
+                        ch.data.push(v);
+                    }
+                }
+            }
+            ch.current = true;
+        }
+
+        packet.streamID = chan as u64;
+        packet.flags = if ch.time == 0.0 {
+            AARTSAAPI_PACKET_STREAM_START as u64
+        } else {
+            0
+        };
+        packet.startTime = ch.time;
+        packet.endTime = ch.time + duration;
+        packet.spanFrequency = rate;
+        packet.rbwFrequency = rate / PACKET_LEN as f64;
+        packet.total = PACKET_LEN as i64;
+        packet.fp32 = ch.data.as_mut_ptr();
+        if iq {
+            packet.startFrequency = center;
+            packet.stepFrequency = rate;
+            packet.num = PACKET_LEN as i64;
+            packet.size = 2;
+            packet.stride = 2 * interleaved as i64;
+        } else {
+            packet.startFrequency = center - rate / 2.0;
+            packet.stepFrequency = rate / PACKET_LEN as f64;
+            packet.num = rows as i64;
+            packet.size = PACKET_LEN as i64;
+            packet.stride = stride as i64;
+        }
+        if let Some((num, null)) = self.malformed {
+            packet.num = num.unwrap_or(packet.num);
+            if null {
+                packet.fp32 = std::ptr::null_mut();
+            }
+        }
+    }
+}
+
+impl DevState {
+    /// Status packet with the oldest queued record.
+    fn fill_status(&mut self, packet: &mut AARTSAAPI_Packet) -> u32 {
+        let Some(&(time, kind, value)) = self.status_records.front() else {
+            return EMPTY;
+        };
+        self.status_data = vec![kind, value];
+        *packet = AARTSAAPI_Packet {
+            cbsize: packet.cbsize,
+            streamID: crate::STATUS_CHANNEL as u64,
+            flags: 0,
+            startTime: time,
+            endTime: time,
+            startFrequency: 0.0,
+            stepFrequency: 0.0,
+            spanFrequency: 0.0,
+            rbwFrequency: 0.0,
+            num: 1,
+            total: 1,
+            size: 2,
+            stride: 2,
+            fp32: self.status_data.as_mut_ptr(),
+        };
+        OK
+    }
+}
+
+fn dev_id(d: *const AARTSAAPI_Device) -> usize {
+    unsafe { (*d).d as usize }
+}
+
+fn node_id(c: *const AARTSAAPI_Config) -> usize {
+    unsafe { (*c).d as usize - 1 }
+}
+
+fn set_node(c: *mut AARTSAAPI_Config, node: usize) {
+    unsafe { (*c).d = (node + 1) as *mut c_void };
+}
+
+/// Run `f` on an open device.
+fn with_dev<F: FnOnce(&mut DevState) -> u32>(d: *const AARTSAAPI_Device, f: F) -> u32 {
+    let mut s = stub();
+    match s.as_mut().unwrap().open.get_mut(&dev_id(d)) {
+        Some(dev) => f(dev),
+        None => ERROR_NOT_OPEN,
+    }
+}
+
+pub unsafe extern "C" fn AARTSAAPI_Version() -> u32 {
+    0x0001_0000
+}
+
+pub unsafe extern "C" fn AARTSAAPI_Init(_memory: u32) -> AARTSAAPI_Result {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    if s.initialized {
+        return ERROR_BUSY;
+    }
+    log(s, "Init");
+    s.initialized = true;
+    OK
+}
+
+pub unsafe extern "C" fn AARTSAAPI_Shutdown() -> AARTSAAPI_Result {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    if !s.initialized {
+        return ERROR_NOT_INITIALIZED;
+    }
+    // library handles and devices have to be closed before
+    if s.handles > 0 || !s.open.is_empty() {
+        return ERROR_BUSY;
+    }
+    log(s, "Shutdown");
+    s.initialized = false;
+    OK
+}
+
+pub unsafe extern "C" fn AARTSAAPI_Open(handle: *mut AARTSAAPI_Handle) -> AARTSAAPI_Result {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    if !s.initialized {
+        return ERROR_NOT_INITIALIZED;
+    }
+    log(s, "Open");
+    s.handles += 1;
+    (*handle).d = std::ptr::dangling_mut();
+    OK
+}
+
+pub unsafe extern "C" fn AARTSAAPI_Close(_handle: *mut AARTSAAPI_Handle) -> AARTSAAPI_Result {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    if !s.initialized || s.handles == 0 {
+        return ERROR_NOT_INITIALIZED;
+    }
+    log(s, "Close");
+    s.handles -= 1;
+    OK
+}
+
+pub unsafe extern "C" fn AARTSAAPI_RescanDevices(
+    _handle: *mut AARTSAAPI_Handle,
+    _timeout: c_int,
+) -> AARTSAAPI_Result {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    if s.rescan_retries > 0 {
+        s.rescan_retries -= 1;
+        RETRY
+    } else {
+        for (serial, rescans) in &mut s.rebooting {
+            *rescans -= 1;
+            match rescans {
+                // the device left the bus
+                1 => s.devices.retain(|d| d != serial),
+                0 if !s.devices.contains(serial) => s.devices.push(serial.clone()),
+                _ => {}
+            }
+        }
+        s.rebooting.retain(|(_, rescans)| *rescans > 0);
+        OK
+    }
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ResetDevices(
+    _handle: *mut AARTSAAPI_Handle,
+) -> AARTSAAPI_Result {
+    OK
+}
+
+pub unsafe extern "C" fn AARTSAAPI_EnumDevice(
+    _handle: *mut AARTSAAPI_Handle,
+    _type: *const wchar_t,
+    index: i32,
+    dinfo: *mut AARTSAAPI_DeviceInfo,
+) -> AARTSAAPI_Result {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    match s.devices.get(index as usize) {
+        Some(serial) => {
+            write_wide(&mut (*dinfo).serialNumber, serial);
+            (*dinfo).ready = true;
+            (*dinfo).boost = false;
+            (*dinfo).superspeed = true;
+            (*dinfo).active = s.open.values().any(|d| d.serial == *serial);
+            OK
+        }
+        None => EMPTY,
+    }
+}
+
+pub unsafe extern "C" fn AARTSAAPI_OpenDevice(
+    _handle: *mut AARTSAAPI_Handle,
+    dhandle: *mut AARTSAAPI_Device,
+    type_: *const wchar_t,
+    serialNumber: *const wchar_t,
+) -> AARTSAAPI_Result {
+    let serial = read_wide(serialNumber);
+    // file devices replay the recording at the path in place of the serial
+    let device_type = read_wide(type_);
+    let file = device_type.starts_with("spectranfile");
+    // shared devices attach through a suite that holds the device
+    let shared = device_type == crate::SHARED_DEVICE_TYPE;
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    if !s.initialized {
+        return ERROR_NOT_INITIALIZED;
+    }
+    let found = if file {
+        std::path::Path::new(&serial).is_file()
+    } else if shared {
+        s.devices.contains(&serial) && s.suite.contains(&serial)
+    } else {
+        s.devices.contains(&serial)
+    };
+    if !found {
+        return ERROR_NOT_FOUND;
+    }
+    if !shared && (s.suite.contains(&serial) || s.open.values().any(|d| d.serial == serial)) {
+        return ERROR_BUSY;
+    }
+    log(s, "OpenDevice");
+    let id = s.next_id;
+    s.next_id += 1;
+    s.open.insert(id, DevState::new(serial));
+    (*dhandle).d = id as *mut c_void;
+    OK
+}
+
+pub unsafe extern "C" fn AARTSAAPI_CloseDevice(
+    _handle: *mut AARTSAAPI_Handle,
+    dhandle: *mut AARTSAAPI_Device,
+) -> AARTSAAPI_Result {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    match s.open.remove(&dev_id(dhandle)) {
+        Some(_) => {
+            log(s, "CloseDevice");
+            OK
+        }
+        None => ERROR_NOT_OPEN,
+    }
+}
+
+fn transition(d: *mut AARTSAAPI_Device, call: &str, from: Status, to: Status) -> u32 {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    let r = match s.open.get_mut(&dev_id(d)) {
+        Some(dev) if dev.lost && (to == Status::Running || from == Status::Idle) => {
+            ERROR_NOT_CONNECTED
+        }
+        Some(dev) if dev.status == from => {
+            dev.status = to;
+            if to == Status::Running {
+                dev.started = Instant::now();
+                dev.channels.clear();
+                dev.tx_queue = None;
+            }
+            OK
+        }
+        Some(_) => ERROR_NOT_CONNECTED,
+        None => ERROR_NOT_OPEN,
+    };
+    if r == OK {
+        log(s, call);
+    }
+    r
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConnectDevice(
+    dhandle: *mut AARTSAAPI_Device,
+) -> AARTSAAPI_Result {
+    transition(dhandle, "ConnectDevice", Status::Idle, Status::Connected)
+}
+
+pub unsafe extern "C" fn AARTSAAPI_DisconnectDevice(
+    dhandle: *mut AARTSAAPI_Device,
+) -> AARTSAAPI_Result {
+    transition(dhandle, "DisconnectDevice", Status::Connected, Status::Idle)
+}
+
+pub unsafe extern "C" fn AARTSAAPI_StartDevice(dhandle: *mut AARTSAAPI_Device) -> AARTSAAPI_Result {
+    transition(dhandle, "StartDevice", Status::Connected, Status::Running)
+}
+
+pub unsafe extern "C" fn AARTSAAPI_StopDevice(dhandle: *mut AARTSAAPI_Device) -> AARTSAAPI_Result {
+    transition(dhandle, "StopDevice", Status::Running, Status::Connected)
+}
+
+pub unsafe extern "C" fn AARTSAAPI_GetDeviceState(
+    dhandle: *mut AARTSAAPI_Device,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| match d.status {
+        Status::Idle => IDLE,
+        Status::Connected => CONNECTED,
+        Status::Running => RUNNING,
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_AvailPackets(
+    dhandle: *mut AARTSAAPI_Device,
+    channel: i32,
+    num: *mut i32,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| {
+        if d.lost {
+            return ERROR_NOT_CONNECTED;
+        }
+        if channel == crate::STATUS_CHANNEL {
+            *num = d.status_records.len() as i32;
+            return OK;
+        }
+        if d.payload(channel).is_none() {
+            return ERROR_INVALID_CHANNEL;
+        }
+        // transmitting devices report the packets, which are not yet transmitted
+        if let Some(q) = &mut d.tx_queue {
+            let now = d.started.elapsed().as_secs_f64();
+            q.retain(|end| *end > now);
+            *num = q.len() as i32;
+            return OK;
+        }
+        *num = if d.status == Status::Running {
+            QUEUE_LEN
+        } else {
+            0
+        };
+        OK
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_GetPacket(
+    dhandle: *mut AARTSAAPI_Device,
+    channel: i32,
+    index: i32,
+    packet: *mut AARTSAAPI_Packet,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| {
+        if d.lost || d.status == Status::Idle {
+            return ERROR_NOT_CONNECTED;
+        }
+        if channel == crate::STATUS_CHANNEL {
+            return d.fill_status(&mut *packet);
+        }
+        let Some(iq) = d.payload(channel) else {
+            return ERROR_INVALID_CHANNEL;
+        };
+        if d.status != Status::Running {
+            d.empty_polls += 1;
+            return EMPTY;
+        }
+        if index != 0 {
+            return ERROR_BUFFER_SIZE;
+        }
+        d.fill(channel, iq, &mut *packet);
+        OK
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConsumePackets(
+    dhandle: *mut AARTSAAPI_Device,
+    channel: i32,
+    num: i32,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| {
+        if d.lost {
+            return ERROR_NOT_CONNECTED;
+        }
+        if channel == crate::STATUS_CHANNEL {
+            for _ in 0..num {
+                d.status_records.pop_front();
+            }
+            return OK;
+        }
+        let rate = d.sample_rate();
+        let rows = match d.payload(channel) {
+            Some(false) => d.spectra_rows,
+            _ => 1,
+        };
+        if let Some(ch) = d.channels.get_mut(&channel) {
+            ch.time += (num as usize * rows * PACKET_LEN) as f64 / rate;
+            ch.current = false;
+        }
+        OK
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_GetMasterStreamTime(
+    dhandle: *mut AARTSAAPI_Device,
+    stime: *mut f64,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| {
+        *stime = d.started.elapsed().as_secs_f64();
+        OK
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_SendPacket(
+    dhandle: *mut AARTSAAPI_Device,
+    _channel: i32,
+    packet: *const AARTSAAPI_Packet,
+) -> AARTSAAPI_Result {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    let p = &*packet;
+    let (r, serial) = match s.open.get_mut(&dev_id(dhandle)) {
+        Some(d) if d.lost || d.status != Status::Running => (ERROR_NOT_CONNECTED, None),
+        Some(d) => {
+            d.tx_queue.get_or_insert_with(Vec::new).push(p.endTime);
+            (OK, Some(d.serial.clone()))
+        }
+        None => (ERROR_NOT_OPEN, None),
+    };
+    if let Some(serial) = serial {
+        s.sent
+            .entry(serial)
+            .or_default()
+            .push((p.startTime, p.startFrequency, p.num as usize));
+    }
+    r
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigRoot(
+    dhandle: *mut AARTSAAPI_Device,
+    config: *mut AARTSAAPI_Config,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |_| {
+        set_node(config, 0);
+        OK
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigHealth(
+    dhandle: *mut AARTSAAPI_Device,
+    config: *mut AARTSAAPI_Config,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| {
+        set_node(config, d.health);
+        OK
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigFirst(
+    dhandle: *mut AARTSAAPI_Device,
+    group: *mut AARTSAAPI_Config,
+    config: *mut AARTSAAPI_Config,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| {
+        match d.nodes[node_id(group)].children.first() {
+            Some(c) => {
+                set_node(config, *c);
+                OK
+            }
+            None => EMPTY,
+        }
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigNext(
+    dhandle: *mut AARTSAAPI_Device,
+    group: *mut AARTSAAPI_Config,
+    config: *mut AARTSAAPI_Config,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| {
+        let children = &d.nodes[node_id(group)].children;
+        let current = node_id(config);
+        match children.iter().position(|c| *c == current) {
+            Some(i) if i + 1 < children.len() => {
+                set_node(config, children[i + 1]);
+                OK
+            }
+            _ => EMPTY,
+        }
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigFind(
+    dhandle: *mut AARTSAAPI_Device,
+    group: *mut AARTSAAPI_Config,
+    config: *mut AARTSAAPI_Config,
+    name: *const wchar_t,
+) -> AARTSAAPI_Result {
+    let path = read_wide(name);
+    with_dev(dhandle, |d| match find(d, node_id(group), &path) {
+        Some(node) => {
+            set_node(config, node);
+            OK
+        }
+        None => ERROR_NOT_FOUND,
+    })
+}
+
+/// Node at `path`, relative to `group`.
+fn find(d: &DevState, group: usize, path: &str) -> Option<usize> {
+    let mut node = group;
+    for part in path.split('/').filter(|p| !p.is_empty()) {
+        node = *d.nodes[node]
+            .children
+            .iter()
+            .find(|c| d.nodes[**c].name == part)?;
+    }
+    Some(node)
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigGetInfo(
+    dhandle: *mut AARTSAAPI_Device,
+    config: *mut AARTSAAPI_Config,
+    cinfo: *mut AARTSAAPI_ConfigInfo,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| {
+        let n = &d.nodes[node_id(config)];
+        let info = &mut *cinfo;
+        write_wide(&mut info.name, n.name);
+        write_wide(&mut info.title, n.title);
+        write_wide(&mut info.unit, "");
+        info.type_ = n.kind;
+        info.minValue = n.min;
+        info.maxValue = n.max;
+        info.stepValue = n.step;
+        info.disabledOptions = n.disabled;
+        match &n.value {
+            Value::String(v) => write_wide(&mut info.options, v),
+            _ => write_wide(&mut info.options, &n.options.join(";")),
+        }
+        OK
+    })
+}
+
+/// Press a button, i.e., a boolean node without value.
+fn press(d: *mut AARTSAAPI_Device, config: *mut AARTSAAPI_Config) -> u32 {
+    let mut s = stub();
+    let s = s.as_mut().unwrap();
+    let Some(dev) = s.open.get_mut(&dev_id(d)) else {
+        return ERROR_NOT_OPEN;
+    };
+    if dev.lost || dev.status == Status::Idle {
+        return ERROR_NOT_CONNECTED;
+    }
+    let name = dev.nodes[node_id(config)].name;
+    if name == "reset" || name == "reboot" {
+        // the hardware drops the connection, loses its configuration, and re-enumerates
+        dev.status = Status::Idle;
+        dev.channels.clear();
+        dev.nodes = tree().0;
+        dev.lost = true;
+        let serial = dev.serial.clone();
+        s.rebooting.push((serial, 3));
+    }
+    log(s, &format!("Press {name}"));
+    OK
+}
+
+fn set(d: *mut AARTSAAPI_Device, config: *mut AARTSAAPI_Config, value: Value) -> u32 {
+    let button = stub()
+        .as_ref()
+        .unwrap()
+        .open
+        .get(&dev_id(d))
+        .is_some_and(|dev| {
+            let n = &dev.nodes[node_id(config)];
+            n.kind == BOOL && matches!(n.value, Value::None)
+        });
+    if button {
+        return press(d, config);
+    }
+    with_dev(d, |d| {
+        let n = &mut d.nodes[node_id(config)];
+        if n.failures > 0 {
+            n.failures -= 1;
+            return ERROR_BUSY;
+        }
+        let value = match (n.kind, value) {
+            (NUMBER, Value::Float(f)) => Value::Float(f),
+            (NUMBER, Value::Int(i)) => Value::Float(i as f64),
+            (NUMBER, Value::String(s)) => match s.trim().parse::<f64>() {
+                Ok(f) => Value::Float(f),
+                Err(_) => return ERROR_VALUE_INVALID,
+            },
+            (ENUM, Value::Int(i)) if (i as usize) < n.options.len() => Value::Int(i),
+            (ENUM, Value::String(s)) => match n.options.iter().position(|o| *o == s) {
+                Some(i) => Value::Int(i as i64),
+                None => match s.trim().parse::<usize>() {
+                    Ok(i) if i < n.options.len() => Value::Int(i as i64),
+                    _ => return ERROR_VALUE_INVALID,
+                },
+            },
+            (BOOL, Value::Int(i)) => Value::Int(i),
+            (BOOL, Value::String(s)) => match s.as_str() {
+                "true" | "1" => Value::Int(1),
+                "false" | "0" => Value::Int(0),
+                _ => return ERROR_VALUE_INVALID,
+            },
+            (STRING, Value::String(s)) => Value::String(s),
+            _ => return ERROR_VALUE_INVALID,
+        };
+        if let Value::Float(f) = value {
+            if f < n.min || f > n.max {
+                return ERROR_VALUE_INVALID;
+            }
+            if n.step > 0.0 {
+                let rounded = n.min + ((f - n.min) / n.step).round() * n.step;
+                if rounded != f {
+                    n.value = Value::Float(rounded.min(n.max));
+                    return WARNING_VALUE_ADJUSTED;
+                }
+            }
+        }
+        if let (ENUM, Value::Int(i)) = (n.kind, &value) {
+            if *i < 64 && n.disabled & (1 << i) != 0 {
+                return ERROR_VALUE_INVALID;
+            }
+        }
+        n.value = value;
+        OK
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigSetFloat(
+    dhandle: *mut AARTSAAPI_Device,
+    config: *mut AARTSAAPI_Config,
+    value: f64,
+) -> AARTSAAPI_Result {
+    set(dhandle, config, Value::Float(value))
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigGetFloat(
+    dhandle: *mut AARTSAAPI_Device,
+    config: *mut AARTSAAPI_Config,
+    value: *mut f64,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| match d.nodes[node_id(config)].value {
+        Value::Float(f) => {
+            *value = f;
+            OK
+        }
+        Value::Int(i) => {
+            *value = i as f64;
+            OK
+        }
+        _ => ERROR_INVALID_CONFIG,
+    })
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigSetString(
+    dhandle: *mut AARTSAAPI_Device,
+    config: *mut AARTSAAPI_Config,
+    value: *const wchar_t,
+) -> AARTSAAPI_Result {
+    set(dhandle, config, Value::String(read_wide(value)))
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigSetInteger(
+    dhandle: *mut AARTSAAPI_Device,
+    config: *mut AARTSAAPI_Config,
+    value: i64,
+) -> AARTSAAPI_Result {
+    set(dhandle, config, Value::Int(value))
+}
+
+pub unsafe extern "C" fn AARTSAAPI_ConfigGetInteger(
+    dhandle: *mut AARTSAAPI_Device,
+    config: *mut AARTSAAPI_Config,
+    value: *mut i64,
+) -> AARTSAAPI_Result {
+    with_dev(dhandle, |d| match d.nodes[node_id(config)].value {
+        Value::Int(i) => {
+            *value = i;
+            OK
+        }
+        Value::Float(f) => {
+            *value = f as i64;
+            OK
+        }
+        _ => ERROR_INVALID_CONFIG,
+    })
+}
+
+/// Control of the simulated devices of the `sys-stub` feature.
+pub mod control {
+    use super::stub;
+    use super::Value;
+
+    /// Serial number of the device that is present by default.
+    pub const DEFAULT_SERIAL: &str = "STUB0001";
+
+    /// Phase of the test tone on the second receiver (data channel 1) relative to the first in
+    /// radians.
+    pub const RX2_PHASE: f32 = std::f32::consts::FRAC_PI_4;
+
+    /// Reset the stub to a single device with [`DEFAULT_SERIAL`], clearing the call log.
+    ///
+    /// Open devices are closed, i.e., this must not be called while [`Device`](crate::Device)s
+    /// are in use.
+    pub fn reset() {
+        let mut s = stub();
+        let s = s.as_mut().unwrap();
+        s.devices = vec![DEFAULT_SERIAL.to_string()];
+        s.open.clear();
+        s.calls.clear();
+        s.sent.clear();
+        s.rescan_retries = 0;
+        s.suite.clear();
+        s.rebooting.clear();
+    }
+
+    /// Simulate RTSA-Suite holding a device.
+    ///
+    /// While held, exclusive opens fail with [`Error::DeviceInUse`](crate::Error::DeviceInUse)
+    /// and the device can only be attached with
+    /// [`Device::open_shared()`](crate::Device::open_shared).
+    pub fn set_suite_holds(serial: &str, held: bool) {
+        let mut s = stub();
+        let s = s.as_mut().unwrap();
+        s.suite.retain(|d| d != serial);
+        if held {
+            s.suite.push(serial.to_string());
+        }
+    }
+
+    /// Set the serial numbers of the connected devices.
+    pub fn set_devices(serials: &[&str]) {
+        let mut s = stub();
+        s.as_mut().unwrap().devices = serials.iter().map(|s| s.to_string()).collect();
+    }
+
+    /// Simulate unplugging a device.
+    ///
+    /// The device is removed from the device list and open handles fail with
+    /// [`Error::ErrorNotConnected`](crate::Error::ErrorNotConnected) until they are closed.
+    pub fn unplug(serial: &str) {
+        let mut s = stub();
+        let s = s.as_mut().unwrap();
+        s.devices.retain(|d| d != serial);
+        for d in s.open.values_mut().filter(|d| d.serial == serial) {
+            d.lost = true;
+        }
+    }
+
+    /// Simulate plugging a device in.
+    pub fn plug(serial: &str) {
+        let mut s = stub();
+        let s = s.as_mut().unwrap();
+        if !s.devices.iter().any(|d| d == serial) {
+            s.devices.push(serial.to_string());
+        }
+    }
+
+    /// Check if device `serial` is re-enumerating after a reset or reboot, i.e., it did not
+    /// reappear in a rescan yet.
+    pub fn reenumerating(serial: &str) -> bool {
+        let s = stub();
+        s.as_ref()
+            .unwrap()
+            .rebooting
+            .iter()
+            .any(|(d, _)| d == serial)
+    }
+
+    /// Let the next `n` device rescans return [`Error::Retry`](crate::Error::Retry), i.e., simulate
+    /// a slow USB enumeration.
+    pub fn set_rescan_retries(n: usize) {
+        stub().as_mut().unwrap().rescan_retries = n;
+    }
+
+    /// Queue a status record of `kind` with `value` on the open device `serial`.
+    ///
+    /// The record is timestamped with the current stream time and delivered in a packet of its
+    /// own on the [`STATUS_CHANNEL`](crate::STATUS_CHANNEL).
+    pub fn push_status(serial: &str, kind: u32, value: f32) {
+        let mut s = stub();
+        for d in s
+            .as_mut()
+            .unwrap()
+            .open
+            .values_mut()
+            .filter(|d| d.serial == serial)
+        {
+            let time = d.started.elapsed().as_secs_f64();
+            d.status_records.push_back((time, kind as f32, value));
+        }
+    }
+
+    /// Set the number of FFT rows in the spectra packets of the open device `serial`.
+    ///
+    /// Rows are padded to a stride of 8 floats more than their size, if there is more than one.
+    pub fn set_spectra_rows(serial: &str, rows: usize) {
+        let mut s = stub();
+        for d in s
+            .as_mut()
+            .unwrap()
+            .open
+            .values_mut()
+            .filter(|d| d.serial == serial)
+        {
+            d.spectra_rows = rows.max(1);
+        }
+    }
+
+    /// Deliver malformed data packets from the open device `serial`, i.e., override their `num`
+    /// field and, if `null_payload` is set, their payload pointer. `None` restores valid packets.
+    pub fn set_malformed_packets(serial: &str, malformed: Option<(Option<i64>, bool)>) {
+        let mut s = stub();
+        for d in s
+            .as_mut()
+            .unwrap()
+            .open
+            .values_mut()
+            .filter(|d| d.serial == serial)
+        {
+            d.malformed = malformed;
+        }
+    }
+
+    /// Number of polls of the data channels of the open device `serial` that found no packet.
+    pub fn empty_polls(serial: &str) -> usize {
+        let s = stub();
+        s.as_ref()
+            .unwrap()
+            .open
+            .values()
+            .filter(|d| d.serial == serial)
+            .map(|d| d.empty_polls)
+            .sum()
+    }
+
+    /// Report the open device `serial` as hardware variant `product` with the comma-separated
+    /// license `options`, e.g., `SPECTRAN V6 PLUS` and `RTBW245, TX`.
+    pub fn set_variant(serial: &str, product: &str, options: &str) {
+        let mut s = stub();
+        for d in s
+            .as_mut()
+            .unwrap()
+            .open
+            .values_mut()
+            .filter(|d| d.serial == serial)
+        {
+            for (name, value) in [("productname", product), ("options", options)] {
+                let node = d.nodes[d.health]
+                    .children
+                    .iter()
+                    .copied()
+                    .find(|c| d.nodes[*c].name == name);
+                if let Some(n) = node {
+                    d.nodes[n].value = Value::String(value.into());
+                }
+            }
+        }
+    }
+
+    /// Disable options of the enum parameter `path` of the open device `serial`, i.e., bit `i`
+    /// of `mask` disables option `i`.
+    pub fn set_disabled_options(serial: &str, path: &str, mask: u64) {
+        let mut s = stub();
+        for d in s
+            .as_mut()
+            .unwrap()
+            .open
+            .values_mut()
+            .filter(|d| d.serial == serial)
+        {
+            if let Some(n) = super::find(d, 0, path) {
+                d.nodes[n].disabled = mask;
+            }
+        }
+    }
+
+    /// Let the next `n` sets of parameter `path` of the open device `serial` fail with
+    /// [`Error::ErrorBusy`](crate::Error::ErrorBusy).
+    pub fn fail_config_sets(serial: &str, path: &str, n: usize) {
+        let mut s = stub();
+        for d in s
+            .as_mut()
+            .unwrap()
+            .open
+            .values_mut()
+            .filter(|d| d.serial == serial)
+        {
+            if let Some(i) = super::find(d, 0, path) {
+                d.nodes[i].failures = n;
+            }
+        }
+    }
+
+    /// Number of open library handles, `None` if the library is not initialized.
+    pub fn api_handles() -> Option<usize> {
+        let s = stub();
+        let s = s.as_ref().unwrap();
+        s.initialized.then_some(s.handles)
+    }
+
+    /// Calls to the state changing functions of the API, e.g., `OpenDevice` or `StartDevice`.
+    pub fn calls() -> Vec<String> {
+        stub().as_ref().unwrap().calls.clone()
+    }
+
+    /// Clear the call log.
+    pub fn clear_calls() {
+        stub().as_mut().unwrap().calls.clear();
+    }
+
+    /// Number of samples, sent to a device.
+    pub fn sent_samples(serial: &str) -> usize {
+        sent_packets(serial).iter().map(|p| p.2).sum()
+    }
+
+    /// Start time, frequency, and number of samples of the packets, sent to a device.
+    pub fn sent_packets(serial: &str) -> Vec<(f64, f64, usize)> {
+        stub()
+            .as_ref()
+            .unwrap()
+            .sent
+            .get(serial)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,213 @@
+//! Duplicate the packets of a data channel to multiple consumers, e.g., a recorder and a live
+//! display.
+//!
+//! - start a [`Tee`] with a started [`Device`]
+//! - add outputs with [`Tee::output()`], each with its own capacity and [`DropPolicy`]
+//! - read the packets of an output in its own thread, e.g., by iterating over the [`TeeOutput`]
+//!
+//! Outputs with [`DropPolicy::Block`] get every packet, but stall the tee and, thereby, all other
+//! outputs, while they are full. Outputs that drop packets never stall the tee, i.e., a slow live
+//! display does not stall a recording, and a recording only stalls the display, if it cannot keep
+//! up with the stream.
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::pipeline::DropPolicy;
+use crate::pipeline::OwnedPacket;
+use crate::pipeline::Queue;
+use crate::poll::Poller;
+use crate::Device;
+use crate::Error;
+
+type Outputs = Arc<Mutex<Vec<Arc<Queue>>>>;
+
+/// Receives the packets of a data channel in a background thread and copies them to all
+/// outputs, see the [module documentation](self).
+pub struct Tee {
+    stop: Arc<AtomicBool>,
+    outputs: Outputs,
+    receiver: Option<JoinHandle<std::result::Result<Device, Error>>>,
+}
+
+impl Tee {
+    /// Start receiving from data channel `chan` of the [`Device`].
+    ///
+    /// The [`Device`] has to be started. It is returned by [`stop()`](Self::stop). While the tee
+    /// has no outputs, packets stay in the device queue.
+    pub fn start(mut dev: Device, chan: i32) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let outputs = Outputs::default();
+
+        let s = stop.clone();
+        let o = outputs.clone();
+        let receiver = std::thread::spawn(move || {
+            let r = Self::run(&mut dev, chan, &s, &o);
+            for q in o.lock().unwrap().drain(..) {
+                q.close();
+            }
+            r.map(|_| dev)
+        });
+
+        Self {
+            stop,
+            outputs,
+            receiver: Some(receiver),
+        }
+    }
+
+    fn run(dev: &mut Device, chan: i32, stop: &AtomicBool, outputs: &Outputs) -> crate::Result {
+        let mut poller = Poller::new(dev.poll_strategy());
+        while !stop.load(Ordering::Acquire) {
+            let queues = {
+                let mut o = outputs.lock().unwrap();
+                o.retain(|q| !q.is_closed());
+                o.clone()
+            };
+            if queues.is_empty() {
+                poller.wait();
+                continue;
+            }
+
+            let p = match dev.try_packet(chan) {
+                Ok(p) => p,
+                Err(Error::Empty) => {
+                    poller.wait();
+                    continue;
+                }
+                Err(e) => {
+                    dev.handle_stream_error(e)?;
+                    continue;
+                }
+            };
+            poller = Poller::new(dev.poll_strategy());
+
+            let p = Arc::new(OwnedPacket::from(&p));
+            dev.consume(chan)?;
+            for q in &queues {
+                q.push(p.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Add an output, queueing up to `capacity` packets, with the given [`DropPolicy`].
+    ///
+    /// The output gets the packets, received after it was added. Dropping the output removes it
+    /// from the tee.
+    pub fn output(&self, capacity: usize, policy: DropPolicy) -> TeeOutput {
+        let queue = Arc::new(Queue::new(capacity.max(1), policy));
+        let mut outputs = self.outputs.lock().unwrap();
+        if self.receiver.as_ref().is_some_and(|r| r.is_finished()) {
+            queue.close();
+        } else {
+            outputs.push(queue.clone());
+        }
+        TeeOutput { queue }
+    }
+
+    /// Number of connected outputs.
+    pub fn outputs(&self) -> usize {
+        self.outputs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|q| !q.is_closed())
+            .count()
+    }
+
+    /// Stop receiving and get the [`Device`] back.
+    ///
+    /// The outputs are closed, i.e., they return the packets that are still queued and `None`
+    /// afterwards.
+    pub fn stop(mut self) -> std::result::Result<Device, Error> {
+        self.stop.store(true, Ordering::Release);
+        // wake outputs that block the receive thread
+        for q in self.outputs.lock().unwrap().iter() {
+            q.close();
+        }
+        self.receiver
+            .take()
+            .unwrap()
+            .join()
+            .map_err(|_| Error::Error)?
+    }
+}
+
+impl Drop for Tee {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        for q in self.outputs.lock().unwrap().iter() {
+            q.close();
+        }
+        if let Some(h) = self.receiver.take() {
+            let _ = h.join();
+        }
+    }
+}
+
+/// Output of a [`Tee`], returned by [`Tee::output()`].
+///
+/// Iterating over the output blocks for the next packet and ends, once the tee is stopped and
+/// the queued packets are read.
+pub struct TeeOutput {
+    queue: Arc<Queue>,
+}
+
+impl TeeOutput {
+    /// Wait for the next packet, `None` if the tee is stopped and the output is drained.
+    pub fn recv(&self) -> Option<Arc<OwnedPacket>> {
+        self.queue.pop()
+    }
+
+    /// Wait at most `timeout` for the next packet.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Arc<OwnedPacket>> {
+        self.queue.pop_timeout(timeout)
+    }
+
+    /// Get the next packet, if one is queued.
+    pub fn try_recv(&self) -> Option<Arc<OwnedPacket>> {
+        self.queue.try_pop()
+    }
+
+    /// Number of queued packets.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Check if there are no queued packets.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of packets dropped by the [`DropPolicy`] of the output.
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped()
+    }
+}
+
+impl Iterator for TeeOutput {
+    type Item = Arc<OwnedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+impl Drop for TeeOutput {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+impl std::fmt::Debug for TeeOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TeeOutput")
+            .field("len", &self.len())
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
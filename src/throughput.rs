@@ -0,0 +1,141 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::ConfigItem;
+use crate::Device;
+use crate::DeviceStatus;
+use crate::Error;
+use crate::OutputFormat;
+
+/// Result of [`Device::throughput_test()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThroughputReport {
+    /// Wall-clock duration of the test.
+    pub duration: Duration,
+    /// Number of received packets.
+    pub packets: u64,
+    /// Number of received samples.
+    pub samples: u64,
+    /// Nominal sample rate of the stream in Hz, `None` if no packet was received.
+    pub sample_rate: Option<f64>,
+    /// Number of detected gaps in the stream, see [`QueueStats`](crate::QueueStats).
+    pub drops: u64,
+    /// Total duration of the detected gaps in seconds.
+    pub dropped_time: f64,
+    /// CPU time of the receive loop, `None` if not supported on the platform.
+    pub cpu_time: Option<Duration>,
+}
+
+impl ThroughputReport {
+    /// Sustained rate in mega samples per second.
+    pub fn msps(&self) -> f64 {
+        self.samples as f64 / self.duration.as_secs_f64().max(f64::EPSILON) / 1e6
+    }
+
+    /// CPU usage of the receive loop as fraction of one core.
+    pub fn cpu_usage(&self) -> Option<f64> {
+        self.cpu_time
+            .map(|c| c.as_secs_f64() / self.duration.as_secs_f64().max(f64::EPSILON))
+    }
+
+    /// Check if the host kept up with the device, i.e., packets were received and no gaps were
+    /// detected.
+    pub fn passed(&self) -> bool {
+        self.packets > 0 && self.drops == 0
+    }
+}
+
+/// CPU time of the calling thread.
+#[cfg(target_os = "linux")]
+fn thread_cpu_time() -> Option<Duration> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let r = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) };
+    (r == 0).then(|| Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+/// CPU time of the calling thread.
+#[cfg(not(target_os = "linux"))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// Selected option of an enum parameter.
+fn option(item: ConfigItem) -> Option<String> {
+    match item {
+        ConfigItem::Enum { value, options, .. } => options.get(value as usize).cloned(),
+        _ => None,
+    }
+}
+
+impl Device {
+    /// Stream IQ samples at the maximum rate and measure, whether the host keeps up.
+    ///
+    /// The device has to be connected. The test sets IQ output without decimation, starts the
+    /// device, and receives packets of data channel 0 with the [`PollStrategy`] of the device
+    /// for `duration`. Afterwards, the device is stopped and the previous output format and
+    /// decimation are restored. This validates that the USB controller and host can handle the
+    /// full rate.
+    ///
+    /// [`PollStrategy`]: crate::PollStrategy
+    pub fn throughput_test(
+        &mut self,
+        duration: Duration,
+    ) -> std::result::Result<ThroughputReport, Error> {
+        self.expect_status(DeviceStatus::Connected)?;
+        let format = option(self.get("device/outputformat")?);
+        let decimation = option(self.get("main/decimation")?);
+
+        self.set_output_format(OutputFormat::Iq)?;
+        self.set("main/decimation", "Full")?;
+        self.start()?;
+        let report = self.measure_throughput(duration);
+        let stopped = self.stop();
+
+        if let Some(f) = format {
+            self.set("device/outputformat", f)?;
+        }
+        if let Some(d) = decimation {
+            self.set("main/decimation", d)?;
+        }
+        let report = report?;
+        stopped?;
+        Ok(report)
+    }
+
+    fn measure_throughput(
+        &mut self,
+        duration: Duration,
+    ) -> std::result::Result<ThroughputReport, Error> {
+        let chan = 0;
+        self.reset_queue_stats(chan);
+        let cpu_start = thread_cpu_time();
+        let start = Instant::now();
+
+        let mut samples = 0;
+        while start.elapsed() < duration {
+            let p = self.packet(chan)?;
+            samples += p.num().max(0) as u64;
+            self.consume(chan)?;
+        }
+
+        let elapsed = start.elapsed();
+        let cpu_time = match (cpu_start, thread_cpu_time()) {
+            (Some(a), Some(b)) => Some(b.saturating_sub(a)),
+            _ => None,
+        };
+        let stats = self.queue_stats(chan)?;
+        Ok(ThroughputReport {
+            duration: elapsed,
+            packets: stats.packets,
+            samples,
+            sample_rate: stats.sample_rate,
+            drops: stats.drops,
+            dropped_time: stats.dropped_time,
+            cpu_time,
+        })
+    }
+}
@@ -0,0 +1,178 @@
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::Device;
+use crate::Error;
+use crate::Packet;
+use crate::PacketMeta;
+
+/// Point in time of the device stream clock, in seconds.
+///
+/// Packet timestamps and [`Device::clock()`] use the master stream clock of the RTSA library.
+/// [`ClockAnchor`] relates it to the system clock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamTime(f64);
+
+impl StreamTime {
+    /// Create a stream time from seconds.
+    pub fn from_secs(secs: f64) -> Self {
+        Self(secs)
+    }
+
+    /// Seconds of the stream clock.
+    pub fn as_secs(&self) -> f64 {
+        self.0
+    }
+
+    /// Time since the start of the stream clock.
+    ///
+    /// Returns `None` for negative times.
+    pub fn to_duration(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.0).ok()
+    }
+
+    /// Convert to system time, using a [`ClockAnchor`].
+    pub fn to_system_time(&self, anchor: &ClockAnchor) -> SystemTime {
+        let d = self.0 - anchor.stream.0;
+        if d >= 0.0 {
+            anchor.system + Duration::from_secs_f64(d)
+        } else {
+            anchor.system - Duration::from_secs_f64(-d)
+        }
+    }
+
+    /// Signed difference `self - earlier` in seconds.
+    pub fn secs_since(&self, earlier: StreamTime) -> f64 {
+        self.0 - earlier.0
+    }
+
+    /// Duration since `earlier`, or `None` if `earlier` is later than `self`.
+    pub fn duration_since(&self, earlier: StreamTime) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.0 - earlier.0).ok()
+    }
+
+    /// Check if both times are at most `tolerance` seconds apart.
+    pub fn approx_eq(&self, other: StreamTime, tolerance: f64) -> bool {
+        (self.0 - other.0).abs() <= tolerance
+    }
+
+    /// Time of sample `index` of a stream that starts at `self` with the given sample rate.
+    pub fn sample_time(&self, index: i64, sample_rate: f64) -> StreamTime {
+        Self(self.0 + index as f64 / sample_rate)
+    }
+
+    /// Index of the sample at `time` of a stream that starts at `self` with the given sample
+    /// rate, rounded to the closest sample.
+    pub fn sample_index(&self, time: StreamTime, sample_rate: f64) -> i64 {
+        ((time.0 - self.0) * sample_rate).round() as i64
+    }
+}
+
+impl From<f64> for StreamTime {
+    fn from(value: f64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<StreamTime> for f64 {
+    fn from(value: StreamTime) -> Self {
+        value.0
+    }
+}
+
+impl std::ops::Add<Duration> for StreamTime {
+    type Output = StreamTime;
+
+    fn add(self, rhs: Duration) -> StreamTime {
+        Self(self.0 + rhs.as_secs_f64())
+    }
+}
+
+impl std::ops::Sub<Duration> for StreamTime {
+    type Output = StreamTime;
+
+    fn sub(self, rhs: Duration) -> StreamTime {
+        Self(self.0 - rhs.as_secs_f64())
+    }
+}
+
+impl std::fmt::Display for StreamTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.9}s", self.0)
+    }
+}
+
+/// Simultaneous readings of the stream clock and the system clock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockAnchor {
+    /// Stream clock time.
+    pub stream: StreamTime,
+    /// System time.
+    pub system: SystemTime,
+}
+
+impl Device {
+    /// Get [`Device`] clock time as [`StreamTime`].
+    pub fn stream_time(&mut self) -> std::result::Result<StreamTime, Error> {
+        self.clock().map(StreamTime)
+    }
+
+    /// Read the stream clock together with the system clock.
+    ///
+    /// The system time is taken in the middle of the clock query to minimize the offset.
+    pub fn clock_anchor(&mut self) -> std::result::Result<ClockAnchor, Error> {
+        let before = SystemTime::now();
+        let stream = self.stream_time()?;
+        let after = SystemTime::now();
+        let system = before + after.duration_since(before).unwrap_or_default() / 2;
+        Ok(ClockAnchor { stream, system })
+    }
+}
+
+impl Packet {
+    /// Get packet start time as [`StreamTime`].
+    pub fn start_stream_time(&self) -> StreamTime {
+        StreamTime(self.start_time())
+    }
+
+    /// Get packet end time as [`StreamTime`].
+    pub fn end_stream_time(&self) -> StreamTime {
+        StreamTime(self.end_time())
+    }
+
+    /// Sample rate of the IQ samples, derived from packet start and end time.
+    pub fn sample_rate(&self) -> Option<f64> {
+        let d = self.end_time() - self.start_time();
+        if self.num() > 0 && d > 0.0 {
+            Some(self.num() as f64 / d)
+        } else {
+            None
+        }
+    }
+
+    /// Time of IQ sample `index` in the packet.
+    pub fn sample_time(&self, index: usize) -> StreamTime {
+        match self.sample_rate() {
+            Some(r) => self.start_stream_time().sample_time(index as i64, r),
+            None => self.start_stream_time(),
+        }
+    }
+}
+
+impl PacketMeta {
+    /// Packet start time as [`StreamTime`].
+    pub fn start_stream_time(&self) -> StreamTime {
+        StreamTime(self.start_time)
+    }
+
+    /// Sample rate of the IQ samples, derived from packet start and end time.
+    pub fn sample_rate(&self) -> Option<f64> {
+        let d = self.end_time - self.start_time;
+        if self.num > 0 && d > 0.0 {
+            Some(self.num as f64 / d)
+        } else {
+            None
+        }
+    }
+}
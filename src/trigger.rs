@@ -0,0 +1,328 @@
+//! Triggered IQ capture, similar to the acquisition modes of an oscilloscope.
+//!
+//! - capture on a level or time [`Trigger`] with [`Device::capture()`]
+//! - check spectra against a frequency [`Mask`], i.e., a limit line, with [`Mask::check()`]
+//! - capture when the spectrum of the stream violates a mask with [`Device::capture_mask()`],
+//!   e.g., to catch intermittent interference
+use num_complex::Complex32;
+use std::collections::VecDeque;
+
+use crate::dsp::SpectrumEngine;
+use crate::Device;
+use crate::Error;
+use crate::Spectrum;
+
+/// Condition that starts a [`Device::capture()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    /// Trigger on the first sample with a power above the threshold in dBFS.
+    Level(f32),
+    /// Trigger on the first sample at or after the given device clock time.
+    Time(f64),
+}
+
+/// IQ samples, captured with a [`Trigger`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    /// Device clock time of the trigger sample.
+    pub trigger_time: f64,
+    /// Index of the trigger sample, i.e., number of pre-trigger samples.
+    pub trigger_index: usize,
+    /// Captured samples.
+    pub samples: Vec<Complex32>,
+}
+
+impl Device {
+    /// Capture IQ samples from data channel `chan`, once the [`Trigger`] fires.
+    ///
+    /// The capture contains up to `pre_trigger` samples before the trigger, followed by
+    /// `post_trigger` samples, starting with the trigger sample. This call blocks until the
+    /// capture is complete. The [`Device`] has to be started and configured to output IQ samples.
+    pub fn capture(
+        &mut self,
+        chan: i32,
+        trigger: Trigger,
+        pre_trigger: usize,
+        post_trigger: usize,
+    ) -> std::result::Result<Capture, Error> {
+        let mut history = VecDeque::with_capacity(pre_trigger + 1);
+        let mut capture: Option<Capture> = None;
+
+        let threshold = match trigger {
+            Trigger::Level(db) => 10f32.powf(db / 10.0),
+            Trigger::Time(_) => 0.0,
+        };
+
+        loop {
+            let p = self.packet(chan)?;
+            let samples = p.samples();
+
+            let mut offset = 0;
+            if capture.is_none() {
+                for (i, s) in samples.iter().enumerate() {
+                    let t = p.sample_time(i).as_secs();
+                    let fired = match trigger {
+                        Trigger::Level(_) => s.norm_sqr() > threshold,
+                        Trigger::Time(at) => t >= at,
+                    };
+                    if fired {
+                        capture = Some(Capture {
+                            trigger_time: t,
+                            trigger_index: history.len(),
+                            samples: history.drain(..).collect(),
+                        });
+                        offset = i;
+                        break;
+                    }
+                    if pre_trigger > 0 {
+                        if history.len() == pre_trigger {
+                            history.pop_front();
+                        }
+                        history.push_back(*s);
+                    }
+                }
+            }
+
+            if let Some(c) = capture.as_mut() {
+                let missing = c.trigger_index + post_trigger - c.samples.len();
+                let n = std::cmp::min(missing, samples.len() - offset);
+                c.samples.extend_from_slice(&samples[offset..offset + n]);
+            }
+
+            self.consume(chan)?;
+
+            if let Some(c) = &capture {
+                if c.samples.len() == c.trigger_index + post_trigger {
+                    break;
+                }
+            }
+        }
+
+        capture.ok_or(Error::Error)
+    }
+}
+
+/// Frequency mask, i.e., a limit line for spectra.
+///
+/// The limit is interpolated linearly between the points. Below the first and above the last
+/// point, it is the level of the nearest point, i.e., a mask with one point is a flat limit.
+/// Levels are in the unit of the checked spectra, i.e., dBm for device spectra and dBFS for
+/// the [`SpectrumEngine`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mask {
+    points: Vec<(f64, f32)>,
+}
+
+/// Bins of a [`Spectrum`] above a [`Mask`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaskViolation {
+    /// Start time of the spectrum.
+    pub time: f64,
+    /// Frequency of the bin with the largest margin in Hz.
+    pub frequency: f64,
+    /// Level of the bin.
+    pub level: f32,
+    /// Limit of the mask at the bin.
+    pub limit: f32,
+    /// Level above the limit in dB.
+    pub margin: f32,
+    /// Number of adjacent bins above the limit.
+    pub bins: usize,
+}
+
+impl Mask {
+    /// Create a mask from `(frequency, level)` points.
+    ///
+    /// Returns [`Error::ErrorValueInvalid`], if there are no points, if a value is not finite,
+    /// or if the frequencies are not ascending. Two points with the same frequency are a step.
+    pub fn new(points: Vec<(f64, f32)>) -> std::result::Result<Self, Error> {
+        let finite = points.iter().all(|(f, l)| f.is_finite() && l.is_finite());
+        let ascending = points.windows(2).all(|w| w[0].0 <= w[1].0);
+        if points.is_empty() || !finite || !ascending {
+            return Err(Error::ErrorValueInvalid);
+        }
+        Ok(Self { points })
+    }
+
+    /// Create a flat mask with the same limit at all frequencies.
+    pub fn flat(level: f32) -> Self {
+        Self {
+            points: vec![(0.0, level)],
+        }
+    }
+
+    /// Create a mask `margin` dB above a reference spectrum, e.g., of a quiet band.
+    pub fn from_spectrum(reference: &Spectrum, margin: f32) -> Self {
+        let points = reference
+            .data
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                let f = reference.start_frequency + i as f64 * reference.step_frequency;
+                (f, l + margin)
+            })
+            .collect::<Vec<_>>();
+        if points.is_empty() {
+            Self::flat(f32::INFINITY)
+        } else {
+            Self { points }
+        }
+    }
+
+    /// Points of the mask.
+    pub fn points(&self) -> &[(f64, f32)] {
+        &self.points
+    }
+
+    /// Limit at a frequency in Hz.
+    pub fn limit(&self, frequency: f64) -> f32 {
+        let i = self.points.partition_point(|(f, _)| *f <= frequency);
+        if i == 0 {
+            return self.points[0].1;
+        }
+        if i == self.points.len() {
+            return self.points[i - 1].1;
+        }
+        let (f0, l0) = self.points[i - 1];
+        let (f1, l1) = self.points[i];
+        l0 + ((frequency - f0) / (f1 - f0)) as f32 * (l1 - l0)
+    }
+
+    /// Check if a bin of the spectrum is above the limit.
+    pub fn violated(&self, spectrum: &Spectrum) -> bool {
+        spectrum
+            .data
+            .iter()
+            .enumerate()
+            .any(|(i, l)| *l > self.limit(bin_frequency(spectrum, i)))
+    }
+
+    /// Compare a spectrum with the mask.
+    ///
+    /// Returns one violation per range of adjacent bins above the limit in ascending frequency,
+    /// each with the bin of the largest margin.
+    pub fn check(&self, spectrum: &Spectrum) -> Vec<MaskViolation> {
+        let mut violations = Vec::new();
+        let mut current: Option<MaskViolation> = None;
+        for (i, level) in spectrum.data.iter().enumerate() {
+            let frequency = bin_frequency(spectrum, i);
+            let limit = self.limit(frequency);
+            let margin = level - limit;
+            if margin <= 0.0 {
+                violations.extend(current.take());
+                continue;
+            }
+            let v = current.get_or_insert(MaskViolation {
+                time: spectrum.start_time,
+                frequency,
+                level: *level,
+                limit,
+                margin,
+                bins: 0,
+            });
+            v.bins += 1;
+            if margin > v.margin {
+                v.frequency = frequency;
+                v.level = *level;
+                v.limit = limit;
+                v.margin = margin;
+            }
+        }
+        violations.extend(current);
+        violations
+    }
+}
+
+fn bin_frequency(spectrum: &Spectrum, i: usize) -> f64 {
+    spectrum.start_frequency + i as f64 * spectrum.step_frequency
+}
+
+/// IQ samples, captured with a [`Mask`] trigger.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskCapture {
+    /// Spectrum that violated the mask.
+    pub spectrum: Spectrum,
+    /// Violations of the spectrum.
+    pub violations: Vec<MaskViolation>,
+    /// Captured samples, the trigger sample is the first sample of the spectrum.
+    pub capture: Capture,
+}
+
+impl Device {
+    /// Capture IQ samples from data channel `chan`, once a spectrum of the stream violates the
+    /// [`Mask`].
+    ///
+    /// The spectra are computed with the `engine`, i.e., its size, window, and averaging. The
+    /// capture contains up to `pre_trigger` samples before the first sample of the violating
+    /// spectrum, followed by `post_trigger` samples. This call blocks until the capture is
+    /// complete. The [`Device`] has to be started and configured to output IQ samples.
+    pub fn capture_mask(
+        &mut self,
+        chan: i32,
+        mask: &Mask,
+        engine: &mut SpectrumEngine,
+        pre_trigger: usize,
+        post_trigger: usize,
+    ) -> std::result::Result<MaskCapture, Error> {
+        let mut history: VecDeque<Complex32> = VecDeque::new();
+        let mut capture: Option<MaskCapture> = None;
+
+        loop {
+            let p = self.packet(chan)?;
+            let samples = p.samples();
+
+            let mut offset = 0;
+            if capture.is_none() {
+                history.extend(samples);
+                let triggered = engine.process_packet(&p).into_iter().find_map(|s| {
+                    let violations = mask.check(&s);
+                    (!violations.is_empty()).then_some((s, violations))
+                });
+                let rate = p.sample_rate();
+                if let (Some((spectrum, violations)), Some(rate)) = (triggered, rate) {
+                    // index of the first sample of the spectrum in the history
+                    let back = ((p.start_time() - spectrum.start_time) * rate).round() as i64;
+                    let start = history.len() as i64 - samples.len() as i64 - back;
+                    let start = start.clamp(0, history.len() as i64) as usize;
+                    let first = start.saturating_sub(pre_trigger);
+                    let mut c = Capture {
+                        trigger_time: spectrum.start_time,
+                        trigger_index: start - first,
+                        samples: history.drain(..).skip(first).collect(),
+                    };
+                    c.samples.truncate(c.trigger_index + post_trigger);
+                    capture = Some(MaskCapture {
+                        spectrum,
+                        violations,
+                        capture: c,
+                    });
+                    offset = samples.len();
+                } else {
+                    // keep the pre-trigger samples and the samples of a pending spectrum
+                    let keep = pre_trigger + engine.size() * engine.averages();
+                    let excess = history.len().saturating_sub(keep);
+                    history.drain(..excess);
+                }
+            }
+
+            if let Some(c) = capture.as_mut() {
+                let c = &mut c.capture;
+                let missing = c.trigger_index + post_trigger - c.samples.len();
+                let n = std::cmp::min(missing, samples.len() - offset);
+                c.samples.extend_from_slice(&samples[offset..offset + n]);
+            }
+
+            self.consume(chan)?;
+
+            if let Some(c) = &capture {
+                if c.capture.samples.len() == c.capture.trigger_index + post_trigger {
+                    break;
+                }
+            }
+        }
+
+        capture.ok_or(Error::Error)
+    }
+}
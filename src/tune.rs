@@ -0,0 +1,229 @@
+//! Detection of frequency and rate changes in a packet stream.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::capabilities::DECIMATION_PATH;
+use crate::pipeline::OwnedPacket;
+use crate::pipeline::Payload;
+use crate::ConfigItem;
+use crate::Device;
+use crate::DeviceStatus;
+use crate::Error;
+use crate::Result;
+
+/// Maximum time for the first packet after a decimation change of a started device.
+const RESYNC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Item of a [`TunedStream`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// Packet of the stream.
+    Packet(OwnedPacket),
+    /// The stream was retuned, i.e., the start frequency of the packets changed.
+    Retuned {
+        /// Start frequency before the change.
+        old: f64,
+        /// Start frequency after the change.
+        new: f64,
+        /// Index of the first sample at the new frequency, counting all received samples or
+        /// spectra rows.
+        at_sample: u64,
+    },
+    /// The IQ sample rate changed, e.g., through [`Device::set_decimation_live()`].
+    RateChanged {
+        /// Sample rate before the change in Hz.
+        old: f64,
+        /// Sample rate after the change in Hz.
+        new: f64,
+    },
+}
+
+/// Stream of packets of a data channel, annotated with frequency and rate changes.
+///
+/// When the device is retuned mid-stream, packets after the change carry a new start frequency,
+/// i.e., the center frequency for IQ packets. The stream reports the change as
+/// [`StreamEvent::Retuned`] before the first packet at the new frequency. Likewise, a change of
+/// the sample rate of IQ packets is reported as [`StreamEvent::RateChanged`]. Packets, starting
+/// within `settle` after a change, are dropped, since they may hold the transient of the
+/// retuning.
+#[derive(Debug, Clone)]
+pub struct TunedStream {
+    /// Time after a frequency change, in which packets are dropped (default: zero, i.e., no
+    /// packets are dropped).
+    pub settle: Duration,
+    chan: i32,
+    frequency: Option<f64>,
+    rate: Option<f64>,
+    samples: u64,
+    settle_until: Option<f64>,
+    dropped: u64,
+    pending: VecDeque<StreamEvent>,
+}
+
+impl TunedStream {
+    /// Create a stream for data channel `chan`.
+    pub fn new(chan: i32) -> Self {
+        Self {
+            settle: Duration::ZERO,
+            chan,
+            frequency: None,
+            rate: None,
+            samples: 0,
+            settle_until: None,
+            dropped: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Data channel of the stream.
+    pub fn chan(&self) -> i32 {
+        self.chan
+    }
+
+    /// Current start frequency of the stream, `None` before the first packet.
+    pub fn frequency(&self) -> Option<f64> {
+        self.frequency
+    }
+
+    /// Current sample rate of the IQ stream, `None` before the first IQ packet.
+    pub fn sample_rate(&self) -> Option<f64> {
+        self.rate
+    }
+
+    /// Number of packets, dropped after frequency changes.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Get the next event, receiving packets from the [`Device`].
+    ///
+    /// This call is blocking. Received packets are consumed.
+    pub fn next(&mut self, dev: &mut Device) -> std::result::Result<StreamEvent, Error> {
+        loop {
+            if let Some(e) = self.pending.pop_front() {
+                return Ok(e);
+            }
+            let p = OwnedPacket::from(&dev.packet(self.chan)?);
+            dev.consume(self.chan)?;
+            let events = self.process(p);
+            self.pending.extend(events);
+        }
+    }
+
+    /// Annotate a packet that was received elsewhere, e.g., through a
+    /// [`Pipeline`](crate::pipeline::Pipeline).
+    ///
+    /// Returns the events for the packet, i.e., a [`StreamEvent::RateChanged`], if the sample
+    /// rate changed, a [`StreamEvent::Retuned`], if the frequency changed, and the packet,
+    /// unless it is dropped.
+    pub fn process(&mut self, packet: OwnedPacket) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        let f = packet.meta.start_frequency;
+        let start = packet.meta.start_time;
+
+        let rate = match packet.payload {
+            Payload::Iq(_) => packet.meta.sample_rate(),
+            _ => None,
+        };
+        if let (Some(old), Some(new)) = (self.rate, rate) {
+            if (new - old).abs() > 1e-6 * old.max(new) {
+                events.push(StreamEvent::RateChanged { old, new });
+                self.settle_until = Some(start + self.settle.as_secs_f64());
+            }
+        }
+        self.rate = rate.or(self.rate);
+
+        if let Some(old) = self.frequency {
+            if (f - old).abs() > 1e-9 * old.abs().max(f.abs()).max(1.0) {
+                events.push(StreamEvent::Retuned {
+                    old,
+                    new: f,
+                    at_sample: self.samples,
+                });
+                self.settle_until = Some(start + self.settle.as_secs_f64());
+            }
+        }
+        self.frequency = Some(f);
+        self.samples += packet.meta.num.max(0) as u64;
+
+        match self.settle_until {
+            Some(t) if start < t => self.dropped += 1,
+            _ => {
+                self.settle_until = None;
+                events.push(StreamEvent::Packet(packet));
+            }
+        }
+        events
+    }
+}
+
+impl Device {
+    /// Change the decimation of the IQ stream with the least state transitions.
+    ///
+    /// `decimation` is one of the [`decimations`](crate::Capabilities::decimations) of the
+    /// device, e.g., 4 for `1 / 4`. A device that is not started is only configured. A started
+    /// device is stopped, but stays connected, the stale packets of its data channels are
+    /// discarded, and it is started again with [`start_and_wait()`](Self::start_and_wait), i.e.,
+    /// the stream is running at the new rate, when the call returns. The packets after the
+    /// change are reported as [`StreamEvent::RateChanged`] by a [`TunedStream`] as well.
+    ///
+    /// Returns the [`StreamEvent::RateChanged`] with the nominal sample rates before and after
+    /// the change, which are equal, if the decimation was already set; the stream is not touched
+    /// in this case. Returns [`Error::ErrorValueInvalid`] without accessing the device, if the
+    /// decimation is not supported, and [`Error::Retry`], if no packet arrived within 5 seconds
+    /// after the restart. If the device refuses the new decimation, the previous one is restored
+    /// and a started stream is started again, before the error is returned.
+    pub fn set_decimation_live(
+        &mut self,
+        decimation: u32,
+    ) -> std::result::Result<StreamEvent, Error> {
+        let index = self
+            .capabilities()?
+            .decimations
+            .iter()
+            .position(|d| *d == decimation)
+            .ok_or(Error::ErrorValueInvalid)?;
+        let old = self.sample_rate()?.value();
+        let ConfigItem::Enum { value: current, .. } = self.get(DECIMATION_PATH)? else {
+            return Err(Error::ErrorValueInvalid);
+        };
+        if current as usize == index {
+            return Ok(StreamEvent::RateChanged { old, new: old });
+        }
+
+        let started = self.status == DeviceStatus::Started;
+        if started {
+            self.stop()?;
+        }
+        let new = match self
+            .set_int(DECIMATION_PATH, index as i64)
+            .and_then(|_| self.sample_rate())
+        {
+            Ok(rate) => rate.value(),
+            Err(e) => {
+                // bring the stream back at the previous rate and report the original error
+                let _ = self.set_int(DECIMATION_PATH, current);
+                if started {
+                    let _ = self.resync();
+                }
+                return Err(e);
+            }
+        };
+        if started {
+            self.resync()?;
+        }
+        Ok(StreamEvent::RateChanged { old, new })
+    }
+
+    /// Discard the stale packets of the data channels and start the stopped device again.
+    fn resync(&mut self) -> Result {
+        let channels = self
+            .known_output_format()
+            .map_or(&[0][..], |f| f.channels());
+        for chan in channels {
+            self.consume_all(*chan)?;
+        }
+        self.start_and_wait(RESYNC_TIMEOUT, |_| {})?;
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use crate::ConfigValue;
+use crate::Packet;
+use crate::PacketMeta;
+use crate::StreamTime;
+
+macro_rules! unit {
+    ($(#[$meta:meta])* $name:ident, $suffix:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(transparent)
+        )]
+        pub struct $name(pub f64);
+
+        impl $name {
+            /// Value as plain number.
+            pub const fn value(self) -> f64 {
+                self.0
+            }
+        }
+
+        impl From<f64> for $name {
+            fn from(v: f64) -> Self {
+                Self(v)
+            }
+        }
+
+        impl From<$name> for f64 {
+            fn from(v: $name) -> Self {
+                v.0
+            }
+        }
+
+        impl From<$name> for ConfigValue {
+            fn from(v: $name) -> Self {
+                ConfigValue::Float(v.0)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                std::fmt::Display::fmt(&self.0, f)?;
+                f.write_str($suffix)
+            }
+        }
+
+        impl std::ops::Add for $name {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl std::ops::Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl std::ops::Neg for $name {
+            type Output = Self;
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl std::ops::Mul<f64> for $name {
+            type Output = Self;
+            fn mul(self, rhs: f64) -> Self {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl std::ops::Div<f64> for $name {
+            type Output = Self;
+            fn div(self, rhs: f64) -> Self {
+                Self(self.0 / rhs)
+            }
+        }
+
+        /// Ratio of two values.
+        impl std::ops::Div for $name {
+            type Output = f64;
+            fn div(self, rhs: Self) -> f64 {
+                self.0 / rhs.0
+            }
+        }
+    };
+}
+
+unit!(
+    /// Frequency in Hz.
+    Hz,
+    " Hz"
+);
+
+unit!(
+    /// Level or gain in dB, e.g., a reference level in dBm.
+    Db,
+    " dB"
+);
+
+unit!(
+    /// Time in seconds.
+    Seconds,
+    " s"
+);
+
+impl Hz {
+    /// Frequency in kHz.
+    pub fn as_khz(self) -> f64 {
+        self.0 / 1e3
+    }
+
+    /// Frequency in MHz.
+    pub fn as_mhz(self) -> f64 {
+        self.0 / 1e6
+    }
+
+    /// Frequency in GHz.
+    pub fn as_ghz(self) -> f64 {
+        self.0 / 1e9
+    }
+
+    /// Period of the frequency, e.g., the sample period of a sample rate.
+    pub fn period(self) -> Seconds {
+        Seconds(1.0 / self.0)
+    }
+}
+
+impl Db {
+    /// Linear power ratio.
+    pub fn to_power_ratio(self) -> f64 {
+        10f64.powf(self.0 / 10.0)
+    }
+
+    /// Linear amplitude ratio.
+    pub fn to_amplitude_ratio(self) -> f64 {
+        10f64.powf(self.0 / 20.0)
+    }
+}
+
+impl Seconds {
+    /// Time in milliseconds.
+    pub fn as_millis(self) -> f64 {
+        self.0 * 1e3
+    }
+
+    /// Convert to a [`Duration`], `None` for negative or non-finite times.
+    pub fn to_duration(self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.0).ok()
+    }
+}
+
+impl From<Duration> for Seconds {
+    fn from(d: Duration) -> Self {
+        Seconds(d.as_secs_f64())
+    }
+}
+
+impl From<Seconds> for StreamTime {
+    fn from(s: Seconds) -> Self {
+        StreamTime::from_secs(s.0)
+    }
+}
+
+impl From<StreamTime> for Seconds {
+    fn from(t: StreamTime) -> Self {
+        Seconds(t.as_secs())
+    }
+}
+
+impl Packet {
+    /// Center frequency, i.e., the start frequency plus half the span.
+    pub fn center_frequency(&self) -> Hz {
+        Hz(self.start_frequency() + self.span_frequency() / 2.0)
+    }
+
+    /// Frequency span.
+    pub fn span(&self) -> Hz {
+        Hz(self.span_frequency())
+    }
+
+    /// Resolution bandwidth.
+    pub fn rbw(&self) -> Hz {
+        Hz(self.rbw_frequency())
+    }
+
+    /// Time from the start to the end of the packet.
+    pub fn duration(&self) -> Seconds {
+        Seconds(self.end_time() - self.start_time())
+    }
+}
+
+impl PacketMeta {
+    /// Center frequency, i.e., the start frequency plus half the span.
+    pub fn center_frequency(&self) -> Hz {
+        Hz(self.start_frequency + self.span_frequency / 2.0)
+    }
+
+    /// Frequency span.
+    pub fn span(&self) -> Hz {
+        Hz(self.span_frequency)
+    }
+
+    /// Resolution bandwidth.
+    pub fn rbw(&self) -> Hz {
+        Hz(self.rbw_frequency)
+    }
+
+    /// Time from the start to the end of the packet.
+    pub fn duration(&self) -> Seconds {
+        Seconds(self.end_time - self.start_time)
+    }
+}
+
+/// Constructors of the unit types for numbers, e.g., `810.mhz()` or `-20.db()`.
+///
+/// Implemented for `f64` and `i32`, i.e., for float and integer literals.
+pub trait Units: Sized {
+    /// Frequency in Hz.
+    fn hz(self) -> Hz;
+
+    /// Frequency in kHz.
+    fn khz(self) -> Hz {
+        self.hz() * 1e3
+    }
+
+    /// Frequency in MHz.
+    fn mhz(self) -> Hz {
+        self.hz() * 1e6
+    }
+
+    /// Frequency in GHz.
+    fn ghz(self) -> Hz {
+        self.hz() * 1e9
+    }
+
+    /// Level or gain in dB.
+    fn db(self) -> Db;
+
+    /// Time in seconds.
+    fn secs(self) -> Seconds;
+
+    /// Time in milliseconds.
+    fn millis(self) -> Seconds {
+        self.secs() / 1e3
+    }
+
+    /// Time in microseconds.
+    fn micros(self) -> Seconds {
+        self.secs() / 1e6
+    }
+}
+
+impl Units for f64 {
+    fn hz(self) -> Hz {
+        Hz(self)
+    }
+
+    fn db(self) -> Db {
+        Db(self)
+    }
+
+    fn secs(self) -> Seconds {
+        Seconds(self)
+    }
+}
+
+impl Units for i32 {
+    fn hz(self) -> Hz {
+        Hz(self as f64)
+    }
+
+    fn db(self) -> Db {
+        Db(self as f64)
+    }
+
+    fn secs(self) -> Seconds {
+        Seconds(self as f64)
+    }
+}
@@ -0,0 +1,244 @@
+//! Export of IQ packets as VITA 49.2 / DIFI frames over UDP.
+//!
+//! Data frames are DIFI signal data packets with 16-bit complex samples, timestamped with UTC
+//! seconds and picoseconds. Context frames carry bandwidth, RF frequency, reference level,
+//! sample rate, and payload format. They are sent before the first data frame, whenever the
+//! frequency, rate, or reference level changes, and every `context_interval` data frames.
+use num_complex::Complex32;
+use std::net::ToSocketAddrs;
+use std::net::UdpSocket;
+use std::ops::ControlFlow;
+use std::time::UNIX_EPOCH;
+
+use crate::ClockAnchor;
+use crate::Packet;
+use crate::PacketMeta;
+use crate::PacketSink;
+use crate::StreamTime;
+
+/// OUI of the DIFI consortium.
+const DIFI_OUI: u32 = 0x6A_621E;
+const DATA_CLASS: u32 = 0x0000;
+const CONTEXT_CLASS: u32 = 0x0001;
+
+/// Convert to 64-bit fixed-point with the radix point at bit 20, used for frequencies.
+fn fixed20(v: f64) -> u64 {
+    (v * (1u64 << 20) as f64).round() as i64 as u64
+}
+
+/// Context of the stream, sent in context frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Context {
+    frequency: f64,
+    sample_rate: f64,
+    reflevel: f64,
+}
+
+/// Send IQ packets as VITA 49 / DIFI frames over UDP.
+#[derive(Debug)]
+pub struct Vita49Sender {
+    socket: UdpSocket,
+    stream_id: u32,
+    anchor: Option<ClockAnchor>,
+    reflevel: f64,
+    /// Maximum number of samples per data frame (default: 360, i.e., frames fit a 1500 byte MTU).
+    pub max_samples: usize,
+    /// Data frames between context frames (default: 100).
+    pub context_interval: usize,
+    data_count: u8,
+    context_count: u8,
+    since_context: usize,
+    context: Option<Context>,
+}
+
+impl Vita49Sender {
+    /// Create a sender for the given destination and stream ID.
+    pub fn new<A: ToSocketAddrs>(dest: A, stream_id: u32) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(dest)?;
+        Ok(Self {
+            socket,
+            stream_id,
+            anchor: None,
+            reflevel: 0.0,
+            max_samples: 360,
+            context_interval: 100,
+            data_count: 0,
+            context_count: 0,
+            since_context: 0,
+            context: None,
+        })
+    }
+
+    /// Map device stream time to UTC with a [`ClockAnchor`].
+    ///
+    /// Without an anchor, the stream time is used as seconds since the Unix epoch.
+    pub fn with_clock_anchor(mut self, anchor: ClockAnchor) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    /// Set the reference level in dBm, i.e., the power of a full-scale sample.
+    pub fn set_reflevel(&mut self, reflevel: f64) {
+        self.reflevel = reflevel;
+    }
+
+    /// Send the IQ samples of a packet, split into data frames.
+    pub fn send_packet(&mut self, packet: &Packet) -> std::io::Result<()> {
+        let sample_rate = packet.sample_rate().unwrap_or(packet.step_frequency());
+        let context = Context {
+            frequency: packet.start_frequency() + packet.span_frequency() / 2.0,
+            sample_rate,
+            reflevel: self.reflevel,
+        };
+        self.send(packet.samples(), packet.start_stream_time(), context)
+    }
+
+    /// Send IQ samples, starting at `time`, split into data frames.
+    pub fn send_samples(
+        &mut self,
+        samples: &[Complex32],
+        time: StreamTime,
+        frequency: f64,
+        sample_rate: f64,
+    ) -> std::io::Result<()> {
+        let context = Context {
+            frequency,
+            sample_rate,
+            reflevel: self.reflevel,
+        };
+        self.send(samples, time, context)
+    }
+
+    fn send(
+        &mut self,
+        samples: &[Complex32],
+        time: StreamTime,
+        context: Context,
+    ) -> std::io::Result<()> {
+        for (i, chunk) in samples.chunks(self.max_samples.max(1)).enumerate() {
+            let t = time.sample_time((i * self.max_samples) as i64, context.sample_rate);
+            let (secs, ps) = self.timestamp(t);
+
+            if self.context != Some(context) || self.since_context >= self.context_interval {
+                self.send_context(&context, secs, ps)?;
+            }
+
+            let mut buf = Vec::with_capacity(4 * (7 + chunk.len()));
+            self.header(
+                &mut buf,
+                0x1,
+                self.data_count,
+                7 + chunk.len(),
+                DATA_CLASS,
+                secs,
+                ps,
+            );
+            for s in chunk {
+                let i = (s.re * i16::MAX as f32)
+                    .round()
+                    .clamp(i16::MIN as f32, i16::MAX as f32);
+                let q = (s.im * i16::MAX as f32)
+                    .round()
+                    .clamp(i16::MIN as f32, i16::MAX as f32);
+                buf.extend_from_slice(&(i as i16).to_be_bytes());
+                buf.extend_from_slice(&(q as i16).to_be_bytes());
+            }
+            self.socket.send(&buf)?;
+
+            self.data_count = (self.data_count + 1) % 16;
+            self.since_context += 1;
+        }
+        Ok(())
+    }
+
+    fn send_context(&mut self, c: &Context, secs: u32, ps: u64) -> std::io::Result<()> {
+        // header, stream ID, class ID (2), timestamps (3), CIF0, bandwidth (2), RF frequency (2),
+        // reference level, sample rate (2), payload format (2)
+        const WORDS: usize = 17;
+        let changed = self.context != Some(*c);
+
+        let mut buf = Vec::with_capacity(4 * WORDS);
+        self.header(
+            &mut buf,
+            0x4,
+            self.context_count,
+            WORDS,
+            CONTEXT_CLASS,
+            secs,
+            ps,
+        );
+
+        let cif0: u32 = (changed as u32) << 31 | 1 << 29 | 1 << 27 | 1 << 24 | 1 << 21 | 1 << 15;
+        buf.extend_from_slice(&cif0.to_be_bytes());
+        buf.extend_from_slice(&fixed20(c.sample_rate).to_be_bytes());
+        buf.extend_from_slice(&fixed20(c.frequency).to_be_bytes());
+        let reflevel = (c.reflevel * 128.0).round() as i16 as u16 as u32;
+        buf.extend_from_slice(&reflevel.to_be_bytes());
+        buf.extend_from_slice(&fixed20(c.sample_rate).to_be_bytes());
+        // complex cartesian, signed fixed-point, 16-bit items in 16-bit fields
+        let format: u32 = 0b01 << 29 | 15 << 6 | 15;
+        buf.extend_from_slice(&format.to_be_bytes());
+        buf.extend_from_slice(&0u32.to_be_bytes());
+
+        self.socket.send(&buf)?;
+        self.context_count = (self.context_count + 1) % 16;
+        self.since_context = 0;
+        self.context = Some(*c);
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn header(
+        &self,
+        buf: &mut Vec<u8>,
+        packet_type: u32,
+        count: u8,
+        words: usize,
+        class: u32,
+        secs: u32,
+        ps: u64,
+    ) {
+        // class ID present, TSI: UTC, TSF: real-time picoseconds
+        let header = packet_type << 28
+            | 1 << 27
+            | 0b01 << 22
+            | 0b10 << 20
+            | (count as u32) << 16
+            | words as u32;
+        buf.extend_from_slice(&header.to_be_bytes());
+        buf.extend_from_slice(&self.stream_id.to_be_bytes());
+        buf.extend_from_slice(&DIFI_OUI.to_be_bytes());
+        buf.extend_from_slice(&class.to_be_bytes());
+        buf.extend_from_slice(&secs.to_be_bytes());
+        buf.extend_from_slice(&ps.to_be_bytes());
+    }
+
+    /// UTC seconds and picoseconds of a stream time.
+    fn timestamp(&self, t: StreamTime) -> (u32, u64) {
+        let secs = match &self.anchor {
+            Some(a) => t
+                .to_system_time(a)
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+            None => t.as_secs(),
+        };
+        let whole = secs.floor();
+        let ps = ((secs - whole) * 1e12).round() as u64;
+        (whole as u32, ps.min(999_999_999_999))
+    }
+}
+
+/// Sends the IQ samples of all packets. Send errors, e.g., while no receiver listens, are ignored
+/// like lost datagrams.
+impl PacketSink for Vita49Sender {
+    fn on_packet(&mut self, meta: &PacketMeta, samples: &[Complex32]) -> ControlFlow<()> {
+        if !samples.is_empty() {
+            let sample_rate = meta.sample_rate().unwrap_or(meta.step_frequency);
+            let frequency = meta.start_frequency + meta.span_frequency / 2.0;
+            let _ = self.send_samples(samples, meta.start_stream_time(), frequency, sample_rate);
+        }
+        ControlFlow::Continue(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::ApiHandle;
+use crate::DeviceInfo;
+
+/// Event, reported by a [`DeviceWatcher`].
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum DeviceEvent {
+    /// A device was detected.
+    DeviceAdded(DeviceInfo),
+    /// The device with the given serial number disappeared.
+    DeviceRemoved(String),
+}
+
+/// Stream of [`DeviceEvent`]s, returned by [`ApiHandle::watch_devices()`].
+///
+/// Iterating over the watcher blocks until the next event. The background thread is stopped,
+/// when the watcher is dropped.
+pub struct DeviceWatcher {
+    rx: mpsc::Receiver<DeviceEvent>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl DeviceWatcher {
+    /// Get the next event without blocking.
+    pub fn try_next(&self) -> Option<DeviceEvent> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Wait for the next event for at most `timeout`.
+    pub fn next_timeout(&self, timeout: Duration) -> Option<DeviceEvent> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    fn run(interval: Duration, tx: mpsc::Sender<DeviceEvent>, stop: Arc<AtomicBool>) {
+        let Ok(mut api) = ApiHandle::new() else {
+            return;
+        };
+        let mut known: HashMap<String, DeviceInfo> = HashMap::new();
+
+        while !stop.load(Ordering::Acquire) {
+            let start = Instant::now();
+
+            // errors are transient, e.g., while a device is enumerated, so retry next interval
+            if let Ok(devices) = api.rescan_devices().and_then(|_| api.devices()) {
+                let current: HashMap<String, DeviceInfo> =
+                    devices.into_iter().map(|d| (d.serial(), d)).collect();
+
+                for serial in known.keys() {
+                    if !current.contains_key(serial)
+                        && tx.send(DeviceEvent::DeviceRemoved(serial.clone())).is_err()
+                    {
+                        return;
+                    }
+                }
+                for (serial, info) in current.iter() {
+                    if !known.contains_key(serial)
+                        && tx.send(DeviceEvent::DeviceAdded(info.clone())).is_err()
+                    {
+                        return;
+                    }
+                }
+                known = current;
+            }
+
+            while start.elapsed() < interval && !stop.load(Ordering::Acquire) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+impl Iterator for DeviceWatcher {
+    type Item = DeviceEvent;
+
+    fn next(&mut self) -> Option<DeviceEvent> {
+        self.rx.recv().ok()
+    }
+}
+
+impl Drop for DeviceWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(h) = self.handle.take() {
+            let _ = h.join();
+        }
+    }
+}
+
+impl ApiHandle {
+    /// Watch for devices that are plugged or unplugged.
+    ///
+    /// A background thread rescans the devices every `interval` and reports the changes. Devices
+    /// that are present when the watch starts are reported as [`DeviceEvent::DeviceAdded`].
+    pub fn watch_devices(&self, interval: Duration) -> DeviceWatcher {
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let s = stop.clone();
+        let handle = std::thread::spawn(move || DeviceWatcher::run(interval, tx, s));
+
+        DeviceWatcher {
+            rx,
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
@@ -0,0 +1,3418 @@
+//! Tests of the wrapper against the in-crate stub of the RTSA library.
+//!
+//! Run with `cargo test --no-default-features --features sys-stub`.
+#![cfg(feature = "sys-stub")]
+
+use aaronia_rtsa::stub;
+use aaronia_rtsa::ApiHandle;
+use aaronia_rtsa::ConfigChange;
+use aaronia_rtsa::ConfigItem;
+use aaronia_rtsa::ConfigProfile;
+use aaronia_rtsa::ConfigValue;
+use aaronia_rtsa::Device;
+use aaronia_rtsa::DeviceState;
+use aaronia_rtsa::Error;
+use aaronia_rtsa::OutputFormat;
+use aaronia_rtsa::PacketData;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+/// The stub has global state, so tests must not run concurrently.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn setup() -> MutexGuard<'static, ()> {
+    let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    stub::reset();
+    guard
+}
+
+fn device() -> Device {
+    let mut api = ApiHandle::new().unwrap();
+    api.rescan_devices().unwrap();
+    api.get_device().unwrap()
+}
+
+#[test]
+fn enumerate_devices() {
+    let _g = setup();
+    stub::set_devices(&["A", "B"]);
+
+    let mut api = ApiHandle::new().unwrap();
+    api.rescan_devices().unwrap();
+    let serials: Vec<String> = api.devices().unwrap().iter().map(|d| d.serial()).collect();
+    assert_eq!(serials, vec!["A", "B"]);
+
+    stub::set_devices(&[]);
+    assert!(matches!(api.get_device(), Err(Error::Empty)));
+}
+
+#[test]
+fn api_memory() {
+    use aaronia_rtsa::Memory;
+
+    let _g = setup();
+    let first = ApiHandle::try_with_mem(Memory::Large).unwrap();
+    assert_eq!(first.memory(), Memory::Large);
+    let same = ApiHandle::try_with_mem(Memory::Large).unwrap();
+    assert!(matches!(
+        ApiHandle::try_with_mem(Memory::Ludicrous),
+        Err(Error::AlreadyInitialized {
+            current: Memory::Large
+        })
+    ));
+    // the lenient constructor ignores the size
+    let other = ApiHandle::with_mem(Memory::Ludicrous).unwrap();
+    assert_eq!(other.memory(), Memory::Large);
+    drop((first, same, other));
+
+    // the last handle shuts the library down, i.e., the next one chooses the size again
+    let api = ApiHandle::try_with_mem(Memory::Ludicrous).unwrap();
+    assert_eq!(api.memory(), Memory::Ludicrous);
+}
+
+#[test]
+fn teardown_order() {
+    use std::sync::Arc;
+
+    let _g = setup();
+    let mut api = ApiHandle::new().unwrap();
+    api.rescan_devices().unwrap();
+    let mut dev = api.get_device().unwrap();
+    assert!(Arc::ptr_eq(api.runtime(), dev.runtime()));
+    assert_eq!(stub::api_handles(), Some(2));
+
+    // the device keeps the library initialized
+    drop(api);
+    assert_eq!(stub::api_handles(), Some(1));
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    dev.packet(0).unwrap();
+    stub::clear_calls();
+    drop(dev);
+    assert_eq!(stub::api_handles(), None);
+    let calls = stub::calls();
+    assert_eq!(
+        calls[calls.len() - 3..],
+        ["CloseDevice", "Close", "Shutdown"]
+    );
+
+    // handles and devices, dropped concurrently, shut the library down once, after all of
+    // them are closed
+    for _ in 0..20 {
+        stub::clear_calls();
+        let mut api = ApiHandle::new().unwrap();
+        api.rescan_devices().unwrap();
+        let dev = api.get_device().unwrap();
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    for _ in 0..10 {
+                        let mut api = ApiHandle::new().unwrap();
+                        api.devices().unwrap();
+                    }
+                })
+            })
+            .chain(std::iter::once(std::thread::spawn(move || {
+                let mut dev = dev;
+                dev.open().unwrap();
+                dev.connect().unwrap();
+                dev.start().unwrap();
+                dev.packet(0).unwrap();
+            })))
+            .collect();
+        drop(api);
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(stub::api_handles(), None);
+        let calls = stub::calls();
+        let count = |c: &str| calls.iter().filter(|s| *s == c).count();
+        assert_eq!(count("Init"), count("Shutdown"));
+        assert_eq!(count("Open"), count("Close"));
+    }
+}
+
+#[test]
+fn memory_stats() {
+    use aaronia_rtsa::recommended_memory;
+    use aaronia_rtsa::Memory;
+    use std::time::Duration;
+
+    let _g = setup();
+    let api = ApiHandle::with_mem(Memory::Medium).unwrap();
+    let stats = api.memory_stats();
+    assert_eq!(stats.memory, Memory::Medium);
+    assert_eq!(stats.handles, 1);
+    assert_eq!(stats.capacity, Memory::Medium.buffer_size());
+    assert_eq!(stats.buffered, None);
+
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    let packet = dev.packet(0).unwrap();
+    let bytes = packet.layout().bytes();
+    dev.consume(0).unwrap();
+    let available = dev.queue_stats(0).unwrap().available;
+    let stats = api.memory_stats();
+    assert_eq!(stats.handles, 2);
+    assert_eq!(stats.buffered, Some(available * bytes));
+    assert!(stats.utilization().unwrap() > 0.0);
+    drop(dev);
+    assert_eq!(api.memory_stats().buffered, None);
+
+    let second = Duration::from_secs(1);
+    assert_eq!(recommended_memory(1e6, second), Memory::Small);
+    assert_eq!(recommended_memory(20e6, second), Memory::Medium);
+    assert_eq!(recommended_memory(245e6, second), Memory::Large);
+    assert_eq!(recommended_memory(245e6, 10 * second), Memory::Ludicrous);
+}
+
+#[test]
+fn state_machine() {
+    let _g = setup();
+    let mut dev = device();
+
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    assert_eq!(dev.state().unwrap(), DeviceState::Connected);
+    dev.start().unwrap();
+    assert_eq!(dev.state().unwrap(), DeviceState::Running);
+    dev.stop().unwrap();
+    dev.disconnect().unwrap();
+    assert_eq!(dev.state().unwrap(), DeviceState::Idle);
+    dev.close().unwrap();
+
+    let calls = stub::calls();
+    let expected = [
+        "OpenDevice",
+        "ConnectDevice",
+        "StartDevice",
+        "StopDevice",
+        "DisconnectDevice",
+        "CloseDevice",
+    ];
+    let device_calls: Vec<&str> = calls
+        .iter()
+        .map(|c| c.as_str())
+        .filter(|c| c.ends_with("Device"))
+        .collect();
+    assert_eq!(device_calls, expected);
+}
+
+#[test]
+fn drop_order() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    stub::clear_calls();
+
+    drop(dev);
+
+    let calls = stub::calls();
+    let pos = |c: &str| calls.iter().position(|x| x == c).unwrap();
+    assert!(pos("StopDevice") < pos("DisconnectDevice"));
+    assert!(pos("DisconnectDevice") < pos("CloseDevice"));
+    assert!(pos("CloseDevice") < pos("Close"));
+    assert_eq!(calls.last().unwrap(), "Shutdown");
+}
+
+#[test]
+fn error_mapping() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    // the device is already opened through the first handle
+    let mut other = device();
+    assert!(matches!(other.open(), Err(Error::DeviceInUse { .. })));
+
+    assert!(matches!(dev.get("main/nothing"), Err(Error::ErrorNotFound)));
+    assert!(matches!(
+        dev.set("device/receiverclock", "1GHz"),
+        Err(Error::ErrorValueInvalid)
+    ));
+    assert!(matches!(dev.packet(0), Err(Error::ErrorNotConnected)));
+}
+
+#[test]
+fn config() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    dev.set_float("main/centerfreq", 1e9).unwrap();
+    assert!(matches!(dev.get("main/centerfreq").unwrap(), ConfigItem::Number(f) if f == 1e9));
+
+    dev.set("device/receiverclock", "122MHz").unwrap();
+    match dev.get("device/receiverclock").unwrap() {
+        ConfigItem::Enum { value, options, .. } => assert_eq!(options[value as usize], "122MHz"),
+        i => panic!("unexpected item {i:?}"),
+    }
+
+    let profile = dev.export_config().unwrap();
+    assert_eq!(
+        profile.get("main/centerfreq"),
+        Some(&ConfigValue::Float(1e9))
+    );
+    assert_eq!(
+        profile.get("device/receiverclock"),
+        Some(&ConfigValue::String("122MHz".into()))
+    );
+    assert_eq!(profile.get("device/calibrate"), None);
+
+    dev.set_float("main/centerfreq", 2e9).unwrap();
+    dev.apply_config(&profile).unwrap();
+    assert!(matches!(dev.get("main/centerfreq").unwrap(), ConfigItem::Number(f) if f == 1e9));
+}
+
+#[test]
+fn config_diff() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    let mut a = ConfigProfile::new();
+    a.set("main/centerfreq", ConfigValue::Float(1e9));
+    a.set("main/reflevel", ConfigValue::Int(-20));
+    a.set("device/receiverclock", ConfigValue::String("92MHz".into()));
+    let mut b = a.clone();
+    b.set("main/centerfreq", ConfigValue::Float(2e9));
+    b.remove("device/receiverclock");
+    b.set("main/decimation", ConfigValue::String("1 / 64".into()));
+
+    let changes = a.diff(&b);
+    assert_eq!(
+        changes,
+        vec![
+            ConfigChange::Changed {
+                path: "main/centerfreq".into(),
+                old: ConfigValue::Float(1e9),
+                new: ConfigValue::Float(2e9),
+            },
+            ConfigChange::Removed {
+                path: "device/receiverclock".into(),
+                value: ConfigValue::String("92MHz".into()),
+            },
+            ConfigChange::Added {
+                path: "main/decimation".into(),
+                value: ConfigValue::String("1 / 64".into()),
+            },
+        ]
+    );
+    assert_eq!(
+        changes[0].to_string(),
+        "~ main/centerfreq: 1000000000 -> 2000000000"
+    );
+    assert!(a.diff(&a).is_empty());
+
+    // a profile from an older firmware with a parameter that no longer exists
+    dev.apply_config(&a).unwrap();
+    assert!(dev.diff_against(&a).unwrap().is_empty());
+    a.set("main/oldparameter", ConfigValue::Bool(true));
+    dev.set_float("main/centerfreq", 3e9).unwrap();
+    let changes = dev.diff_against(&a).unwrap();
+    assert_eq!(changes.len(), 2);
+    assert!(matches!(
+        &changes[0],
+        ConfigChange::Changed { path, new: ConfigValue::Float(f), .. }
+            if path == "main/centerfreq" && *f == 3e9
+    ));
+    assert_eq!(changes[1].path(), "main/oldparameter");
+}
+
+#[test]
+fn profiles() {
+    use aaronia_rtsa::profiles;
+    use aaronia_rtsa::profiles::ProfileStore;
+
+    let _g = setup();
+    let dir = std::env::temp_dir().join(format!("rtsa-profiles-{}", std::process::id()));
+    let store = ProfileStore::new(&dir);
+    assert!(store.list().unwrap().is_empty());
+
+    let mut p = ConfigProfile::new();
+    p.set("main/centerfreq", ConfigValue::Float(1e9));
+    p.set("main/reflevel", ConfigValue::Int(-20));
+    p.set("device/receiverclock", ConfigValue::String("92MHz".into()));
+    p.set("main/note", ConfigValue::String("a \"b\" \\ c".into()));
+    p.set("device/usb3", ConfigValue::Bool(true));
+    store.save("fm-broadcast", &p).unwrap();
+    assert_eq!(store.load("fm-broadcast").unwrap(), p);
+    assert!(store.path("../escape").is_err());
+    assert!(store.save("", &p).is_err());
+
+    // hand-written profiles with comments and unquoted options
+    let text = "# FM\nmain/centerfreq = 98.5e6\n\ndevice/receiverchannel = Rx1\n";
+    let parsed = profiles::parse(text).unwrap();
+    assert_eq!(
+        parsed.get("main/centerfreq"),
+        Some(&ConfigValue::Float(98.5e6))
+    );
+    assert_eq!(
+        parsed.get("device/receiverchannel"),
+        Some(&ConfigValue::String("Rx1".into()))
+    );
+    assert_eq!(profiles::parse(&profiles::format(&parsed)).unwrap(), parsed);
+    assert!(profiles::parse("main/centerfreq").is_err());
+    assert!(profiles::parse("main/note = \"open").is_err());
+
+    // save and apply by name through the user directory
+    std::env::set_var("RTSA_PROFILE_DIR", &dir);
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_float("main/centerfreq", 2e9).unwrap();
+    dev.save_profile("wifi-2g4-monitor").unwrap();
+    assert_eq!(
+        store.list().unwrap(),
+        vec!["fm-broadcast", "wifi-2g4-monitor"]
+    );
+    dev.set_float("main/centerfreq", 3e9).unwrap();
+    dev.apply_profile("wifi-2g4-monitor").unwrap();
+    assert!(matches!(dev.get("main/centerfreq").unwrap(), ConfigItem::Number(f) if f == 2e9));
+    assert!(matches!(
+        dev.apply_profile("missing"),
+        Err(Error::Profile { name, .. }) if name == "missing"
+    ));
+    std::env::remove_var("RTSA_PROFILE_DIR");
+
+    store.remove("fm-broadcast").unwrap();
+    assert!(!store.contains("fm-broadcast"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn config_path() {
+    use aaronia_rtsa::config_path;
+    use aaronia_rtsa::ConfigPath;
+    use aaronia_rtsa::ConfigType;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    let freq = config_path!("main/centerfreq");
+    assert_eq!(freq.kind(), ConfigType::Number);
+    assert_eq!(ConfigPath::lookup("main/centerfreq"), Some(freq));
+    assert_eq!(ConfigPath::lookup("main/centrefreq"), None);
+
+    dev.set_typed(freq, &ConfigValue::Float(1e9)).unwrap();
+    assert_eq!(dev.get_typed(freq).unwrap(), ConfigValue::Float(1e9));
+    assert!(matches!(
+        dev.set_typed(freq, &ConfigValue::String("1GHz".into())),
+        Err(Error::ErrorValueInvalid)
+    ));
+
+    // paths work with the untyped API and runtime strings still do
+    let format = config_path!("device/outputformat");
+    dev.set(format, "spectra").unwrap();
+    assert_eq!(
+        dev.get_typed(format).unwrap(),
+        ConfigValue::String("spectra".into())
+    );
+    dev.set(String::from("device/outputformat"), "iq").unwrap();
+    assert!(matches!(
+        dev.get_typed(config_path!("device/preamp")).unwrap(),
+        ConfigValue::Bool(false)
+    ));
+}
+
+#[test]
+fn diagnostics() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    let e = dev.get("main/nothing").unwrap_err();
+    assert!(matches!(e, Error::ErrorNotFound));
+    let d = aaronia_rtsa::last_diagnostic().unwrap();
+    assert_eq!(d.function, "AARTSAAPI_ConfigFind");
+    assert!(d
+        .args
+        .iter()
+        .any(|(k, v)| *k == "path" && v.contains("main/nothing")));
+
+    let detailed = e.with_detail();
+    assert!(detailed.detail.is_some());
+    assert!(detailed.to_string().contains("AARTSAAPI_ConfigFind"));
+
+    // diagnostics of other errors are not attached
+    assert!(Error::ErrorBusy.with_detail().detail.is_none());
+}
+
+#[test]
+fn runtime_locate() {
+    use aaronia_rtsa::runtime;
+
+    let _g = setup();
+    let dir = std::env::temp_dir().join(format!("aaronia-rtsa-locate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("RTSA_DIR", &dir);
+    assert_eq!(runtime::search_dirs()[0], dir);
+
+    // without the library, the error lists the searched directories
+    if runtime::candidates().is_empty() {
+        let e = runtime::locate().unwrap_err();
+        assert!(matches!(&e, Error::LibraryNotLocated { searched } if searched[0] == dir));
+        assert!(e.to_string().contains(&dir.display().to_string()));
+    }
+
+    std::fs::write(dir.join(runtime::LIBRARY_NAME), b"").unwrap();
+    let lib = runtime::locate().unwrap();
+    assert_eq!(lib.path, dir.join(runtime::LIBRARY_NAME));
+    assert_eq!(lib.version, aaronia_rtsa::version());
+
+    std::env::remove_var("RTSA_DIR");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn throughput_test() {
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    #[cfg(not(feature = "strict-state"))]
+    assert!(matches!(
+        dev.throughput_test(Duration::from_millis(10)),
+        Err(Error::WrongState { .. })
+    ));
+
+    dev.set_output_format(OutputFormat::Spectra).unwrap();
+    dev.set("main/decimation", "1 / 4").unwrap();
+    dev.connect().unwrap();
+    let report = dev.throughput_test(Duration::from_millis(20)).unwrap();
+    assert!(report.passed());
+    assert!(report.samples > 0 && report.msps() > 0.0);
+    assert_eq!(report.packets * 1024, report.samples);
+    assert!(report.sample_rate.unwrap() > 90e6);
+    #[cfg(target_os = "linux")]
+    assert!(report.cpu_usage().unwrap() > 0.0);
+
+    // the previous configuration is restored
+    assert_eq!(dev.status(), aaronia_rtsa::DeviceStatus::Connected);
+    assert_eq!(dev.output_format().unwrap(), OutputFormat::Spectra);
+    assert!(matches!(
+        dev.get("main/decimation").unwrap(),
+        ConfigItem::Enum { value: 2, .. }
+    ));
+}
+
+#[test]
+fn start_and_wait() {
+    use aaronia_rtsa::DeviceStatus;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Spectra).unwrap();
+    dev.set_float("main/centerfreq", 2e9).unwrap();
+    dev.connect().unwrap();
+
+    let mut states = Vec::new();
+    let info = dev
+        .start_and_wait(Duration::from_secs(1), |p| states.push(p.state))
+        .unwrap();
+    assert_eq!(states.last(), Some(&DeviceState::Running));
+    assert_eq!(dev.status(), DeviceStatus::Started);
+    assert_eq!(info.chan, 2);
+    assert_eq!(info.sample_rate, None);
+    assert!(info.start_frequency < 2e9);
+
+    // the first packet is still available
+    let p = dev.packet(2).unwrap();
+    assert_eq!(p.start_stream_time(), info.first_timestamp);
+    dev.consume(2).unwrap();
+    assert_eq!(dev.queue_stats(2).unwrap().packets, 1);
+
+    dev.stop().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    let info = dev.start_and_wait(Duration::from_secs(1), |_| {}).unwrap();
+    assert_eq!(info.chan, 0);
+    assert_eq!(info.start_frequency, 2e9);
+    assert!(info.sample_rate.unwrap() > 0.0);
+}
+
+#[test]
+fn burst_detector() {
+    use aaronia_rtsa::burst::Burst;
+    use aaronia_rtsa::burst::BurstDetector;
+    use aaronia_rtsa::StreamTime;
+    use num_complex::Complex32;
+
+    let rate = 1e6;
+    let mut samples = vec![Complex32::new(0.001, 0.0); 3000];
+    for s in &mut samples[1000..1500] {
+        *s = Complex32::new(0.5, 0.0);
+    }
+    let mut det = BurstDetector::new(-20.0);
+    det.window = 8;
+    det.pre_trigger = 10;
+
+    // split into packets to check the state between calls
+    let t0 = StreamTime::from_secs(1.0);
+    let mut bursts = Vec::new();
+    for (i, chunk) in samples.chunks(700).enumerate() {
+        let start = t0.sample_time(700 * i as i64, rate);
+        bursts.extend(det.process_samples(chunk, start, rate, 1e9));
+    }
+    assert_eq!(bursts.len(), 1);
+    let b = &bursts[0];
+    // the window triggers on its first sample, extended by the pre-trigger samples
+    let first = t0.sample_index(b.start, rate);
+    assert!((1000 - 18..=1000 - 10).contains(&first), "{first}");
+    assert!(b.samples.len() >= 500 && b.samples.len() < 540);
+    assert_eq!(b.start.sample_index(b.stop, rate), b.samples.len() as i64);
+    assert!((b.peak_power - -6.02).abs() < 0.1);
+    assert_eq!(b.center_frequency, 1e9);
+    assert!(!det.in_burst());
+
+    // the stub streams a tone 6 dB below full scale, i.e., one long burst
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let mut det = BurstDetector::new(-10.0);
+    det.max_length = 1000;
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::scope(|s| {
+        s.spawn(move || {
+            let bursts: Vec<Burst> = rx.iter().take(3).collect();
+            assert!(bursts
+                .iter()
+                .all(|b| b.truncated && b.samples.len() == 1000));
+            assert_eq!(bursts[0].stop, bursts[1].start);
+        });
+        det.run(&mut dev, 0, &tx).unwrap();
+    });
+}
+
+#[test]
+fn peak_tracker() {
+    use aaronia_rtsa::peaks::find_peaks;
+    use aaronia_rtsa::peaks::PeakEvent;
+    use aaronia_rtsa::peaks::PeakTracker;
+    use aaronia_rtsa::Spectrum;
+
+    // emitter drifting by one bin per spectrum, and a second one, only in the first spectra
+    let spectrum = |t: usize| {
+        let mut data = vec![-100.0; 64];
+        data[10 + t] = -40.0 - t as f32;
+        data[11 + t] = -46.0 - t as f32;
+        if t < 3 {
+            data[50] = -60.0;
+        }
+        Spectrum {
+            start_time: t as f64 * 0.1,
+            end_time: (t + 1) as f64 * 0.1,
+            start_frequency: 1e9,
+            step_frequency: 10e3,
+            rbw_frequency: 10e3,
+            data,
+        }
+    };
+
+    let s = spectrum(0);
+    let view = aaronia_rtsa::SpectrumView {
+        start_frequency: s.start_frequency,
+        step_frequency: s.step_frequency,
+        rbw_frequency: s.rbw_frequency,
+        data: &s.data,
+    };
+    let peaks = find_peaks(&view, -80.0, 0.0);
+    assert_eq!(peaks.len(), 2);
+    assert_eq!(peaks[0].bin, 10);
+    // interpolated towards the stronger neighbor
+    let f = (peaks[0].frequency - 1e9) / 10e3;
+    assert!(f > 10.0 && f < 10.5, "{f}");
+    assert!(peaks[0].level >= -40.0);
+    assert_eq!(find_peaks(&view, -50.0, 0.0).len(), 1);
+    assert_eq!(find_peaks(&view, -80.0, 1e6).len(), 1);
+
+    let mut tracker = PeakTracker::new(-80.0);
+    tracker.tolerance = 15e3;
+    tracker.hold = std::time::Duration::from_millis(150);
+    let mut events = Vec::new();
+    for t in 0..10 {
+        events.extend(tracker.process(&spectrum(t)));
+    }
+    assert_eq!(tracker.emitters().count(), 1);
+    events.extend(tracker.flush());
+    assert_eq!(tracker.emitters().count(), 0);
+
+    let appeared: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            PeakEvent::Appeared(e) => Some(e.id),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(appeared, [0, 1]);
+    let gone: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            PeakEvent::Disappeared(e) => Some(e),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(gone.len(), 2);
+    // the weak emitter is gone after the hold time, the drifting one at the flush
+    assert_eq!(gone[0].id, 1);
+    assert_eq!(gone[0].detections, 3);
+    assert!((gone[0].duration() - 0.3).abs() < 1e-9);
+    assert_eq!(gone[1].id, 0);
+    assert_eq!(gone[1].detections, 10);
+    assert!((gone[1].duration() - 1.0).abs() < 1e-9);
+    assert!(gone[1].max_level >= -40.0 && gone[1].level < gone[1].max_level - 3.0);
+    assert!(gone[1].drift() > 80e3 && gone[1].drift() < 100e3);
+
+    // short detections are not reported, if confirmation is required
+    let mut tracker = PeakTracker::new(-80.0);
+    tracker.confirm = 5;
+    let events: Vec<_> = (0..3).flat_map(|t| tracker.process(&spectrum(t))).collect();
+    assert!(events.is_empty());
+
+    // the stub spectra have one peak in the center bin
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Spectra).unwrap();
+    dev.set_float("main/reflevel", -20.0).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let mut tracker = PeakTracker::new(-60.0);
+    let mut events = Vec::new();
+    for _ in 0..4 {
+        let p = dev.packet(2).unwrap();
+        events.extend(tracker.process_packet(&p));
+        dev.consume(2).unwrap();
+    }
+    assert_eq!(events.len(), 1);
+    let e = tracker.emitters().next().unwrap();
+    assert_eq!(e.detections, 4);
+    assert!((e.max_level - -26.0).abs() < 1e-3);
+}
+
+#[test]
+fn channelizer() {
+    use aaronia_rtsa::dsp::Channelizer;
+    use aaronia_rtsa::measurements::power;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.set_float("main/centerfreq", 1e9).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    // the stub streams a tone at an eighth of the sample rate, i.e., in channel 1
+    let p = dev.packet(0).unwrap();
+    let rate = p.sample_rate().unwrap();
+    let mut ch = Channelizer::new(8, rate);
+    assert_eq!(ch.frequency_offset(1), rate / 8.0);
+    assert_eq!(ch.frequency_offset(7), -rate / 8.0);
+
+    let mut outputs = ch.process_packet(&p);
+    dev.consume(0).unwrap();
+    for _ in 0..3 {
+        let p = dev.packet(0).unwrap();
+        for (o, n) in outputs.iter_mut().zip(ch.process_packet(&p)) {
+            assert_eq!(o.frequency, n.frequency);
+            o.samples.extend(n.samples);
+        }
+        dev.consume(0).unwrap();
+    }
+
+    assert_eq!(outputs.len(), 8);
+    assert_eq!(outputs[1].frequency, 1e9 + rate / 8.0);
+    assert_eq!(outputs[1].sample_rate, rate / 8.0);
+    assert_eq!(outputs[1].samples.len(), 4 * 1024 / 8);
+    // skip the filter transient
+    let tone = power(&outputs[1].samples[32..]);
+    assert!((tone - 0.25).abs() < 0.01, "{tone}");
+    for o in outputs.iter().filter(|o| o.index != 1) {
+        assert!(power(&o.samples[32..]) < 1e-3 * tone, "channel {}", o.index);
+    }
+}
+
+#[test]
+fn spectrum_engine() {
+    use aaronia_rtsa::dsp::SpectrumEngine;
+    use aaronia_rtsa::dsp::Window;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.set_float("main/centerfreq", 1e9).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let mut engine = SpectrumEngine::new(256, Window::BlackmanHarris);
+    engine.set_overlap(0.5);
+    engine.set_averages(4);
+    assert_eq!(engine.hop(), 128);
+
+    // 4 packets of 1024 samples are 31 frames with 50% overlap, i.e., 7 averaged spectra
+    let mut spectra = Vec::new();
+    let mut rate = 0.0;
+    for _ in 0..4 {
+        let p = dev.packet(0).unwrap();
+        rate = p.sample_rate().unwrap();
+        spectra.extend(engine.process_packet(&p));
+        dev.consume(0).unwrap();
+    }
+    assert_eq!(spectra.len(), 7);
+
+    // the stub streams a tone with amplitude 0.5 at an eighth of the sample rate
+    let s = &spectra[0];
+    assert_eq!(s.data.len(), 256);
+    assert_eq!(s.step_frequency, rate / 256.0);
+    assert_eq!(s.start_frequency, 1e9 - rate / 2.0);
+    assert!(s.rbw_frequency > s.step_frequency);
+    let (peak, level) = s
+        .data
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .unwrap();
+    let frequency = s.start_frequency + peak as f64 * s.step_frequency;
+    assert_eq!(frequency, 1e9 + rate / 8.0);
+    assert!((level + 6.02).abs() < 0.1, "{level}");
+    assert!(s.data[64] < level - 90.0);
+    assert!(s.start_time < s.end_time);
+    assert!(spectra[1].start_time > s.start_time);
+}
+
+#[test]
+fn mask_trigger() {
+    use aaronia_rtsa::dsp::SpectrumEngine;
+    use aaronia_rtsa::dsp::Window;
+    use aaronia_rtsa::trigger::Mask;
+    use aaronia_rtsa::Spectrum;
+
+    assert!(matches!(Mask::new(vec![]), Err(Error::ErrorValueInvalid)));
+    assert!(matches!(
+        Mask::new(vec![(2.0, 0.0), (1.0, 0.0)]),
+        Err(Error::ErrorValueInvalid)
+    ));
+    let mask = Mask::new(vec![(10.0, -50.0), (20.0, -30.0), (20.0, -60.0)]).unwrap();
+    assert_eq!(mask.limit(0.0), -50.0);
+    assert_eq!(mask.limit(15.0), -40.0);
+    assert_eq!(mask.limit(20.0), -60.0);
+    assert_eq!(mask.limit(100.0), -60.0);
+
+    let spectrum = Spectrum {
+        start_time: 1.0,
+        end_time: 2.0,
+        start_frequency: 0.0,
+        step_frequency: 5.0,
+        rbw_frequency: 5.0,
+        data: vec![-55.0, -45.0, -42.0, -45.0, -65.0, -58.0],
+    };
+    let violations = mask.check(&spectrum);
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].frequency, 10.0);
+    assert_eq!(violations[0].margin, 8.0);
+    assert_eq!(violations[0].bins, 2);
+    assert_eq!(violations[1].frequency, 25.0);
+    assert_eq!(violations[1].bins, 1);
+    assert!(!Mask::from_spectrum(&spectrum, 1.0).violated(&spectrum));
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.set_float("main/centerfreq", 1e9).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    // the stub streams a tone with amplitude 0.5, i.e., -6 dBFS, at an eighth of the sample rate
+    let mut engine = SpectrumEngine::new(256, Window::Hann);
+    let c = dev
+        .capture_mask(0, &Mask::flat(-20.0), &mut engine, 100, 3000)
+        .unwrap();
+    let rate = c.spectrum.step_frequency * 256.0;
+    assert_eq!(c.violations.len(), 1);
+    assert_eq!(c.violations[0].frequency, 1e9 + rate / 8.0);
+    assert!((c.violations[0].margin - 14.0).abs() < 0.1);
+    assert_eq!(c.capture.trigger_time, c.spectrum.start_time);
+    assert_eq!(c.capture.trigger_index, 0);
+    assert_eq!(c.capture.samples.len(), 3000);
+
+    // spectra of the tone stay below a mask, derived from them
+    let mask = Mask::from_spectrum(&c.spectrum, 3.0);
+    let p = dev.packet(0).unwrap();
+    let spectra = engine.process_packet(&p);
+    dev.consume(0).unwrap();
+    assert!(!spectra.is_empty());
+    assert!(spectra.iter().all(|s| !mask.violated(s)));
+}
+
+#[test]
+fn consume_contract() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    // without consuming, the same packet is returned
+    let first = dev.packet(0).unwrap();
+    let duration = first.end_time() - first.start_time();
+    let again = dev.packet(0).unwrap();
+    assert_eq!(again.start_time(), first.start_time());
+    assert_eq!(again.samples(), first.samples());
+
+    dev.consume(0).unwrap();
+    let t = dev.packet(0).unwrap().start_time();
+    assert!((t - (first.start_time() + duration)).abs() < 1e-9);
+
+    dev.consume_n(0, 0).unwrap();
+    assert_eq!(dev.packet(0).unwrap().start_time(), t);
+    dev.consume_n(0, 3).unwrap();
+    let t = dev.packet(0).unwrap().start_time();
+    assert!((t - (first.start_time() + 4.0 * duration)).abs() < 1e-9);
+
+    let avail = dev.packets_avail(0).unwrap();
+    assert_eq!(dev.consume_all(0).unwrap(), avail);
+    let next = dev.packet(0).unwrap().start_time();
+    assert!((next - (t + avail as f64 * duration)).abs() < 1e-9);
+
+    dev.stop().unwrap();
+    assert_eq!(dev.consume_all(0).unwrap(), 0);
+}
+
+#[test]
+fn packets() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.set("main/decimation", "1 / 64").unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let mut end = None;
+    for _ in 0..4 {
+        let p = dev.packet(0).unwrap();
+        match p.data() {
+            PacketData::Iq(s) => assert_eq!(s.len(), 1024),
+            _ => panic!("expected IQ samples"),
+        }
+        if let Some(end) = end {
+            assert_eq!(p.start_time(), end);
+        }
+        end = Some(p.end_time());
+        dev.consume(0).unwrap();
+    }
+
+    let stats = dev.queue_stats(0).unwrap();
+    assert_eq!(stats.packets, 4);
+    assert_eq!(stats.drops, 0);
+
+    assert!(matches!(dev.packet(2), Err(Error::ErrorInvalidChannel)));
+}
+
+#[test]
+fn recover() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_float("main/centerfreq", 1e9).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    stub::unplug(stub::DEFAULT_SERIAL);
+    assert!(matches!(dev.packet(0), Err(Error::ErrorNotConnected)));
+
+    stub::plug(stub::DEFAULT_SERIAL);
+    dev.recover().unwrap();
+    assert_eq!(dev.state().unwrap(), DeviceState::Running);
+    assert!(matches!(dev.get("main/centerfreq").unwrap(), ConfigItem::Number(f) if f == 1e9));
+    dev.packet(0).unwrap();
+}
+
+#[test]
+fn reset_and_reboot() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    #[cfg(not(feature = "strict-state"))]
+    assert!(matches!(dev.reset(), Err(Error::WrongState { .. })));
+
+    dev.set_float("main/centerfreq", 1e9).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    stub::clear_calls();
+    dev.reboot().unwrap();
+    assert!(stub::calls().contains(&"Press reboot".to_string()));
+    // the device is reopened after it left the bus and came back
+    assert!(!stub::reenumerating(stub::DEFAULT_SERIAL));
+    assert_eq!(dev.status(), aaronia_rtsa::DeviceStatus::Started);
+    assert_eq!(dev.state().unwrap(), DeviceState::Running);
+    assert!(matches!(dev.get("main/centerfreq").unwrap(), ConfigItem::Number(f) if f == 1e9));
+    dev.packet(0).unwrap();
+
+    dev.reset().unwrap();
+    assert!(!stub::reenumerating(stub::DEFAULT_SERIAL));
+    assert_eq!(dev.status(), aaronia_rtsa::DeviceStatus::Opened);
+    assert!(dev.last_config().is_empty());
+    assert!(!matches!(dev.get("main/centerfreq").unwrap(), ConfigItem::Number(f) if f == 1e9));
+    assert!(matches!(
+        dev.get("device/reset").unwrap(),
+        ConfigItem::Button
+    ));
+}
+
+#[test]
+fn find_config() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    let entries = dev.find_config("FREQ").unwrap();
+    let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, vec!["main/centerfreq"]);
+    assert_eq!(entries[0].kind, aaronia_rtsa::ConfigType::Number);
+    assert!(entries[0].min < entries[0].max);
+
+    let clock = dev.config_entry("device/receiverclock").unwrap();
+    assert_eq!(clock.options.len(), 4);
+}
+
+#[test]
+fn set_float_clamped() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    assert!(matches!(
+        dev.set_float("main/reflevel", 50.0),
+        Err(Error::ErrorValueInvalid)
+    ));
+    assert_eq!(dev.set_float_clamped("main/reflevel", 50.0).unwrap(), 10.0);
+    assert_eq!(
+        dev.set_float_clamped("main/reflevel", -30.0).unwrap(),
+        -30.0
+    );
+    assert_eq!(dev.set_float_clamped("main/transgain", 0.3).unwrap(), 0.5);
+
+    // values, rounded by the device, are recorded as applied and replayed by recover()
+    assert!(matches!(
+        dev.set_float("main/transgain", -1.2),
+        Err(Error::WarningValueAdjusted)
+    ));
+    assert!(matches!(
+        dev.last_config().get("main/transgain"),
+        Some(ConfigValue::Float(f)) if *f == -1.0
+    ));
+    stub::unplug(stub::DEFAULT_SERIAL);
+    stub::plug(stub::DEFAULT_SERIAL);
+    dev.recover().unwrap();
+    assert!(matches!(dev.get("main/transgain").unwrap(), ConfigItem::Number(f) if f == -1.0));
+}
+
+#[test]
+fn vita49() {
+    use aaronia_rtsa::vita49::Vita49Sender;
+    use aaronia_rtsa::StreamTime;
+    use num_complex::Complex32;
+
+    let rx = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    rx.set_read_timeout(Some(std::time::Duration::from_secs(1)))
+        .unwrap();
+    let mut tx = Vita49Sender::new(rx.local_addr().unwrap(), 42).unwrap();
+    tx.max_samples = 100;
+
+    let samples = vec![Complex32::new(0.5, -0.5); 150];
+    tx.send_samples(&samples, StreamTime::from_secs(10.25), 1e9, 1e6)
+        .unwrap();
+
+    let mut buf = [0u8; 2048];
+    let word = |b: &[u8], i: usize| u32::from_be_bytes(b[4 * i..4 * i + 4].try_into().unwrap());
+
+    // context
+    let n = rx.recv(&mut buf).unwrap();
+    assert_eq!(n, 17 * 4);
+    assert_eq!(word(&buf, 0) >> 28, 0x4);
+    assert_eq!(word(&buf, 1), 42);
+
+    // data
+    let n = rx.recv(&mut buf).unwrap();
+    assert_eq!(n, (7 + 100) * 4);
+    assert_eq!(word(&buf, 0) >> 28, 0x1);
+    assert_eq!(word(&buf, 0) & 0xffff, 107);
+    assert_eq!(word(&buf, 4), 10);
+    assert_eq!(
+        (word(&buf, 5) as u64) << 32 | word(&buf, 6) as u64,
+        250_000_000_000
+    );
+    assert_eq!(word(&buf, 7), (16384 << 16) | (-16384i16 as u16 as u32));
+
+    let n = rx.recv(&mut buf).unwrap();
+    assert_eq!(n, (7 + 50) * 4);
+    assert_eq!((word(&buf, 0) >> 16) & 0xf, 1);
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn server() {
+    use aaronia_rtsa::server::Server;
+    use num_complex::Complex32;
+    use std::io::Read;
+
+    let mut server = Server::bind("127.0.0.1:0").unwrap();
+    let mut client = std::net::TcpStream::connect(server.local_addr()).unwrap();
+    while server.clients() == 0 {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+
+    let samples = vec![Complex32::new(1.0, -1.0); 10];
+    server.send_samples(&samples, 2.4e9, 1e6, 3.5);
+
+    let mut buf = vec![0u8; aaronia_rtsa::server::HEADER_SIZE + 80];
+    client.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf[0..4], b"RTSA");
+    assert_eq!(u32::from_le_bytes(buf[8..12].try_into().unwrap()), 10);
+    assert_eq!(f64::from_le_bytes(buf[16..24].try_into().unwrap()), 2.4e9);
+    assert_eq!(f64::from_le_bytes(buf[32..40].try_into().unwrap()), 3.5);
+    assert_eq!(f32::from_le_bytes(buf[44..48].try_into().unwrap()), -1.0);
+
+    server.set_sample_format(aaronia_rtsa::SampleFormat::I16);
+    server.send_samples(&samples, 2.4e9, 1e6, 3.5);
+    let mut buf = vec![0u8; aaronia_rtsa::server::HEADER_SIZE + 40];
+    client.read_exact(&mut buf).unwrap();
+    assert_eq!(u16::from_le_bytes(buf[6..8].try_into().unwrap()), 2);
+    assert_eq!(
+        i16::from_le_bytes(buf[42..44].try_into().unwrap()),
+        -i16::MAX
+    );
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn publish_metrics() {
+    use metrics::Counter;
+    use metrics::CounterFn;
+    use metrics::Gauge;
+    use metrics::GaugeFn;
+    use metrics::Histogram;
+    use metrics::Key;
+    use metrics::KeyName;
+    use metrics::Metadata;
+    use metrics::Recorder;
+    use metrics::SharedString;
+    use metrics::Unit;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    type Values = Arc<Mutex<BTreeMap<String, f64>>>;
+
+    /// Metric, identified by its name and labels, e.g., `name{device=X,channel=0}`.
+    struct Slot(String, Values);
+
+    impl CounterFn for Slot {
+        fn increment(&self, value: u64) {
+            *self.1.lock().unwrap().entry(self.0.clone()).or_default() += value as f64;
+        }
+        fn absolute(&self, value: u64) {
+            self.1.lock().unwrap().insert(self.0.clone(), value as f64);
+        }
+    }
+
+    impl GaugeFn for Slot {
+        fn increment(&self, value: f64) {
+            *self.1.lock().unwrap().entry(self.0.clone()).or_default() += value;
+        }
+        fn decrement(&self, value: f64) {
+            *self.1.lock().unwrap().entry(self.0.clone()).or_default() -= value;
+        }
+        fn set(&self, value: f64) {
+            self.1.lock().unwrap().insert(self.0.clone(), value);
+        }
+    }
+
+    #[derive(Default)]
+    struct Capture(Values);
+
+    impl Capture {
+        fn slot(&self, key: &Key) -> Arc<Slot> {
+            let labels: Vec<String> = key
+                .labels()
+                .map(|l| format!("{}={}", l.key(), l.value()))
+                .collect();
+            let name = format!("{}{{{}}}", key.name(), labels.join(","));
+            Arc::new(Slot(name, self.0.clone()))
+        }
+    }
+
+    impl Recorder for Capture {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.slot(key))
+        }
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(self.slot(key))
+        }
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    for _ in 0..2 {
+        dev.packet(0).unwrap();
+        dev.consume(0).unwrap();
+    }
+    let capture = Capture::default();
+    metrics::with_local_recorder(&capture, || dev.publish_metrics()).unwrap();
+    let stats = dev.queue_stats(0).unwrap();
+    let values = capture.0.lock().unwrap();
+
+    let chan = |name: &str| values[&format!("{name}{{device={},channel=0}}", stub::DEFAULT_SERIAL)];
+    assert_eq!(chan("rtsa_packets_total"), 2.0);
+    assert_eq!(chan("rtsa_drops_total"), 0.0);
+    assert_eq!(chan("rtsa_dropped_seconds"), 0.0);
+    assert_eq!(chan("rtsa_sample_rate_hertz"), stats.sample_rate.unwrap());
+    assert_eq!(chan("rtsa_queue_available"), stats.available as f64);
+    assert_eq!(chan("rtsa_queue_high_water"), stats.high_water as f64);
+
+    let sensors: Vec<_> = values
+        .iter()
+        .filter(|(k, _)| k.starts_with("rtsa_temperature_celsius{"))
+        .collect();
+    assert_eq!(sensors.len(), 1);
+    assert!(sensors[0]
+        .0
+        .contains(&format!("device={}", stub::DEFAULT_SERIAL)));
+    assert!(sensors[0].0.contains("sensor=") && sensors[0].0.contains("temperature"));
+    assert_eq!(*sensors[0].1, 40.0);
+    assert!(values
+        .iter()
+        .any(|(k, v)| k.starts_with("rtsa_health{") && k.contains("temperature") && *v == 40.0));
+    // only numeric and boolean health items are published
+    assert!(!values.keys().any(|k| k.contains("firmware")));
+}
+
+#[cfg(feature = "soak")]
+#[test]
+fn soak() {
+    use aaronia_rtsa::soak::Soak;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    let _g = setup();
+    let log = std::env::temp_dir().join(format!("rtsa-soak-{}.jsonl", std::process::id()));
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let mut soak = Soak::new(Duration::from_millis(200));
+    soak.health_interval = Duration::from_millis(50);
+    soak.log = Some(log.clone());
+    let report = soak.run(&mut dev).unwrap();
+    assert!(report.passed(), "{report:?}");
+    assert!(report.samples >= report.packets * 1024);
+    assert!(report.health_samples >= 2);
+    assert_eq!(report.peak_temperature(), Some(40.0));
+    assert_eq!(report.log_error, None);
+    assert!(report.to_json().starts_with("{\"passed\":true,"));
+
+    let lines = std::fs::read_to_string(&log).unwrap();
+    let events: Vec<&str> = lines.lines().collect();
+    assert!(events[0].contains("\"event\":\"health\""));
+    assert!(events[0].contains("\"temperature\":40"));
+    assert!(events.last().unwrap().contains("\"event\":\"report\""));
+    std::fs::remove_file(&log).unwrap();
+
+    // a set stop flag ends the test before the first packet
+    let report = Soak::new(Duration::from_secs(3600))
+        .run_until(&mut dev, &AtomicBool::new(true))
+        .unwrap();
+    assert_eq!(report.packets, 0);
+    assert!(!report.passed());
+    dev.stop().unwrap();
+}
+
+#[test]
+fn session() {
+    use aaronia_rtsa::session::Session;
+    use aaronia_rtsa::DeviceStatus;
+    use aaronia_rtsa::PacketMeta;
+    use num_complex::Complex32;
+    use std::ops::ControlFlow;
+    use std::time::Duration;
+
+    let _g = setup();
+    let dir = std::env::temp_dir().join(format!("rtsa-session-{}", std::process::id()));
+    let mut dev = device();
+    dev.open().unwrap();
+
+    let mut config = ConfigProfile::new();
+    config.set("device/outputformat", ConfigValue::String("iq".into()));
+    config.set("main/centerfreq", ConfigValue::Float(1e9));
+    let mut session = Session::new();
+    session.config = Some(config);
+    session.annotations.operator = Some("Jane \"J\" Doe".into());
+    session.annotations.location = Some("lab 2".into());
+    session.duration = Some(Duration::from_secs(10));
+    let mut packets = 0;
+    session.add_sink(move |_: &PacketMeta, _: &[Complex32]| {
+        packets += 1;
+        if packets == 4 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    let report = session.run(&mut dev, &dir).unwrap();
+    assert_eq!(dev.status(), DeviceStatus::Opened);
+    assert_eq!(report.samples, 4 * 1024);
+    assert_eq!(report.captures, 1);
+    assert_eq!(report.health_samples, 1);
+
+    let config = std::fs::read_to_string(dir.join("config.json")).unwrap();
+    assert!(
+        config.contains("\"main/centerfreq\":1000000000"),
+        "{config}"
+    );
+    let data = std::fs::metadata(dir.join("capture.sigmf-data")).unwrap();
+    assert_eq!(data.len(), 4 * 1024 * 8);
+    let meta = std::fs::read_to_string(dir.join("capture.sigmf-meta")).unwrap();
+    assert!(meta.contains("\"core:datatype\":\"cf32_le\""), "{meta}");
+    assert!(
+        meta.contains("\"core:author\":\"Jane \\\"J\\\" Doe\""),
+        "{meta}"
+    );
+    assert!(meta.contains("\"rtsa:location\":\"lab 2\""), "{meta}");
+    assert!(meta.contains("\"core:sample_start\":0,\"core:frequency\":1000000000,"));
+    let health = std::fs::read_to_string(dir.join("health.jsonl")).unwrap();
+    assert!(health.contains("\"temperature\":40"), "{health}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn pipeline() {
+    use aaronia_rtsa::pipeline::DropPolicy;
+    use aaronia_rtsa::pipeline::Payload;
+    use aaronia_rtsa::pipeline::Pipeline;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let single = Arc::new(AtomicUsize::new(0));
+    let pooled = Arc::new(AtomicUsize::new(0));
+    let (s, p) = (single.clone(), pooled.clone());
+    let pipeline = Pipeline::builder(0)
+        .capacity(4)
+        .drop_policy(DropPolicy::Block)
+        .stage(move |p| {
+            assert!(matches!(&p.payload, Payload::Iq(v) if v.len() == 1024));
+            s.fetch_add(1, Ordering::Relaxed);
+        })
+        .pool(3, move |_| {
+            p.fetch_add(1, Ordering::Relaxed);
+        })
+        .start(dev);
+
+    while single.load(Ordering::Relaxed) < 10 {
+        std::thread::yield_now();
+    }
+    let mut dev = pipeline.stop().unwrap();
+    assert_eq!(
+        single.load(Ordering::Relaxed),
+        pooled.load(Ordering::Relaxed)
+    );
+    dev.stop().unwrap();
+}
+
+#[test]
+fn tee() {
+    use aaronia_rtsa::pipeline::DropPolicy;
+    use aaronia_rtsa::tee::Tee;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let tee = Tee::start(dev, 0);
+    let record = tee.output(4, DropPolicy::Block);
+    let newest = tee.output(2, DropPolicy::DropNewest);
+    let oldest = tee.output(2, DropPolicy::DropOldest);
+    let closed = tee.output(2, DropPolicy::Block);
+    drop(closed);
+
+    // the blocking output gets a gapless stream, while the others are not read
+    let recorder = std::thread::spawn(move || {
+        let packets: Vec<_> = record.take(50).collect();
+        for w in packets.windows(2) {
+            assert!((w[1].meta.start_time - w[0].meta.end_time).abs() < 1e-9);
+        }
+        packets.len()
+    });
+    assert_eq!(recorder.join().unwrap(), 50);
+    assert!(newest.dropped() > 0);
+    assert!(oldest.dropped() > 0);
+    assert_eq!(tee.outputs(), 2);
+
+    let mut dev = tee.stop().unwrap();
+    let first = newest.recv().unwrap();
+    let second = newest.recv().unwrap();
+    assert!((second.meta.start_time - first.meta.end_time).abs() < 1e-9);
+    assert!(newest.recv().is_none());
+    let last = oldest.try_recv().unwrap();
+    assert!(last.meta.start_time > second.meta.start_time);
+    assert!(oldest.recv_timeout(Duration::from_millis(10)).is_some());
+    assert!(oldest.is_empty());
+    dev.stop().unwrap();
+}
+
+#[test]
+fn units() {
+    use aaronia_rtsa::config_path;
+    use aaronia_rtsa::sweep::Sweeper;
+    use aaronia_rtsa::Db;
+    use aaronia_rtsa::Hz;
+    use aaronia_rtsa::Seconds;
+    use aaronia_rtsa::Units;
+    use std::time::Duration;
+
+    assert_eq!(810.mhz(), Hz(810e6));
+    assert_eq!(2.4.ghz().as_mhz(), 2400.0);
+    assert_eq!(-20.db(), Db(-20.0));
+    assert!((20.db().to_amplitude_ratio() - 10.0).abs() < 1e-12);
+    assert_eq!(Seconds::from(Duration::from_millis(5)), 5.millis());
+    assert_eq!(
+        10.mhz().period().to_duration(),
+        Some(Duration::from_nanos(100))
+    );
+    assert_eq!(Hz::from(1e3), 1.khz());
+    assert_eq!(f64::from(1.khz()), 1e3);
+    assert_eq!(format!("{}", 1.5.khz()), "1500 Hz");
+
+    let sweep = Sweeper::new(1.ghz(), 2.ghz(), 40.mhz());
+    assert_eq!(sweep.start_frequency, 1e9);
+    assert_eq!(Sweeper::new(1e9, 2e9, 40e6).step, sweep.step);
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_float("main/centerfreq", 810.mhz()).unwrap();
+    assert!(matches!(dev.get("main/centerfreq").unwrap(), ConfigItem::Number(f) if f == 810e6));
+    let freq = config_path!("main/centerfreq");
+    dev.set_typed(freq, &2.4.ghz().into()).unwrap();
+    assert_eq!(dev.get_typed(freq).unwrap(), ConfigValue::Float(2.4e9));
+
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    let p = dev.packet(0).unwrap();
+    assert_eq!(
+        p.center_frequency(),
+        Hz(p.start_frequency() + p.span_frequency() / 2.0)
+    );
+    assert_eq!(p.span(), p.meta().span());
+    assert_eq!(p.duration().value(), p.end_time() - p.start_time());
+    assert_eq!(p.rbw(), p.meta().rbw());
+    dev.stop().unwrap();
+}
+
+#[test]
+fn open_options() {
+    use aaronia_rtsa::OpenOptions;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open_with(&OpenOptions::new().boost(true)).unwrap();
+
+    assert!(matches!(
+        dev.get("device/boost").unwrap(),
+        ConfigItem::Bool(true)
+    ));
+    assert!(matches!(
+        dev.get("device/usbcompatibility").unwrap(),
+        ConfigItem::Bool(false)
+    ));
+    dev.close().unwrap();
+}
+
+#[test]
+fn clock_sync() {
+    use aaronia_rtsa::ClockSync;
+    use aaronia_rtsa::ReferenceClock;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    assert_eq!(
+        dev.reference_clock().unwrap(),
+        Some(ReferenceClock::Internal)
+    );
+    dev.set_reference_clock(ReferenceClock::Gps).unwrap();
+    assert_eq!(dev.reference_clock().unwrap(), Some(ReferenceClock::Gps));
+
+    dev.connect().unwrap();
+    let mut sync = ClockSync::new(8);
+    let status = sync.update(&mut dev).unwrap();
+    assert_eq!(status.locked, Some(true));
+    assert!(status.drift_ppm.is_none());
+    sync.update(&mut dev).unwrap();
+    assert!(sync.offset().is_some());
+}
+
+#[test]
+fn monitor() {
+    use aaronia_rtsa::monitor::Band;
+    use aaronia_rtsa::monitor::CsvLog;
+    use aaronia_rtsa::monitor::Monitor;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Spectra).unwrap();
+    dev.set_float("main/reflevel", -20.0).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let bands = vec![Band::new("a", 400e6, 402e6), Band::new("b", 800e6, 840e6)];
+    let mut monitor = Monitor::new(bands, -30.0);
+    monitor.interval = std::time::Duration::ZERO;
+    let mut csv = CsvLog::new(Vec::new());
+    monitor.run(&mut dev, &mut csv, Some(2)).unwrap();
+    assert_eq!(csv.into_inner().iter().filter(|b| **b == b'\n').count(), 5);
+
+    for s in monitor.stats() {
+        assert_eq!(s.scans, 2);
+        assert_eq!(s.duty_cycle(), 1.0);
+        assert_eq!(s.max_power, -26.0);
+        assert!(s.last_seen.is_some());
+    }
+
+    monitor.threshold = -10.0;
+    monitor.reset();
+    monitor.scan(&mut dev).unwrap();
+    assert_eq!(monitor.stats()[0].duty_cycle(), 0.0);
+}
+
+#[test]
+fn iter_devices() {
+    let _g = setup();
+    stub::set_devices(&["A", "B", "C"]);
+
+    let mut api = ApiHandle::new().unwrap();
+    api.rescan_devices().unwrap();
+    let b = api
+        .iter_devices("spectranv6")
+        .find(|d| d.as_ref().map(|d| d.serial() == "B").unwrap_or(true))
+        .unwrap()
+        .unwrap();
+    assert_eq!(b.serial(), "B");
+
+    let mut devices = api.devices().unwrap();
+    stub::set_devices(&["B", "D"]);
+    assert_eq!(api.refresh_devices(&mut devices).unwrap(), 1);
+    let serials: Vec<String> = devices.iter().map(|d| d.serial()).collect();
+    assert_eq!(serials, vec!["A", "B", "C", "D"]);
+    assert!(!devices[0].ready());
+}
+
+#[test]
+fn buffer_pool() {
+    use aaronia_rtsa::BufferPool;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let pool = BufferPool::new(2048, 4);
+    let mut buf = pool.get();
+    dev.packet_into(0, &mut buf).unwrap();
+    assert_eq!(buf.samples().len(), 1024);
+    assert_eq!(buf.samples().as_ptr() as usize % 64, 0);
+    assert_eq!(buf.samples()[0].re, 0.5);
+    assert_eq!(buf.meta().unwrap().num, 1024);
+
+    let ptr = buf.as_f32().as_ptr();
+    drop(buf);
+    assert_eq!(pool.available(), 1);
+    assert_eq!(pool.get().as_f32().as_ptr(), ptr);
+}
+
+#[test]
+fn rf_path() {
+    use aaronia_rtsa::RfSwitch;
+    use aaronia_rtsa::RxChannel;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    let caps = dev.rf_capabilities().unwrap();
+    assert_eq!(
+        caps.rx_channels,
+        vec![
+            RxChannel::Rx1,
+            RxChannel::Rx2,
+            RxChannel::Both,
+            RxChannel::Off
+        ]
+    );
+    assert_eq!(caps.antennas, vec!["RF1", "RF2"]);
+    assert_eq!(caps.switches, vec![RfSwitch::Preamp]);
+
+    dev.set_rx_channel(RxChannel::Both).unwrap();
+    assert_eq!(dev.rx_channel().unwrap(), RxChannel::Both);
+    dev.set_rx_antenna("rf2").unwrap();
+    assert_eq!(dev.rx_antenna().unwrap(), "RF2");
+    dev.set_rf_switch(RfSwitch::Preamp, true).unwrap();
+    assert!(dev.rf_switch(RfSwitch::Preamp).unwrap());
+    assert!(dev.set_rf_switch(RfSwitch::Attenuator, true).is_err());
+}
+
+#[test]
+fn rescan_timeout() {
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut api = ApiHandle::new().unwrap();
+
+    stub::set_rescan_retries(2);
+    assert!(matches!(api.try_rescan(), Err(Error::Retry)));
+    assert!(matches!(api.try_rescan(), Err(Error::Retry)));
+    api.try_rescan().unwrap();
+
+    stub::set_rescan_retries(3);
+    api.rescan_devices_timeout(Duration::from_secs(1)).unwrap();
+
+    stub::set_rescan_retries(usize::MAX);
+    assert!(matches!(
+        api.rescan_devices_timeout(Duration::from_millis(10)),
+        Err(Error::Retry)
+    ));
+}
+
+#[test]
+fn cancel_token() {
+    use aaronia_rtsa::CancelToken;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut api = ApiHandle::new().unwrap();
+    let token = CancelToken::new();
+    api.rescan_devices_cancellable(&token).unwrap();
+
+    // cancel a scan that never completes from another thread
+    stub::set_rescan_retries(usize::MAX);
+    let t = token.clone();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        t.cancel();
+    });
+    assert!(matches!(
+        api.rescan_devices_cancellable(&token),
+        Err(Error::Cancelled)
+    ));
+    canceller.join().unwrap();
+    stub::set_rescan_retries(0);
+
+    // the queue stays empty, while the device is not started
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    let token = CancelToken::new();
+    let t = token.clone();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        t.cancel();
+    });
+    assert!(matches!(
+        dev.packet_cancellable(0, &token),
+        Err(Error::Cancelled)
+    ));
+    canceller.join().unwrap();
+
+    dev.start().unwrap();
+    assert!(dev.packet_cancellable(0, &CancelToken::new()).is_ok());
+}
+
+#[cfg(not(feature = "strict-state"))]
+#[test]
+fn wrong_state() {
+    use aaronia_rtsa::DeviceStatus;
+
+    let _g = setup();
+    let mut dev = device();
+
+    assert!(matches!(
+        dev.connect(),
+        Err(Error::WrongState {
+            expected: DeviceStatus::Opened,
+            actual: DeviceStatus::Uninit
+        })
+    ));
+    dev.open().unwrap();
+    assert!(matches!(dev.open(), Err(Error::WrongState { .. })));
+    assert!(matches!(dev.stop(), Err(Error::WrongState { .. })));
+    assert_eq!(dev.status(), DeviceStatus::Opened);
+}
+
+#[test]
+fn spectra_rows() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Spectra).unwrap();
+    dev.set_float("main/reflevel", -20.0).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    stub::set_spectra_rows(stub::DEFAULT_SERIAL, 4);
+
+    let p = dev.packet(2).unwrap();
+    let rows: Vec<_> = p.spectra_rows().collect();
+    assert_eq!(rows.len(), 4);
+    let step = (p.end_time() - p.start_time()) / 4.0;
+    for (i, r) in rows.iter().enumerate() {
+        assert_eq!(r.index, i);
+        assert_eq!(r.spectrum.data.len(), 1024);
+        assert!(r.spectrum.data.iter().all(|v| !v.is_nan()));
+        assert_eq!(r.spectrum.data[512], -26.0 - i as f32);
+        assert!((r.start_time - (p.start_time() + i as f64 * step)).abs() < 1e-12);
+        assert!((r.end_time - r.start_time - step).abs() < 1e-12);
+    }
+    assert_eq!(rows[3].end_time, p.end_time());
+    let end = p.end_time();
+    dev.consume(2).unwrap();
+
+    let p = dev.packet(2).unwrap();
+    assert_eq!(p.start_time(), end);
+}
+
+#[test]
+fn packet_layout() {
+    use aaronia_rtsa::PayloadKind;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Both).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    stub::set_spectra_rows(stub::DEFAULT_SERIAL, 4);
+
+    let p = dev.packet(0).unwrap();
+    let layout = p.layout();
+    assert!(layout.is_valid());
+    assert_eq!(layout.kind, PayloadKind::Iq);
+    assert_eq!((layout.rows, layout.row_len, layout.channels), (1, 1024, 1));
+    assert_eq!(layout.bytes(), 1024 * 8);
+    assert_eq!(p.try_samples().unwrap(), p.samples());
+    assert!(matches!(
+        p.try_spectrum_row(0),
+        Err(Error::ErrorInvalidParameter)
+    ));
+    dev.consume(0).unwrap();
+
+    let p = dev.packet(2).unwrap();
+    let layout = p.layout();
+    assert!(layout.is_valid());
+    assert_eq!(layout.kind, PayloadKind::Spectrum);
+    assert_eq!((layout.rows, layout.row_len), (4, 1024));
+    assert!(layout.stride >= 1024);
+    assert_eq!(layout.floats, 3 * layout.stride + 1024);
+    assert_eq!(p.try_payload().unwrap().len(), layout.floats);
+    for (i, r) in p.spectra_rows().enumerate() {
+        assert_eq!(p.try_spectrum_row(i).unwrap(), r.spectrum.data);
+    }
+    assert!(matches!(
+        p.try_spectrum_row(4),
+        Err(Error::ErrorInvalidParameter)
+    ));
+    assert!(matches!(p.try_samples(), Err(Error::ErrorInvalidParameter)));
+    assert!(
+        matches!(p.data(), PacketData::Spectrum(s) if s.data == p.try_spectrum_row(0).unwrap())
+    );
+    dev.consume(2).unwrap();
+
+    // rows of malformed packets are skipped instead of read
+    stub::set_malformed_packets(stub::DEFAULT_SERIAL, Some((None, true)));
+    let p = dev.packet(2).unwrap();
+    assert!(!p.layout().is_valid());
+    assert!(matches!(
+        p.try_spectrum_row(0),
+        Err(Error::ErrorInvalidSize)
+    ));
+    assert_eq!(p.spectra_rows().count(), 0);
+    assert!(matches!(p.data(), PacketData::Raw(d) if d.is_empty()));
+    dev.consume(2).unwrap();
+    let p = dev.packet(0).unwrap();
+    assert!(matches!(p.try_samples(), Err(Error::ErrorInvalidSize)));
+    assert!(matches!(p.data(), PacketData::Raw(d) if d.is_empty()));
+    dev.consume(0).unwrap();
+    stub::set_malformed_packets(stub::DEFAULT_SERIAL, None);
+
+    dev.stop().unwrap();
+    dev.set_output_format(OutputFormat::Raw).unwrap();
+    dev.start().unwrap();
+    for num in [-1, i64::MAX] {
+        stub::set_malformed_packets(stub::DEFAULT_SERIAL, Some((Some(num), false)));
+        let p = dev.packet(0).unwrap();
+        assert!(!p.layout().is_valid());
+        assert!(matches!(p.data(), PacketData::Raw(d) if d.is_empty()));
+        dev.consume(0).unwrap();
+    }
+    stub::set_malformed_packets(stub::DEFAULT_SERIAL, None);
+    let p = dev.packet(0).unwrap();
+    assert!(matches!(p.data(), PacketData::Raw(d) if d.len() == p.layout().floats));
+}
+
+#[test]
+fn render_spectrogram() {
+    use aaronia_rtsa::render::Colormap;
+    use aaronia_rtsa::render::Spectrogram;
+    use aaronia_rtsa::render::Waterfall;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Spectra).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    stub::set_spectra_rows(stub::DEFAULT_SERIAL, 4);
+
+    let mut waterfall = Waterfall::new(10);
+    for _ in 0..3 {
+        waterfall.push_packet(&dev.packet(2).unwrap());
+        dev.consume(2).unwrap();
+    }
+    assert_eq!(waterfall.len(), 10);
+    assert_eq!(waterfall.bins(), 1024);
+    let times: Vec<f64> = waterfall.rows().map(|(t, _)| t).collect();
+    assert!(times.windows(2).all(|t| t[0] > t[1]));
+
+    let mut spectrogram = Spectrogram {
+        width: Some(256),
+        axes: false,
+        ..Spectrogram::new()
+    };
+    let img = spectrogram.render(&waterfall);
+    assert_eq!((img.width, img.height), (256, 10));
+    assert_eq!(img.data.len(), 256 * 10 * 3);
+
+    spectrogram.axes = true;
+    let annotated = spectrogram.render(&waterfall);
+    assert!(annotated.width > 256 && annotated.height > 10);
+
+    // fixed range maps the extremes to the ends of the colormap
+    let spectrogram = Spectrogram {
+        colormap: Colormap::Inferno,
+        min: Some(-100.0),
+        max: Some(0.0),
+        ..Spectrogram::new()
+    };
+    let row = spectrogram.render_row(&[-120.0, -50.0, 10.0]);
+    assert_eq!(&row[0..3], &Colormap::Inferno.color(0.0));
+    assert_eq!(&row[3..6], &Colormap::Inferno.color(0.5));
+    assert_eq!(&row[6..9], &Colormap::Inferno.color(1.0));
+    assert_eq!(Colormap::Viridis.color(1.0), [253, 231, 37]);
+}
+
+#[test]
+fn coherent_rx() {
+    use aaronia_rtsa::coherent::phase_difference;
+    use aaronia_rtsa::coherent::CoherentRx;
+    use aaronia_rtsa::coherent::PairAligner;
+    use aaronia_rtsa::RxChannel;
+    use aaronia_rtsa::StreamTime;
+    use num_complex::Complex32;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.configure_coherent_rx().unwrap();
+    assert_eq!(dev.rx_channel().unwrap(), RxChannel::Both);
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let mut rx = CoherentRx::new();
+    let mut pairs = Vec::new();
+    let t0 = rx.read_pairs(&mut dev, &mut pairs).unwrap();
+    let t1 = rx.read_pairs(&mut dev, &mut pairs).unwrap();
+    assert_eq!(pairs.len(), 2048);
+    assert!(t1 > t0);
+    assert_eq!(rx.aligner().dropped(), 0);
+    let phase = phase_difference(&pairs);
+    assert!((phase - stub::RX2_PHASE).abs() < 1e-3, "phase {phase}");
+
+    // the stream that starts earlier is cut to the start of the other one
+    let mut aligner = PairAligner::new();
+    let ones = vec![Complex32::new(1.0, 0.0); 10];
+    aligner.push_samples(0, &ones, StreamTime::from_secs(0.0), 1.0, 1e9);
+    aligner.push_samples(1, &ones, StreamTime::from_secs(3.0), 1.0, 1e9);
+    let mut out = Vec::new();
+    assert_eq!(aligner.pairs(&mut out), Some(StreamTime::from_secs(3.0)));
+    assert_eq!(out.len(), 7);
+    assert_eq!(aligner.dropped(), 3);
+    assert_eq!(aligner.end(0), None);
+    assert_eq!(aligner.end(1), Some(StreamTime::from_secs(13.0)));
+}
+
+#[test]
+fn iq_correction() {
+    use aaronia_rtsa::dsp::IqCorrector;
+    use num_complex::Complex32;
+
+    // tone with DC offset, 10% gain, and 5 degrees phase imbalance
+    let (gain, phase) = (1.1f32, 5f32.to_radians());
+    let dc = Complex32::new(0.05, -0.02);
+    let tone = |n: usize| {
+        let t = 2.0 * std::f32::consts::PI * 0.01 * n as f32;
+        Complex32::new(0.5 * t.cos(), 0.5 * gain * (t + phase).sin()) + dc
+    };
+    let mut corrector = IqCorrector::new();
+    let mut samples: Vec<Complex32> = (0..100_000).map(tone).collect();
+    corrector.process(&mut samples);
+    assert!((corrector.dc_offset() - dc).norm() < 1e-3);
+    assert!((corrector.gain_imbalance() - gain).abs() < 1e-2);
+    assert!((corrector.phase_imbalance() - phase).abs() < 1e-2);
+    for (n, s) in samples.iter().enumerate().skip(1000).step_by(997) {
+        let t = 2.0 * std::f32::consts::PI * 0.01 * n as f32;
+        let ideal = Complex32::new(0.5 * t.cos(), 0.5 * t.sin());
+        assert!((s - ideal).norm() < 1e-2, "sample {n}: {s} vs. {ideal}");
+    }
+
+    // the stub tone is clean and fetching a packet again does not correct it twice
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.set_iq_correction(Some(IqCorrector::new()));
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    assert!(dev.iq_correction(0).is_none());
+
+    let first = dev.packet(0).unwrap().samples().to_vec();
+    assert_eq!(dev.packet(0).unwrap().samples(), &first[..]);
+    assert!((first[0] - Complex32::new(0.5, 0.0)).norm() < 1e-4);
+    let c = dev.iq_correction(0).unwrap();
+    assert!(c.dc_offset().norm() < 1e-4);
+    assert!((c.gain_imbalance() - 1.0).abs() < 1e-3);
+
+    dev.set_iq_correction(None);
+    assert!(dev.iq_correction(0).is_none());
+}
+
+#[test]
+fn iq_health() {
+    use aaronia_rtsa::measurements::IqStats;
+    use num_complex::Complex32;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let health = dev
+        .iq_health_check(0, std::time::Duration::from_millis(10))
+        .unwrap();
+    assert!(health.is_ok(), "{health:?}");
+    assert!((health.stats.rms_dbfs() + 6.02).abs() < 0.01);
+    assert_eq!(health.stats.clipped(), 0);
+
+    let mut stats = IqStats::new();
+    let skewed: Vec<Complex32> = (0..1000)
+        .map(|n| {
+            let p = 2.0 * std::f32::consts::PI * n as f32 / 10.0;
+            Complex32::new(0.1 + p.cos(), 0.5 * (p + 0.1).sin())
+        })
+        .collect();
+    stats.push(&skewed);
+    assert!((stats.dc_offset().re - 0.1).abs() < 1e-4);
+    assert!((stats.gain_imbalance_db() - 6.02).abs() < 0.01);
+    assert!((stats.phase_imbalance_deg() - 0.1f32.to_degrees()).abs() < 0.01);
+    assert_eq!(stats.clipped(), 100);
+    stats.reset();
+    assert_eq!(stats.count(), 0);
+}
+
+#[test]
+fn device_info() {
+    use aaronia_rtsa::HardwareModel;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    let info = dev.info().unwrap();
+    assert_eq!(info.serial, stub::DEFAULT_SERIAL);
+    assert_eq!(info.model, Some(HardwareModel::V6Eco));
+    assert_eq!(info.model_name.as_deref(), Some("SPECTRAN V6 ECO"));
+    assert_eq!(info.firmware_version.as_deref(), Some("1.4.2"));
+    assert_eq!(info.calibration_date.as_deref(), Some("2024-03-01"));
+    assert_eq!(info.options, ["RTBW245", "TX"]);
+
+    assert_eq!(HardwareModel::parse("Spectran V6"), Some(HardwareModel::V6));
+    assert_eq!(
+        HardwareModel::parse("SPECTRAN V6 X"),
+        Some(HardwareModel::X)
+    );
+    assert_eq!(HardwareModel::parse("unknown"), None);
+}
+
+#[test]
+fn capabilities() {
+    use aaronia_rtsa::config_path;
+    use aaronia_rtsa::HardwareModel;
+    use aaronia_rtsa::Hz;
+    use aaronia_rtsa::RxChannel;
+    use aaronia_rtsa::Units;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    // the bandwidth option unlocks all clocks of the Eco
+    let caps = dev.capabilities().unwrap();
+    assert_eq!(caps.model, Some(HardwareModel::V6Eco));
+    assert_eq!(caps.receiver_clocks.len(), 4);
+    assert_eq!(caps.decimations[..3], [1, 2, 4]);
+    assert_eq!(caps.max_sample_rate(), Some(245.mhz()));
+    assert!(caps.tx);
+    assert!(caps.supports_frequency(2.4.ghz()));
+    assert!(!caps.supports_frequency(100.mhz()));
+    assert!(caps.rx_channels.contains(&RxChannel::Both));
+
+    dev.set_center_frequency(5.8.ghz()).unwrap();
+    assert!(matches!(
+        dev.set_center_frequency(10.ghz()),
+        Err(Error::ErrorValueInvalid)
+    ));
+    assert!(matches!(
+        dev.set_typed(config_path!("main/centerfreq"), &10.ghz().into()),
+        Err(Error::ErrorValueInvalid)
+    ));
+    dev.set_sample_rate(61.25.mhz()).unwrap();
+    assert_eq!(dev.sample_rate().unwrap(), 61.25.mhz());
+    assert!(matches!(
+        dev.set_sample_rate(100.mhz()),
+        Err(Error::ErrorValueInvalid)
+    ));
+    dev.close().unwrap();
+
+    // without options, the model limits the clocks and TX
+    dev.open().unwrap();
+    stub::set_variant(stub::DEFAULT_SERIAL, "SPECTRAN V6 ECO", "");
+    let caps = dev.capabilities().unwrap();
+    assert_eq!(caps.receiver_clocks, [Hz(92e6)]);
+    assert!(!caps.tx);
+    assert!(!caps.supports_sample_rate(122.mhz()));
+    assert!(matches!(
+        dev.set_typed(config_path!("device/receiverclock"), &ConfigValue::Int(3)),
+        Err(Error::ErrorValueInvalid)
+    ));
+    dev.set_sample_rate(46.mhz()).unwrap();
+    dev.close().unwrap();
+
+    dev.open().unwrap();
+    stub::set_variant(stub::DEFAULT_SERIAL, "SPECTRAN V6 PLUS", "");
+    let caps = dev.capabilities().unwrap();
+    assert_eq!(caps.model, Some(HardwareModel::V6Plus));
+    assert_eq!(caps.max_sample_rate(), Some(245.mhz()));
+    assert!(caps.tx);
+}
+
+#[test]
+fn packet_sink() {
+    use aaronia_rtsa::record::Recorder;
+    use aaronia_rtsa::PacketMeta;
+    use aaronia_rtsa::PacketSink;
+    use num_complex::Complex32;
+    use std::ops::ControlFlow;
+
+    let _g = setup();
+    let dir = std::env::temp_dir().join(format!("rtsa-sink-{}", std::process::id()));
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    // a closure counts the packets and stops, a recorder writes them
+    let mut times = Vec::new();
+    let counter = |meta: &PacketMeta, samples: &[Complex32]| {
+        assert_eq!(samples.len(), 1024);
+        times.push(meta.start_time);
+        if times.len() < 5 {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    };
+    let mut sink = (counter, Recorder::new(&dir, "rx").unwrap());
+    dev.run_sink(0, &mut sink).unwrap();
+    let (_, mut rec) = sink;
+    rec.finish().unwrap();
+    assert_eq!(rec.samples(), 5 * 1024);
+    assert_eq!(times.len(), 5);
+
+    // packets are consumed, i.e., the next sink continues the stream
+    let mut n = 0;
+    let mut sinks: Vec<Box<dyn PacketSink>> = vec![
+        Box::new(|_: &PacketMeta, _: &[Complex32]| ControlFlow::Continue(())),
+        Box::new(|meta: &PacketMeta, _: &[Complex32]| {
+            assert!(meta.start_time > *times.last().unwrap());
+            n += 1;
+            if n == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        }),
+    ];
+    dev.run_sink(0, &mut sinks).unwrap();
+    drop(sinks);
+    assert_eq!(n, 3);
+
+    dev.stop().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn packet_source() {
+    use aaronia_rtsa::io::Cf32Player;
+    use aaronia_rtsa::io::Cf32Writer;
+    use aaronia_rtsa::io::Endian;
+    use aaronia_rtsa::io::IqWriter;
+    use aaronia_rtsa::Transmitter;
+    use aaronia_rtsa::TxFormat;
+    use num_complex::Complex32;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    // packets are back to back, a new frequency starts a new segment
+    let mut n = 0;
+    let mut source = |samples: &mut Vec<Complex32>| {
+        n += 1;
+        samples.resize(1000, Complex32::new(1.0, 0.0));
+        match n {
+            1..=3 => Some(TxFormat {
+                frequency: 1e9,
+                sample_rate: 1e6,
+            }),
+            4 => Some(TxFormat {
+                frequency: 2e9,
+                sample_rate: 1e6,
+            }),
+            _ => None,
+        }
+    };
+    let mut tx = Transmitter::new();
+    tx.lookahead = Duration::from_millis(20);
+    tx.run(&mut dev, &mut source).unwrap();
+    assert_eq!(tx.packets(), 4);
+    assert_eq!(tx.underruns(), 0);
+    let sent = stub::sent_packets(stub::DEFAULT_SERIAL);
+    assert_eq!(sent.len(), 4);
+    assert!((sent[1].0 - sent[0].0 - 1e-3).abs() < 1e-9);
+    assert!((sent[2].0 - sent[1].0 - 1e-3).abs() < 1e-9);
+    assert_eq!(sent[3].1, 2e9);
+    // pacing keeps the packets within the lookahead of the device clock
+    assert!(sent[3].0 - dev.clock().unwrap() < 0.03);
+
+    // a source that falls behind restarts the schedule
+    let mut n = 0;
+    let mut slow = |samples: &mut Vec<Complex32>| {
+        n += 1;
+        if n == 2 {
+            std::thread::sleep(Duration::from_millis(30));
+        }
+        samples.resize(100, Complex32::new(1.0, 0.0));
+        (n <= 3).then_some(TxFormat {
+            frequency: 1e9,
+            sample_rate: 1e6,
+        })
+    };
+    let mut tx = Transmitter::new();
+    tx.run(&mut dev, &mut slow).unwrap();
+    assert_eq!(tx.packets(), 3);
+    assert_eq!(tx.underruns(), 1);
+
+    // a file is played in packets of `packet_len` samples
+    let mut w = Cf32Writer::new(Vec::new(), Endian::Little);
+    w.write(&vec![Complex32::new(0.5, -0.5); 2500]).unwrap();
+    let file = w.into_inner();
+    let mut player = Cf32Player::new(&file[..], Endian::Little, 1e9, 1e6);
+    player.packet_len = 1000;
+    let before = stub::sent_packets(stub::DEFAULT_SERIAL).len();
+    dev.run_source(0, &mut player).unwrap();
+    assert!(player.error().is_none());
+    let sent = stub::sent_packets(stub::DEFAULT_SERIAL);
+    let lens: Vec<usize> = sent[before..].iter().map(|p| p.2).collect();
+    assert_eq!(lens, vec![1000, 1000, 500]);
+    dev.stop().unwrap();
+}
+
+#[test]
+fn tx_watermarks() {
+    use aaronia_rtsa::Transmitter;
+    use aaronia_rtsa::TxFormat;
+    use aaronia_rtsa::Watermarks;
+    use num_complex::Complex32;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    // packets of 10 ms, the lookahead alone would queue 100 of them
+    let mut source = |samples: &mut Vec<Complex32>| {
+        samples.resize(10_000, Complex32::new(1.0, 0.0));
+        Some(TxFormat {
+            frequency: 1e9,
+            sample_rate: 1e6,
+        })
+    };
+    let mut tx = Transmitter::new();
+    tx.lookahead = Duration::from_secs(1);
+    tx.watermarks = Some(Watermarks { low: 2, high: 4 });
+    for _ in 0..10 {
+        assert!(tx.send_next(&mut dev, &mut source).unwrap());
+        assert!(tx.queue_depth().is_none_or(|d| d < 4));
+        assert!(dev.packets_avail(0).unwrap() <= 4);
+    }
+    assert_eq!(tx.packets(), 10);
+    assert_eq!(tx.underruns(), 0);
+    // the queue runs low, while it fills after the start
+    assert!(tx.low_water() >= 1);
+    dev.stop().unwrap();
+}
+
+#[test]
+fn recorder() {
+    use aaronia_rtsa::record::Recorder;
+    use aaronia_rtsa::StreamTime;
+
+    let _g = setup();
+    let dir = std::env::temp_dir().join(format!("rtsa-recorder-{}", std::process::id()));
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let mut rec = Recorder::new(&dir, "rx").unwrap();
+    rec.segment_size = Some(3000 * 8);
+    let mut first = None;
+    for _ in 0..4 {
+        let p = dev.packet(0).unwrap();
+        first.get_or_insert(p.samples()[1]);
+        rec.write_packet(&p).unwrap();
+        dev.consume(0).unwrap();
+    }
+    let tone = [num_complex::Complex32::new(1.0, 0.0); 100];
+    rec.write(&tone, StreamTime::from_secs(100.0), 1e9, 1e6)
+        .unwrap();
+    rec.finish().unwrap();
+    assert_eq!(rec.segments(), 2);
+    assert_eq!(rec.samples(), 4196);
+
+    let seg0 = std::fs::read(dir.join("rx-00000.cf32")).unwrap();
+    let seg1 = std::fs::read(dir.join("rx-00001.cf32")).unwrap();
+    assert_eq!(seg0.len(), 3000 * 8);
+    assert_eq!(seg1.len(), 1196 * 8);
+    let first = first.unwrap();
+    assert_eq!(seg0[8..12], first.re.to_le_bytes());
+    assert_eq!(seg0[12..16], first.im.to_le_bytes());
+
+    let index = std::fs::read_to_string(dir.join("rx.index.csv")).unwrap();
+    let lines: Vec<&str> = index.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("0,rx-00000.cf32,0,0,"));
+    assert!(lines[2].starts_with("1,rx-00001.cf32,3000,0,"));
+    assert_eq!(
+        lines[3],
+        "1,rx-00001.cf32,4096,1096,100.000000000,1000000000,1000000"
+    );
+
+    drop(rec);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn sample_format() {
+    use aaronia_rtsa::record::Recorder;
+    use aaronia_rtsa::SampleFormat;
+    use aaronia_rtsa::StreamTime;
+    use num_complex::Complex32;
+
+    let samples = [
+        Complex32::new(0.5, -0.25),
+        Complex32::new(1.0, -1.0),
+        Complex32::new(2.0, -3.0),
+    ];
+    for format in [SampleFormat::F32, SampleFormat::I16, SampleFormat::I8] {
+        let mut bytes = Vec::new();
+        let clipped = format.encode(&samples, &mut bytes);
+        assert_eq!(bytes.len(), 3 * format.sample_bytes());
+        assert_eq!(format.to_string().parse::<SampleFormat>().unwrap(), format);
+        assert_eq!(format.extension().parse::<SampleFormat>().unwrap(), format);
+
+        let decoded = format.decode(&bytes);
+        assert!((decoded[0] - samples[0]).norm() < 0.01);
+        assert!((decoded[1] - samples[1]).norm() < 1e-6);
+        if format == SampleFormat::F32 {
+            assert_eq!(clipped, 0);
+            assert_eq!(decoded[2], samples[2]);
+        } else {
+            assert_eq!(clipped, 2);
+            assert!((decoded[2] - samples[1]).norm() < 1e-6);
+        }
+    }
+
+    let dir = std::env::temp_dir().join(format!("rtsa-format-{}", std::process::id()));
+    let mut rec = Recorder::new(&dir, "rx").unwrap();
+    rec.format = SampleFormat::I16;
+    rec.segment_size = Some(100 * 4);
+    rec.write(&[samples[2]; 150], StreamTime::from_secs(0.0), 1e9, 1e6)
+        .unwrap();
+    rec.finish().unwrap();
+    assert_eq!(rec.segments(), 2);
+    assert_eq!(rec.clipped(), 300);
+    let seg0 = std::fs::read(dir.join("rx-00000.cs16")).unwrap();
+    assert_eq!(seg0.len(), 100 * 4);
+    assert_eq!(SampleFormat::I16.decode(&seg0)[0], samples[1]);
+
+    drop(rec);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn recorder_zstd() {
+    use aaronia_rtsa::record::Recorder;
+    use aaronia_rtsa::StreamTime;
+
+    let dir = std::env::temp_dir().join(format!("rtsa-recorder-zstd-{}", std::process::id()));
+    let mut rec = Recorder::new(&dir, "rx").unwrap();
+    rec.compression = Some(3);
+    let samples = vec![num_complex::Complex32::new(0.5, -0.5); 10000];
+    rec.write(&samples, StreamTime::from_secs(1.0), 1e9, 1e6)
+        .unwrap();
+    rec.finish().unwrap();
+
+    let file = std::fs::File::open(dir.join("rx-00000.cf32.zst")).unwrap();
+    let data = zstd::decode_all(file).unwrap();
+    assert_eq!(data.len(), 10000 * 8);
+    assert_eq!(data[0..4], 0.5f32.to_le_bytes());
+
+    drop(rec);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn file_device() {
+    let _g = setup();
+    let path = std::env::temp_dir().join(format!("rtsa-file-{}.rtsa", std::process::id()));
+    std::fs::write(&path, b"").unwrap();
+
+    let mut api = ApiHandle::new().unwrap();
+    assert!(matches!(
+        api.open_file_device(path.with_extension("missing")),
+        Err(Error::ErrorNotFound)
+    ));
+
+    let mut dev = api.open_file_device(&path).unwrap();
+    assert!(dev.is_file_device());
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    assert_eq!(dev.packet(0).unwrap().samples().len(), 1024);
+    dev.consume(0).unwrap();
+    dev.stop().unwrap();
+    dev.disconnect().unwrap();
+    dev.close().unwrap();
+
+    assert!(!device().is_file_device());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn open_shared() {
+    let _g = setup();
+    stub::set_suite_holds(stub::DEFAULT_SERIAL, true);
+
+    let mut dev = device();
+    match dev.open() {
+        Err(Error::DeviceInUse { serial, .. }) => assert_eq!(serial, stub::DEFAULT_SERIAL),
+        r => panic!("unexpected result {r:?}"),
+    }
+    dev.open_shared().unwrap();
+    assert!(dev.is_shared());
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    dev.packet(0).unwrap();
+    dev.consume(0).unwrap();
+    dev.stop().unwrap();
+    dev.disconnect().unwrap();
+    dev.close().unwrap();
+
+    // without the suite, devices can only be opened exclusively
+    stub::set_suite_holds(stub::DEFAULT_SERIAL, false);
+    let mut dev = device();
+    assert!(matches!(dev.open_shared(), Err(Error::ErrorNotFound)));
+    assert!(!dev.is_shared());
+    dev.open().unwrap();
+    dev.close().unwrap();
+}
+
+#[test]
+fn status_events() {
+    use aaronia_rtsa::StatusEvent;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    assert!(dev.status_events().unwrap().is_empty());
+
+    stub::push_status(stub::DEFAULT_SERIAL, 1, 2.0);
+    stub::push_status(stub::DEFAULT_SERIAL, 2, 41.5);
+    stub::push_status(stub::DEFAULT_SERIAL, 3, 0.0);
+    stub::push_status(stub::DEFAULT_SERIAL, 1, 0.0);
+    stub::push_status(stub::DEFAULT_SERIAL, 42, 7.0);
+
+    let events = dev.status_events().unwrap();
+    assert_eq!(events.len(), 5);
+    assert!(matches!(
+        events[0],
+        StatusEvent::Overload { receivers: 2, .. }
+    ));
+    assert!(events[0].is_overload());
+    assert!(matches!(events[1], StatusEvent::Temperature { celsius, .. } if celsius == 41.5));
+    assert!(matches!(events[2], StatusEvent::Pps { .. }));
+    assert!(!events[3].is_overload());
+    assert!(matches!(
+        events[4],
+        StatusEvent::Other { kind: 42, value, .. } if value == 7.0
+    ));
+    assert!(events.windows(2).all(|w| w[0].time() <= w[1].time()));
+
+    // status packets are consumed and do not interfere with the data channels
+    assert!(dev.status_events().unwrap().is_empty());
+    assert_eq!(dev.packet(0).unwrap().samples().len(), 1024);
+    dev.consume(0).unwrap();
+}
+
+#[test]
+fn tuned_stream() {
+    use aaronia_rtsa::tune::StreamEvent;
+    use aaronia_rtsa::tune::TunedStream;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.set_float("main/centerfreq", 1e9).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let mut stream = TunedStream::new(0);
+    stream.settle = Duration::from_nanos(1);
+    for _ in 0..2 {
+        assert!(matches!(
+            stream.next(&mut dev).unwrap(),
+            StreamEvent::Packet(_)
+        ));
+    }
+    assert_eq!(stream.frequency(), Some(1e9));
+
+    dev.set_float("main/centerfreq", 2e9).unwrap();
+    assert_eq!(
+        stream.next(&mut dev).unwrap(),
+        StreamEvent::Retuned {
+            old: 1e9,
+            new: 2e9,
+            at_sample: 2048,
+        }
+    );
+    // the first packet after the change is within the settle time
+    match stream.next(&mut dev).unwrap() {
+        StreamEvent::Packet(p) => assert_eq!(p.meta.start_frequency, 2e9),
+        e => panic!("unexpected event {e:?}"),
+    }
+    assert_eq!(stream.dropped(), 1);
+}
+
+#[test]
+fn decimation_live() {
+    use aaronia_rtsa::pipeline::OwnedPacket;
+    use aaronia_rtsa::tune::StreamEvent;
+    use aaronia_rtsa::tune::TunedStream;
+    use aaronia_rtsa::DeviceStatus;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    let full = dev.sample_rate().unwrap().value();
+
+    // not started, i.e., only configured
+    assert_eq!(
+        dev.set_decimation_live(2).unwrap(),
+        StreamEvent::RateChanged {
+            old: full,
+            new: full / 2.0,
+        }
+    );
+    assert_eq!(dev.status(), DeviceStatus::Connected);
+    assert!(matches!(
+        dev.set_decimation_live(3),
+        Err(Error::ErrorValueInvalid)
+    ));
+
+    dev.start().unwrap();
+    let mut stream = TunedStream::new(0);
+    for _ in 0..2 {
+        assert!(matches!(
+            stream.next(&mut dev).unwrap(),
+            StreamEvent::Packet(_)
+        ));
+    }
+    assert_eq!(stream.sample_rate(), Some(full / 2.0));
+
+    // unchanged decimation does not touch the stream
+    stub::clear_calls();
+    assert_eq!(
+        dev.set_decimation_live(2).unwrap(),
+        StreamEvent::RateChanged {
+            old: full / 2.0,
+            new: full / 2.0,
+        }
+    );
+    assert!(!stub::calls().iter().any(|c| c.contains("Stop")));
+
+    // the device stays connected and is running at the new rate afterwards
+    stub::clear_calls();
+    let event = dev.set_decimation_live(8).unwrap();
+    assert_eq!(
+        event,
+        StreamEvent::RateChanged {
+            old: full / 2.0,
+            new: full / 8.0,
+        }
+    );
+    assert_eq!(dev.status(), DeviceStatus::Started);
+    let calls = stub::calls();
+    assert!(calls.iter().any(|c| c.contains("StopDevice")));
+    assert!(!calls.iter().any(|c| c.contains("DisconnectDevice")));
+    let p = dev.try_packet(0).unwrap();
+    assert_eq!(p.sample_rate(), Some(full / 8.0));
+
+    // the consumer of the stream sees the change in-band
+    assert_eq!(stream.next(&mut dev).unwrap(), event);
+    match stream.next(&mut dev).unwrap() {
+        StreamEvent::Packet(p) => assert_eq!(p.meta.sample_rate(), Some(full / 8.0)),
+        e => panic!("unexpected event {e:?}"),
+    }
+    assert_eq!(stream.sample_rate(), Some(full / 8.0));
+
+    // a refused decimation leaves the stream running at the previous rate
+    stub::fail_config_sets(stub::DEFAULT_SERIAL, "main/decimation", 1);
+    assert!(matches!(dev.set_decimation_live(4), Err(Error::ErrorBusy)));
+    assert_eq!(dev.status(), DeviceStatus::Started);
+    assert_eq!(dev.sample_rate().unwrap().value(), full / 8.0);
+    let p = dev.try_packet(0).unwrap();
+    assert_eq!(p.sample_rate(), Some(full / 8.0));
+
+    // spectra packets do not report rate changes
+    let mut stream = TunedStream::new(2);
+    dev.stop().unwrap();
+    dev.set_output_format(OutputFormat::Spectra).unwrap();
+    dev.start().unwrap();
+    let p = OwnedPacket::from(&dev.packet(2).unwrap());
+    assert_eq!(stream.process(p).len(), 1);
+    assert_eq!(stream.sample_rate(), None);
+}
+
+#[test]
+fn gain_model() {
+    use aaronia_rtsa::RfSwitch;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    let model = dev.gain_model().unwrap();
+    assert_eq!(model.reflevel, -20.0);
+    assert_eq!((model.reflevel_min, model.reflevel_max), (-100.0, 10.0));
+    assert_eq!(model.stages.len(), 1);
+    assert_eq!(model.stages[0].path, "device/preamp");
+    assert_eq!(model.stages[0].steps, vec![0.0, 20.0]);
+    assert_eq!(model.stages[0].gain_db, Some(0.0));
+    assert_eq!(model.total_gain_db(), 20.0);
+    assert_eq!(model.stage_gain_db(), 0.0);
+
+    dev.set_rf_switch(RfSwitch::Preamp, true).unwrap();
+    dev.set_float("main/reflevel", -30.0).unwrap();
+    let model = dev.gain_model().unwrap();
+    assert_eq!(model.stages[0].gain_db, Some(20.0));
+    assert_eq!(model.stage_gain_db(), 20.0);
+    assert_eq!(model.total_gain_db(), 30.0);
+    assert_eq!(model.full_scale_dbm(), -30.0);
+}
+
+#[test]
+fn gain_model_calibration() {
+    use aaronia_rtsa::measurements::Calibration;
+    use aaronia_rtsa::RfSwitch;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_rf_switch(RfSwitch::Preamp, true).unwrap();
+    dev.set_float("main/reflevel", -40.0).unwrap();
+
+    // both models map a full-scale sample to the same absolute power
+    let model = dev.gain_model().unwrap();
+    let cal = Calibration::from_device(&mut dev).unwrap();
+    assert_eq!(model.stage_gain_db(), 20.0);
+    assert_eq!(model.full_scale_dbm() as f32, cal.dbfs_to_dbm(0.0));
+    assert_eq!(cal.dbfs_to_dbm(-10.0), -50.0);
+}
+
+#[test]
+fn rx_gain() {
+    use aaronia_rtsa::agc::Agc;
+    use aaronia_rtsa::RxChannel;
+    use num_complex::Complex32;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    // the stub receivers share their settings
+    assert_eq!(dev.set_rx_reflevel(RxChannel::Rx1, -150.0).unwrap(), -100.0);
+    assert!(dev.set_rx_preamp(RxChannel::Both, true).unwrap());
+    let gain = dev.rx_gain(RxChannel::Rx2).unwrap();
+    assert_eq!(gain.reflevel, -100.0);
+    assert_eq!(gain.preamp, Some(true));
+    assert_eq!(gain.attenuation, None);
+    assert!(matches!(
+        dev.set_rx_attenuation(RxChannel::Rx1, 10.0),
+        Err(Error::ErrorNotFound)
+    ));
+    assert!(matches!(
+        dev.set_rx_preamp(RxChannel::Off, false),
+        Err(Error::ErrorValueInvalid)
+    ));
+
+    let mut agc = Agc::new(-100.0, 10.0);
+    agc.interval = Duration::ZERO;
+    agc.check_health = false;
+    agc.channel = RxChannel::Rx2;
+    let clipping = [Complex32::new(1.0, 0.0)];
+    assert_eq!(agc.adjustment(&mut dev, &clipping).unwrap(), None);
+    assert_eq!(agc.adjustment(&mut dev, &clipping).unwrap(), Some(10.0));
+    // peak at -6 dBFS is 4 dB above the target with 10 dB headroom
+    let tone = [Complex32::new(0.5, 0.0)];
+    assert_eq!(agc.update(&mut dev, &tone).unwrap(), Some(-96.0));
+    assert_eq!(dev.rx_gain(RxChannel::Rx1).unwrap().reflevel, -96.0);
+}
+
+#[test]
+fn hopper() {
+    use aaronia_rtsa::hop::Hop;
+    use aaronia_rtsa::hop::Hopper;
+    use num_complex::Complex32;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let dwell = Duration::from_millis(2);
+    let hops = vec![
+        Hop::new(1e9, dwell),
+        Hop::new(1.1e9, dwell),
+        Hop::new(1.2e9, 2 * dwell),
+    ];
+    let mut hopper = Hopper::new(vec![Complex32::new(1.0, 0.0); 100], 1e6, hops);
+    hopper.settle = Duration::from_micros(200);
+    hopper.run(&mut dev, Some(6)).unwrap();
+
+    let sent = stub::sent_packets(stub::DEFAULT_SERIAL);
+    assert_eq!(sent.len(), 6);
+    let freqs: Vec<f64> = sent.iter().map(|p| p.1).collect();
+    assert_eq!(freqs, [1e9, 1.1e9, 1.2e9, 1e9, 1.1e9, 1.2e9]);
+    let gaps: Vec<f64> = sent.windows(2).map(|w| w[1].0 - w[0].0).collect();
+    for (gap, expected) in gaps.iter().zip([2e-3, 2e-3, 4e-3, 2e-3, 2e-3]) {
+        assert!((gap - expected).abs() < 1e-9);
+    }
+    assert!(sent.iter().all(|p| p.2 == 100));
+
+    let mut slow = Hopper::new(
+        vec![Complex32::new(1.0, 0.0); 10000],
+        1e6,
+        vec![Hop::new(1e9, dwell)],
+    );
+    assert!(matches!(slow.hop(&mut dev), Err(Error::ErrorValueInvalid)));
+}
+
+#[test]
+fn siggen() {
+    use aaronia_rtsa::siggen::SignalGenerator;
+    use aaronia_rtsa::siggen::Waveform;
+    use aaronia_rtsa::StreamTime;
+
+    let mut cw = SignalGenerator::new(Waveform::Cw { offset: 1e5 }, 8e5);
+    let s = cw.generate(16);
+    assert!(s.iter().all(|s| (s.norm() - 0.5).abs() < 1e-6));
+    assert!((s[2].re - 0.0).abs() < 1e-6 && (s[2].im - 0.5).abs() < 1e-6);
+    assert!((cw.generate(1)[0] - s[0]).norm() < 1e-5);
+
+    let mut noise = SignalGenerator::new(Waveform::Noise, 1e6);
+    noise.amplitude = 1.0;
+    let s = noise.generate(100_000);
+    let power = s.iter().map(|s| s.norm_sqr()).sum::<f32>() / s.len() as f32;
+    assert!((power - 1.0).abs() < 0.02);
+
+    let mut qpsk = SignalGenerator::new(Waveform::Qpsk { symbol_rate: 1e5 }, 1e6);
+    let s = qpsk.generate(1000);
+    assert!(s.iter().all(|s| (s.re.abs() - s.im.abs()).abs() < 1e-6));
+    assert!(s.chunks(10).all(|c| c.iter().all(|x| *x == c[0])));
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    let chirp = Waveform::Chirp {
+        start: -1e5,
+        stop: 1e5,
+        period: std::time::Duration::from_millis(1),
+    };
+    let packets = SignalGenerator::new(chirp, 1e6).packets(2e9, 500, StreamTime::from_secs(1.0));
+    for p in packets.take(3) {
+        assert_eq!(p.samples().len(), 500);
+        dev.send_packet(0, &p).unwrap();
+    }
+    let sent = stub::sent_packets(stub::DEFAULT_SERIAL);
+    let times: Vec<f64> = sent.iter().map(|p| p.0).collect();
+    assert_eq!(times, [1.0, 1.0005, 1.001]);
+    assert!(sent.iter().all(|p| p.1 == 2e9 && p.2 == 500));
+}
+
+#[test]
+fn set_enum() {
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+
+    assert_eq!(dev.set_enum("device/outputformat", "IQ").unwrap(), "iq");
+    assert_eq!(
+        dev.set_enum("device/outputformat", "spektra").unwrap(),
+        "spectra"
+    );
+    assert_eq!(dev.set_enum("main/decimation", "1/64").unwrap(), "1 / 64");
+    assert_eq!(
+        dev.set_enum("device/receiverchannel", "rx12").unwrap(),
+        "Rx12"
+    );
+    assert_eq!(dev.set_enum("device/gaincontrol", "pe").unwrap(), "peak");
+    assert!(matches!(
+        dev.get("main/decimation").unwrap(),
+        ConfigItem::Enum { value: 6, .. }
+    ));
+
+    match dev.set_enum("device/outputformat", "fft") {
+        Err(e @ Error::InvalidOption { .. }) => assert_eq!(
+            e.to_string(),
+            "Invalid option \"fft\" for device/outputformat, valid options: iq, spectra, both, raw"
+        ),
+        r => panic!("unexpected result {r:?}"),
+    }
+    // normalized match wins over longer options with the same prefix
+    assert!(matches!(
+        dev.set_enum("device/receiverchannel", "rx1"),
+        Ok(ref o) if o == "Rx1"
+    ));
+    assert!(matches!(
+        dev.set_enum("device/receiverchannel", "r"),
+        Err(Error::InvalidOption { .. })
+    ));
+    assert!(matches!(
+        dev.set_enum("main/centerfreq", "1"),
+        Err(Error::ErrorInvalidParameter)
+    ));
+}
+
+#[test]
+fn disabled_options() {
+    use aaronia_rtsa::config_path;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_enum("device/outputformat", "iq").unwrap();
+    // `both` and `raw` are not available
+    stub::set_disabled_options(stub::DEFAULT_SERIAL, "device/outputformat", 0b1100);
+
+    assert!(matches!(
+        dev.get("device/outputformat").unwrap(),
+        ConfigItem::Enum {
+            value: 0,
+            disabled: 0b1100,
+            ..
+        }
+    ));
+    let item = dev.get("device/outputformat").unwrap();
+    assert!(item.is_disabled(2) && item.is_disabled(3) && !item.is_disabled(1));
+    assert!(!item.is_disabled(64));
+    let entry = dev.config_entry("device/outputformat").unwrap();
+    assert!(entry.is_disabled(2) && !entry.is_disabled(1));
+    assert_eq!(entry.enabled_options(), vec!["iq", "spectra"]);
+
+    match dev.set_enum("device/outputformat", "raw") {
+        Err(e @ Error::OptionDisabled { .. }) => assert_eq!(
+            e.to_string(),
+            "Option \"raw\" of device/outputformat is disabled, enabled options: iq, spectra"
+        ),
+        r => panic!("unexpected result {r:?}"),
+    }
+    let path = config_path!("device/outputformat");
+    assert!(matches!(
+        dev.set_typed(path, &ConfigValue::String("both".into())),
+        Err(Error::OptionDisabled { .. })
+    ));
+    assert!(matches!(
+        dev.set_typed(path, &ConfigValue::Int(3)),
+        Err(Error::OptionDisabled { .. })
+    ));
+    dev.set_typed(path, &ConfigValue::String("spectra".into()))
+        .unwrap();
+    assert!(matches!(
+        dev.get("device/outputformat").unwrap(),
+        ConfigItem::Enum { value: 1, .. }
+    ));
+
+    // the typed setters refuse disabled options as well
+    assert!(matches!(
+        dev.set_output_format(OutputFormat::Raw),
+        Err(Error::OptionDisabled { .. })
+    ));
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    stub::set_disabled_options(stub::DEFAULT_SERIAL, "device/antenna", 0b10);
+    assert!(matches!(
+        dev.set_rx_antenna("rf2"),
+        Err(Error::OptionDisabled { .. })
+    ));
+    dev.set_rx_antenna("rf1").unwrap();
+    stub::set_disabled_options(stub::DEFAULT_SERIAL, "device/receiverchannel", 0b10);
+    assert!(matches!(
+        dev.set_rx_channel(aaronia_rtsa::RxChannel::Rx2),
+        Err(Error::OptionDisabled { .. })
+    ));
+}
+
+#[test]
+fn select() {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::Context;
+    use std::task::Poll;
+    use std::task::Wake;
+    use std::task::Waker;
+
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(f: F) -> F::Output {
+        let mut f = std::pin::pin!(f);
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(v) = f.as_mut().poll(&mut cx) {
+                return v;
+            }
+            std::thread::park();
+        }
+    }
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Both).unwrap();
+    dev.connect().unwrap();
+    assert!(matches!(dev.try_select(&[0, 2]), Err(Error::Empty)));
+    assert!(matches!(
+        dev.try_select(&[]),
+        Err(Error::ErrorInvalidChannel)
+    ));
+    dev.start().unwrap();
+
+    let mut chans = Vec::new();
+    for _ in 0..4 {
+        let (chan, p) = dev.select(&[0, 2]).unwrap();
+        match chan {
+            0 => assert!(matches!(p.data(), PacketData::Iq(_))),
+            _ => assert!(matches!(p.data(), PacketData::Spectrum(_))),
+        }
+        dev.consume(chan).unwrap();
+        chans.push(chan);
+    }
+    assert_eq!(chans, [0, 2, 0, 2]);
+
+    let (chan, _) = block_on(dev.select_async(&[0, 2])).unwrap();
+    assert_eq!(chan, 0);
+    dev.consume(0).unwrap();
+
+    dev.set_poll_strategy(aaronia_rtsa::PollStrategy::Fixed(
+        std::time::Duration::from_millis(1),
+    ));
+    dev.stop().unwrap();
+    let mut select = std::pin::pin!(dev.select_async(&[0, 2]));
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    assert!(select
+        .as_mut()
+        .poll(&mut Context::from_waker(&waker))
+        .is_pending());
+    // woken by the timer after the poll interval
+    let start = std::time::Instant::now();
+    std::thread::park_timeout(std::time::Duration::from_secs(1));
+    assert!(start.elapsed() < std::time::Duration::from_millis(500));
+}
+
+#[cfg(feature = "crossbeam")]
+#[test]
+fn spawn_rx() {
+    use aaronia_rtsa::pipeline::Payload;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Iq).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let (bridge, rx) = dev.spawn_rx(0, 4);
+    let mut last = None;
+    for _ in 0..10 {
+        let p = rx.recv().unwrap();
+        assert!(matches!(p.payload, Payload::Iq(ref s) if s.len() == 1024));
+        if let Some(end) = last {
+            assert!(p.meta.start_time >= end);
+        }
+        last = Some(p.meta.end_time);
+    }
+
+    // the stub never runs dry, i.e., the full channel drops packets
+    let dropped = bridge.dropped();
+    while bridge.dropped() == dropped {
+        std::thread::yield_now();
+    }
+    assert!(bridge.is_running());
+    assert!(bridge.forwarded() >= 10);
+
+    let dev = bridge.stop().unwrap();
+
+    // dropping the receiver ends the thread
+    let (bridge, rx) = dev.spawn_rx(0, 1);
+    drop(rx);
+    while bridge.is_running() {
+        std::thread::yield_now();
+    }
+    let mut dev = bridge.stop().unwrap();
+    dev.stop().unwrap();
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn python_bindings() {
+    use aaronia_rtsa::python::aaronia_rtsa;
+    use pyo3::prelude::*;
+
+    let _g = setup();
+    pyo3::append_to_inittab!(aaronia_rtsa);
+    Python::initialize();
+    Python::attach(|py| {
+        // objects of the script are released with its globals
+        let globals = pyo3::types::PyDict::new(py);
+        py.run(
+            cr#"
+import aaronia_rtsa as rtsa
+
+api = rtsa.ApiHandle("medium")
+api.rescan_devices()
+assert [d.serial for d in api.devices()] == ["STUB0001"]
+dev = api.get_device()
+dev.open()
+dev.connect()
+dev.set("main/centerfreq", 2.4e9)
+dev.set("main/decimation", "1 / 4")
+assert dev.get("main/centerfreq") == 2.4e9
+assert dev.get("main/decimation") == "1 / 4"
+try:
+    dev.set("main/centerfreq", "high")
+    assert False
+except rtsa.RtsaError:
+    pass
+dev.start()
+assert dev.status == "started"
+packet = dev.packet(0)
+assert packet.start_frequency == 2.4e9
+assert packet.sample_rate > 0
+dev.consume(0)
+dev.stop()
+"#,
+            Some(&globals),
+            None,
+        )
+        .unwrap();
+    });
+}
+
+#[cfg(feature = "capi")]
+#[test]
+fn capi() {
+    use aaronia_rtsa::capi::*;
+    use std::ffi::CStr;
+
+    let _g = setup();
+    unsafe {
+        let mut api = std::ptr::null_mut();
+        assert_eq!(rtsa_api_new(RtsaMemory::Medium, &mut api), RtsaStatus::Ok);
+        assert_eq!(rtsa_api_rescan(api), RtsaStatus::Ok);
+        let mut count = 0;
+        assert_eq!(rtsa_api_device_count(api, &mut count), RtsaStatus::Ok);
+        assert_eq!(count, 1);
+
+        let mut dev = std::ptr::null_mut();
+        assert_eq!(rtsa_device_new(api, 1, &mut dev), RtsaStatus::Error);
+        assert_eq!(rtsa_device_new(api, 0, &mut dev), RtsaStatus::Ok);
+        // the device keeps the library initialized
+        rtsa_api_free(api);
+
+        // lifecycle errors are reported with their message
+        assert_eq!(rtsa_device_start(dev), RtsaStatus::Error);
+        let mut msg = [0 as std::ffi::c_char; 256];
+        let len = rtsa_last_error(msg.as_mut_ptr(), msg.len());
+        assert!(len > 0);
+        assert_eq!(
+            CStr::from_ptr(msg.as_ptr()).to_bytes().len(),
+            len.min(msg.len() - 1)
+        );
+
+        assert_eq!(rtsa_device_open(dev), RtsaStatus::Ok);
+        assert_eq!(rtsa_device_connect(dev), RtsaStatus::Ok);
+        let path = c"main/centerfreq";
+        assert_eq!(
+            rtsa_device_set_float(dev, path.as_ptr(), 2.4e9),
+            RtsaStatus::Ok
+        );
+        let mut freq = 0.0;
+        assert_eq!(
+            rtsa_device_get_float(dev, path.as_ptr(), &mut freq),
+            RtsaStatus::Ok
+        );
+        assert_eq!(freq, 2.4e9);
+        assert_eq!(
+            rtsa_device_set(dev, c"main/decimation".as_ptr(), c"1 / 2".as_ptr()),
+            RtsaStatus::Ok
+        );
+        assert_eq!(
+            rtsa_device_set(dev, std::ptr::null(), c"1 / 2".as_ptr()),
+            RtsaStatus::InvalidArgument
+        );
+
+        // nothing is streamed, before the device is started
+        let mut packet = RtsaPacket::default();
+        assert_eq!(
+            rtsa_device_packet(dev, 0, 0, &mut packet),
+            RtsaStatus::Empty
+        );
+        assert_eq!(rtsa_device_start(dev), RtsaStatus::Ok);
+        assert_eq!(
+            rtsa_device_packet(dev, 0, 1000, &mut packet),
+            RtsaStatus::Ok
+        );
+        assert_eq!(packet.start_frequency, 2.4e9);
+        assert!(packet.num > 0);
+        let samples = std::slice::from_raw_parts(packet.samples, 2 * packet.num);
+        assert!(samples.iter().all(|s| s.is_finite()));
+        assert_eq!(rtsa_device_consume(dev, 0), RtsaStatus::Ok);
+
+        // releasing a started device stops and closes it
+        rtsa_device_free(dev);
+        rtsa_device_free(std::ptr::null_mut());
+    }
+}
+
+#[test]
+fn resampler() {
+    use aaronia_rtsa::dsp::Resampler;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set("main/decimation", "1 / 4").unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let rate = 92e6 / 4.0;
+    let mut r = Resampler::new(rate, rate / 2.0);
+    assert_eq!(r.ratio(), (1, 2));
+    assert_eq!(r.output_rate(), rate / 2.0);
+
+    let mut out = Vec::new();
+    let mut times = Vec::new();
+    for _ in 0..4 {
+        let p = dev.packet(0).unwrap();
+        assert_eq!(p.sample_rate(), Some(rate));
+        let first = r.process_packet(&p, &mut out);
+        assert_eq!(first, p.start_time() - r.delay());
+        times.push(first);
+        dev.consume(0).unwrap();
+    }
+    // 1024 samples per packet, decimated by 2
+    assert_eq!(out.len(), 4 * 512);
+    for t in times.windows(2) {
+        assert!((t[1] - t[0] - 1024.0 / rate).abs() < 1e-12);
+    }
+
+    // the tone at an eighth of the input rate is at a quarter of the output rate, i.e., it
+    // advances by a quarter turn per sample, after the filter settled
+    for w in out[512..].windows(2) {
+        assert!((w[0].norm() - 0.5).abs() < 0.01, "level {}", w[0].norm());
+        let step = (w[1] * w[0].conj()).arg();
+        assert!(
+            (step - std::f32::consts::FRAC_PI_2).abs() < 0.01,
+            "step {step}"
+        );
+    }
+}
+
+#[test]
+fn multi_channel_packet() {
+    use num_complex::Complex32;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set("device/receiverchannel", "Rx1+Rx2").unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    let rotation = Complex32::from_polar(1.0, stub::RX2_PHASE);
+    for _ in 0..2 {
+        let p = dev.packet(0).unwrap();
+        let mc = p.multi_channel();
+        assert_eq!(mc.channels(), 2);
+        assert_eq!(mc.len(), 1024);
+        let (rx1, rx2) = (mc.samples_ch(0), mc.samples_ch(1));
+        assert_eq!((rx1.len(), rx2.len()), (1024, 1024));
+
+        // samples with the same index are taken at the same time, i.e., the receivers only
+        // differ by their phase offset
+        for (a, b) in rx1.iter().zip(rx2.iter()) {
+            assert!((a * rotation - b).norm() < 1e-5);
+        }
+        // the channels are not mixed, rx1 is the tone at an eighth of the sample rate
+        let n0 = (p.start_time() * p.sample_rate().unwrap()).round() as usize;
+        for (i, s) in rx1.iter().enumerate() {
+            let phase = 2.0 * std::f32::consts::PI * ((n0 + i) % 8) as f32 / 8.0;
+            assert!((s - Complex32::from_polar(0.5, phase)).norm() < 1e-5);
+        }
+        let mut buf = vec![Complex32::new(0.0, 0.0); 10];
+        assert_eq!(rx2.copy_to(&mut buf), 10);
+        assert_eq!(buf, rx2.to_vec()[..10]);
+        assert_eq!(rx2.get(1024), None);
+        dev.consume(0).unwrap();
+    }
+
+    // single receivers are not interleaved
+    dev.stop().unwrap();
+    dev.set("device/receiverchannel", "Rx1").unwrap();
+    dev.start().unwrap();
+    let p = dev.packet(0).unwrap();
+    assert_eq!(p.multi_channel().channels(), 1);
+    assert_eq!(p.multi_channel().samples_ch(0).to_vec(), p.samples());
+}
+
+#[test]
+fn watch_devices() {
+    use aaronia_rtsa::DeviceEvent;
+    use std::time::Duration;
+
+    let _g = setup();
+    stub::set_devices(&["A"]);
+    let api = ApiHandle::new().unwrap();
+    let watcher = api.watch_devices(Duration::from_millis(10));
+    let timeout = Duration::from_secs(2);
+
+    // devices, present at the start, are reported as added
+    assert!(matches!(
+        watcher.next_timeout(timeout),
+        Some(DeviceEvent::DeviceAdded(d)) if d.serial() == "A"
+    ));
+
+    stub::set_devices(&["A", "B"]);
+    assert!(matches!(
+        watcher.next_timeout(timeout),
+        Some(DeviceEvent::DeviceAdded(d)) if d.serial() == "B"
+    ));
+
+    stub::set_devices(&["B"]);
+    assert!(matches!(
+        watcher.next_timeout(timeout),
+        Some(DeviceEvent::DeviceRemoved(s)) if s == "A"
+    ));
+
+    // unchanged devices are not reported again
+    assert!(watcher.next_timeout(Duration::from_millis(100)).is_none());
+    drop(watcher);
+}
+
+#[test]
+fn send_at() {
+    use aaronia_rtsa::MIN_LEAD_TIME;
+    use num_complex::Complex32;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    let samples = vec![Complex32::new(0.5, 0.0); 100];
+
+    // inside the lead window, the device clock only advances
+    let now = dev.stream_time().unwrap();
+    let inside = now + Duration::from_secs_f64(MIN_LEAD_TIME / 2.0);
+    assert!(matches!(
+        dev.send_at(0, &samples, inside, 1e9, 1e6),
+        Err(Error::TooLate)
+    ));
+    assert!(matches!(
+        dev.send_at(0, &samples, now, 1e9, 1e6),
+        Err(Error::TooLate)
+    ));
+    assert!(stub::sent_packets(stub::DEFAULT_SERIAL).is_empty());
+
+    // outside the lead window, the packet is scheduled at the given time
+    let now = dev.stream_time().unwrap();
+    let outside = now + Duration::from_secs_f64(20.0 * MIN_LEAD_TIME);
+    dev.send_at(0, &samples, outside, 1e9, 1e6).unwrap();
+    let sent = stub::sent_packets(stub::DEFAULT_SERIAL);
+    assert_eq!(sent, vec![(outside.as_secs(), 1e9, 100)]);
+}
+
+#[test]
+fn aggregator() {
+    use aaronia_rtsa::aggregate::Aggregator;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set_output_format(OutputFormat::Spectra).unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+
+    // the stub peak in the center bin is 6 dB below the reference level
+    let mut agg = Aggregator::new(0.5);
+    let mut times = Vec::new();
+    for reflevel in [-20.0, -40.0, -30.0] {
+        dev.set_float("main/reflevel", reflevel).unwrap();
+        let p = dev.packet(2).unwrap();
+        times.push((p.start_time(), p.end_time()));
+        agg.push_packet(&p);
+        dev.consume(2).unwrap();
+    }
+    assert_eq!(agg.count(), 3);
+
+    let lin = |db: f32| 10f32.powf(db / 10.0);
+    let mut avg = lin(-26.0);
+    for db in [-46.0, -36.0] {
+        avg += 0.5 * (lin(db) - avg);
+    }
+    let center = 512;
+    let max = agg.max_hold().unwrap();
+    let min = agg.min_hold().unwrap();
+    let average = agg.average().unwrap();
+    assert_eq!(max.data[center], -26.0);
+    assert_eq!(min.data[center], -46.0);
+    assert!((average.data[center] - 10.0 * avg.log10()).abs() < 1e-3);
+    for trace in [max, min, &average] {
+        assert_eq!(trace.data.len(), 1024);
+        assert!((trace.data[0] + 120.0).abs() < 1e-3);
+        assert_eq!(trace.start_time, times[0].0);
+        assert_eq!(trace.end_time, times[2].1);
+    }
+
+    agg.reset();
+    assert_eq!(agg.count(), 0);
+    assert!(agg.max_hold().is_none() && agg.average().is_none());
+}
+
+#[test]
+fn demodulator() {
+    use aaronia_rtsa::demod::Demodulator;
+    use aaronia_rtsa::demod::Mode;
+    use num_complex::Complex32;
+    use std::f64::consts::PI;
+
+    // amplitude and frequency of an audio tone from its peaks and zero crossings
+    let tone = |audio: &[f32], rate: f64| {
+        let amplitude = audio.iter().fold(0f32, |m, v| m.max(v.abs()));
+        let crossings = audio
+            .windows(2)
+            .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+            .count();
+        (
+            amplitude,
+            crossings as f64 / 2.0 * rate / audio.len() as f64,
+        )
+    };
+
+    // 1 kHz tone, FM with 2.5 kHz deviation and AM with 50 % modulation depth, for one second
+    let rate = 240e3;
+    let modulation = |i: usize| (2.0 * PI * 1e3 * i as f64 / rate).sin();
+    let mut phase = 0.0;
+    let fm: Vec<Complex32> = (0..240_000)
+        .map(|i| {
+            phase += 2.0 * PI * 2.5e3 * modulation(i) / rate;
+            Complex32::from_polar(0.5, phase as f32)
+        })
+        .collect();
+    let am: Vec<Complex32> = (0..240_000)
+        .map(|i| Complex32::new(0.5 * (1.0 + 0.5 * modulation(i) as f32), 0.0))
+        .collect();
+
+    for (mode, iq, expected) in [(Mode::Nfm, &fm, 0.5), (Mode::Am, &am, 0.25)] {
+        let mut demod = Demodulator::new(mode, rate, 48e3);
+        assert_eq!(demod.audio_rate(), 48e3);
+        let mut audio = Vec::new();
+        for chunk in iq.chunks(1024) {
+            demod.process(chunk, &mut audio);
+        }
+        assert_eq!(audio.len(), 48_000);
+
+        // the last 100 ms, after filters and the carrier removal settled
+        let (amplitude, frequency) = tone(&audio[43_200..], 48e3);
+        assert!(
+            (amplitude - expected).abs() < 0.05 * expected,
+            "{mode:?} amplitude {amplitude}"
+        );
+        assert!(
+            (frequency - 1e3).abs() < 20.0,
+            "{mode:?} frequency {frequency}"
+        );
+    }
+
+    // the stub tone at an eighth of the sample rate is a constant frequency offset for FM
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.set("main/decimation", "1 / 512").unwrap();
+    dev.connect().unwrap();
+    dev.start().unwrap();
+    let rate = 92e6 / 512.0;
+    let mut demod = Demodulator::new(Mode::Nfm, rate, 48e3);
+    let mut audio = Vec::new();
+    for _ in 0..16 {
+        let p = dev.packet(0).unwrap();
+        demod.process_packet(&p, &mut audio);
+        dev.consume(0).unwrap();
+    }
+    let offset = (rate / 8.0 / 5e3) as f32;
+    for a in &audio[audio.len() / 2..] {
+        assert!((a - offset).abs() < 1e-3, "{a} != {offset}");
+    }
+}
+
+#[test]
+fn poll_backoff() {
+    use aaronia_rtsa::CancelToken;
+    use aaronia_rtsa::PollStrategy;
+    use std::time::Duration;
+
+    let _g = setup();
+    let mut dev = device();
+    dev.open().unwrap();
+    dev.connect().unwrap();
+
+    // a backoff from zero still sleeps, i.e., the empty queue is polled about once per `max`
+    dev.set_poll_strategy(PollStrategy::Backoff {
+        min: Duration::ZERO,
+        max: Duration::from_millis(1),
+    });
+    let token = CancelToken::new();
+    let cancel = token.clone();
+    let t = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        cancel.cancel();
+    });
+    assert!(matches!(
+        dev.packet_cancellable(0, &token),
+        Err(Error::Cancelled)
+    ));
+    t.join().unwrap();
+    let polls = stub::empty_polls(stub::DEFAULT_SERIAL);
+    assert!((10..200).contains(&polls), "{polls} polls");
+}